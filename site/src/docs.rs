@@ -360,6 +360,7 @@ impl Allowed {
                 ("gifs", &[PrimClass::Sys(SysOpClass::Gifs)]),
                 ("audio", &[PrimClass::Sys(SysOpClass::Audio)]),
                 ("tcp", &[PrimClass::Sys(SysOpClass::Tcp)]),
+                ("udp", &[PrimClass::Sys(SysOpClass::Udp)]),
                 ("env", &[PrimClass::Sys(SysOpClass::Env)]),
                 ("command", &[PrimClass::Sys(SysOpClass::Command)]),
                 ("filesystem", &[PrimClass::Sys(SysOpClass::Filesystem)]),
@@ -474,6 +475,7 @@ impl Allowed {
                         SysOpClass::Images => ("System - Images".into_view(), "Work with static images"),
                         SysOpClass::Gifs => ("System - GIFs".into_view(), "Work with animated GIFs"),
                         SysOpClass::Tcp => ("System - TCP".into_view(), "Work with TCP sockets"),
+                        SysOpClass::Udp => ("System - UDP".into_view(), "Work with UDP sockets"),
                         SysOpClass::Misc => ("System - Misc".into_view(), ""),
                     }
                 }
@@ -13,7 +13,6 @@ use leptos::*;
 use uiua::{
     ast::Item, image_to_bytes, spans, value_to_gif_bytes, value_to_image, value_to_wav_bytes,
     DiagnosticKind, Report, ReportFragment, ReportKind, RunMode, SpanKind, SysBackend, Uiua,
-    UiuaResult, Value,
 };
 use wasm_bindgen::JsCast;
 use web_sys::{HtmlBrElement, HtmlDivElement, HtmlStyleElement, Node};
@@ -633,12 +632,7 @@ fn init_rt() -> Uiua {
     Uiua::with_backend(WebBackend::default())
         .with_mode(RunMode::All)
         .with_execution_limit(Duration::from_secs_f64(get_execution_limit()))
-}
-
-fn just_values(code: &str) -> UiuaResult<Vec<Value>> {
-    let mut rt = init_rt();
-    rt.load_str(code)?;
-    Ok(rt.take_stack())
+        .with_memory_limit(1 << 30)
 }
 
 fn challenge_code(input: &str, test: &str, flip: bool) -> String {
@@ -657,30 +651,25 @@ impl State {
                 run_code_single(&challenge_code(&chal.answer, &chal.example, chal.flip));
             example.insert(0, OutputItem::Faint(format!("Example: {}", chal.example)));
             let mut output_sections = vec![example];
-            let mut correct = true;
-            for test in &chal.tests {
-                let answer = || just_values(&challenge_code(&chal.answer, test, chal.flip));
-                let user_input = challenge_code(code, test, chal.flip);
-                let user_output = || just_values(&user_input);
-                correct = correct
-                    && match (answer(), user_output()) {
-                        (Ok(answer), Ok(users)) => answer == users,
-                        (Err(answer), Err(users)) => answer.to_string() == users.to_string(),
-                        _ => false,
-                    };
-                let mut output = run_code_single(&user_input);
-                output.insert(0, OutputItem::Faint(format!("Input: {test}")));
-                output_sections.push(output);
+            // Check the user's code against the answer for every test, including the hidden one
+            let mut codes = Vec::new();
+            for test in chal.tests.iter().chain([&chal.hidden]) {
+                codes.push(challenge_code(&chal.answer, test, chal.flip));
+                codes.push(challenge_code(code, test, chal.flip));
             }
-            let hidden_answer =
-                || just_values(&challenge_code(&chal.answer, &chal.hidden, chal.flip));
-            let hidden_user_output = || just_values(&challenge_code(code, &chal.hidden, chal.flip));
-            correct = correct
-                && match (hidden_answer(), hidden_user_output()) {
+            let codes: Vec<&str> = codes.iter().map(String::as_str).collect();
+            let correct = init_rt().run_many(&codes).chunks(2).all(|results| {
+                match (&results[0], &results[1]) {
                     (Ok(answer), Ok(users)) => answer == users,
                     (Err(answer), Err(users)) => answer.to_string() == users.to_string(),
                     _ => false,
-                };
+                }
+            });
+            for test in &chal.tests {
+                let mut output = run_code_single(&challenge_code(code, test, chal.flip));
+                output.insert(0, OutputItem::Faint(format!("Input: {test}")));
+                output_sections.push(output);
+            }
             let mut output = if chal.did_init_run.get() {
                 vec![OutputItem::String(if correct {
                     "✅ Correct!".into()
@@ -928,6 +917,7 @@ pub fn progressive_strings(input: &str) -> Vec<String> {
                 lines.push(line);
             }
             Item::Binding(binding) => lines.push(vec![binding.span().as_str().into()]),
+            Item::Import(import) => lines.push(vec![import.span().as_str().into()]),
             Item::TestScope(items) => lines.push(vec![items.span.as_str().into()]),
             Item::ExtraNewlines(span) => lines.push(vec![span.as_str().into()]),
         }
@@ -70,8 +70,11 @@ pub const EXAMPLES: &[&str] = &[
 #[test]
 fn test_examples() {
     use uiua::Uiua;
+
+    use crate::backend::WebBackend;
+
     for example in EXAMPLES {
-        let mut env = Uiua::with_native_sys();
+        let mut env = Uiua::with_backend(WebBackend::default());
         if let Err(e) = env.load_str(example) {
             panic!("Example failed:\n{example}\n{e}");
         } else if let Some(diag) = env.take_diagnostics().into_iter().next() {
@@ -429,7 +429,8 @@ fn site() {
                             code.clone(),
                             std::thread::spawn(move || {
                                 let mut env =
-                                    uiua::Uiua::with_native_sys().with_mode(uiua::RunMode::All);
+                                    uiua::Uiua::with_backend(crate::backend::WebBackend::default())
+                                        .with_mode(uiua::RunMode::All);
                                 (env.load_str(&code).map(|_| env), should_fail)
                             }),
                         ));
@@ -328,6 +328,10 @@ pub fn Optimizations() -> impl IntoView {
             <tr><th><Prim prim=Scan/></th> <td>"✔"</td>  <td>"✔"</td> <td></td> <td></td> <td></td></tr>
         </table>
 
+        <h2>"Parallelism"</h2>
+        <p>"When "<Prim prim=Rows/>" or "<Prim prim=Each/>" is used on a large array, and its function has no side effects, the native interpreter splits the iterations between all available cores. Functions that use "<Prim prim=Rand/>", system functions, or threading primitives always run on a single core."</p>
+        <p>"This is not done on the website, which only has one thread."</p>
+
         <h2>"Complexity"</h2>
         <p>"Some combinations of functions are special-cased in the interpreter to run in less time complexity or in fewer operations than is implied by each function individually."</p>
         <p>"This table shows how various combinations of functions are optimized:"</p>
@@ -674,6 +674,9 @@ fn TutorialBindings() -> impl IntoView {
         <p>"Bindings run the code to the right of the "<code>"←"</code>", then pop the top value off the stack and bind it to the name on the left."</p>
         <p>"Note, though, that an empty right side is perfectly valid! This means you can bind values that were created on previous lines."</p>
         <Editor example="×6 7\nAnswer ←\n[Answer]"/>
+        <p>"A binding with several names destructures an array, binding each of its rows to a name. The number of rows must match the number of names."</p>
+        <Editor example="A B C ← [1 2 3]\n[C B A]"/>
+        <Editor example="Name Age ← {\"Dan\" 28}\nAge"/>
 
         <h2 id="binding-functions">"Binding Functions"</h2>
         <p>"If the code on the right side of the "<code>"←"</code>" requires more than 0 values to be on the stack, then instead of evaluating its right side immediately, the right side will be bound as a function."</p>
@@ -725,7 +728,14 @@ X ← (
 X 5"/>
 
         <h2 id="local-bindings">"A Note on Local Bindings"</h2>
-        <p>"Bindings in Uiua can "<em>"only"</em>" be global. There is no way to give a name to a value within an inline function. A "<code>"←"</code>" inside "<code>"()"</code>"s is a syntax error."</p>
+        <p>"A binding at the start of a line inside an inline function is "<em>"local"</em>" to that function. It can be used by the lines after it, but not outside the function. Local bindings must use "<code>"←"</code>" rather than "<code>"="</code>"."</p>
+        <Editor example="\
+F ← (
+  Sq ← ×.
+  +1 Sq
+)
+F 5"/>
+        <p>"However, like all bindings, a local binding is bound when it is compiled, not when the function is called. There is no way to give a name to one of a function's arguments."</p>
         <p>"This is a deliberate design decision. It forces you to write tacit code, a.k.a. code with functions that do not mention their arguments. Uiua is designed to make writing tacit code as workable as possible. "<em>"How"</em>" it does this will be discussed in "<A href="/docs/advancedstack">"later"</A>" "<A href="/docs/advancedarray">"sections"</A>"."</p>
 
         <h2 id="format-strings">"Format Strings"</h2>
@@ -753,6 +763,17 @@ X 5"/>
         <p>"If the compiler cannot derive the stack signature of a function and you give it one which is "<em>"wrong"</em>", the function will throw an error at runtime."</p>
         <Editor example="f = |1.2 /∘\nf [1 2 3]"/> // Should fail
 
+        <h2 id="recursion">"Recursion"</h2>
+        <p>"A named function can call itself, but only if it has a declared signature. Without one, the compiler would need to know the function's signature to infer it."</p>
+        <p>"Recursive functions usually use a "<A href="/docs/controlflow#switch">"switch function"</A>" to decide whether to stop."</p>
+        <Editor example="Fact ← |1.1 (×Fact-1.|1;)<2.\nFact 5"/>
+        <p>"A function can also call functions that are bound after it, as long as they have declared signatures. This lets functions be mutually recursive."</p>
+        <Editor example="Even ← |1.1 (Odd-1|1;)=0.\nOdd ← |1.1 (Even-1|0;)=0.\nEven 10"/>
+        <p>"If a function calls itself at its very end, or at the end of a switch branch at its very end, the call restarts the function instead of making a new call. Such a function can recurse as deeply as it needs to."</p>
+        <Editor example="CountDown ← |1.1 (CountDown-1|∘)=0.\nCountDown 100000"/>
+        <p>"Most of the time, an array-oriented solution is simpler and faster than a recursive one."</p>
+        <Editor example="/×+1⇡5"/>
+
         <h2 id="challenges">"Challenges"</h2>
 
         <Challenge
@@ -936,12 +957,14 @@ splitArray([1, 2, 3, 7, 2, 4, 5])"</code>
         <p>"While "<Prim prim=Do/>" is very powerful, it should only be used when necessary."</p>
 
         <h2 id="try">"Catching errors with "<Prim prim=Try/></h2>
-        <p>"The "<Prim prim=Try/>" modifier takes two functions. If the first function throws an error, the second function is called with the same arguments plus an error message."</p>
+        <p>"The "<Prim prim=Try/>" modifier takes two functions. If the first function throws an error, the second function is called with the same arguments plus the error."</p>
         <p>"We can see how this works by using it with "<Prim prim=Parse/>"."</p>
-        <p>"If the parsing fails, we "<Prim prim=Box/>" "<Prim prim=Both/>" the argument and the error message and put them in an array."</p>
+        <p>"If the parsing fails, we "<Prim prim=Box/>" "<Prim prim=Both/>" the argument and the error and put them in an array."</p>
         <Editor example="f ← ⍣parse[∩□]\nf \"5\"\nf \"dog\""/>
         <p>"If we don't care about an error and just want to supply a default value, we can use "<Prim prim=Gap/>" to discard the argument and error message."</p>
         <Editor example="f ← ⍣parse⋅⋅0\nf \"5\"\nf \"dog\""/>
+        <p>"Runtime errors are "<Prim prim=Map/>"s of the error's kind, message, span, and trace. Their parts can be gotten with "<Prim prim=Get/>"."</p>
+        <Editor example="f ← ⍣parse⋅(⊔get \"message\")\nf \"5\"\nf \"dog\""/>
 
         <h2 id="switch">"Switch Functions"</h2>
         <p>"Switch functions are inline functions that choose a branch based on an index. Like normal inline functions, they are surrounded by "<code>"()"</code>"s. Branches are separated by "<code>"|"</code>"s."</p>
@@ -955,6 +978,9 @@ splitArray([1, 2, 3, 7, 2, 4, 5])"</code>
         <p>"Signatures in switch functions are a bit messy, so try to avoid them when possible."</p>
         <p>"Because a second "<code>"|"</code>" immediately after another indicates a signature, branches that do nothing must contain "<Prim prim=Identity/>"."</p>
         <Editor example="f ← (+5|∘|÷10)+∩>5,10.\n[f2 f6 f200]"/>
+        <p>"If the index is a list, a branch is chosen for each row of the arguments. All the branches must return the same number of values."</p>
+        <Editor example="(×10|+1|¯) [0 1 2 1] [1 2 3 4]"/>
+        <Editor example="(+|-) [0 1] [10 20] [1 2]"/>
 
         <h2 id="if"><Prim prim=If/></h2>
         <p>"The "<Prim prim=If/>" modifier is similar to a switch function, but it only has two branches. The true branch is the first function and the false branch is the second"</p>
@@ -1115,6 +1141,12 @@ F!!!+×⊂ [1 2 3][4 5 6]"/>
 ReduceAll! ← ⍥/^2⧻△.
 ReduceAll!+[1_2_3 4_5_6]"/>
 
+        <h2 id="aliases">"Aliases"</h2>
+        <p>"A modifier binding whose body is just another modifier, with no functions, gives that modifier a new name. The name must still end in as many "<code>"!"</code>"s as the number of functions the modifier takes."</p>
+        <Editor example="\
+Both! ← ∩
+Both!⇌ [1 2 3] [4 5 6]"/>
+
         <h2 id="challenges">"Challenges"</h2>
 
         <Challenge
@@ -1179,6 +1211,11 @@ Sqr ← Ex \"Square\"
 Inc Sqr Dub 5"/>
         <p>"When you write code like this that imports several items, the formatter will automatically indent each item. Try it out!"</p>
 
+        <h2 id="namespaces">"Namespaced Imports"</h2>
+        <p>"Instead of importing items one by one, you can bind an entire module to a name with "<code>"¤"</code>" followed by the module's path. The module's bindings can then be used by prefixing them with the module's name and a "<code>"¤"</code>"."</p>
+        <Editor example="Ex ¤ \"example.ua\"\nEx¤Increment Ex¤Square Ex¤Double 5"/>
+        <p>"A module is only run the first time it is imported. Importing a module that is currently being imported is an error."</p>
+
     }
 }
 
@@ -99,7 +99,7 @@ macro_rules! uiuisms {
         fn uiuisms() {
             for code in [$($code),*] {
                 println!("Testing Uiuism:\n{code}");
-                let mut env = uiua::Uiua::with_native_sys();
+                let mut env = uiua::Uiua::with_backend(crate::backend::WebBackend::default());
                 if let Err(e) = env.load_str(code) {
                     panic!("Uiuism failed\n{code}\n{}", e.report());
                 }
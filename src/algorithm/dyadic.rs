@@ -2,10 +2,11 @@
 
 use std::{
     borrow::Cow,
-    cmp::Ordering,
-    collections::{HashMap, HashSet},
-    iter::repeat,
-    mem::take,
+    cmp::{Ordering, Reverse},
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::Hasher,
+    iter::{repeat, repeat_n},
+    mem::{size_of, take},
 };
 
 use ecow::EcoVec;
@@ -17,7 +18,7 @@ use crate::{
     boxed::Boxed,
     cowslice::{cowslice, CowSlice},
     value::Value,
-    Uiua, UiuaResult,
+    ErrorKind, Uiua, UiuaResult,
 };
 
 #[cfg(feature = "bytes")]
@@ -36,7 +37,10 @@ impl Value {
             (Value::Box(a), Value::Box(b)) => on_success(a, b, ctx),
             (Value::Box(a), b) => on_success(a, b.coerce_to_boxes(), ctx),
             (a, Value::Box(b)) => on_success(a.coerce_to_boxes(), b, ctx),
-            (a, b) => Err(ctx.error(on_error(a.type_name(), b.type_name()))),
+            (a, b) => Err(C::kind_error(
+                ctx.error(on_error(a.type_name(), b.type_name())),
+                ErrorKind::Type,
+            )),
         }
     }
 }
@@ -96,6 +100,7 @@ fn shape_index_to_data_index_test() {
 impl<T: ArrayValue> Array<T> {
     /// Fill the array with the given value so it matches the given shape
     pub fn fill_to_shape(&mut self, shape: &[usize], fill_value: T) {
+        self.map_keys = None;
         while self.rank() < shape.len() {
             self.shape.insert(0, 1);
         }
@@ -181,6 +186,20 @@ impl Value {
             (Value::Complex(a), Value::Byte(b)) => a.join_impl(b.convert(), ctx)?.into(),
             #[cfg(all(feature = "complex", feature = "bytes"))]
             (Value::Byte(a), Value::Complex(b)) => a.convert().join_impl(b, ctx)?.into(),
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Rational(b)) => a.join_impl(b, ctx)?.into(),
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Num(b)) => a.join_impl(b.convert(), ctx)?.into(),
+            #[cfg(feature = "rational")]
+            (Value::Num(a), Value::Rational(b)) => a.convert().join_impl(b, ctx)?.into(),
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Rational(a), Value::Byte(b)) => a.join_impl(b.convert(), ctx)?.into(),
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Byte(a), Value::Rational(b)) => a.convert().join_impl(b, ctx)?.into(),
+            #[cfg(all(feature = "rational", feature = "complex"))]
+            (Value::Complex(a), Value::Rational(b)) => a.join_impl(b.convert(), ctx)?.into(),
+            #[cfg(all(feature = "rational", feature = "complex"))]
+            (Value::Rational(a), Value::Complex(b)) => a.convert().join_impl(b, ctx)?.into(),
             (a, b) => a.coerce_to_functions(
                 b,
                 ctx,
@@ -257,6 +276,32 @@ impl Value {
                 a.append(b, ctx)?;
                 *self = a.into();
             }
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Rational(b)) => a.append(b, ctx)?,
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Num(b)) => a.append(b.convert(), ctx)?,
+            #[cfg(feature = "rational")]
+            (Value::Num(a), Value::Rational(b)) => {
+                let mut a = a.convert_ref();
+                a.append(b, ctx)?;
+                *self = a.into();
+            }
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Rational(a), Value::Byte(b)) => a.append(b.convert(), ctx)?,
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Byte(a), Value::Rational(b)) => {
+                let mut a = a.convert_ref();
+                a.append(b, ctx)?;
+                *self = a.into();
+            }
+            #[cfg(all(feature = "rational", feature = "complex"))]
+            (Value::Complex(a), Value::Rational(b)) => a.append(b.convert(), ctx)?,
+            #[cfg(all(feature = "rational", feature = "complex"))]
+            (Value::Rational(a), Value::Complex(b)) => {
+                let mut a = a.convert_ref();
+                a.append(b, ctx)?;
+                *self = a.into();
+            }
             (a, b) => {
                 *self = a.clone().coerce_to_functions(
                     b,
@@ -287,6 +332,7 @@ impl<T: ArrayValue> Array<T> {
     }
     fn join_impl<C: FillContext>(mut self, mut other: Self, ctx: &C) -> Result<Self, C::Error> {
         crate::profile_function!();
+        self.map_keys = None;
         let res = match self.rank().cmp(&other.rank()) {
             Ordering::Less => {
                 if self.shape() == [0] {
@@ -300,18 +346,20 @@ impl<T: ArrayValue> Array<T> {
                     target_shape
                 } else {
                     if other.rank() - self.rank() > 1 {
-                        return Err(C::fill_error(ctx.error(format!(
+                        let error = ctx.error(format!(
                             "Cannot join rank {} array with rank {} array",
                             self.rank(),
                             other.rank()
-                        ))));
+                        ));
+                        return Err(C::fill_error(C::kind_error(error, ErrorKind::Shape)));
                     }
                     if self.shape() != &other.shape()[1..] {
-                        return Err(C::fill_error(ctx.error(format!(
+                        let error = ctx.error(format!(
                             "Cannot join arrays of shapes {} and {}",
                             self.format_shape(),
                             other.format_shape()
-                        ))));
+                        ));
+                        return Err(C::fill_error(C::kind_error(error, ErrorKind::Shape)));
                     }
                     other.shape
                 };
@@ -342,11 +390,12 @@ impl<T: ArrayValue> Array<T> {
                             array.fill_to_shape(&new_shape, fill);
                         }
                     } else if self.shape[1..] != other.shape[1..] {
-                        return Err(C::fill_error(ctx.error(format!(
+                        let error = ctx.error(format!(
                             "Cannot join arrays of shapes {} and {}",
                             self.format_shape(),
                             other.format_shape()
-                        ))));
+                        ));
+                        return Err(C::fill_error(C::kind_error(error, ErrorKind::Shape)));
                     }
                     self.data.extend(other.data);
                     self.shape[0] += other.shape[0];
@@ -358,6 +407,7 @@ impl<T: ArrayValue> Array<T> {
         Ok(res)
     }
     fn append<C: FillContext>(&mut self, mut other: Self, ctx: &C) -> Result<(), C::Error> {
+        self.map_keys = None;
         let target_shape = if let Some(fill) = ctx.fill::<T>() {
             while self.rank() <= other.rank() {
                 self.shape.push(1);
@@ -369,18 +419,20 @@ impl<T: ArrayValue> Array<T> {
             target_shape
         } else {
             if self.rank() <= other.rank() || self.rank() - other.rank() > 1 {
-                return Err(C::fill_error(ctx.error(format!(
+                let error = ctx.error(format!(
                     "Cannot append rank {} array with rank {} array",
                     self.rank(),
                     other.rank()
-                ))));
+                ));
+                return Err(C::fill_error(C::kind_error(error, ErrorKind::Shape)));
             }
             if &self.shape()[1..] != other.shape() {
-                return Err(C::fill_error(ctx.error(format!(
+                let error = ctx.error(format!(
                     "Cannot append arrays of shapes {} and {}",
                     self.format_shape(),
                     other.format_shape()
-                ))));
+                ));
+                return Err(C::fill_error(C::kind_error(error, ErrorKind::Shape)));
             }
             take(&mut self.shape)
         };
@@ -470,6 +522,32 @@ impl Value {
                 a.couple_impl(b, ctx)?;
                 *self = a.into();
             }
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Rational(b)) => a.couple_impl(b, ctx)?,
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Num(b)) => a.couple_impl(b.convert(), ctx)?,
+            #[cfg(feature = "rational")]
+            (Value::Num(a), Value::Rational(b)) => {
+                let mut a = a.convert_ref();
+                a.couple_impl(b, ctx)?;
+                *self = a.into();
+            }
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Rational(a), Value::Byte(b)) => a.couple_impl(b.convert(), ctx)?,
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Byte(a), Value::Rational(b)) => {
+                let mut a = a.convert_ref();
+                a.couple_impl(b, ctx)?;
+                *self = a.into();
+            }
+            #[cfg(all(feature = "rational", feature = "complex"))]
+            (Value::Complex(a), Value::Rational(b)) => a.couple_impl(b.convert(), ctx)?,
+            #[cfg(all(feature = "rational", feature = "complex"))]
+            (Value::Rational(a), Value::Complex(b)) => {
+                let mut a = a.convert_ref();
+                a.couple_impl(b, ctx)?;
+                *self = a.into();
+            }
             (a, b) => {
                 *self = a.clone().coerce_to_functions(
                     b,
@@ -492,6 +570,8 @@ impl Value {
             Value::Byte(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
             #[cfg(feature = "complex")]
             Value::Complex(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
+            #[cfg(feature = "rational")]
+            Value::Rational(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
             Value::Char(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
             Value::Box(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
         }
@@ -514,17 +594,19 @@ impl<T: ArrayValue> Array<T> {
     }
     fn couple_impl<C: FillContext>(&mut self, mut other: Self, ctx: &C) -> Result<(), C::Error> {
         crate::profile_function!();
+        self.map_keys = None;
         if self.shape != other.shape {
             if let Some(fill) = ctx.fill::<T>() {
                 let new_shape = max_shape(&self.shape, &other.shape);
                 self.fill_to_shape(&new_shape, fill.clone());
                 other.fill_to_shape(&new_shape, fill);
             } else {
-                return Err(C::fill_error(ctx.error(format!(
+                let error = ctx.error(format!(
                     "Cannot couple arrays with shapes {} and {}",
                     self.format_shape(),
                     other.format_shape()
-                ))));
+                ));
+                return Err(C::fill_error(C::kind_error(error, ErrorKind::Shape)));
             }
         }
         self.data.extend(other.data);
@@ -643,12 +725,15 @@ impl Value {
     /// `reshape` this value with another
     pub fn reshape(&mut self, shape: &Self, env: &Uiua) -> UiuaResult {
         if let Ok(n) = shape.as_nat(env, "") {
+            env.validate_alloc(n.saturating_mul(self.element_count()), self.element_size())?;
             match self {
                 Value::Num(a) => a.reshape_scalar(n),
                 #[cfg(feature = "bytes")]
                 Value::Byte(a) => a.reshape_scalar(n),
                 #[cfg(feature = "complex")]
                 Value::Complex(a) => a.reshape_scalar(n),
+                #[cfg(feature = "rational")]
+                Value::Rational(a) => a.reshape_scalar(n),
                 Value::Char(a) => a.reshape_scalar(n),
                 Value::Box(a) => a.reshape_scalar(n),
             }
@@ -658,12 +743,18 @@ impl Value {
                 "Shape should be a single natural number \
                 or a list of integers",
             )?;
+            let elements = (target_shape.iter())
+                .filter(|&&dim| dim >= 0)
+                .fold(1usize, |acc, &dim| acc.saturating_mul(dim as usize));
+            env.validate_alloc(elements, self.element_size())?;
             match self {
                 Value::Num(a) => a.reshape(&target_shape, env),
                 #[cfg(feature = "bytes")]
                 Value::Byte(a) => a.reshape(&target_shape, env),
                 #[cfg(feature = "complex")]
                 Value::Complex(a) => a.reshape(&target_shape, env),
+                #[cfg(feature = "rational")]
+                Value::Rational(a) => a.reshape(&target_shape, env),
                 Value::Char(a) => a.reshape(&target_shape, env),
                 Value::Box(a) => a.reshape(&target_shape, env),
             }?
@@ -675,6 +766,7 @@ impl Value {
 impl<T: ArrayValue> Array<T> {
     /// `reshape` this array by replicating it as the rows of a new array
     pub fn reshape_scalar(&mut self, count: usize) {
+        self.map_keys = None;
         self.data.modify(|data| {
             if count == 0 {
                 data.clear();
@@ -690,6 +782,7 @@ impl<T: ArrayValue> Array<T> {
     }
     /// `reshape` the array
     pub fn reshape(&mut self, dims: &[isize], env: &Uiua) -> UiuaResult {
+        self.map_keys = None;
         let mut neg_count = 0;
         for dim in dims {
             if *dim < 0 {
@@ -798,6 +891,14 @@ impl Value {
             "Keep amount must be a natural number \
             or list of natural numbers",
         )?;
+        let elements = if self.rank() == 0 {
+            counts[0].saturating_mul(kept.element_count())
+        } else {
+            (counts.iter())
+                .fold(0usize, |acc, &n| acc.saturating_add(n))
+                .saturating_mul(kept.row_len())
+        };
+        env.validate_alloc(elements, kept.element_size())?;
         Ok(if self.rank() == 0 {
             match kept {
                 Value::Num(a) => a.scalar_keep(counts[0]).into(),
@@ -805,6 +906,8 @@ impl Value {
                 Value::Byte(a) => a.scalar_keep(counts[0]).into(),
                 #[cfg(feature = "complex")]
                 Value::Complex(a) => a.scalar_keep(counts[0]).into(),
+                #[cfg(feature = "rational")]
+                Value::Rational(a) => a.scalar_keep(counts[0]).into(),
                 Value::Char(a) => a.scalar_keep(counts[0]).into(),
                 Value::Box(a) => a.scalar_keep(counts[0]).into(),
             }
@@ -815,19 +918,40 @@ impl Value {
                 Value::Byte(a) => a.list_keep(&counts, env)?.into(),
                 #[cfg(feature = "complex")]
                 Value::Complex(a) => a.list_keep(&counts, env)?.into(),
+                #[cfg(feature = "rational")]
+                Value::Rational(a) => a.list_keep(&counts, env)?.into(),
                 Value::Char(a) => a.list_keep(&counts, env)?.into(),
                 Value::Box(a) => a.list_keep(&counts, env)?.into(),
             }
         })
     }
-    pub(crate) fn unkeep(self, kept: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
-        let counts = self.as_nats(
+    pub(crate) fn unkeep(self, mut kept: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
+        let mut counts = self.as_nats(
             env,
             "Keep amount must be a natural number \
             or list of natural numbers",
         )?;
         if self.rank() == 0 {
-            return Err(env.error("Cannot invert scalar keep"));
+            // Scalar keep repeats the whole array, so the first repetition is used
+            if into.rank() == 0 {
+                return Ok(if counts[0] > 0 && kept.row_count() > 0 {
+                    kept.row(0)
+                } else {
+                    into
+                });
+            }
+            if kept.row_count() != counts[0] * into.row_count() {
+                return Err(env.error(format!(
+                    "Kept array has {} rows, but it was created with {}, \
+                    so the keep cannot be inverted",
+                    kept.row_count(),
+                    counts[0] * into.row_count()
+                )));
+            }
+            if counts[0] > 1 {
+                kept = Value::from(into.row_count()).take(kept, env)?;
+            }
+            counts = vec![counts[0].min(1); into.row_count()];
         }
         Ok(match (kept, into) {
             (Value::Num(a), Value::Num(b)) => a.unkeep(&counts, b, env)?.into(),
@@ -852,6 +976,7 @@ impl Value {
 impl<T: ArrayValue> Array<T> {
     /// `keep` this array by replicating it as the rows of a new array
     pub fn scalar_keep(mut self, count: usize) -> Self {
+        self.map_keys = None;
         // Scalar kept
         if self.rank() == 0 {
             self.shape.push(count);
@@ -889,6 +1014,7 @@ impl<T: ArrayValue> Array<T> {
     }
     /// `keep` this array with some counts
     pub fn list_keep(mut self, counts: &[usize], env: &Uiua) -> UiuaResult<Self> {
+        self.map_keys = None;
         let mut amount = Cow::Borrowed(counts);
         match amount.len().cmp(&self.row_count()) {
             Ordering::Equal => {}
@@ -985,9 +1111,17 @@ impl<T: ArrayValue> Array<T> {
         self.validate_shape();
         Ok(self)
     }
+    /// Put kept rows back into the array they were kept from
+    ///
+    /// When a row was kept more than once, the first of its copies is used.
     pub(crate) fn unkeep(self, counts: &[usize], into: Self, env: &Uiua) -> UiuaResult<Self> {
-        if counts.iter().any(|&n| n > 1) {
-            return Err(env.error("Cannot invert keep with non-boolean counts"));
+        let expected: usize = counts.iter().sum();
+        if self.row_count() != expected {
+            return Err(env.error(format!(
+                "Kept array has {} rows, but it was created with {expected}, \
+                so the keep cannot be inverted",
+                self.row_count()
+            )));
         }
         let mut new_rows: Vec<_> = Vec::with_capacity(counts.len());
         let mut transformed = self.into_rows();
@@ -995,12 +1129,7 @@ impl<T: ArrayValue> Array<T> {
             if *count == 0 {
                 new_rows.push(into_row);
             } else {
-                let new_row = transformed.next().ok_or_else(|| {
-                    env.error(
-                        "Kept array has fewer rows than it was created with, \
-                        so the keep cannot be inverted",
-                    )
-                })?;
+                let new_row = transformed.next().unwrap();
                 if new_row.shape != into_row.shape {
                     return Err(env.error(format!(
                         "Kept array's shape was changed from {} to {}, \
@@ -1010,6 +1139,9 @@ impl<T: ArrayValue> Array<T> {
                     )));
                 }
                 new_rows.push(new_row);
+                for _ in 1..*count {
+                    transformed.next();
+                }
             }
         }
         Self::from_row_arrays(new_rows, env)
@@ -1060,6 +1192,8 @@ impl Value {
             )?,
             #[cfg(feature = "complex")]
             Value::Complex(a) => Value::Complex(a.pick(index_shape, &index_data, env)?),
+            #[cfg(feature = "rational")]
+            Value::Rational(a) => Value::Rational(a.pick(index_shape, &index_data, env)?),
             Value::Char(a) => Value::Char(a.pick(index_shape, &index_data, env)?),
             Value::Box(a) => Value::Box(a.pick(index_shape, &index_data, env)?),
         })
@@ -1090,6 +1224,7 @@ impl Value {
             |a, b| a.unpick(index_shape, &index_data, b, env).map(Into::into),
             |a, b| a.unpick(index_shape, &index_data, b, env).map(Into::into),
             |a, b| a.unpick(index_shape, &index_data, b, env).map(Into::into),
+            |a, b| a.unpick(index_shape, &index_data, b, env).map(Into::into),
             |a, b| {
                 env.error(format!(
                     "Cannot unpick {} array from {} array",
@@ -1155,6 +1290,7 @@ impl<T: ArrayValue> Array<T> {
                         "Index {i} is out of bounds of length {s} (dimension {d}) in shape {}",
                         self.format_shape()
                     ))
+                    .with_kind(ErrorKind::Index)
                     .fill());
             }
             let i = if i >= 0 { i as usize } else { (s + i) as usize };
@@ -1238,6 +1374,159 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+impl Value {
+    /// Use this value as a boolean mask to select the cells of another
+    pub fn masked(&self, from: Self, env: &Uiua) -> UiuaResult<Self> {
+        let (cells, frame_rank) = self.mask_cells(from.shape(), env)?;
+        Ok(match from {
+            Value::Num(a) => a.gather_cells(&cells, frame_rank).into(),
+            #[cfg(feature = "bytes")]
+            Value::Byte(a) => a.gather_cells(&cells, frame_rank).into(),
+            #[cfg(feature = "complex")]
+            Value::Complex(a) => a.gather_cells(&cells, frame_rank).into(),
+            #[cfg(feature = "rational")]
+            Value::Rational(a) => a.gather_cells(&cells, frame_rank).into(),
+            Value::Char(a) => a.gather_cells(&cells, frame_rank).into(),
+            Value::Box(a) => a.gather_cells(&cells, frame_rank).into(),
+        })
+    }
+    /// Use this value as indices to `scatter` values into a copy of another
+    pub fn scatter(&self, values: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
+        let (index_shape, index_data) = self.as_shaped_indices(env)?;
+        let frame_rank = match index_shape {
+            [] | [_] => 1,
+            [_, n] => *n,
+            _ => {
+                return Err(env.error(format!(
+                    "Scatter indices must have rank 0, 1, or 2, but their shape is {}",
+                    FormatShape(index_shape)
+                )))
+            }
+        };
+        if frame_rank > into.rank() {
+            return Err(env.error(format!(
+                "Cannot scatter into rank {} array with indices of length {frame_rank}",
+                into.rank()
+            )));
+        }
+        let frame = &into.shape()[..frame_rank];
+        let mut cells = Vec::with_capacity(index_data.len() / frame_rank.max(1));
+        for index in index_data.chunks_exact(frame_rank.max(1)) {
+            let mut cell = 0;
+            for (d, (&i, &s)) in index.iter().zip(frame).enumerate() {
+                let s = s as isize;
+                if i >= s || i < -s {
+                    return Err(env
+                        .error(format!(
+                            "Index {i} is out of bounds of length {s} (dimension {d}) in shape {}",
+                            into.format_shape()
+                        ))
+                        .with_kind(ErrorKind::Index));
+                }
+                cell = cell * s as usize + if i >= 0 { i } else { s + i } as usize;
+            }
+            cells.push(cell);
+        }
+        values.scatter_cells(&cells, frame_rank, into, env)
+    }
+    pub(crate) fn unmasked(&self, values: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
+        let (cells, frame_rank) = self.mask_cells(into.shape(), env)?;
+        values.scatter_cells(&cells, frame_rank, into, env)
+    }
+    /// Get the indices of the cells of an array with the given shape that this mask selects
+    fn mask_cells(&self, shape: &[usize], env: &Uiua) -> UiuaResult<(Vec<usize>, usize)> {
+        let mask = self.as_number_array(
+            env,
+            "Mask must be an array of booleans",
+            |_| true,
+            |n| n == 0.0 || n == 1.0,
+            |n| n == 1.0,
+        )?;
+        if !shape.starts_with(&mask.shape) {
+            return Err(env.error(format!(
+                "Cannot mask array of shape {} with mask of shape {}",
+                FormatShape(shape),
+                mask.format_shape()
+            )));
+        }
+        let cells = (mask.data.iter().enumerate())
+            .filter(|(_, &b)| b)
+            .map(|(i, _)| i)
+            .collect();
+        Ok((cells, mask.rank()))
+    }
+    fn scatter_cells(
+        self,
+        cells: &[usize],
+        frame_rank: usize,
+        into: Self,
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
+        self.generic_bin_into(
+            into,
+            |a, b| b.scatter_cells(cells, frame_rank, a, env).map(Into::into),
+            |a, b| b.scatter_cells(cells, frame_rank, a, env).map(Into::into),
+            |a, b| b.scatter_cells(cells, frame_rank, a, env).map(Into::into),
+            |a, b| b.scatter_cells(cells, frame_rank, a, env).map(Into::into),
+            |a, b| b.scatter_cells(cells, frame_rank, a, env).map(Into::into),
+            |a, b| b.scatter_cells(cells, frame_rank, a, env).map(Into::into),
+            |a, b| {
+                env.error(format!(
+                    "Cannot scatter {} into {}",
+                    a.type_name_plural(),
+                    b.type_name_plural()
+                ))
+            },
+        )
+    }
+}
+
+impl<T: ArrayValue> Array<T> {
+    /// Get the cells at the given indices of the array's first `frame_rank` axes
+    fn gather_cells(&self, cells: &[usize], frame_rank: usize) -> Self {
+        let cell_shape = &self.shape[frame_rank..];
+        let cell_len: usize = cell_shape.iter().product();
+        let mut data = EcoVec::with_capacity(cells.len() * cell_len);
+        for &cell in cells {
+            data.extend_from_slice(&self.data[cell * cell_len..(cell + 1) * cell_len]);
+        }
+        let mut shape = Shape::from([cells.len()].as_ref());
+        shape.extend_from_slice(cell_shape);
+        Array::new(shape, data)
+    }
+    /// Replace the cells at the given indices of the array's first `frame_rank` axes
+    ///
+    /// The values may either have a row for each cell or be a single cell.
+    fn scatter_cells(
+        mut self,
+        cells: &[usize],
+        frame_rank: usize,
+        values: Self,
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
+        let cell_shape = &self.shape[frame_rank..];
+        let cell_len: usize = cell_shape.iter().product();
+        let broadcast = values.shape == cell_shape;
+        if !broadcast
+            && (values.shape.first() != Some(&cells.len()) || values.shape[1..] != *cell_shape)
+        {
+            return Err(env.error(format!(
+                "Cannot scatter array of shape {} into {} cells of shape {}",
+                values.format_shape(),
+                cells.len(),
+                FormatShape(cell_shape)
+            )));
+        }
+        let data = self.data.as_mut_slice();
+        for (i, &cell) in cells.iter().enumerate() {
+            let start = if broadcast { 0 } else { i * cell_len };
+            data[cell * cell_len..(cell + 1) * cell_len]
+                .clone_from_slice(&values.data[start..start + cell_len]);
+        }
+        Ok(self)
+    }
+}
+
 impl Value {
     /// Use this value to `take` from another
     pub fn take(self, from: Self, env: &Uiua) -> UiuaResult<Self> {
@@ -1255,6 +1544,8 @@ impl Value {
             )?,
             #[cfg(feature = "complex")]
             Value::Complex(a) => Value::Complex(a.take(&index, env)?),
+            #[cfg(feature = "rational")]
+            Value::Rational(a) => Value::Rational(a.take(&index, env)?),
             Value::Char(a) => Value::Char(a.take(&index, env)?),
             Value::Box(a) => Value::Box(a.take(&index, env)?),
         })
@@ -1271,6 +1562,8 @@ impl Value {
             Value::Byte(a) => Value::Byte(a.drop(&index, env)?),
             #[cfg(feature = "complex")]
             Value::Complex(a) => Value::Complex(a.drop(&index, env)?),
+            #[cfg(feature = "rational")]
+            Value::Rational(a) => Value::Rational(a.drop(&index, env)?),
             Value::Char(a) => Value::Char(a.drop(&index, env)?),
             Value::Box(a) => Value::Box(a.drop(&index, env)?),
         })
@@ -1284,6 +1577,7 @@ impl Value {
             |a, b| a.untake(&index, b, env).map(Into::into),
             |a, b| a.untake(&index, b, env).map(Into::into),
             |a, b| a.untake(&index, b, env).map(Into::into),
+            |a, b| a.untake(&index, b, env).map(Into::into),
             |a, b| {
                 env.error(format!(
                     "Cannot untake {} into {}",
@@ -1302,6 +1596,7 @@ impl Value {
             |a, b| a.undrop(&index, b, env).map(Into::into),
             |a, b| a.undrop(&index, b, env).map(Into::into),
             |a, b| a.undrop(&index, b, env).map(Into::into),
+            |a, b| a.undrop(&index, b, env).map(Into::into),
             |a, b| {
                 env.error(format!(
                     "Cannot undrop {} into {}",
@@ -1316,12 +1611,16 @@ impl Value {
 impl<T: ArrayValue> Array<T> {
     /// `take` from this array
     pub fn take(mut self, index: &[isize], env: &Uiua) -> UiuaResult<Self> {
+        self.map_keys = None;
         Ok(match index {
             [] => self,
             &[taking] => {
                 let row_len = self.row_len();
                 let row_count = self.row_count();
                 let abs_taking = taking.unsigned_abs();
+                if abs_taking > row_count {
+                    env.validate_alloc(abs_taking.saturating_mul(row_len), size_of::<T>())?;
+                }
                 let mut filled = false;
                 self.data.modify(|data| {
                     if taking >= 0 {
@@ -1463,6 +1762,7 @@ impl<T: ArrayValue> Array<T> {
     }
     /// `drop` from this array
     pub fn drop(mut self, index: &[isize], env: &Uiua) -> UiuaResult<Self> {
+        self.map_keys = None;
         Ok(match index {
             [] => self,
             &[dropping] => {
@@ -1600,7 +1900,14 @@ impl<T: ArrayValue> Array<T> {
 
 impl Value {
     /// Use this value to `rotate` another
-    pub fn rotate(&self, mut rotated: Self, env: &Uiua) -> UiuaResult<Self> {
+    pub fn rotate(&self, rotated: Self, env: &Uiua) -> UiuaResult<Self> {
+        self.rotate_impl(rotated, false, env)
+    }
+    /// `shift` another value by this one, filling in the vacated elements
+    pub fn shift(&self, shifted: Self, env: &Uiua) -> UiuaResult<Self> {
+        self.rotate_impl(shifted, true, env)
+    }
+    fn rotate_impl(&self, mut rotated: Self, shift: bool, env: &Uiua) -> UiuaResult<Self> {
         let by = self.as_ints(env, "Rotation amount must be a list of integers")?;
         #[cfg(feature = "bytes")]
         if env.fill::<f64>().is_some() {
@@ -1609,13 +1916,15 @@ impl Value {
             }
         }
         match &mut rotated {
-            Value::Num(a) => a.rotate(&by, env)?,
+            Value::Num(a) => a.rotate(&by, shift, env)?,
             #[cfg(feature = "bytes")]
-            Value::Byte(a) => a.rotate(&by, env)?,
+            Value::Byte(a) => a.rotate(&by, shift, env)?,
             #[cfg(feature = "complex")]
-            Value::Complex(a) => a.rotate(&by, env)?,
-            Value::Char(a) => a.rotate(&by, env)?,
-            Value::Box(a) => a.rotate(&by, env)?,
+            Value::Complex(a) => a.rotate(&by, shift, env)?,
+            #[cfg(feature = "rational")]
+            Value::Rational(a) => a.rotate(&by, shift, env)?,
+            Value::Char(a) => a.rotate(&by, shift, env)?,
+            Value::Box(a) => a.rotate(&by, shift, env)?,
         }
         Ok(rotated)
     }
@@ -1623,78 +1932,118 @@ impl Value {
 
 impl<T: ArrayValue> Array<T> {
     /// `rotate` this array by the given amount
-    pub fn rotate(&mut self, by: &[isize], env: &Uiua) -> UiuaResult {
+    ///
+    /// If there is a fill value, or if this is a `shift`, elements are filled in instead of wrapping.
+    pub fn rotate(&mut self, by: &[isize], shift: bool, env: &Uiua) -> UiuaResult {
         if by.len() > self.rank() {
             return Err(env.error(format!(
-                "Cannot rotate rank {} array with index of length {}",
+                "Cannot {} rank {} array with index of length {}",
+                if shift { "shift" } else { "rotate" },
                 self.rank(),
                 by.len()
             )));
         }
-        let data = self.data.as_mut_slice();
-        rotate(by, &self.shape, data);
-        if let Some(fill) = env.fill::<T>() {
-            fill_shift(by, &self.shape, data, fill);
-        }
+        self.map_keys = None;
+        let fill = env.fill::<T>().or_else(|| shift.then(T::default_fill));
+        self.data = rotated(by, &self.shape, &self.data, fill.as_ref()).into();
         Ok(())
     }
 }
 
-fn rotate<T>(by: &[isize], shape: &[usize], data: &mut [T]) {
-    if by.is_empty() || shape.is_empty() {
-        return;
-    }
-    let row_count = shape[0];
-    if row_count == 0 {
-        return;
-    }
-    let row_len = shape[1..].iter().product();
-    let offset = by[0];
-    let mid = (row_count as isize + offset).rem_euclid(row_count as isize) as usize;
-    let (left, right) = data.split_at_mut(mid * row_len);
-    left.reverse();
-    right.reverse();
-    data.reverse();
-    let index = &by[1..];
-    let shape = &shape[1..];
-    if index.is_empty() || shape.is_empty() {
-        return;
-    }
-    for cell in data.chunks_mut(row_len) {
-        rotate(index, shape, cell);
-    }
+/// Rotate data along its first axes in a single copy
+///
+/// Rows past the last rotated axis are copied as contiguous blocks.
+/// If there is a fill value, rows that would wrap around are filled instead.
+fn rotated<T: Clone>(by: &[isize], shape: &[usize], data: &[T], fill: Option<&T>) -> EcoVec<T> {
+    let mut out = EcoVec::with_capacity(data.len());
+    rotated_into(by, shape, data, fill, &mut out);
+    out
 }
 
-fn fill_shift<T: Clone>(by: &[isize], shape: &[usize], data: &mut [T], fill: T) {
-    if by.is_empty() || shape.is_empty() {
-        return;
-    }
-    let row_count = shape[0];
-    if row_count == 0 {
+fn rotated_into<T: Clone>(
+    by: &[isize],
+    shape: &[usize],
+    data: &[T],
+    fill: Option<&T>,
+    out: &mut EcoVec<T>,
+) {
+    let (Some((&offset, by)), Some((&row_count, shape))) = (by.split_first(), shape.split_first())
+    else {
+        out.extend_from_slice(data);
         return;
-    }
-    let offset = by[0];
-    let row_len: usize = shape[1..].iter().product();
-    if offset != 0 {
-        let abs_offset = offset.unsigned_abs() * row_len;
-        let data_len = data.len();
-        if offset > 0 {
-            for val in &mut data[data_len.saturating_sub(abs_offset)..] {
-                *val = fill.clone();
-            }
-        } else {
-            for val in &mut data[..abs_offset] {
-                *val = fill.clone();
+    };
+    let row_len: usize = shape.iter().product();
+    // Offsets past the row count would wrap around or fill every row anyway
+    let n = row_count as isize;
+    let offset = match fill {
+        Some(_) => offset.clamp(-n, n),
+        None if n > 0 => offset.rem_euclid(n),
+        None => 0,
+    };
+    for i in 0..row_count {
+        let src = i as isize + offset;
+        let src = match fill {
+            Some(_) => (0..row_count as isize)
+                .contains(&src)
+                .then_some(src as usize),
+            None => Some(src.rem_euclid(row_count as isize) as usize),
+        };
+        match (src, fill) {
+            (Some(j), _) => {
+                let row = &data[j * row_len..(j + 1) * row_len];
+                rotated_into(by, shape, row, fill, out);
             }
+            (None, Some(fill)) => out.extend(repeat_n(fill.clone(), row_len)),
+            (None, None) => unreachable!("rows only go missing when filling"),
         }
     }
-    let index = &by[1..];
-    let shape = &shape[1..];
-    if index.is_empty() || shape.is_empty() {
-        return;
+}
+
+impl Value {
+    pub(crate) fn unfill_rotate(self, by: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
+        let by = by.as_ints(env, "Rotation amount must be a list of integers")?;
+        self.generic_bin_into(
+            into,
+            |a, b| a.unfill_rotate(&by, b, env).map(Into::into),
+            |a, b| a.unfill_rotate(&by, b, env).map(Into::into),
+            |a, b| a.unfill_rotate(&by, b, env).map(Into::into),
+            |a, b| a.unfill_rotate(&by, b, env).map(Into::into),
+            |a, b| a.unfill_rotate(&by, b, env).map(Into::into),
+            |a, b| a.unfill_rotate(&by, b, env).map(Into::into),
+            |a, b| {
+                env.error(format!(
+                    "Cannot unrotate {} into {}",
+                    a.type_name(),
+                    b.type_name()
+                ))
+            },
+        )
     }
-    for cell in data.chunks_mut(row_len) {
-        fill_shift(index, shape, cell, fill.clone());
+}
+
+impl<T: ArrayValue> Array<T> {
+    /// Rotate a filled rotation back, keeping the rows that were filled
+    fn unfill_rotate(mut self, by: &[isize], mut into: Self, env: &Uiua) -> UiuaResult<Self> {
+        if self.shape != into.shape {
+            return Err(env.error(format!(
+                "Rotated array's shape was changed from {} to {}, \
+                so the rotation cannot be inverted",
+                into.format_shape(),
+                self.format_shape()
+            )));
+        }
+        let unby: Vec<isize> = by.iter().map(|&i| i.saturating_neg()).collect();
+        // Mark which elements of the rotated array were not filled
+        let kept = rotated(by, &self.shape, &vec![true; self.data.len()], Some(&false));
+        let kept = rotated(&unby, &self.shape, &kept, None);
+        self.data = rotated(&unby, &self.shape, &self.data, None).into();
+        into.map_keys = None;
+        for ((into, new), kept) in into.data.as_mut_slice().iter_mut().zip(self.data).zip(kept) {
+            if kept {
+                *into = new;
+            }
+        }
+        Ok(into)
     }
 }
 
@@ -1712,6 +2061,8 @@ impl Value {
             )?,
             #[cfg(feature = "complex")]
             Value::Complex(a) => a.select_impl(indices_shape, &indices_data, env)?.into(),
+            #[cfg(feature = "rational")]
+            Value::Rational(a) => a.select_impl(indices_shape, &indices_data, env)?.into(),
             Value::Char(a) => a.select_impl(indices_shape, &indices_data, env)?.into(),
             Value::Box(a) => a.select_impl(indices_shape, &indices_data, env)?.into(),
         })
@@ -1730,6 +2081,7 @@ impl Value {
             |a, b| a.unselect_impl(ind_shape, &ind, b, env).map(Into::into),
             |a, b| a.unselect_impl(ind_shape, &ind, b, env).map(Into::into),
             |a, b| a.unselect_impl(ind_shape, &ind, b, env).map(Into::into),
+            |a, b| a.unselect_impl(ind_shape, &ind, b, env).map(Into::into),
             |a, b| {
                 env.error(format!(
                     "Cannot untake {} into {}",
@@ -1802,6 +2154,7 @@ impl<T: ArrayValue> Array<T> {
                             "Index {} is out of bounds of length {}",
                             i, row_count
                         ))
+                        .with_kind(ErrorKind::Index)
                         .fill());
                 }
                 ui
@@ -1817,6 +2170,7 @@ impl<T: ArrayValue> Array<T> {
                             "Index {} is out of bounds of length {}",
                             i, row_count
                         ))
+                        .with_kind(ErrorKind::Index)
                         .fill());
                 }
                 pos_i
@@ -1854,6 +2208,7 @@ impl<T: ArrayValue> Array<T> {
                             "Index {} is out of bounds of length {}",
                             i, into_row_count
                         ))
+                        .with_kind(ErrorKind::Index)
                         .fill());
                 }
                 ui
@@ -1865,6 +2220,7 @@ impl<T: ArrayValue> Array<T> {
                             "Index {} is out of bounds of length {}",
                             i, into_row_count
                         ))
+                        .with_kind(ErrorKind::Index)
                         .fill());
                 }
                 pos_i
@@ -1882,57 +2238,120 @@ impl<T: ArrayValue> Array<T> {
 impl Value {
     /// Use this array to `windows` another
     pub fn windows(&self, from: &Self, env: &Uiua) -> UiuaResult<Self> {
-        let size_spec = self.as_nats(env, "Window size must be a list of natural numbers")?;
+        let spec = self.as_number_array(
+            env,
+            "Window size must be a list of natural numbers \
+            or a table with a size, stride, and dilation for each axis",
+            |shape| shape.len() <= 1 || shape.len() == 2 && (1..=3).contains(&shape[1]),
+            |n| n.fract() == 0.0 && n >= 0.0,
+            |n| n as usize,
+        )?;
+        let (size_spec, strides, dilations) = if spec.rank() == 2 {
+            let params = spec.shape[1];
+            let column = |i: usize| {
+                (i < params).then(|| spec.data.iter().skip(i).step_by(params).copied().collect())
+            };
+            (column(0).unwrap(), column(1), column(2))
+        } else {
+            (spec.data.to_vec(), None, None)
+        };
         if size_spec.iter().any(|&s| s == 0) {
             return Err(env.error("Window size cannot be zero"));
         }
+        if (strides.iter().chain(&dilations).flatten()).any(|&s: &usize| s == 0) {
+            return Err(env.error("Window strides and dilations cannot be zero"));
+        }
+        let strides = strides.unwrap_or_else(|| vec![1; size_spec.len()]);
+        let dilations = dilations.unwrap_or_else(|| vec![1; size_spec.len()]);
+        let windows = Windows {
+            size: &size_spec,
+            strides: &strides,
+            dilations: &dilations,
+        };
         Ok(match from {
-            Value::Num(a) => a.windows(&size_spec, env)?.into(),
+            Value::Num(a) => a.windows(windows, env)?.into(),
+            #[cfg(feature = "bytes")]
+            Value::Byte(a) if env.fill::<f64>().is_some() => {
+                a.convert_ref::<f64>().windows(windows, env)?.into()
+            }
             #[cfg(feature = "bytes")]
-            Value::Byte(a) => a.windows(&size_spec, env)?.into(),
+            Value::Byte(a) => a.windows(windows, env)?.into(),
             #[cfg(feature = "complex")]
-            Value::Complex(a) => a.windows(&size_spec, env)?.into(),
-            Value::Char(a) => a.windows(&size_spec, env)?.into(),
-            Value::Box(a) => a.windows(&size_spec, env)?.into(),
+            Value::Complex(a) => a.windows(windows, env)?.into(),
+            #[cfg(feature = "rational")]
+            Value::Rational(a) => a.windows(windows, env)?.into(),
+            Value::Char(a) => a.windows(windows, env)?.into(),
+            Value::Box(a) => a.windows(windows, env)?.into(),
         })
     }
 }
 
+/// The size, strides, and dilations of the `windows` of an array
+#[derive(Clone, Copy)]
+pub struct Windows<'a> {
+    pub size: &'a [usize],
+    pub strides: &'a [usize],
+    pub dilations: &'a [usize],
+}
+
 impl<T: ArrayValue> Array<T> {
     /// Get the `windows` of this array
-    pub fn windows(&self, size_spec: &[usize], env: &Uiua) -> UiuaResult<Self> {
+    pub fn windows(&self, windows: Windows, env: &Uiua) -> UiuaResult<Self> {
+        let size_spec = windows.size;
         if size_spec.len() > self.shape.len() {
             return Err(env.error(format!(
                 "Window size {size_spec:?} has too many axes for shape {}",
                 self.format_shape()
             )));
         }
+        // Make a new window shape with the same rank as the windowed array
+        let mut true_size: Vec<usize> = Vec::with_capacity(self.shape.len());
+        true_size.extend(size_spec);
+        let mut strides = windows.strides.to_vec();
+        let mut dilations = windows.dilations.to_vec();
+        if true_size.len() < self.shape.len() {
+            true_size.extend(&self.shape[size_spec.len()..]);
+            strides.resize(self.shape.len(), 1);
+            dilations.resize(self.shape.len(), 1);
+        }
+        // The number of rows each window spans along each axis
+        //
+        // Axes that the size does not cover can have length 0
+        let spans: Vec<usize> = (true_size.iter().zip(&dilations))
+            .map(|(size, dilation)| size.saturating_sub(1) * dilation + 1)
+            .collect();
+        // Filling pads the array so that there is a window at each row
+        let padded;
+        let arr = if let Some(fill) = env.fill::<T>() {
+            padded = self.fill_pad(&spans[..size_spec.len()], fill, env)?;
+            &padded
+        } else {
+            self
+        };
         // Determine the shape of the windows array
         let mut new_shape = Shape::with_capacity(self.shape.len() + size_spec.len());
         new_shape.extend(
-            self.shape
-                .iter()
-                .zip(size_spec)
-                .map(|(a, b)| (a + 1).saturating_sub(*b)),
+            (arr.shape.iter().zip(&spans).zip(&strides))
+                .take(size_spec.len())
+                .map(|((len, span), stride)| {
+                    if len >= span {
+                        (len - span) / stride + 1
+                    } else {
+                        0
+                    }
+                }),
         );
         new_shape.extend_from_slice(size_spec);
-        new_shape.extend_from_slice(&self.shape[size_spec.len()..]);
+        new_shape.extend_from_slice(&arr.shape[size_spec.len()..]);
         // Check if the window size is too large
-        for (size, sh) in size_spec.iter().zip(&self.shape) {
-            if *size > *sh {
-                return Ok(Self::new(new_shape, CowSlice::new()));
-            }
-        }
-        // Make a new window shape with the same rank as the windowed array
-        let mut true_size: Vec<usize> = Vec::with_capacity(self.shape.len());
-        true_size.extend(size_spec);
-        if true_size.len() < self.shape.len() {
-            true_size.extend(&self.shape[true_size.len()..]);
+        if new_shape.contains(&0) {
+            return Ok(Self::new(new_shape, CowSlice::new()));
         }
+        env.validate_alloc(new_shape.iter().product(), size_of::<T>())?;
 
         let mut dst = EcoVec::new();
-        let mut corner = vec![0; self.shape.len()];
-        let mut curr = vec![0; self.shape.len()];
+        let mut corner = vec![0; arr.shape.len()];
+        let mut curr = vec![0; arr.shape.len()];
         'windows: loop {
             // Reset curr
             for i in curr.iter_mut() {
@@ -1943,11 +2362,14 @@ impl<T: ArrayValue> Array<T> {
                 // Copy the current item
                 let mut src_index = 0;
                 let mut stride = 1;
-                for ((c, i), s) in corner.iter().zip(&curr).zip(&self.shape).rev() {
-                    src_index += (*c + *i) * stride;
+                for (((c, i), s), d) in (corner.iter().zip(&curr).zip(&arr.shape))
+                    .zip(&dilations)
+                    .rev()
+                {
+                    src_index += (*c + *i * d) * stride;
                     stride *= s;
                 }
-                dst.push(self.data[src_index].clone());
+                dst.push(arr.data[src_index].clone());
                 // Go to the next item
                 for i in (0..curr.len()).rev() {
                     if curr[i] == true_size[i] - 1 {
@@ -1961,16 +2383,44 @@ impl<T: ArrayValue> Array<T> {
             }
             // Go to the next corner
             for i in (0..corner.len()).rev() {
-                if corner[i] == self.shape[i] - true_size[i] {
+                if corner[i] + spans[i] + strides[i] > arr.shape[i] {
                     corner[i] = 0;
                 } else {
-                    corner[i] += 1;
+                    corner[i] += strides[i];
                     continue 'windows;
                 }
             }
             break Ok(Array::new(new_shape, dst));
         }
     }
+    /// Pad the first axes of this array with a fill value so that windows with the given spans fit
+    ///
+    /// As with `convolve`, when the padding cannot be split evenly, the extra fill goes at the start.
+    fn fill_pad(&self, spans: &[usize], fill: T, env: &Uiua) -> UiuaResult<Self> {
+        let mut shape = self.shape.clone();
+        for (len, span) in shape.iter_mut().zip(spans) {
+            *len += span - 1;
+        }
+        env.validate_alloc(shape.iter().product(), size_of::<T>())?;
+        let mut data: EcoVec<T> = repeat_n(fill, shape.iter().product()).collect();
+        let slice = data.make_mut();
+        for (i, elem) in self.data.iter().enumerate() {
+            let mut rest = i;
+            let mut index = 0;
+            let mut stride = 1;
+            for axis in (0..shape.len()).rev() {
+                let mut pos = rest % self.shape[axis];
+                rest /= self.shape[axis];
+                if let Some(span) = spans.get(axis) {
+                    pos += span / 2;
+                }
+                index += pos * stride;
+                stride *= shape[axis];
+            }
+            slice[index] = elem.clone();
+        }
+        Ok(Array::new(shape, data))
+    }
 }
 
 impl Value {
@@ -2085,6 +2535,290 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+impl Value {
+    /// `replace` each occurence of one array in another with a third array
+    pub fn replace(self, replacement: Self, searched: Self, env: &Uiua) -> UiuaResult<Self> {
+        let (pattern_rows, starts) = match (&self, &searched) {
+            (Value::Num(a), Value::Num(b)) => a.replace_starts(b, env)?,
+            #[cfg(feature = "bytes")]
+            (Value::Byte(a), Value::Byte(b)) => a.replace_starts(b, env)?,
+            #[cfg(feature = "complex")]
+            (Value::Complex(a), Value::Complex(b)) => a.replace_starts(b, env)?,
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Rational(b)) => a.replace_starts(b, env)?,
+            (Value::Char(a), Value::Char(b)) => a.replace_starts(b, env)?,
+            (Value::Box(a), Value::Box(b)) => a.replace_starts(b, env)?,
+            #[cfg(feature = "bytes")]
+            (Value::Num(a), Value::Byte(b)) => a.replace_starts(&b.clone().convert(), env)?,
+            #[cfg(feature = "bytes")]
+            (Value::Byte(a), Value::Num(b)) => a.clone().convert().replace_starts(b, env)?,
+            (a, b) => {
+                return Err(env.error(format!(
+                    "Cannot replace {} in {} array",
+                    a.type_name(),
+                    b.type_name(),
+                )))
+            }
+        };
+        if starts.is_empty() {
+            return Ok(searched);
+        }
+        // A replacement with the rank of a row is a single row
+        let replacement_rows: Vec<Value> = if replacement.rank() + 1 == searched.rank() {
+            vec![replacement]
+        } else {
+            replacement.into_rows().collect()
+        };
+        let empty = searched.first_dim_zero();
+        let mut rows = Vec::new();
+        let mut starts = starts.into_iter().peekable();
+        let mut skip = 0;
+        for (i, row) in searched.into_rows().enumerate() {
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            if starts.next_if_eq(&i).is_some() {
+                rows.extend(replacement_rows.iter().cloned());
+                skip = pattern_rows - 1;
+            } else {
+                rows.push(row);
+            }
+        }
+        if rows.is_empty() {
+            return Ok(empty);
+        }
+        Value::from_row_values(rows, env)
+    }
+}
+
+impl<T: ArrayValue> Array<T> {
+    /// Get the row indices where non-overlapping occurences of this array start in another
+    ///
+    /// Rows are compared by hash first so that most mismatches are rejected without comparing every item.
+    fn replace_starts(&self, searched: &Self, env: &Uiua) -> UiuaResult<(usize, Vec<usize>)> {
+        let pattern_rows = if searched.rank() == 0 {
+            None
+        } else if self.rank() + 1 == searched.rank() && self.shape[..] == searched.shape[1..] {
+            Some(1)
+        } else if self.rank() == searched.rank() && self.shape[1..] == searched.shape[1..] {
+            Some(self.row_count())
+        } else {
+            None
+        };
+        let Some(pattern_rows) = pattern_rows else {
+            return Err(env.error(format!(
+                "Cannot replace array of shape {} in array of shape {}",
+                self.format_shape(),
+                searched.format_shape()
+            )));
+        };
+        if pattern_rows == 0 {
+            return Err(env.error("Cannot replace an empty array"));
+        }
+        let row_len = searched.row_len();
+        let row_hash = |row: &[T]| {
+            let mut hasher = DefaultHasher::new();
+            row.iter().for_each(|x| x.array_hash(&mut hasher));
+            hasher.finish()
+        };
+        let hashes: Vec<u64> = if row_len == 0 {
+            vec![0; searched.row_count()]
+        } else {
+            searched.data.chunks_exact(row_len).map(row_hash).collect()
+        };
+        let pattern_hashes: Vec<u64> = if row_len == 0 {
+            vec![0; pattern_rows]
+        } else {
+            self.data.chunks_exact(row_len).map(row_hash).collect()
+        };
+        let mut starts = Vec::new();
+        let mut i = 0;
+        while i + pattern_rows <= hashes.len() {
+            let found = hashes[i..i + pattern_rows] == pattern_hashes[..]
+                && (searched.data[i * row_len..(i + pattern_rows) * row_len].iter())
+                    .zip(&self.data)
+                    .all(|(a, b)| a.array_eq(b));
+            if found {
+                starts.push(i);
+                i += pattern_rows;
+            } else {
+                i += 1;
+            }
+        }
+        Ok((pattern_rows, starts))
+    }
+}
+
+impl Value {
+    /// `split` an array on this delimiter or list of boxed delimiters
+    ///
+    /// If `limit` is given, at most that many splits are made.
+    pub fn split(&self, searched: Self, limit: Option<usize>, env: &Uiua) -> UiuaResult<Self> {
+        if searched.rank() == 0 {
+            return Err(env.error("Cannot split a scalar"));
+        }
+        let delims: Vec<&Value> = match self {
+            Value::Box(arr) if arr.rank() == 1 && !matches!(searched, Value::Box(_)) => {
+                arr.data.iter().map(Boxed::as_value).collect()
+            }
+            delim => vec![delim],
+        };
+        fn convert<T: ArrayValue>(
+            delims: &[&Value],
+            searched: &Value,
+            f: impl Fn(&Value) -> Option<Array<T>>,
+            env: &Uiua,
+        ) -> UiuaResult<Vec<Array<T>>> {
+            (delims.iter())
+                .map(|&d| {
+                    f(d).ok_or_else(|| {
+                        env.error(format!(
+                            "Cannot split {} array on {} array",
+                            searched.type_name(),
+                            d.type_name()
+                        ))
+                    })
+                })
+                .collect()
+        }
+        let nums = |d: &Value| match d {
+            Value::Num(d) => Some(d.clone()),
+            #[cfg(feature = "bytes")]
+            Value::Byte(d) => Some(d.convert_ref()),
+            _ => None,
+        };
+        let parts: Vec<Value> = match &searched {
+            Value::Num(a) => {
+                let delims = convert(&delims, &searched, nums, env)?;
+                a.split(&delims, limit, env)?
+            }
+            #[cfg(feature = "bytes")]
+            Value::Byte(a) => {
+                if let Ok(delims) = convert(&delims, &searched, |d| d.as_byte_array().cloned(), env)
+                {
+                    a.split(&delims, limit, env)?
+                } else {
+                    let delims = convert(&delims, &searched, nums, env)?;
+                    a.convert_ref::<f64>().split(&delims, limit, env)?
+                }
+            }
+            #[cfg(feature = "complex")]
+            Value::Complex(a) => {
+                let delims = convert(
+                    &delims,
+                    &searched,
+                    |d| match d {
+                        Value::Complex(d) => Some(d.clone()),
+                        _ => None,
+                    },
+                    env,
+                )?;
+                a.split(&delims, limit, env)?
+            }
+            #[cfg(feature = "rational")]
+            Value::Rational(a) => {
+                let delims = convert(
+                    &delims,
+                    &searched,
+                    |d| match d {
+                        Value::Rational(d) => Some(d.clone()),
+                        _ => None,
+                    },
+                    env,
+                )?;
+                a.split(&delims, limit, env)?
+            }
+            Value::Char(a) => {
+                let delims = convert(
+                    &delims,
+                    &searched,
+                    |d| match d {
+                        Value::Char(d) => Some(d.clone()),
+                        _ => None,
+                    },
+                    env,
+                )?;
+                a.split(&delims, limit, env)?
+            }
+            Value::Box(a) => {
+                let delims = convert(
+                    &delims,
+                    &searched,
+                    |d| Some(d.coerce_as_boxes().into_owned()),
+                    env,
+                )?;
+                a.split(&delims, limit, env)?
+            }
+        };
+        Ok(Array::from_iter(parts.into_iter().map(Boxed)).into())
+    }
+}
+
+impl<T: ArrayValue> Array<T>
+where
+    Value: From<Array<T>>,
+{
+    /// Split this array on any of some delimiters
+    ///
+    /// At each position, the longest matching delimiter is used.
+    fn split(
+        &self,
+        delims: &[Array<T>],
+        limit: Option<usize>,
+        env: &Uiua,
+    ) -> UiuaResult<Vec<Value>> {
+        let row_len = self.row_len();
+        let mut patterns = Vec::with_capacity(delims.len());
+        for delim in delims {
+            let rows = if delim.rank() + 1 == self.rank() && delim.shape[..] == self.shape[1..] {
+                1
+            } else if delim.rank() == self.rank() && delim.shape[1..] == self.shape[1..] {
+                delim.row_count()
+            } else {
+                return Err(env.error(format!(
+                    "Cannot split array of shape {} on delimiter of shape {}",
+                    self.format_shape(),
+                    delim.format_shape()
+                )));
+            };
+            if rows == 0 {
+                return Err(env.error("Cannot split on an empty delimiter"));
+            }
+            patterns.push((rows, &delim.data[..]));
+        }
+        patterns.sort_by_key(|(rows, _)| Reverse(*rows));
+        let part = |start: usize, end: usize| {
+            let mut shape = self.shape.clone();
+            shape[0] = end - start;
+            Value::from(Array::new(
+                shape,
+                self.data.slice(start * row_len..end * row_len),
+            ))
+        };
+        let mut parts = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+        while i < self.row_count() && limit.is_none_or(|limit| parts.len() < limit) {
+            let found = patterns.iter().find(|(rows, pattern)| {
+                i + rows <= self.row_count()
+                    && (self.data[i * row_len..(i + rows) * row_len].iter())
+                        .zip(pattern.iter())
+                        .all(|(a, b)| a.array_eq(b))
+            });
+            if let Some(&(rows, _)) = found {
+                parts.push(part(start, i));
+                i += rows;
+                start = i;
+            } else {
+                i += 1;
+            }
+        }
+        parts.push(part(start, self.row_count()));
+        Ok(parts)
+    }
+}
+
 impl Value {
     /// Check which rows of this value are `member`s of another
     pub fn member(&self, of: &Self, env: &Uiua) -> UiuaResult<Self> {
@@ -2098,6 +2832,16 @@ impl Value {
             (Value::Num(a), Value::Byte(b)) => a.member(&b.convert_ref(), env)?.into(),
             #[cfg(feature = "bytes")]
             (Value::Byte(a), Value::Num(b)) => a.convert_ref().member(b, env)?.into(),
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Rational(b)) => a.member(b, env)?.into(),
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Num(b)) => a.member(&b.convert_ref(), env)?.into(),
+            #[cfg(feature = "rational")]
+            (Value::Num(a), Value::Rational(b)) => a.convert_ref().member(b, env)?.into(),
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Rational(a), Value::Byte(b)) => a.member(&b.convert_ref(), env)?.into(),
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Byte(a), Value::Rational(b)) => a.convert_ref().member(b, env)?.into(),
             (a, b) => {
                 return Err(env.error(format!(
                     "Cannot look for members of {} array in {} array",
@@ -2168,6 +2912,16 @@ impl Value {
             (Value::Num(a), Value::Byte(b)) => a.index_of(&b.clone().convert(), env)?.into(),
             #[cfg(feature = "bytes")]
             (Value::Byte(a), Value::Num(b)) => a.clone().convert().index_of(b, env)?.into(),
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Rational(b)) => a.index_of(b, env)?.into(),
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Num(b)) => a.index_of(&b.convert_ref(), env)?.into(),
+            #[cfg(feature = "rational")]
+            (Value::Num(a), Value::Rational(b)) => a.convert_ref().index_of(b, env)?.into(),
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Rational(a), Value::Byte(b)) => a.index_of(&b.convert_ref(), env)?.into(),
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Byte(a), Value::Rational(b)) => a.convert_ref().index_of(b, env)?.into(),
             (a, b) => {
                 return Err(env.error(format!(
                     "Cannot look for indices of {} in {}",
@@ -2243,3 +2997,651 @@ impl<T: ArrayValue> Array<T> {
         })
     }
 }
+
+impl Value {
+    /// Get the `bins` of the rows of this value in a sorted value
+    pub fn bins(&self, sorted: &Value, env: &Uiua) -> UiuaResult<Value> {
+        Ok(match (self, sorted) {
+            (Value::Num(a), Value::Num(b)) => a.bins(b, env)?.into(),
+            #[cfg(feature = "bytes")]
+            (Value::Byte(a), Value::Byte(b)) => a.bins(b, env)?.into(),
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Rational(b)) => a.bins(b, env)?.into(),
+            (Value::Char(a), Value::Char(b)) => a.bins(b, env)?.into(),
+            (Value::Box(a), Value::Box(b)) => a.bins(b, env)?.into(),
+            #[cfg(feature = "bytes")]
+            (Value::Num(a), Value::Byte(b)) => a.bins(&b.clone().convert(), env)?.into(),
+            #[cfg(feature = "bytes")]
+            (Value::Byte(a), Value::Num(b)) => a.clone().convert().bins(b, env)?.into(),
+            (a, b) => {
+                return Err(env.error(format!(
+                    "Cannot find bins of {} in {}",
+                    a.type_name(),
+                    b.type_name(),
+                )))
+            }
+        })
+    }
+}
+
+impl<T: ArrayValue> Array<T> {
+    /// Get the `bins` of the rows of this array in a sorted array
+    ///
+    /// Each bin is the number of rows of the sorted array that are less than or equal to the row.
+    pub fn bins(&self, sorted: &Array<T>, env: &Uiua) -> UiuaResult<Array<f64>> {
+        if sorted.rank() == 0 {
+            return Err(env.error("Cannot find bins in a scalar"));
+        }
+        let row_rank = sorted.rank() - 1;
+        if self.rank() < row_rank || self.shape[self.rank() - row_rank..] != sorted.shape[1..] {
+            return Err(env.error(format!(
+                "Cannot find bins of array of shape {} in array of shape {}",
+                self.format_shape(),
+                sorted.format_shape()
+            )));
+        }
+        let cmp = |a: &[T], b: &[T]| {
+            (a.iter().zip(b))
+                .map(|(a, b)| a.array_cmp(b))
+                .find(|o| o != &Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        };
+        let rows: Vec<&[T]> = sorted.row_slices().collect();
+        if rows
+            .windows(2)
+            .any(|w| cmp(w[0], w[1]) == Ordering::Greater)
+        {
+            return Err(env.error("Bins's searched-in array must be sorted in ascending order"));
+        }
+        let shape: Shape = self.shape[..self.rank() - row_rank]
+            .iter()
+            .copied()
+            .collect();
+        let row_len = sorted.row_len();
+        let data: EcoVec<f64> = if row_len == 0 {
+            repeat_n(rows.len() as f64, shape.iter().product()).collect()
+        } else {
+            (self.data.chunks_exact(row_len))
+                .map(|query| {
+                    rows.partition_point(|row| cmp(row, query) != Ordering::Greater) as f64
+                })
+                .collect()
+        };
+        Ok(Array::new(shape, data))
+    }
+}
+
+impl Value {
+    /// Get the `base` digits of the value
+    pub fn base(&self, of: &Self, env: &Uiua) -> UiuaResult<Self> {
+        // Negative numbers have negative digits
+        let signed = |digit: f64, n: f64| if n < 0.0 { 0.0 - digit } else { digit };
+        let nums = of.as_number_array(
+            env,
+            "Numbers to get the digits of must be finite",
+            |_| true,
+            f64::is_finite,
+            |n| n,
+        )?;
+        let digits = if self.rank() == 0 {
+            let base = self.as_num(env, "Base must be a number")?;
+            if base.is_nan() || base <= 1.0 {
+                return Err(env.error(format!("Base must be greater than 1, but it is {base}")));
+            }
+            let digits: Vec<Vec<f64>> = (nums.data.iter())
+                .map(|&n| {
+                    let mut m = n.abs();
+                    let mut digits = Vec::new();
+                    while m != 0.0 {
+                        digits.push(signed(m.rem_euclid(base), n));
+                        m = (m / base).floor();
+                    }
+                    digits
+                })
+                .collect();
+            let len = digits.iter().map(Vec::len).max().unwrap_or(0);
+            let mut data = EcoVec::with_capacity(digits.len() * len);
+            for digits in digits {
+                data.extend(digits.iter().copied().chain(repeat(0.0)).take(len));
+            }
+            let mut shape = nums.shape.clone();
+            shape.push(len);
+            Array::new(shape, data)
+        } else {
+            let bases = self.as_nums(env, "Base must be a number or list of numbers")?;
+            if let Some(base) = bases.iter().find(|&&b| b.is_nan() || b <= 0.0) {
+                return Err(env.error(format!("Bases must be positive, but one is {base}")));
+            }
+            let mut data = EcoVec::with_capacity(nums.element_count() * (bases.len() + 1));
+            for &n in &nums.data {
+                let mut m = n.abs();
+                for &base in &bases {
+                    data.push(signed(m.rem_euclid(base), n));
+                    m = (m / base).floor();
+                }
+                data.push(signed(m, n));
+            }
+            let mut shape = nums.shape.clone();
+            shape.push(bases.len() + 1);
+            Array::new(shape, data)
+        };
+        Ok(digits.into())
+    }
+    /// Turn `base` digits back into numbers
+    pub fn inv_base(&self, digits: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let digits = digits.as_number_array(
+            env,
+            "Digits must be a non-scalar array of numbers",
+            |shape| !shape.is_empty(),
+            |_| true,
+            |n| n,
+        )?;
+        let len = *digits.shape.last().unwrap();
+        let bases = if self.rank() == 0 {
+            vec![self.as_num(env, "Base must be a number")?; len.saturating_sub(1)]
+        } else {
+            let bases = self.as_nums(env, "Base must be a number or list of numbers")?;
+            if len > bases.len() + 1 {
+                return Err(env.error(format!(
+                    "{} bases can only have up to {} digits, but there are {len}",
+                    bases.len(),
+                    bases.len() + 1
+                )));
+            }
+            bases
+        };
+        let shape = Shape::from(&digits.shape[..digits.rank() - 1]);
+        let data: EcoVec<f64> = if len == 0 {
+            vec![0.0; shape.iter().product()].into()
+        } else {
+            (digits.data.chunks_exact(len))
+                .map(|digits| {
+                    (digits[..len - 1].iter().zip(&bases).rev())
+                        .fold(digits[len - 1], |n, (&digit, &base)| n * base + digit)
+                })
+                .collect()
+        };
+        Ok(Array::new(shape, data).into())
+    }
+}
+
+impl Value {
+    /// Pack the last axis of an array of bits into bytes, using this value as the bit order
+    pub fn pack_bits(&self, bits: &Self, env: &Uiua) -> UiuaResult<Array<u8>> {
+        let msb_first = self.bit_order(env)?;
+        let bits = bits.as_number_array(
+            env,
+            "Bits must be a non-scalar array of 0s and 1s",
+            |shape| !shape.is_empty(),
+            |n| n == 0.0 || n == 1.0,
+            |n| n == 1.0,
+        )?;
+        let len = *bits.shape.last().unwrap();
+        let mut shape = bits.shape.clone();
+        *shape.last_mut().unwrap() = len.div_ceil(8);
+        let mut data = EcoVec::with_capacity(shape.iter().product());
+        if len > 0 {
+            for row in bits.data.chunks_exact(len) {
+                for chunk in row.chunks(8) {
+                    let mut byte = 0u8;
+                    for (i, _) in chunk.iter().enumerate().filter(|(_, &bit)| bit) {
+                        byte |= if msb_first { 0x80 >> i } else { 1 << i };
+                    }
+                    data.push(byte);
+                }
+            }
+        }
+        Ok(Array::new(shape, data))
+    }
+    /// Unpack bytes into bits, using this value as the bit order
+    pub fn inv_pack_bits(&self, bytes: &Self, env: &Uiua) -> UiuaResult<Array<u8>> {
+        let msb_first = self.bit_order(env)?;
+        let bytes = bytes.as_number_array(
+            env,
+            "Packed bits must be a non-scalar array of bytes",
+            |shape| !shape.is_empty(),
+            |n| n.fract() == 0.0 && (0.0..256.0).contains(&n),
+            |n| n as u8,
+        )?;
+        let mut shape = bytes.shape.clone();
+        *shape.last_mut().unwrap() *= 8;
+        let mut data = EcoVec::with_capacity(bytes.data.len() * 8);
+        for &byte in &bytes.data {
+            for i in 0..8 {
+                let shift = if msb_first { 7 - i } else { i };
+                data.push((byte >> shift) & 1);
+            }
+        }
+        Ok(Array::new(shape, data))
+    }
+    fn bit_order(&self, env: &Uiua) -> UiuaResult<bool> {
+        match self.as_nat(env, "Bit order must be 0 or 1")? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(env.error("Bit order must be 0 or 1")),
+        }
+    }
+}
+
+impl Value {
+    /// Get `count` numbers starting at `start` and separated by `step`
+    pub fn steps(&self, count: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let nums = self.as_nums(env, "Start and step must be a pair of numbers")?;
+        let [start, step] = match nums.as_slice() {
+            &[start, step] if self.rank() == 1 => [start, step],
+            _ => {
+                return Err(env.error(format!(
+                    "Start and step must be a pair of numbers, but its shape is {}",
+                    self.format_shape()
+                )))
+            }
+        };
+        let count = count.as_nat(env, "Step count must be a natural number")?;
+        env.validate_alloc(count, size_of::<f64>())?;
+        // Starts and steps that are exactly short decimals are scaled to integers
+        // so that the error of each number is not larger than a single rounding
+        let scale = (0..16).map(|d| 10f64.powi(d)).find(|&scale| {
+            [start, step].iter().all(|&n| {
+                let scaled = (n * scale).round();
+                scaled.abs() < 1e15 && scaled / scale == n
+            })
+        });
+        Ok(if let Some(scale) = scale {
+            let (start, step) = ((start * scale).round(), (step * scale).round());
+            (0..count)
+                .map(|i| (start + i as f64 * step) / scale)
+                .collect()
+        } else {
+            (0..count).map(|i| start + i as f64 * step).collect()
+        })
+    }
+}
+
+/// A parsed number format specifier for `fmtnum`
+struct NumFormat {
+    align: char,
+    plus: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    scientific: bool,
+}
+
+impl NumFormat {
+    fn parse(spec: &str) -> Option<Self> {
+        let mut chars = spec.chars().peekable();
+        let align = chars.next_if(|c| "<>^".contains(*c)).unwrap_or('>');
+        let plus = chars.next_if_eq(&'+').is_some();
+        let zero = chars.next_if_eq(&'0').is_some();
+        let digits = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+            let mut s = String::new();
+            while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                s.push(c);
+            }
+            s
+        };
+        let width = digits(&mut chars);
+        let width = if width.is_empty() {
+            0
+        } else {
+            width.parse().ok()?
+        };
+        let precision = if chars.next_if_eq(&'.').is_some() {
+            Some(digits(&mut chars).parse().ok()?)
+        } else {
+            None
+        };
+        let scientific = chars.next_if_eq(&'e').is_some();
+        chars.next().is_none().then_some(NumFormat {
+            align,
+            plus,
+            zero,
+            width,
+            precision,
+            scientific,
+        })
+    }
+    fn format(&self, n: f64) -> String {
+        let sign = if n < 0.0 {
+            "¯"
+        } else if self.plus && !n.is_nan() {
+            "+"
+        } else {
+            ""
+        };
+        let abs = n.abs();
+        let digits = if n.is_nan() {
+            "NaN".into()
+        } else if abs.is_infinite() {
+            "∞".into()
+        } else {
+            match (self.scientific, self.precision) {
+                (true, Some(p)) => format!("{abs:.p$e}").replace('-', "¯"),
+                (true, None) => format!("{abs:e}").replace('-', "¯"),
+                (false, Some(p)) => format!("{abs:.p$}"),
+                (false, None) => abs.to_string(),
+            }
+        };
+        let len = sign.chars().count() + digits.chars().count();
+        if self.zero && abs.is_finite() && len < self.width {
+            format!("{sign}{}{digits}", "0".repeat(self.width - len))
+        } else {
+            format!("{sign}{digits}")
+        }
+    }
+}
+
+impl Value {
+    /// Format numbers as strings according to a format specifier
+    pub fn fmtnum(&self, nums: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let spec = self.as_string(env, "Number format must be a string")?;
+        let format = NumFormat::parse(&spec).ok_or_else(|| {
+            env.error(format!(
+                "Invalid number format {spec:?}. \
+                Formats look like [align][+][0][width][.precision][e]"
+            ))
+        })?;
+        let nums = nums.as_number_array(
+            env,
+            "Only numbers can be formatted",
+            |_| true,
+            |_| true,
+            |n| n,
+        )?;
+        let strings: Vec<String> = nums.data.iter().map(|&n| format.format(n)).collect();
+        let width = (strings.iter().map(|s| s.chars().count()))
+            .max()
+            .unwrap_or(0)
+            .max(format.width);
+        env.validate_alloc(strings.len() * width, size_of::<char>())?;
+        let mut data = EcoVec::with_capacity(strings.len() * width);
+        for s in &strings {
+            let padded = match format.align {
+                '<' => format!("{s:<width$}"),
+                '^' => format!("{s:^width$}"),
+                _ => format!("{s:>width$}"),
+            };
+            data.extend(padded.chars());
+        }
+        let mut shape = nums.shape;
+        shape.push(width);
+        Ok(Array::new(shape, data).into())
+    }
+}
+
+impl Value {
+    /// `convolve` this kernel with another array
+    pub fn convolve(&self, array: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let requirement = "Only numbers can be convolved";
+        let test_shape: fn(&[usize]) -> bool = |_| true;
+        let kernel = self.as_number_array(env, requirement, test_shape, |_| true, |n| n)?;
+        let array = array.as_number_array(env, requirement, test_shape, |_| true, |n| n)?;
+        Ok(kernel.convolve(array, env)?.into())
+    }
+}
+
+impl Array<f64> {
+    /// `convolve` this kernel with another array
+    pub fn convolve(&self, mut array: Self, env: &Uiua) -> UiuaResult<Self> {
+        if self.rank() > array.rank() {
+            return Err(env.error(format!(
+                "Cannot convolve an array of shape {} with a kernel of shape {} \
+                because the kernel has more axes",
+                array.format_shape(),
+                self.format_shape()
+            )));
+        }
+        if self.element_count() == 0 {
+            return Err(env.error(format!(
+                "Cannot convolve with an empty kernel of shape {}",
+                self.format_shape()
+            )));
+        }
+        if array.element_count() == 0 {
+            return Err(env.error(format!(
+                "Cannot convolve an empty array of shape {}",
+                array.format_shape()
+            )));
+        }
+        let scalar = array.rank() == 0;
+        if scalar {
+            array.shape.push(1);
+        }
+        // Axes past the kernel's are convolved independently
+        let rank = array.rank();
+        let mut kernel_shape: Vec<usize> = self.shape.to_vec();
+        kernel_shape.resize(rank, 1);
+        // Convolution is cross-correlation with a reversed kernel
+        let kernel: Vec<f64> = self.data.iter().rev().copied().collect();
+        // Filling pads the array so that the result has the same shape
+        if let Some(fill) = env.num_fill() {
+            let padded_shape: Shape = (array.shape.iter().zip(&kernel_shape))
+                .map(|(n, k)| n + k - 1)
+                .collect();
+            env.validate_alloc(padded_shape.iter().product(), size_of::<f64>())?;
+            let mut padded = vec![fill; padded_shape.iter().product()];
+            let row_len = array.shape[rank - 1];
+            let before_last = kernel_shape[rank - 1] / 2;
+            for (i, row) in array.data.chunks_exact(row_len).enumerate() {
+                let mut index = 0;
+                let mut rest = i;
+                for axis in (0..rank - 1).rev() {
+                    let pos = rest % array.shape[axis] + kernel_shape[axis] / 2;
+                    rest /= array.shape[axis];
+                    index += pos * padded_shape[axis + 1..].iter().product::<usize>();
+                }
+                let start = index + before_last;
+                padded[start..start + row_len].copy_from_slice(row);
+            }
+            array = Array::new(padded_shape, padded.as_slice());
+        }
+        let mut shape: Shape = (array.shape.iter().zip(&kernel_shape))
+            .map(|(n, k)| (n + 1).saturating_sub(*k))
+            .collect();
+        let len: usize = shape.iter().product();
+        env.validate_alloc(len, size_of::<f64>())?;
+        let mut data = vec![0.0; len];
+        if len > 0 {
+            let mut in_strides = vec![1; rank];
+            for axis in (0..rank - 1).rev() {
+                in_strides[axis] = in_strides[axis + 1] * array.shape[axis + 1];
+            }
+            let row_len = shape[rank - 1];
+            // Add each weighted shift of the array to the result, a row at a time
+            for (k, &weight) in kernel.iter().enumerate() {
+                if weight == 0.0 {
+                    continue;
+                }
+                let mut k_offset = 0;
+                let mut rest = k;
+                for axis in (0..rank).rev() {
+                    k_offset += rest % kernel_shape[axis] * in_strides[axis];
+                    rest /= kernel_shape[axis];
+                }
+                for (r, out_row) in data.chunks_exact_mut(row_len).enumerate() {
+                    let mut offset = k_offset;
+                    let mut rest = r;
+                    for axis in (0..rank - 1).rev() {
+                        offset += rest % shape[axis] * in_strides[axis];
+                        rest /= shape[axis];
+                    }
+                    let in_row = &array.data[offset..offset + row_len];
+                    for (o, i) in out_row.iter_mut().zip(in_row) {
+                        *o += weight * i;
+                    }
+                }
+            }
+        }
+        if scalar {
+            shape.pop();
+        }
+        Ok(Array::new(shape, data.as_slice()))
+    }
+}
+
+impl Value {
+    /// Contract arrays according to an `einsum` index specification
+    pub fn einsum(&self, operands: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let (inputs, output) = self.einsum_spec(env)?;
+        let requirement = "Einsum's operands must be numbers";
+        let as_nums =
+            |value: &Value| value.as_number_array(env, requirement, |_| true, |_| true, |n| n);
+        let operands = match operands {
+            Value::Box(arr) if arr.rank() == 1 => (arr.data.iter())
+                .map(|bx| as_nums(bx.as_value()))
+                .collect::<UiuaResult<Vec<_>>>()?,
+            value => vec![as_nums(value)?],
+        };
+        Ok(einsum(&inputs, &output, &operands, env)?.into())
+    }
+    /// Parse an `einsum` specification into index ids for each input and the output
+    fn einsum_spec(&self, env: &Uiua) -> UiuaResult<(Vec<Vec<usize>>, Vec<usize>)> {
+        let requirement = "Einsum's specification must be a string or a list of boxed index lists";
+        match self {
+            Value::Char(_) => {
+                let spec = self.as_string(env, requirement)?;
+                let mut labels = Vec::new();
+                let mut ids = |s: &str| -> Vec<usize> {
+                    (s.chars().filter(|c| !c.is_whitespace()))
+                        .map(|c| label_id(&mut labels, c))
+                        .collect()
+                };
+                let (ins, out) = match spec.split_once("->") {
+                    Some((ins, out)) => (ins, Some(out)),
+                    None => (spec.as_str(), None),
+                };
+                let inputs: Vec<Vec<usize>> = ins.split(',').map(&mut ids).collect();
+                let output = if let Some(out) = out {
+                    ids(out)
+                } else {
+                    // Without an output, indices that appear once are kept in alphabetical order
+                    let mut once: Vec<usize> = (0..labels.len())
+                        .filter(|&l| inputs.iter().flatten().filter(|&&i| i == l).count() == 1)
+                        .collect();
+                    once.sort_by_key(|&l| labels[l]);
+                    once
+                };
+                Ok((inputs, output))
+            }
+            Value::Box(arr) if arr.rank() == 1 && arr.row_count() > 0 => {
+                let mut labels = Vec::new();
+                let mut lists = Vec::with_capacity(arr.row_count());
+                for bx in &arr.data {
+                    let list = bx.as_value().as_ints(env, requirement)?;
+                    lists.push(list.into_iter().map(|l| label_id(&mut labels, l)).collect());
+                }
+                let output = lists.pop().unwrap();
+                Ok((lists, output))
+            }
+            _ => Err(env.error(requirement)),
+        }
+    }
+}
+
+/// Get the id of an index label, adding it if it is new
+fn label_id<T: PartialEq>(labels: &mut Vec<T>, label: T) -> usize {
+    labels.iter().position(|l| *l == label).unwrap_or_else(|| {
+        labels.push(label);
+        labels.len() - 1
+    })
+}
+
+/// Contract the operands in a single pass over all index combinations
+///
+/// Output indices are iterated outermost, so each output element is the sum over the contracted indices.
+/// Repeated indices within an operand take its diagonal.
+fn einsum(
+    inputs: &[Vec<usize>],
+    output: &[usize],
+    operands: &[Array<f64>],
+    env: &Uiua,
+) -> UiuaResult<Array<f64>> {
+    if inputs.len() != operands.len() {
+        return Err(env.error(format!(
+            "Einsum's specification has {} inputs, but there are {} operands",
+            inputs.len(),
+            operands.len()
+        )));
+    }
+    let label_count = (inputs.iter().flatten().chain(output).max()).map_or(0, |&l| l + 1);
+    let mut sizes = vec![None; label_count];
+    for (i, (labels, operand)) in inputs.iter().zip(operands).enumerate() {
+        if labels.len() != operand.rank() {
+            return Err(env.error(format!(
+                "Einsum operand {i} has shape {}, but its specification has {} indices",
+                operand.format_shape(),
+                labels.len()
+            )));
+        }
+        for (&label, &n) in labels.iter().zip(&operand.shape) {
+            match sizes[label] {
+                Some(size) if size != n => {
+                    return Err(env.error(format!(
+                        "Einsum index has length {size} in one place and {n} in another"
+                    )))
+                }
+                _ => sizes[label] = Some(n),
+            }
+        }
+    }
+    for (i, &label) in output.iter().enumerate() {
+        if sizes[label].is_none() {
+            return Err(env.error("Einsum's output has an index that is not in any input"));
+        }
+        if output[..i].contains(&label) {
+            return Err(env.error("Einsum's output has a repeated index"));
+        }
+    }
+    // Output axes come first, then the contracted ones
+    let order: Vec<usize> = (output.iter().copied())
+        .chain((0..label_count).filter(|l| !output.contains(l) && sizes[*l].is_some()))
+        .collect();
+    let dims: Vec<usize> = order.iter().map(|&l| sizes[l].unwrap()).collect();
+    let strides: Vec<Vec<usize>> = (inputs.iter().zip(operands))
+        .map(|(labels, operand)| {
+            let mut strides = vec![0; order.len()];
+            let mut stride = 1;
+            for (&label, &n) in labels.iter().zip(&operand.shape).rev() {
+                let axis = order.iter().position(|&l| l == label).unwrap();
+                strides[axis] += stride;
+                stride *= n;
+            }
+            strides
+        })
+        .collect();
+    let shape: Shape = dims[..output.len()].iter().copied().collect();
+    let outer: usize = shape.iter().product();
+    let inner: usize = dims[output.len()..].iter().product();
+    env.validate_alloc(outer, size_of::<f64>())?;
+    let mut data = EcoVec::with_capacity(outer);
+    if inner == 0 {
+        data.extend(repeat_n(0.0, outer));
+    } else {
+        let mut index = vec![0; dims.len()];
+        let mut offsets = vec![0; operands.len()];
+        for _ in 0..outer {
+            let mut sum = 0.0;
+            for _ in 0..inner {
+                sum += (operands.iter().zip(&offsets))
+                    .map(|(operand, &offset)| operand.data[offset])
+                    .product::<f64>();
+                // Advance to the next index combination
+                for axis in (0..dims.len()).rev() {
+                    index[axis] += 1;
+                    if index[axis] < dims[axis] {
+                        for (offset, strides) in offsets.iter_mut().zip(&strides) {
+                            *offset += strides[axis];
+                        }
+                        break;
+                    }
+                    index[axis] = 0;
+                    for (offset, strides) in offsets.iter_mut().zip(&strides) {
+                        *offset -= strides[axis] * (dims[axis] - 1);
+                    }
+                }
+            }
+            data.push(sum);
+        }
+    }
+    Ok(Array::new(shape, data))
+}
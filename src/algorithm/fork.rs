@@ -1,6 +1,8 @@
 //! Algorithms for forking modifiers
 
-use crate::{value::Value, Uiua, UiuaResult};
+use std::sync::Arc;
+
+use crate::{value::Value, ErrorKind, Function, Uiua, UiuaResult};
 
 pub fn both(env: &mut Uiua) -> UiuaResult {
     let f = env.pop_function()?;
@@ -205,3 +207,79 @@ pub fn iff(env: &mut Uiua) -> UiuaResult {
 
     Ok(())
 }
+
+/// Select the branch a switch should call
+///
+/// If the switch index is a list, a branch is called for each row and `None` is returned.
+pub fn switch_branch(count: usize, env: &mut Uiua) -> UiuaResult<Option<Arc<Function>>> {
+    let selector = env.pop("switch index")?;
+    if env.function_stack.len() < count {
+        return Err(env.error(
+            "Function stack was empty when getting switch function. \
+            This is a bug in the interpreter.",
+        ));
+    }
+    let functions: Vec<_> = (env.function_stack)
+        .drain(env.function_stack.len() - count..)
+        .collect();
+    if let Ok(i) = selector.as_nat(env, "") {
+        if i >= count {
+            return Err(env
+                .error(format!(
+                    "Switch index {i} is out of bounds for switch of size {count}"
+                ))
+                .with_kind(ErrorKind::Index));
+        }
+        return Ok(Some(functions[i].clone()));
+    }
+    // Call a branch for each row
+    let indices = selector.as_nats(
+        env,
+        "Switch index must be a natural number or list of natural numbers",
+    )?;
+    if let Some(i) = indices.iter().find(|&&i| i >= count) {
+        return Err(env
+            .error(format!(
+                "Switch index {i} is out of bounds for switch of size {count}"
+            ))
+            .with_kind(ErrorKind::Index));
+    }
+    let outputs = functions.first().map_or(0, |f| f.signature().outputs);
+    if let Some(f) = functions.iter().find(|f| f.signature().outputs != outputs) {
+        return Err(env.error(format!(
+            "Iterating switch's branches must all return the same number of values, \
+            but they return {outputs} and {}",
+            f.signature().outputs
+        )));
+    }
+    let arg_count = functions.iter().map(|f| f.signature().args).max();
+    let mut args = Vec::with_capacity(arg_count.unwrap_or(0));
+    for i in 0..arg_count.unwrap_or(0) {
+        let arg = env.pop(i + 2)?;
+        if arg.row_count() != indices.len() {
+            return Err(env.error(format!(
+                "Switch's index must have the same number of rows as its arguments, \
+                but it has {} rows and its argument has {} rows",
+                indices.len(),
+                arg.row_count()
+            )));
+        }
+        args.push(arg.into_rows().collect::<Vec<_>>().into_iter());
+    }
+    let mut new_rows = vec![Vec::with_capacity(indices.len()); outputs];
+    for i in indices {
+        let rows: Vec<Value> = args.iter_mut().map(|rows| rows.next().unwrap()).collect();
+        let f = &functions[i];
+        for row in rows.into_iter().take(f.signature().args).rev() {
+            env.push(row);
+        }
+        env.call(f.clone())?;
+        for rows in &mut new_rows {
+            rows.push(env.pop("switch branch result")?);
+        }
+    }
+    for rows in new_rows.into_iter().rev() {
+        env.push(Value::from_row_values(rows, env)?);
+    }
+    Ok(None)
+}
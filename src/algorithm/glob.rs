@@ -0,0 +1,138 @@
+//! Glob pattern matching
+
+use ecow::EcoVec;
+
+use crate::{array::Array, value::Value, Uiua, UiuaResult};
+
+impl Value {
+    /// Check which strings in an array match this `glob` pattern
+    pub fn glob(&self, target: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let pattern = self.as_string(env, "Pattern must be a string")?;
+        let glob = Glob::new(&pattern).map_err(|e| env.error(e))?;
+        let mut chars = Vec::new();
+        Ok(match target {
+            Value::Char(arr) if arr.rank() <= 1 => {
+                chars.extend(arr.data.iter().copied());
+                glob.matches(&chars).into()
+            }
+            Value::Char(arr) => {
+                let row_len = *arr.shape.last().unwrap();
+                let data: EcoVec<u8> = if row_len == 0 {
+                    let matches = glob.matches(&[]) as u8;
+                    EcoVec::from_iter(std::iter::repeat_n(matches, arr.element_count()))
+                } else {
+                    (arr.data.chunks_exact(row_len))
+                        .map(|row| glob.matches(row) as u8)
+                        .collect()
+                };
+                Array::new(&arr.shape[..arr.rank() - 1], data).into()
+            }
+            Value::Box(arr) => {
+                let mut data = EcoVec::with_capacity(arr.element_count());
+                for b in &arr.data {
+                    let s = b
+                        .as_value()
+                        .as_string(env, "Glob targets must be strings")?;
+                    chars.clear();
+                    chars.extend(s.chars());
+                    data.push(glob.matches(&chars) as u8);
+                }
+                Array::new(arr.shape.clone(), data).into()
+            }
+            value => {
+                return Err(env.error(format!(
+                    "Cannot match glob pattern against {}",
+                    value.type_name_plural()
+                )))
+            }
+        })
+    }
+}
+
+/// A compiled glob pattern
+struct Glob(Vec<GlobToken>);
+
+enum GlobToken {
+    /// A literal character
+    Char(char),
+    /// `?`, which matches any one character but `/`
+    Any,
+    /// `*`, which matches any characters but `/`
+    Star,
+    /// `**`, which matches any characters
+    DoubleStar,
+    /// `[...]`, which matches one character in or, if negated, not in some ranges
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Glob {
+    fn new(pattern: &str) -> Result<Self, String> {
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                '?' => GlobToken::Any,
+                '*' if chars.next_if_eq(&'*').is_some() => GlobToken::DoubleStar,
+                '*' => GlobToken::Star,
+                '\\' => GlobToken::Char(
+                    (chars.next()).ok_or("Invalid glob pattern: trailing backslash")?,
+                ),
+                '[' => {
+                    let negated = chars.next_if(|&c| c == '!' || c == '^').is_some();
+                    let mut ranges = Vec::new();
+                    let mut first = true;
+                    loop {
+                        let c = match chars.next() {
+                            Some(']') if !first => break,
+                            Some('\\') => chars.next(),
+                            c => c,
+                        }
+                        .ok_or("Invalid glob pattern: unclosed [")?;
+                        first = false;
+                        if chars.peek() == Some(&'-') {
+                            let mut lookahead = chars.clone();
+                            lookahead.next();
+                            if let Some(end) = lookahead.next().filter(|&end| end != ']') {
+                                chars = lookahead;
+                                ranges.push((c, end));
+                                continue;
+                            }
+                        }
+                        ranges.push((c, c));
+                    }
+                    GlobToken::Class { negated, ranges }
+                }
+                c => GlobToken::Char(c),
+            });
+        }
+        Ok(Glob(tokens))
+    }
+    fn matches(&self, text: &[char]) -> bool {
+        // `next[j]` is whether the tokens after the current one match `text[j..]`
+        let n = text.len();
+        let mut next = vec![false; n + 1];
+        next[n] = true;
+        let mut curr = vec![false; n + 1];
+        for token in self.0.iter().rev() {
+            for j in (0..=n).rev() {
+                let c = text.get(j).copied();
+                curr[j] = match token {
+                    GlobToken::Char(t) => c == Some(*t) && next[j + 1],
+                    GlobToken::Any => c.is_some_and(|c| c != '/') && next[j + 1],
+                    GlobToken::Star => next[j] || c.is_some_and(|c| c != '/') && curr[j + 1],
+                    GlobToken::DoubleStar => next[j] || c.is_some() && curr[j + 1],
+                    GlobToken::Class { negated, ranges } => {
+                        c.is_some_and(|c| {
+                            ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c)) != *negated
+                        }) && next[j + 1]
+                    }
+                };
+            }
+            std::mem::swap(&mut curr, &mut next);
+        }
+        next[0]
+    }
+}
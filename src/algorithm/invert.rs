@@ -41,6 +41,8 @@ fn prim_inverse(prim: Primitive, span: usize) -> Option<Instr> {
         Sin => Instr::ImplPrim(Asin, span),
         Atan => Instr::ImplPrim(InvAtan, span),
         Complex => Instr::ImplPrim(InvComplex, span),
+        Rational => Instr::ImplPrim(InvRational, span),
+        Map => Instr::ImplPrim(InvMap, span),
         Reverse => Instr::Prim(Reverse, span),
         Transpose => Instr::ImplPrim(InvTranspose, span),
         Bits => Instr::ImplPrim(InverseBits, span),
@@ -50,6 +52,11 @@ fn prim_inverse(prim: Primitive, span: usize) -> Option<Instr> {
         Unbox => Instr::Prim(Box, span),
         Where => Instr::ImplPrim(InvWhere, span),
         Utf => Instr::ImplPrim(InvUtf, span),
+        Utf16 => Instr::ImplPrim(InvUtf16, span),
+        Latin1 => Instr::ImplPrim(InvLatin1, span),
+        DateTime => Instr::ImplPrim(InvDateTime, span),
+        Fft => Instr::ImplPrim(InvFft, span),
+        Conjugate => Instr::Prim(Conjugate, span),
         _ => return None,
     })
 }
@@ -66,8 +73,14 @@ fn impl_prim_inverse(prim: ImplPrimitive, span: usize) -> Option<Instr> {
         InvTrace => Instr::Prim(Trace, span),
         InvWhere => Instr::Prim(Where, span),
         InvUtf => Instr::Prim(Utf, span),
+        InvUtf16 => Instr::Prim(Utf16, span),
+        InvLatin1 => Instr::Prim(Latin1, span),
+        InvDateTime => Instr::Prim(DateTime, span),
+        InvFft => Instr::Prim(Fft, span),
         InvAtan => Instr::Prim(Atan, span),
         InvComplex => Instr::Prim(Complex, span),
+        InvRational => Instr::Prim(Rational, span),
+        InvMap => Instr::Prim(Map, span),
         InvCouple => Instr::Prim(Couple, span),
         _ => return None,
     })
@@ -103,7 +116,7 @@ fn invert_instr_impl(mut instrs: &[Instr]) -> Option<Vec<Instr>> {
                 return Some(inv);
             }
         }
-        [PushFunc(val)] => {
+        [PushFunc(val, _)] => {
             if let Some((prim, span)) = val.as_primitive() {
                 return Some(vec![prim_inverse(prim, span)?]);
             }
@@ -116,6 +129,8 @@ fn invert_instr_impl(mut instrs: &[Instr]) -> Option<Vec<Instr>> {
 
     let patterns: &[&dyn InvertPattern] = &[
         &invert_invert_pattern,
+        &invert_set_inverse_pattern,
+        &invert_format_pattern,
         &(Val, ([Rotate], [Neg, Rotate])),
         &([Rotate], [Neg, Rotate]),
         &([Sqrt], [2.i(), Pow.i()]),
@@ -129,6 +144,8 @@ fn invert_instr_impl(mut instrs: &[Instr]) -> Option<Vec<Instr>> {
         &([Dup, Mul], [Sqrt]),
         &(Val, ([Pow], [1.i(), Flip.i(), Div.i(), Pow.i()])),
         &(Val, ([Log], [Flip, Pow])),
+        &(Val, ([Base], [ImplPrimitive::InvBase.i()])),
+        &(Val, ([PackBits], [ImplPrimitive::InvPackBits.i()])),
     ];
 
     let mut inverted = Vec::new();
@@ -219,6 +236,7 @@ fn under_instrs_impl(instrs: &[Instr], g_sig: Signature) -> Option<(Vec<Instr>,
     }
 
     let patterns: &[&dyn UnderPattern] = &[
+        &UnderPatternFn(under_set_under_pattern),
         &UnderPatternFn(under_both_pattern),
         &UnderPatternFn(under_rows_pattern),
         &UnderPatternFn(under_each_pattern),
@@ -232,6 +250,8 @@ fn under_instrs_impl(instrs: &[Instr], g_sig: Signature) -> Option<(Vec<Instr>,
         &bin!(Sub, Add),
         &bin!(Mul, Div),
         &bin!(Div, Mul),
+        &bin!(Base, InvBase),
+        &bin!(PackBits, InvPackBits),
         &(
             [Flip, Pow],
             [Dup.i(), PushTempN(1).i(), Flip.i(), Pow.i()],
@@ -260,6 +280,8 @@ fn under_instrs_impl(instrs: &[Instr], g_sig: Signature) -> Option<(Vec<Instr>,
         &stash2!(Select, Unselect),
         &(Val, stash2!(Pick, Unpick)),
         &stash2!(Pick, Unpick),
+        &(Val, stash2!(Masked, Unmasked)),
+        &stash2!(Masked, Unmasked),
         &(
             Val,
             (
@@ -273,11 +295,14 @@ fn under_instrs_impl(instrs: &[Instr], g_sig: Signature) -> Option<(Vec<Instr>,
             [Over.i(), Over.i(), PushTempN(2).i(), Keep.i()],
             [PopTempN(1).i(), Flip.i(), PopTempN(1).i(), Unkeep.i()],
         ),
+        &UnderPatternFn(under_fill_rotate_pattern),
         &(
             [Rotate],
             [Dup.i(), PushTempN(1).i(), Rotate.i()],
             [PopTempN(1).i(), Neg.i(), Rotate.i()],
         ),
+        &(Val, stash2!(Shift, UnfillRotate)),
+        &stash2!(Shift, UnfillRotate),
         &(
             [First],
             [Dup.i(), PushTempN(1).i(), First.i()],
@@ -298,6 +323,11 @@ fn under_instrs_impl(instrs: &[Instr], g_sig: Signature) -> Option<(Vec<Instr>,
             [Dup.i(), Shape.i(), PushTempN(1).i(), Deshape.i()],
             [PopTempN(1).i(), Reshape.i()],
         ),
+        &(
+            [Where],
+            [Dup.i(), Shape.i(), PushTempN(1).i(), Where.i()],
+            [PopTempN(1).i(), Unwhere.i()],
+        ),
         &(
             [Now],
             [Now.i(), PushTempN(1).i()],
@@ -313,6 +343,11 @@ fn under_instrs_impl(instrs: &[Instr], g_sig: Signature) -> Option<(Vec<Instr>,
             [Sys(SysOp::FCreate).i(), Dup.i(), PushTempN(1).i()],
             [PopTempN(1).i(), Sys(SysOp::Close).i()],
         ),
+        &(
+            [Sys(SysOp::TcpListen)],
+            [Sys(SysOp::TcpListen).i(), Dup.i(), PushTempN(1).i()],
+            [PopTempN(1).i(), Sys(SysOp::Close).i()],
+        ),
         &(
             [Sys(SysOp::TcpConnect)],
             [Sys(SysOp::TcpConnect).i(), Dup.i(), PushTempN(1).i()],
@@ -323,6 +358,11 @@ fn under_instrs_impl(instrs: &[Instr], g_sig: Signature) -> Option<(Vec<Instr>,
             [Sys(SysOp::TcpAccept).i(), Dup.i(), PushTempN(1).i()],
             [PopTempN(1).i(), Sys(SysOp::Close).i()],
         ),
+        &(
+            [Sys(SysOp::UdpBind)],
+            [Sys(SysOp::UdpBind).i(), Dup.i(), PushTempN(1).i()],
+            [PopTempN(1).i(), Sys(SysOp::Close).i()],
+        ),
         &([Rock], [Rock], [1.i(), Drop.i()]),
         &([Surface], [Surface], [1.i(), Drop.i()]),
         &([Deep], [Deep], [1.i(), Drop.i()]),
@@ -427,8 +467,8 @@ impl AsInstr for PopTempN {
 }
 
 impl AsInstr for i32 {
-    fn as_instr(&self, _: usize) -> Instr {
-        Instr::push(Value::from(*self))
+    fn as_instr(&self, span: usize) -> Instr {
+        Instr::push(Value::from(*self), span)
     }
 }
 
@@ -471,12 +511,73 @@ trait UnderPattern: fmt::Debug {
 }
 
 fn invert_invert_pattern(input: &[Instr]) -> Option<(&[Instr], Vec<Instr>)> {
-    let [Instr::PushFunc(func), Instr::Prim(Primitive::Invert, _), input @ ..] = input else {
+    let [Instr::PushFunc(func, _), Instr::Prim(Primitive::Invert, _), input @ ..] = input else {
         return None;
     };
     Some((input, func.instrs.clone()))
 }
 
+fn invert_set_inverse_pattern(input: &[Instr]) -> Option<(&[Instr], Vec<Instr>)> {
+    let [Instr::PushFunc(inv, inv_span), Instr::PushFunc(f, f_span), Instr::Prim(Primitive::SetInverse, span), input @ ..] =
+        input
+    else {
+        return None;
+    };
+    let inverted = vec![
+        Instr::PushFunc(f.clone(), *f_span),
+        Instr::PushFunc(inv.clone(), *inv_span),
+        Instr::Prim(Primitive::SetInverse, *span),
+    ];
+    Some((input, inverted))
+}
+
+fn invert_format_pattern(input: &[Instr]) -> Option<(&[Instr], Vec<Instr>)> {
+    let (instr, input) = input.split_first()?;
+    let inverted = match instr {
+        Instr::Format { parts, span } => Instr::MatchFormatPattern {
+            parts: parts.clone(),
+            span: *span,
+        },
+        Instr::MatchFormatPattern { parts, span } => Instr::Format {
+            parts: parts.clone(),
+            span: *span,
+        },
+        _ => return None,
+    };
+    Some((input, vec![inverted]))
+}
+
+fn under_set_under_pattern(input: &[Instr], _: Signature) -> Option<(&[Instr], Under)> {
+    let [Instr::PushFunc(after, _), Instr::PushFunc(before, _), Instr::PushFunc(..), Instr::Prim(Primitive::SetUnder, span), input @ ..] =
+        input
+    else {
+        return None;
+    };
+    let span = *span;
+    // Values below the first output of the before function are kept for the after function
+    let kept = before.signature().outputs.checked_sub(1)?;
+    let mut befores = before.instrs.clone();
+    let mut afters = Vec::new();
+    if kept > 0 {
+        befores.extend([
+            Instr::PushTemp {
+                stack: TempStack::Inline,
+                count: 1,
+                span,
+            },
+            PushTempN(kept).as_instr(span),
+            Instr::PopTemp {
+                stack: TempStack::Inline,
+                count: 1,
+                span,
+            },
+        ]);
+        afters.push(PopTempN(kept).as_instr(span));
+    }
+    afters.extend(after.instrs.iter().cloned());
+    Some((input, (befores, afters)))
+}
+
 fn under_from_inverse_pattern(input: &[Instr], _: Signature) -> Option<(&[Instr], Under)> {
     if input.is_empty() {
         return None;
@@ -544,7 +645,8 @@ fn under_temp_pattern(input: &[Instr], _: Signature) -> Option<(&[Instr], Under)
 }
 
 fn under_both_pattern(input: &[Instr], g_sig: Signature) -> Option<(&[Instr], Under)> {
-    let [Instr::PushFunc(func), Instr::Prim(Primitive::Both, span), input @ ..] = input else {
+    let [Instr::PushFunc(func, func_span), Instr::Prim(Primitive::Both, span), input @ ..] = input
+    else {
         return None;
     };
     let (befores, afters) = under_instrs(&func.instrs, g_sig)?;
@@ -552,7 +654,7 @@ fn under_both_pattern(input: &[Instr], g_sig: Signature) -> Option<(&[Instr], Un
         (2, 1) => {
             let before_func = Function::new(func.id.clone(), befores, func.signature());
             let befores = vec![
-                Instr::push_func(before_func),
+                Instr::push_func(before_func, *func_span),
                 Instr::Prim(Primitive::Both, *span),
             ];
             (befores, afters)
@@ -561,11 +663,11 @@ fn under_both_pattern(input: &[Instr], g_sig: Signature) -> Option<(&[Instr], Un
             let before_func = Function::new(func.id.clone(), befores, func.signature());
             let after_func = Function::new(func.id.clone(), afters, func.signature());
             let befores = vec![
-                Instr::push_func(before_func),
+                Instr::push_func(before_func, *func_span),
                 Instr::Prim(Primitive::Both, *span),
             ];
             let afters = vec![
-                Instr::push_func(after_func),
+                Instr::push_func(after_func, *func_span),
                 Instr::Prim(Primitive::Both, *span),
             ];
             (befores, afters)
@@ -576,41 +678,86 @@ fn under_both_pattern(input: &[Instr], g_sig: Signature) -> Option<(&[Instr], Un
 }
 
 fn under_each_pattern(input: &[Instr], g_sig: Signature) -> Option<(&[Instr], Under)> {
-    let &[Instr::PushFunc(ref f), Instr::Prim(Primitive::Each, span), ref input @ ..] = input
+    let &[Instr::PushFunc(ref f, f_span), Instr::Prim(Primitive::Each, span), ref input @ ..] =
+        input
     else {
         return None;
     };
     let (f_before, f_after) = f.under(g_sig)?;
     let befores = vec![
-        Instr::PushFunc(f_before.into()),
+        Instr::PushFunc(f_before.into(), f_span),
         Instr::Prim(Primitive::Each, span),
     ];
     let afters = vec![
-        Instr::PushFunc(f_after.into()),
+        Instr::PushFunc(f_after.into(), f_span),
         Instr::Prim(Primitive::Each, span),
     ];
     Some((input, (befores, afters)))
 }
 
 fn under_rows_pattern(input: &[Instr], g_sig: Signature) -> Option<(&[Instr], Under)> {
-    let &[Instr::PushFunc(ref f), Instr::Prim(Primitive::Rows, span), ref input @ ..] = input
+    let &[Instr::PushFunc(ref f, f_span), Instr::Prim(Primitive::Rows, span), ref input @ ..] =
+        input
     else {
         return None;
     };
     let (f_before, f_after) = f.under(g_sig)?;
     let befores = vec![
-        Instr::PushFunc(f_before.into()),
+        Instr::PushFunc(f_before.into(), f_span),
         Instr::Prim(Primitive::Rows, span),
     ];
     let afters = vec![
-        Instr::PushFunc(f_after.into()),
+        Instr::PushFunc(f_after.into(), f_span),
         Instr::Prim(Primitive::Rows, span),
     ];
     Some((input, (befores, afters)))
 }
 
+fn under_fill_rotate_pattern(input: &[Instr], _: Signature) -> Option<(&[Instr], Under)> {
+    // The rotation amount may be pushed before the filled function
+    let start = (0..input.len()).find(|&i| {
+        matches!(
+            input[i..],
+            [
+                Instr::PushFunc(..),
+                Instr::PushFunc(..),
+                Instr::Prim(Primitive::Fill, _),
+                ..
+            ]
+        )
+    })?;
+    let (val, input) = input.split_at(start);
+    if !val.is_empty() && instrs_signature(val).ok()? != Signature::new(0, 1) {
+        return None;
+    }
+    let [Instr::PushFunc(f, f_span), Instr::PushFunc(fill, fill_span), Instr::Prim(Primitive::Fill, span), input @ ..] =
+        input
+    else {
+        return None;
+    };
+    let [Instr::Prim(Primitive::Rotate, _)] = f.instrs.as_slice() else {
+        return None;
+    };
+    let span = *span;
+    let mut befores = val.to_vec();
+    befores.extend([
+        Instr::Prim(Primitive::Over, span),
+        Instr::Prim(Primitive::Over, span),
+        PushTempN(2).as_instr(span),
+        Instr::PushFunc(f.clone(), *f_span),
+        Instr::PushFunc(fill.clone(), *fill_span),
+        Instr::Prim(Primitive::Fill, span),
+    ]);
+    let afters = vec![
+        PopTempN(2).as_instr(span),
+        Instr::ImplPrim(ImplPrimitive::UnfillRotate, span),
+    ];
+    Some((input, (befores, afters)))
+}
+
 fn under_partition_pattern(input: &[Instr], g_sig: Signature) -> Option<(&[Instr], Under)> {
-    let &[Instr::PushFunc(ref f), Instr::Prim(Primitive::Partition, span), ref input @ ..] = input
+    let &[Instr::PushFunc(ref f, f_span), Instr::Prim(Primitive::Partition, span), ref input @ ..] =
+        input
     else {
         return None;
     };
@@ -623,18 +770,19 @@ fn under_partition_pattern(input: &[Instr], g_sig: Signature) -> Option<(&[Instr
             count: 2,
             span,
         },
-        Instr::PushFunc(f_before.into()),
+        Instr::PushFunc(f_before.into(), f_span),
         Instr::Prim(Primitive::Partition, span),
     ];
     let afters = vec![
-        Instr::PushFunc(f_after.into()),
+        Instr::PushFunc(f_after.into(), f_span),
         Instr::ImplPrim(ImplPrimitive::Unpartition, span),
     ];
     Some((input, (befores, afters)))
 }
 
 fn under_group_pattern(input: &[Instr], g_sig: Signature) -> Option<(&[Instr], Under)> {
-    let &[Instr::PushFunc(ref f), Instr::Prim(Primitive::Group, span), ref input @ ..] = input
+    let &[Instr::PushFunc(ref f, f_span), Instr::Prim(Primitive::Group, span), ref input @ ..] =
+        input
     else {
         return None;
     };
@@ -647,11 +795,11 @@ fn under_group_pattern(input: &[Instr], g_sig: Signature) -> Option<(&[Instr], U
             count: 2,
             span,
         },
-        Instr::PushFunc(f_before.into()),
+        Instr::PushFunc(f_before.into(), f_span),
         Instr::Prim(Primitive::Group, span),
     ];
     let afters = vec![
-        Instr::PushFunc(f_after.into()),
+        Instr::PushFunc(f_after.into(), f_span),
         Instr::ImplPrim(ImplPrimitive::Ungroup, span),
     ];
     Some((input, (befores, afters)))
@@ -897,13 +1045,13 @@ impl InvertPattern for Val {
             }
         }
         match input.get(0) {
-            Some(instr @ Instr::Push(_)) => Some((&input[1..], vec![instr.clone()])),
+            Some(instr @ Instr::Push(..)) => Some((&input[1..], vec![instr.clone()])),
             Some(instr @ Instr::Prim(prim, _))
                 if prim.args() == Some(0) && prim.outputs() == Some(0) =>
             {
                 Some((&input[1..], vec![instr.clone()]))
             }
-            Some(Instr::BeginArray) => {
+            Some(Instr::BeginArray(_)) => {
                 let mut depth = 1;
                 let mut i = 1;
                 loop {
@@ -912,7 +1060,7 @@ impl InvertPattern for Val {
                         if depth == 0 {
                             break;
                         }
-                    } else if let Instr::BeginArray = input.get(i)? {
+                    } else if let Instr::BeginArray(_) = input.get(i)? {
                         depth += 1;
                     }
                     i += 1;
@@ -1,7 +1,16 @@
 //! Algorithms for looping modifiers
 
+use std::{
+    collections::HashMap,
+    mem::{size_of, swap},
+    sync::Arc,
+};
+
 use crate::{
-    array::{Array, ArrayValue},
+    algorithm::map::normalize_bytes,
+    array::{Array, ArrayValue, Shape},
+    cowslice::CowSlice,
+    function::Function,
     value::Value,
     ExactDoubleIterator, Signature, Uiua, UiuaResult,
 };
@@ -36,6 +45,21 @@ pub(crate) fn rank_list(name: &str, env: &mut Uiua) -> UiuaResult<Vec<Option<isi
     env.pop("rank list")?.as_rank_list(env, "")
 }
 
+/// Pop the arguments for a rank list, and get the depth at which to split each one
+pub(crate) fn ranked_args(
+    ns: &[Option<isize>],
+    env: &mut Uiua,
+) -> UiuaResult<(Vec<Value>, Vec<usize>)> {
+    let mut args = Vec::with_capacity(ns.len());
+    for i in 0..ns.len() {
+        args.push(env.pop(i + 1)?);
+    }
+    let depths = (ns.iter().zip(&args))
+        .map(|(&n, arg)| rank_to_depth(n, arg.rank()))
+        .collect();
+    Ok((args, depths))
+}
+
 pub fn repeat(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     let f = env.pop_function()?;
@@ -73,6 +97,286 @@ pub fn repeat(env: &mut Uiua) -> UiuaResult {
     Ok(())
 }
 
+pub fn stencil(env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    let f = env.pop_function()?;
+    let sig = f.signature();
+    if sig != (1, 1) {
+        return Err(env.error(format!(
+            "Stencil's function's signature must be |1.1, but it is {sig}"
+        )));
+    }
+    let radius = env.pop(1)?;
+    let xs = env.pop(2)?;
+    let radius = if radius.rank() == 0 {
+        let r = radius.as_nat(env, "Stencil radius must be a natural number")?;
+        vec![r; xs.rank()]
+    } else {
+        radius.as_nats(env, "Stencil radius must be a list of natural numbers")?
+    };
+    if radius.len() > xs.rank() {
+        return Err(env.error(format!(
+            "Stencil radius {radius:?} has too many axes for shape {}",
+            xs.format_shape()
+        )));
+    }
+    // A neighborhood can wrap around an axis at most once
+    if let Some((r, dim)) = (radius.iter().zip(xs.shape())).find(|&(&r, &dim)| dim > 0 && r > dim) {
+        return Err(env.error(format!(
+            "Stencil radius {r} is larger than an axis of length {dim}"
+        )));
+    }
+    let cells = match xs {
+        Value::Num(a) => stencil_impl(f, &radius, a, env)?,
+        #[cfg(feature = "bytes")]
+        Value::Byte(a) => stencil_impl(f, &radius, a, env)?,
+        #[cfg(feature = "complex")]
+        Value::Complex(a) => stencil_impl(f, &radius, a, env)?,
+        #[cfg(feature = "rational")]
+        Value::Rational(a) => stencil_impl(f, &radius, a, env)?,
+        Value::Char(a) => stencil_impl(f, &radius, a, env)?,
+        Value::Box(a) => stencil_impl(f, &radius, a, env)?,
+    };
+    env.push(cells);
+    Ok(())
+}
+
+pub fn sort_by(descending: bool, env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    let name = if descending { "Sortbydown" } else { "Sortby" };
+    let f = env.pop_function()?;
+    let sig = f.signature();
+    if sig != (1, 1) {
+        return Err(env.error(format!(
+            "{name}'s function's signature must be |1.1, but it is {sig}"
+        )));
+    }
+    let xs = env.pop(1)?;
+    if xs.rank() == 0 {
+        return Err(env.error(format!("Cannot {} a scalar", name.to_lowercase())));
+    }
+    // Each key is computed once, then the rows are sorted by them
+    let mut keys = Vec::with_capacity(xs.row_count());
+    for row in xs.rows() {
+        env.push(row);
+        env.call(f.clone())?;
+        keys.push(env.pop("sort key")?);
+    }
+    let mut indices: Vec<usize> = (0..keys.len()).collect();
+    if descending {
+        indices.sort_by(|&a, &b| keys[b].cmp(&keys[a]));
+    } else {
+        indices.sort_by(|&a, &b| keys[a].cmp(&keys[b]));
+    }
+    let indices = Value::from(Array::from_iter(indices.into_iter().map(|i| i as f64)));
+    env.push(indices.select(&xs, env)?);
+    Ok(())
+}
+
+pub fn sort_with(env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    let f = env.pop_function()?;
+    let sig = f.signature();
+    if sig != (2, 1) {
+        return Err(env.error(format!(
+            "Sortwith's function's signature must be |2.1, but it is {sig}"
+        )));
+    }
+    let xs = env.pop(1)?;
+    if xs.rank() == 0 {
+        return Err(env.error("Cannot sortwith a scalar"));
+    }
+    let rows: Vec<Value> = xs.rows().collect();
+    // Whether row `b` belongs before row `a`
+    let before = |a: usize, b: usize, env: &mut Uiua| -> UiuaResult<bool> {
+        env.push(rows[a].clone());
+        env.push(rows[b].clone());
+        env.call(f.clone())?;
+        let ordering = (env.pop("comparison")?)
+            .as_num(env, "Sortwith's function must return a single number")?;
+        Ok(ordering > 0.0)
+    };
+    // A bottom-up merge sort, which is stable and calls the function O(n log n) times
+    let len = rows.len();
+    let mut indices: Vec<usize> = (0..len).collect();
+    let mut merged = indices.clone();
+    let mut width = 1;
+    while width < len {
+        for start in (0..len).step_by(2 * width) {
+            let mid = (start + width).min(len);
+            let end = (start + 2 * width).min(len);
+            let (mut i, mut j) = (start, mid);
+            for slot in &mut merged[start..end] {
+                let take_right = i == mid || j < end && before(indices[i], indices[j], env)?;
+                if take_right {
+                    *slot = indices[j];
+                    j += 1;
+                } else {
+                    *slot = indices[i];
+                    i += 1;
+                }
+            }
+        }
+        swap(&mut indices, &mut merged);
+        width *= 2;
+    }
+    let indices = Value::from(Array::from_iter(indices.into_iter().map(|i| i as f64)));
+    env.push(indices.select(&xs, env)?);
+    Ok(())
+}
+
+/// Call a function on each row to get its key, and give each distinct key a class
+///
+/// Returns the rows, the class of each row, and the number of classes.
+fn key_classes(name: &str, env: &mut Uiua) -> UiuaResult<(Value, Vec<usize>, usize)> {
+    let f = env.pop_function()?;
+    let sig = f.signature();
+    if sig != (1, 1) {
+        return Err(env.error(format!(
+            "{name}'s function's signature must be |1.1, but it is {sig}"
+        )));
+    }
+    let xs = env.pop(1)?;
+    if xs.rank() == 0 {
+        return Err(env.error(format!("Cannot {} a scalar", name.to_lowercase())));
+    }
+    let mut indices: HashMap<Value, usize> = HashMap::new();
+    let mut classes = Vec::with_capacity(xs.row_count());
+    for row in xs.rows() {
+        env.push(row);
+        env.call(f.clone())?;
+        let key = normalize_bytes(env.pop("key")?);
+        let new_class = indices.len();
+        classes.push(*indices.entry(key).or_insert(new_class));
+    }
+    Ok((xs, classes, indices.len()))
+}
+
+pub fn deduplicate_by(env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    let (xs, classes, _) = key_classes("Deduplicateby", env)?;
+    let mut next_class = 0;
+    let indices = (classes.into_iter().enumerate())
+        .filter(|&(_, class)| {
+            let first = class == next_class;
+            next_class += first as usize;
+            first
+        })
+        .map(|(i, _)| i as f64);
+    let indices = Value::from(Array::from_iter(indices));
+    env.push(indices.select(&xs, env)?);
+    Ok(())
+}
+
+pub fn classify_by(env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    let (_, classes, _) = key_classes("Classifyby", env)?;
+    env.push(Array::from_iter(classes.into_iter().map(|c| c as f64)));
+    Ok(())
+}
+
+pub fn occurrences_by(env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    let (_, classes, class_count) = key_classes("Occurrencesby", env)?;
+    let mut counts = vec![0.0; class_count];
+    for class in classes {
+        counts[class] += 1.0;
+    }
+    env.push(Array::from(counts.as_slice()));
+    Ok(())
+}
+
+/// Call a function on the neighborhood of every cell in an array
+///
+/// Neighborhoods are built one at a time, so the full set of them is never materialized.
+fn stencil_impl<T: ArrayValue>(
+    f: Arc<Function>,
+    radius: &[usize],
+    xs: Array<T>,
+    env: &mut Uiua,
+) -> UiuaResult<Value>
+where
+    Array<T>: Into<Value>,
+{
+    let fill = T::get_fill(env);
+    let grid_shape = &xs.shape[..radius.len()];
+    let cell_shape = &xs.shape[radius.len()..];
+    let cell_len: usize = cell_shape.iter().product();
+    let cell_count: usize = grid_shape.iter().product();
+    let too_large = || env.error("Stencil neighborhoods would be too large");
+    let mut hood_shape: Shape = (radius.iter())
+        .map(|r| r.checked_mul(2).and_then(|d| d.checked_add(1)))
+        .collect::<Option<_>>()
+        .ok_or_else(too_large)?;
+    hood_shape.extend_from_slice(cell_shape);
+    let hood_len = (hood_shape.iter())
+        .try_fold(1usize, |acc, &n| acc.checked_mul(n))
+        .ok_or_else(too_large)?;
+    env.validate_alloc(hood_len, size_of::<T>())?;
+    let hood_cells: usize = hood_shape[..radius.len()].iter().product();
+    let mut new_cells = Value::builder(cell_count);
+    let mut pos = vec![0; radius.len()];
+    let mut offset = vec![0; radius.len()];
+    let mut hood = Vec::with_capacity(hood_cells * cell_len);
+    for _ in 0..cell_count {
+        // Build the neighborhood
+        hood.clear();
+        offset.iter_mut().for_each(|o| *o = 0);
+        for _ in 0..hood_cells {
+            let mut src = Some(0);
+            for (((&p, &o), &r), &dim) in pos.iter().zip(&offset).zip(radius).zip(grid_shape) {
+                let i = (p + o) as isize - r as isize;
+                let i = if (0..dim as isize).contains(&i) {
+                    Some(i as usize)
+                } else if fill.is_some() {
+                    None
+                } else {
+                    Some(i.rem_euclid(dim as isize) as usize)
+                };
+                src = src.zip(i).map(|(src, i)| src * dim + i);
+            }
+            match (src, &fill) {
+                (Some(src), _) => {
+                    hood.extend_from_slice(&xs.data[src * cell_len..][..cell_len]);
+                }
+                (None, Some(fill)) => hood.extend((0..cell_len).map(|_| fill.clone())),
+                (None, None) => unreachable!("out-of-bounds cells are only skipped with a fill"),
+            }
+            increment_index(&mut offset, &hood_shape[..radius.len()]);
+        }
+        // Call the function
+        env.push(Array::new(
+            hood_shape.clone(),
+            hood.iter().cloned().collect::<CowSlice<_>>(),
+        ));
+        env.call(f.clone())?;
+        new_cells.add_row(env.pop("stencil's function result")?, env)?;
+        increment_index(&mut pos, grid_shape);
+    }
+    let mut cells = new_cells.finish();
+    if cell_count > 0 {
+        let shape = cells.shape_mut();
+        shape.remove(0);
+        for &dim in grid_shape.iter().rev() {
+            shape.insert(0, dim);
+        }
+    } else {
+        *cells.shape_mut() = grid_shape.into();
+    }
+    Ok(cells)
+}
+
+/// Increment a multidimensional index, wrapping around at the end
+fn increment_index(index: &mut [usize], shape: &[usize]) {
+    for (i, &dim) in index.iter_mut().zip(shape).rev() {
+        *i += 1;
+        if *i < dim {
+            return;
+        }
+        *i = 0;
+    }
+}
+
 pub fn do_(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     let f = env.pop_function()?;
@@ -121,6 +425,48 @@ pub fn partition(env: &mut Uiua) -> UiuaResult {
     )
 }
 
+pub fn partition_index(env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    let f = env.pop_function()?;
+    let sig = f.signature();
+    if !(2..=3).contains(&sig.args) || sig.outputs != 1 {
+        return Err(env.error(format!(
+            "Partindex's function must take 2 or 3 arguments \
+            and return 1 value, but its signature is {sig}"
+        )));
+    }
+    let markers = env.pop(1)?;
+    let markers = markers.as_ints(env, "Partition indices must be a list of integers")?;
+    let values = env.pop(2)?;
+    let groups = values.partition_groups(&markers, env)?;
+    let mut rows = Vec::with_capacity(groups.len());
+    for (group, (start, marker)) in groups.into_iter().zip(partition_starts(&markers)) {
+        if sig.args == 3 {
+            env.push(marker as f64);
+        }
+        env.push(start as f64);
+        env.push(group);
+        env.call_error_on_break(f.clone(), "break is not allowed in partindex")?;
+        rows.push(env.pop("partindex's function result")?);
+    }
+    let res = Value::from_row_values(rows, env)?;
+    env.push(res);
+    Ok(())
+}
+
+/// Get the index and marker of the first row of each partition group
+fn partition_starts(markers: &[isize]) -> Vec<(usize, isize)> {
+    let mut starts = Vec::new();
+    let mut last_marker = isize::MAX;
+    for (i, &marker) in markers.iter().enumerate() {
+        if marker > 0 && marker != last_marker {
+            starts.push((i, marker));
+        }
+        last_marker = marker;
+    }
+    starts
+}
+
 pub fn unpartition(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     let f = env.pop_function()?;
@@ -242,6 +588,11 @@ impl Value {
                 .partition_groups(markers, env)?
                 .map(Into::into)
                 .collect(),
+            #[cfg(feature = "rational")]
+            Value::Rational(arr) => arr
+                .partition_groups(markers, env)?
+                .map(Into::into)
+                .collect(),
             Value::Char(arr) => arr
                 .partition_groups(markers, env)?
                 .map(Into::into)
@@ -300,6 +651,8 @@ impl Value {
             Value::Byte(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
             #[cfg(feature = "complex")]
             Value::Complex(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
+            #[cfg(feature = "rational")]
+            Value::Rational(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
             Value::Char(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
             Value::Box(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
         })
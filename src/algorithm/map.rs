@@ -0,0 +1,297 @@
+//! Algorithms for map operations
+
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    iter::repeat_n,
+    mem::take,
+    sync::Arc,
+};
+
+use ecow::EcoVec;
+
+use crate::{array::*, boxed::Boxed, value::Value, Uiua, UiuaResult};
+
+/// The keys of a map array
+///
+/// Each row of the keys corresponds to a row of the array.
+/// The indices allow a row to be found from its key in constant time.
+#[derive(Clone)]
+pub struct MapKeys {
+    pub(crate) keys: Value,
+    indices: HashMap<Value, usize>,
+}
+
+impl MapKeys {
+    /// Get the number of keys
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+    fn get(&self, key: &Value) -> Option<usize> {
+        self.indices.get(&self.normalize(key.clone())).copied()
+    }
+    /// Make a key hash and compare the same as the rows of the keys
+    fn normalize(&self, key: Value) -> Value {
+        let mut key = normalize_bytes(key);
+        if let Value::Box(_) = self.keys {
+            key.box_if_not();
+        }
+        key
+    }
+    fn reindex(&mut self) {
+        self.indices = self.keys.rows().enumerate().map(|(i, k)| (k, i)).collect();
+    }
+}
+
+impl PartialEq for MapKeys {
+    fn eq(&self, other: &Self) -> bool {
+        self.keys == other.keys
+    }
+}
+
+impl Hash for MapKeys {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.keys.hash(state);
+    }
+}
+
+/// Bytes and numbers that are equal should be the same key
+pub(crate) fn normalize_bytes(value: Value) -> Value {
+    match value {
+        #[cfg(feature = "bytes")]
+        Value::Byte(arr) => arr.convert::<f64>().into(),
+        value => value,
+    }
+}
+
+/// Box each row of a value so that rows of any type or shape can be added to it
+fn box_rows(value: Value) -> Value {
+    let rows = value.into_rows().map(Value::boxed_if_not);
+    Array::<Boxed>::from_iter(rows).into()
+}
+
+/// Give a value a leading axis of length 1
+fn fixed(mut value: Value) -> Value {
+    value.shape_mut().insert(0, 1);
+    value
+}
+
+impl Value {
+    /// Get the keys of the value if it is a map
+    pub(crate) fn map_keys(&self) -> Option<&MapKeys> {
+        self.generic_ref_shallow(
+            Array::map_keys,
+            Array::map_keys,
+            Array::map_keys,
+            Array::map_keys,
+            Array::map_keys,
+            Array::map_keys,
+        )
+    }
+    pub(crate) fn map_keys_mut(&mut self) -> &mut Option<Arc<MapKeys>> {
+        match self {
+            Value::Num(arr) => &mut arr.map_keys,
+            #[cfg(feature = "bytes")]
+            Value::Byte(arr) => &mut arr.map_keys,
+            #[cfg(feature = "complex")]
+            Value::Complex(arr) => &mut arr.map_keys,
+            #[cfg(feature = "rational")]
+            Value::Rational(arr) => &mut arr.map_keys,
+            Value::Char(arr) => &mut arr.map_keys,
+            Value::Box(arr) => &mut arr.map_keys,
+        }
+    }
+    /// Get the keys of a map, treating empty arrays as empty maps
+    fn expect_map_keys(&self, env: &Uiua) -> UiuaResult<Option<&MapKeys>> {
+        if let Some(keys) = self.map_keys() {
+            Ok(Some(keys))
+        } else if self.rank() == 0 || self.row_count() > 0 {
+            Err(env.error(format!(
+                "Expected a map, but got a {} array of shape {}",
+                self.type_name(),
+                self.format_shape()
+            )))
+        } else {
+            Ok(None)
+        }
+    }
+    /// Take the keys out of a map so that its rows can be changed
+    fn take_map_keys(&mut self, env: &Uiua) -> UiuaResult<MapKeys> {
+        if self.expect_map_keys(env)?.is_none() {
+            return Ok(MapKeys {
+                keys: Value::default(),
+                indices: HashMap::new(),
+            });
+        }
+        let keys = self.map_keys_mut().take().unwrap();
+        Ok(Arc::try_unwrap(keys).unwrap_or_else(|keys| (*keys).clone()))
+    }
+    /// Create a `map` from keys and values
+    pub fn map(self, values: Self, env: &Uiua) -> UiuaResult<Self> {
+        let keys = normalize_bytes(self);
+        if keys.rank() == 0 || values.rank() == 0 || keys.row_count() != values.row_count() {
+            return Err(env.error(format!(
+                "Cannot make a map from keys of shape {} and values of shape {}",
+                keys.format_shape(),
+                values.format_shape()
+            )));
+        }
+        let mut map_keys = MapKeys {
+            keys,
+            indices: HashMap::new(),
+        };
+        map_keys.reindex();
+        if map_keys.len() < values.row_count() {
+            // Later values replace earlier ones with the same key
+            let keys = map_keys.keys;
+            let mut map = keys.first_dim_zero().map(values.first_dim_zero(), env)?;
+            for (key, value) in keys.into_rows().zip(values.into_rows()) {
+                map.insert(key, value, env)?;
+            }
+            return Ok(map);
+        }
+        let mut map = values;
+        *map.map_keys_mut() = Some(Arc::new(map_keys));
+        Ok(map)
+    }
+    /// Create a map from string keys to values, boxing them all
+    pub(crate) fn string_map<'a>(pairs: impl IntoIterator<Item = (&'a str, Value)>) -> Self {
+        let (keys, values): (Vec<_>, Vec<_>) = (pairs.into_iter())
+            .map(|(key, value)| (Boxed(key.into()), Boxed(value)))
+            .unzip();
+        let mut map_keys = MapKeys {
+            keys: Array::from_iter(keys).into(),
+            indices: HashMap::new(),
+        };
+        map_keys.reindex();
+        let mut map = Value::from(Array::from_iter(values));
+        *map.map_keys_mut() = Some(Arc::new(map_keys));
+        map
+    }
+    /// Get the keys and values of a map
+    pub fn unmap(mut self, env: &Uiua) -> UiuaResult<(Self, Self)> {
+        let keys = self.take_map_keys(env)?;
+        Ok((keys.keys, self))
+    }
+    /// `insert` a key-value pair into a map
+    pub fn insert(&mut self, key: Self, value: Self, env: &Uiua) -> UiuaResult {
+        let mut keys = self.take_map_keys(env)?;
+        let key = keys.normalize(key);
+        if let Some(&i) = keys.indices.get(&key) {
+            self.set_row(i, value);
+        } else if keys.len() == 0 {
+            keys.keys = fixed(key.clone());
+            *self = fixed(value);
+            keys.indices.insert(key, 0);
+        } else {
+            let i = keys.len();
+            if keys.keys.append(key.clone(), env).is_err() {
+                keys.keys = box_rows(take(&mut keys.keys));
+                keys.keys
+                    .append(Value::from(key.clone().boxed_if_not()), env)?;
+                keys.reindex();
+            } else {
+                keys.indices.insert(key, i);
+            }
+            if self.append(value.clone(), env).is_err() {
+                *self = box_rows(take(self));
+                self.append(Value::from(value.boxed_if_not()), env)?;
+            }
+        }
+        *self.map_keys_mut() = Some(Arc::new(keys));
+        Ok(())
+    }
+    /// Check if a map `has` a key
+    pub fn has(&self, map: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let keys = map.expect_map_keys(env)?;
+        Ok(keys.and_then(|keys| keys.get(self)).is_some().into())
+    }
+    /// `get` the value for a key in a map
+    pub fn get(&self, map: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let keys = map.expect_map_keys(env)?;
+        if let Some(i) = keys.and_then(|keys| keys.get(self)) {
+            return Ok(map.row(i));
+        }
+        map.generic_ref_shallow(
+            |a| a.fill_row(env),
+            |a| a.fill_row(env),
+            |a| a.fill_row(env),
+            |a| a.fill_row(env),
+            |a| a.fill_row(env),
+            |a| a.fill_row(env),
+        )
+        .ok_or_else(|| env.error(format!("Key {} not found in map", self.show())))
+    }
+    /// `remove` a key from a map
+    pub fn remove(&mut self, key: &Self, env: &Uiua) -> UiuaResult {
+        let mut keys = self.take_map_keys(env)?;
+        let key = keys.normalize(key.clone());
+        if let Some(i) = keys.indices.remove(&key) {
+            keys.keys.remove_row(i);
+            self.remove_row(i);
+            for index in keys.indices.values_mut() {
+                if *index > i {
+                    *index -= 1;
+                }
+            }
+        }
+        *self.map_keys_mut() = Some(Arc::new(keys));
+        Ok(())
+    }
+    /// Replace a row, boxing the rows if the new one does not fit
+    fn set_row(&mut self, i: usize, mut row: Self) {
+        let same_kind = matches!(self, Value::Char(_)) == matches!(row, Value::Char(_));
+        let either_boxed = matches!(self, Value::Box(_)) || matches!(row, Value::Box(_));
+        if row.shape() != &self.shape()[1..] || !(same_kind || either_boxed) {
+            *self = box_rows(take(self));
+            row = row.boxed_if_not().into();
+        }
+        *self = (take(self).generic_bin_into(
+            row,
+            |a, b| Ok(a.set_row(i, b).into()),
+            |a, b| Ok(a.set_row(i, b).into()),
+            |a, b| Ok(a.set_row(i, b).into()),
+            |a, b| Ok(a.set_row(i, b).into()),
+            |a, b| Ok(a.set_row(i, b).into()),
+            |a, b| Ok(a.set_row(i, b).into()),
+            |_, _| (),
+        ))
+        .expect("row should fit once boxed");
+    }
+    fn remove_row(&mut self, i: usize) {
+        self.generic_mut_deep(
+            |a| a.remove_row(i),
+            |a| a.remove_row(i),
+            |a| a.remove_row(i),
+            |a| a.remove_row(i),
+            |a| a.remove_row(i),
+            |a| a.remove_row(i),
+        )
+    }
+}
+
+impl<T: ArrayValue> Array<T> {
+    fn set_row(mut self, i: usize, row: Self) -> Self {
+        let row_len = self.row_len();
+        self.data.as_mut_slice()[i * row_len..(i + 1) * row_len].clone_from_slice(&row.data);
+        self
+    }
+    fn remove_row(&mut self, i: usize) {
+        let row_len = self.row_len();
+        let data: EcoVec<T> = (self.data[..i * row_len].iter())
+            .chain(&self.data[(i + 1) * row_len..])
+            .cloned()
+            .collect();
+        self.data = data.into();
+        self.shape[0] -= 1;
+    }
+    /// Make a row of the fill value, for missing keys
+    fn fill_row(&self, env: &Uiua) -> Option<Value>
+    where
+        Value: From<Self>,
+    {
+        let fill = T::get_fill(env)?;
+        let data: EcoVec<T> = repeat_n(fill, self.row_len()).collect();
+        Some(Array::new(&self.shape[1..], data).into())
+    }
+}
@@ -12,16 +12,19 @@ use tinyvec::TinyVec;
 use crate::UiuaResult;
 use crate::{
     array::{Array, ArrayValue, Shape},
-    Uiua, UiuaError,
+    ErrorKind, Uiua, UiuaError,
 };
 
 mod dyadic;
 pub mod fork;
+mod glob;
 pub(crate) mod invert;
 pub mod loops;
+pub(crate) mod map;
 mod monadic;
 pub mod pervade;
 pub mod reduce;
+mod stats;
 pub mod table;
 pub mod zip;
 
@@ -63,6 +66,7 @@ pub trait FillContext {
     fn fill<T: ArrayValue>(&self) -> Option<T>;
     fn fill_error(error: Self::Error) -> Self::Error;
     fn is_fill_error(error: &Self::Error) -> bool;
+    fn kind_error(error: Self::Error, kind: ErrorKind) -> Self::Error;
 }
 
 impl FillContext for Uiua {
@@ -82,6 +86,9 @@ impl FillContext for Uiua {
     fn is_fill_error(error: &Self::Error) -> bool {
         error.is_fill()
     }
+    fn kind_error(error: Self::Error, kind: ErrorKind) -> Self::Error {
+        error.with_kind(kind)
+    }
 }
 
 impl FillContext for () {
@@ -101,6 +108,9 @@ impl FillContext for () {
     fn is_fill_error(error: &Self::Error) -> bool {
         match *error {}
     }
+    fn kind_error(error: Self::Error, _: ErrorKind) -> Self::Error {
+        error
+    }
 }
 
 pub(crate) fn shape_prefixes_match(a: &[usize], b: &[usize]) -> bool {
@@ -168,11 +178,12 @@ where
                 }
             }
             if !shape_prefixes_match(&a.shape, &b.shape) {
-                return Err(C::fill_error(ctx.error(format!(
+                let error = ctx.error(format!(
                     "Shapes {} and {} do not match",
                     a.format_shape(),
                     b.format_shape()
-                ))));
+                ));
+                return Err(C::fill_error(C::kind_error(error, ErrorKind::Shape)));
             }
         }
     }
@@ -4,21 +4,25 @@ use std::{
     cmp::Ordering,
     collections::{BTreeMap, BTreeSet, HashMap},
     iter::{once, repeat},
+    mem::{size_of, take},
     ptr,
 };
 
-use ecow::EcoVec;
+use ecow::{EcoString, EcoVec};
 use rayon::prelude::*;
 use tinyvec::tiny_vec;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
     array::*,
+    boxed::Boxed,
     cowslice::{cowslice, CowSlice},
     value::Value,
     Uiua, UiuaResult,
 };
 
-use super::{ArrayCmpSlice, FillContext};
+use super::{pervade::is_prime, ArrayCmpSlice, FillContext};
 
 impl Value {
     /// Make the value 1-dimensional
@@ -29,6 +33,7 @@ impl Value {
             Array::deshape,
             Array::deshape,
             Array::deshape,
+            Array::deshape,
         )
     }
     /// Attempt to parse the value into a number
@@ -44,6 +49,7 @@ impl Value {
 impl<T: ArrayValue> Array<T> {
     /// Make the array 1-dimensional
     pub fn deshape(&mut self) {
+        self.map_keys = None;
         self.shape = tiny_vec![self.element_count()];
     }
 }
@@ -57,6 +63,7 @@ impl Value {
             or a list of natural numbers",
         )?;
         if self.rank() == 0 {
+            env.validate_alloc(shape[0], size_of::<f64>())?;
             return Ok((0..shape[0]).collect());
         }
         if shape.is_empty() {
@@ -90,6 +97,7 @@ fn range(shape: &[usize], env: &Uiua) -> UiuaResult<CowSlice<f64>> {
         }
         len = new;
     }
+    env.validate_alloc(len, size_of::<f64>())?;
     let mut data: EcoVec<f64> = EcoVec::with_capacity(len);
     let mut curr = vec![0; shape.len()];
     loop {
@@ -121,6 +129,7 @@ impl Value {
             |a| a.first(env).map(Into::into),
             |a| a.first(env).map(Into::into),
             |a| a.first(env).map(Into::into),
+            |a| a.first(env).map(Into::into),
         )
     }
     /// Get the last row of the value
@@ -131,6 +140,7 @@ impl Value {
             |a| a.last(env).map(Into::into),
             |a| a.last(env).map(Into::into),
             |a| a.last(env).map(Into::into),
+            |a| a.last(env).map(Into::into),
         )
     }
 }
@@ -138,6 +148,7 @@ impl Value {
 impl<T: ArrayValue> Array<T> {
     /// Get the first row of the array
     pub fn first(mut self, env: &Uiua) -> UiuaResult<Self> {
+        self.map_keys = None;
         match &*self.shape {
             [] => Err(env.error("Cannot take first of a scalar")),
             [0, rest @ ..] => {
@@ -159,6 +170,7 @@ impl<T: ArrayValue> Array<T> {
     }
     /// Get the last row of the array
     pub fn last(mut self, env: &Uiua) -> UiuaResult<Self> {
+        self.map_keys = None;
         match &*self.shape {
             [] => Err(env.error("Cannot take last of a scalar")),
             [0, rest @ ..] => {
@@ -190,6 +202,7 @@ impl Value {
             Array::reverse,
             Array::reverse,
             Array::reverse,
+            Array::reverse,
         )
     }
 }
@@ -197,6 +210,7 @@ impl Value {
 impl<T: ArrayValue> Array<T> {
     /// Reverse the rows of the array
     pub fn reverse(&mut self) {
+        self.map_keys = None;
         if self.shape.is_empty() || self.element_count() == 0 {
             return;
         }
@@ -224,6 +238,7 @@ impl Value {
             Array::transpose,
             Array::transpose,
             Array::transpose,
+            Array::transpose,
         )
     }
     /// Inverse transpose the value
@@ -234,6 +249,7 @@ impl Value {
             Array::inv_transpose,
             Array::inv_transpose,
             Array::inv_transpose,
+            Array::inv_transpose,
         )
     }
 }
@@ -242,6 +258,7 @@ impl<T: ArrayValue> Array<T> {
     /// Transpose the array
     pub fn transpose(&mut self) {
         crate::profile_function!();
+        self.map_keys = None;
         if self.shape.len() < 2 {
             return;
         }
@@ -263,6 +280,7 @@ impl<T: ArrayValue> Array<T> {
     /// Inverse transpose the array
     pub fn inv_transpose(&mut self) {
         crate::profile_function!();
+        self.map_keys = None;
         if self.shape.len() < 2 {
             return;
         }
@@ -292,6 +310,7 @@ impl Value {
             Array::rise,
             Array::rise,
             Array::rise,
+            Array::rise,
             env,
         )
     }
@@ -303,31 +322,86 @@ impl Value {
             Array::fall,
             Array::fall,
             Array::fall,
+            Array::fall,
             env,
         )
     }
     /// `classify` the rows of the value
     pub fn classify(&self, env: &Uiua) -> UiuaResult<Self> {
+        if self.is_tolerant(env) {
+            return Ok(self.tolerant_classes(env).into_iter().collect());
+        }
         self.generic_ref_env_deep(
             Array::classify,
             Array::classify,
             Array::classify,
             Array::classify,
             Array::classify,
+            Array::classify,
             env,
         )
         .map(Self::from_iter)
     }
     /// `deduplicate` the rows of the value
-    pub fn deduplicate(&mut self) {
+    pub fn deduplicate(&mut self, env: &Uiua) {
+        if self.is_tolerant(env) {
+            let mut next_class = 0;
+            let kept: Vec<bool> = (self.tolerant_classes(env).into_iter())
+                .map(|class| {
+                    let first = class == next_class;
+                    next_class += first as usize;
+                    first
+                })
+                .collect();
+            self.generic_mut_deep(
+                |arr| arr.keep_rows(&kept),
+                |arr| arr.keep_rows(&kept),
+                |arr| arr.keep_rows(&kept),
+                |arr| arr.keep_rows(&kept),
+                |arr| arr.keep_rows(&kept),
+                |arr| arr.keep_rows(&kept),
+            );
+            return;
+        }
         self.generic_mut_deep(
             Array::deduplicate,
             Array::deduplicate,
             Array::deduplicate,
             Array::deduplicate,
             Array::deduplicate,
+            Array::deduplicate,
         )
     }
+    /// Whether rows of the value should be compared within [`Uiua::tolerance`]
+    ///
+    /// Only numeric and boxed values can be unequal but still within the tolerance.
+    fn is_tolerant(&self, env: &Uiua) -> bool {
+        let numeric = match self {
+            Value::Num(_) | Value::Box(_) => true,
+            #[cfg(feature = "complex")]
+            Value::Complex(_) => true,
+            _ => false,
+        };
+        numeric && env.tolerance() > 0.0 && self.rank() > 0
+    }
+    /// Classify the rows of the value, treating rows that `match` within
+    /// [`Uiua::tolerance`] as the same
+    ///
+    /// Rows are compared to the first row of each class, so this is quadratic
+    /// in the worst case.
+    fn tolerant_classes(&self, env: &Uiua) -> Vec<usize> {
+        let mut firsts: Vec<Value> = Vec::new();
+        let mut classes = Vec::with_capacity(self.row_count());
+        for row in self.rows() {
+            if let Some(class) = firsts.iter().position(|first| first.matches(&row, env)) {
+                classes.push(class);
+            } else {
+                classes.push(firsts.len());
+                firsts.push(row);
+            }
+        }
+        classes
+    }
 }
 
 impl<T: ArrayValue> Array<T> {
@@ -385,6 +459,7 @@ impl<T: ArrayValue> Array<T> {
     }
     /// `deduplicate` the rows of the array
     pub fn deduplicate(&mut self) {
+        self.map_keys = None;
         if self.rank() == 0 {
             return;
         }
@@ -400,6 +475,18 @@ impl<T: ArrayValue> Array<T> {
         self.data = deduped;
         self.shape[0] = new_len;
     }
+    /// Keep only the rows whose corresponding flag is set
+    fn keep_rows(&mut self, kept: &[bool]) {
+        self.map_keys = None;
+        let mut data = CowSlice::new();
+        let mut new_len = 0;
+        for (row, _) in self.row_slices().zip(kept).filter(|(_, &keep)| keep) {
+            data.extend_from_slice(row);
+            new_len += 1;
+        }
+        self.data = data;
+        self.shape[0] = new_len;
+    }
 }
 
 impl Value {
@@ -506,6 +593,7 @@ impl Value {
         Ok(if self.rank() <= 1 {
             let counts = self.as_nats(env, "Argument to where must be an array of naturals")?;
             let total: usize = counts.iter().fold(0, |acc, &b| acc.saturating_add(b));
+            env.validate_alloc(total, size_of::<f64>())?;
             let mut data = EcoVec::with_capacity(total);
             for (i, &b) in counts.iter().enumerate() {
                 for _ in 0..b {
@@ -518,6 +606,7 @@ impl Value {
             let counts =
                 self.as_natural_array(env, "Argument to where must be an array of naturals")?;
             let total: usize = counts.data.iter().fold(0, |acc, &b| acc.saturating_add(b));
+            env.validate_alloc(total.saturating_mul(counts.rank()), size_of::<f64>())?;
             let mut data = EcoVec::with_capacity(total);
             for (i, &b) in counts.data.iter().enumerate() {
                 for _ in 0..b {
@@ -612,7 +701,7 @@ impl Value {
         }
     }
     /// `invert` `where`
-    pub fn inverse_where(&self, env: &Uiua) -> UiuaResult<Self> {
+    pub fn inverse_where(&self, env: &Uiua) -> UiuaResult<Array<f64>> {
         Ok(match self.shape() {
             [] | [_] => {
                 let indices =
@@ -622,6 +711,7 @@ impl Value {
                     .zip(indices.iter().skip(1))
                     .all(|(&a, &b)| a <= b);
                 let size = indices.iter().max().map(|&i| i + 1).unwrap_or(0);
+                env.validate_alloc(size, size_of::<f64>())?;
                 let mut data = EcoVec::with_capacity(size);
                 if is_sorted {
                     let mut j = 0;
@@ -643,7 +733,7 @@ impl Value {
                     }
                     data.extend((0..size).map(|i| counts.get(&i).copied().unwrap_or(0) as f64));
                 }
-                Array::from(data).into()
+                Array::from(data)
             }
             [_, trailing] => {
                 let indices = self.as_natural_array(
@@ -664,7 +754,8 @@ impl Value {
                     }
                     acc
                 });
-                let data_len: usize = shape.iter().product();
+                let data_len = (shape.iter()).fold(1usize, |acc, &n| acc.saturating_mul(n));
+                env.validate_alloc(data_len, size_of::<f64>())?;
                 let mut data = EcoVec::from_iter(repeat(0.0).take(data_len));
                 let data_slice = data.make_mut();
                 for (key, count) in counts {
@@ -676,13 +767,25 @@ impl Value {
                     }
                     data_slice[i] = count as f64;
                 }
-                Array::new(shape, data).into()
+                Array::new(shape, data)
             }
             shape => {
                 return Err(env.error(format!("Cannot invert where of rank-{} array", shape.len())))
             }
         })
     }
+    /// `undo` `where`, keeping at least the shape of the original array
+    pub fn unwhere(&self, shape: &Self, env: &Uiua) -> UiuaResult<Array<f64>> {
+        let shape = shape.as_nats(env, "Shape must be a list of naturals")?;
+        let mut counts = self.inverse_where(env)?;
+        if counts.rank() == shape.len() {
+            let target: Vec<usize> = (counts.shape.iter().zip(&shape))
+                .map(|(&a, &b)| a.max(b))
+                .collect();
+            counts.fill_to_shape(&target, 0.0);
+        }
+        Ok(counts)
+    }
 }
 
 impl Value {
@@ -697,6 +800,85 @@ impl Value {
         let s = String::from_utf8(bytes).map_err(|e| env.error(e))?;
         Ok(s.into())
     }
+    /// Convert a list of UTF-8 bytes to a string value, replacing invalid sequences
+    pub fn utf8_lossy(&self, env: &Uiua) -> UiuaResult<Self> {
+        let bytes = self.as_bytes(env, "Argument to utflossy must be a list of bytes")?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned().into())
+    }
+    /// Convert a string value to a list of UTF-16 code units
+    pub fn utf16(&self, env: &Uiua) -> UiuaResult<Self> {
+        let s = self.as_string(env, "Argument to utfsixteen must be a string")?;
+        Ok(Array::<f64>::from_iter(s.encode_utf16().map(f64::from)).into())
+    }
+    /// Convert a list of UTF-16 code units to a string value
+    pub fn inv_utf16(&self, env: &Uiua) -> UiuaResult<Self> {
+        let units = self.as_number_list(
+            env,
+            "Argument to inverse utfsixteen must be a list of integers from 0 to 65535",
+            |f| f.fract() == 0.0 && (0.0..65536.0).contains(&f),
+            |f| f as u16,
+        )?;
+        let s = String::from_utf16(&units).map_err(|e| env.error(e))?;
+        Ok(s.into())
+    }
+    /// Convert a string value to a list of Latin-1 bytes
+    pub fn latin1(&self, env: &Uiua) -> UiuaResult<Self> {
+        let s = self.as_string(env, "Argument to latin must be a string")?;
+        let bytes = (s.chars())
+            .map(|c| {
+                u8::try_from(c)
+                    .map_err(|_| env.error(format!("Character {c:?} cannot be encoded as Latin-1")))
+            })
+            .collect::<UiuaResult<Vec<u8>>>()?;
+        Ok(Array::<u8>::from_iter(bytes).into())
+    }
+    /// Convert a list of Latin-1 bytes to a string value
+    pub fn inv_latin1(&self, env: &Uiua) -> UiuaResult<Self> {
+        let bytes = self.as_bytes(env, "Argument to inverse latin must be a list of bytes")?;
+        Ok(bytes.into_iter().map(char::from).collect::<String>().into())
+    }
+}
+
+impl Value {
+    /// Split a string into the values that a format string with the given parts put between them
+    pub(crate) fn match_format_pattern(
+        &self,
+        parts: &[EcoString],
+        env: &Uiua,
+    ) -> UiuaResult<Vec<Self>> {
+        let s = self.as_string(env, "Only strings can be matched by a format string")?;
+        let no_match = || {
+            env.error(format!(
+                "String {s:?} does not match the format string pattern {:?}",
+                parts.join("_")
+            ))
+        };
+        let (first, rest) = parts.split_first().ok_or_else(no_match)?;
+        let Some((last, middle)) = rest.split_last() else {
+            return if s == first.as_str() {
+                Ok(Vec::new())
+            } else {
+                Err(no_match())
+            };
+        };
+        let inner = (s.strip_prefix(first.as_str()))
+            .and_then(|s| s.strip_suffix(last.as_str()))
+            .ok_or_else(no_match)?;
+        let mut fields = Vec::with_capacity(rest.len());
+        let mut remaining = inner;
+        for part in middle {
+            if part.is_empty() {
+                // Adjacent fields cannot be told apart, so the first one is empty
+                fields.push(Value::from(""));
+                continue;
+            }
+            let (field, after) = remaining.split_once(part.as_str()).ok_or_else(no_match)?;
+            fields.push(field.into());
+            remaining = after;
+        }
+        fields.push(remaining.into());
+        Ok(fields)
+    }
 }
 
 impl Value {
@@ -728,6 +910,7 @@ impl Value {
 impl<T: ArrayValue> Array<T> {
     /// Join an ocean value
     pub fn ocean(&mut self, value: T) {
+        self.map_keys = None;
         if self.rank() == 0 {
             self.data.extend(once(value));
             self.data.as_mut_slice().rotate_right(1);
@@ -749,6 +932,7 @@ impl Value {
             Array::first_min_index,
             Array::first_min_index,
             Array::first_min_index,
+            Array::first_min_index,
             env,
         )
         .map(Into::into)
@@ -760,6 +944,7 @@ impl Value {
             Array::first_max_index,
             Array::first_max_index,
             Array::first_max_index,
+            Array::first_max_index,
             env,
         )
         .map(Into::into)
@@ -771,6 +956,7 @@ impl Value {
             Array::last_min_index,
             Array::last_min_index,
             Array::last_min_index,
+            Array::last_min_index,
             env,
         )
         .map(Into::into)
@@ -782,6 +968,7 @@ impl Value {
             Array::last_max_index,
             Array::last_max_index,
             Array::last_max_index,
+            Array::last_max_index,
             env,
         )
         .map(Into::into)
@@ -862,3 +1049,369 @@ impl<T: ArrayValue> Array<T> {
         Ok(index as f64)
     }
 }
+
+impl Value {
+    /// Get the `datetime` of the value
+    pub fn datetime(&self, env: &Uiua) -> UiuaResult<Array<f64>> {
+        match self {
+            Value::Num(arr) => arr.datetime(env),
+            #[cfg(feature = "bytes")]
+            Value::Byte(arr) => arr.convert_ref().datetime(env),
+            value => Err(env.error(format!(
+                "Cannot get the datetime of a {} array",
+                value.type_name()
+            ))),
+        }
+    }
+    /// Turn datetime components back into times
+    pub fn inv_datetime(&self, env: &Uiua) -> UiuaResult<Array<f64>> {
+        match self {
+            Value::Num(arr) => arr.inv_datetime(env),
+            #[cfg(feature = "bytes")]
+            Value::Byte(arr) => arr.convert_ref().inv_datetime(env),
+            value => Err(env.error(format!(
+                "Cannot invert the datetime of a {} array",
+                value.type_name()
+            ))),
+        }
+    }
+}
+
+impl Array<f64> {
+    /// Get the `datetime` of the array
+    pub fn datetime(&self, env: &Uiua) -> UiuaResult<Self> {
+        let mut data = EcoVec::with_capacity(self.element_count() * 6);
+        for &time in &self.data {
+            if !time.is_finite() {
+                return Err(env.error(format!("Cannot get the datetime of {time}")));
+            }
+            let days = (time / 86400.0).floor();
+            let (year, month, day) = civil_from_days(days);
+            let seconds = time - days * 86400.0;
+            let hour = (seconds / 3600.0).floor();
+            let minute = ((seconds - hour * 3600.0) / 60.0).floor();
+            let second = seconds - hour * 3600.0 - minute * 60.0;
+            data.extend([year, month, day, hour, minute, second]);
+        }
+        let mut shape = self.shape.clone();
+        shape.push(6);
+        Ok(Array::new(shape, data))
+    }
+    /// Turn datetime components back into times
+    ///
+    /// Components that are out of range carry over into the larger ones.
+    pub fn inv_datetime(&self, env: &Uiua) -> UiuaResult<Self> {
+        let Some(&n) = self.shape.last() else {
+            return Err(env.error("Cannot invert the datetime of a scalar"));
+        };
+        if !(1..=6).contains(&n) {
+            return Err(env.error(format!(
+                "A datetime must have between 1 and 6 components, but its last axis is {n}"
+            )));
+        }
+        let mut data = EcoVec::with_capacity(self.element_count() / n);
+        for components in self.data.chunks_exact(n) {
+            let mut full = [1970.0, 1.0, 1.0, 0.0, 0.0, 0.0];
+            full[..n].copy_from_slice(components);
+            if let Some(c) = full.iter().find(|c| !c.is_finite()) {
+                return Err(env.error(format!("Cannot invert the datetime of {c}")));
+            }
+            let [year, month, day, hour, minute, second] = full;
+            // Carry months over into years before looking up the day
+            let months = year.floor() * 12.0 + month.floor() - 1.0;
+            let year = (months / 12.0).floor();
+            let month = months - year * 12.0 + 1.0;
+            let days = days_from_civil(year, month, 1.0) + day - 1.0;
+            data.push(days * 86400.0 + hour * 3600.0 + minute * 60.0 + second);
+        }
+        let shape = Shape::from(&self.shape[..self.rank() - 1]);
+        Ok(Array::new(shape, data))
+    }
+}
+
+// These are Howard Hinnant's `days_from_civil` and `civil_from_days` algorithms,
+// done with floats so that big years do not overflow.
+
+fn days_from_civil(year: f64, month: f64, day: f64) -> f64 {
+    let year = if month <= 2.0 { year - 1.0 } else { year };
+    let era = (year / 400.0).floor();
+    let year_of_era = year - era * 400.0;
+    let shifted_month = if month > 2.0 {
+        month - 3.0
+    } else {
+        month + 9.0
+    };
+    let day_of_year = ((153.0 * shifted_month + 2.0) / 5.0).floor() + day - 1.0;
+    let day_of_era = year_of_era * 365.0 + (year_of_era / 4.0).floor()
+        - (year_of_era / 100.0).floor()
+        + day_of_year;
+    era * 146097.0 + day_of_era - 719468.0
+}
+
+fn civil_from_days(days: f64) -> (f64, f64, f64) {
+    let days = days + 719468.0;
+    let era = (days / 146097.0).floor();
+    let day_of_era = days - era * 146097.0;
+    let year_of_era = ((day_of_era - (day_of_era / 1460.0).floor()
+        + (day_of_era / 36524.0).floor()
+        - (day_of_era / 146096.0).floor())
+        / 365.0)
+        .floor();
+    let day_of_year = day_of_era
+        - (365.0 * year_of_era + (year_of_era / 4.0).floor() - (year_of_era / 100.0).floor());
+    let shifted_month = ((5.0 * day_of_year + 2.0) / 153.0).floor();
+    let day = day_of_year - ((153.0 * shifted_month + 2.0) / 5.0).floor() + 1.0;
+    let month = if shifted_month < 10.0 {
+        shifted_month + 3.0
+    } else {
+        shifted_month - 9.0
+    };
+    let year = year_of_era + era * 400.0;
+    (if month <= 2.0 { year + 1.0 } else { year }, month, day)
+}
+
+impl Value {
+    /// Get the `fft` of the value
+    pub fn fft(self, env: &Uiua) -> UiuaResult<Self> {
+        self.fft_impl(false, env)
+    }
+    /// Get the inverse `fft` of the value
+    pub fn inv_fft(self, env: &Uiua) -> UiuaResult<Self> {
+        self.fft_impl(true, env)
+    }
+    #[cfg(feature = "complex")]
+    fn fft_impl(self, inverse: bool, env: &Uiua) -> UiuaResult<Self> {
+        use rustfft::{num_complex::Complex64, FftDirection, FftPlanner};
+        let mut arr: Array<crate::Complex> = match self {
+            Value::Num(arr) => arr.convert(),
+            #[cfg(feature = "bytes")]
+            Value::Byte(arr) => arr.convert(),
+            Value::Complex(arr) => arr,
+            value => {
+                return Err(env.error(format!("Cannot run fft on a {} array", value.type_name())))
+            }
+        };
+        let Some(&len) = arr.shape.last() else {
+            return Err(env.error("Cannot run fft on a scalar"));
+        };
+        if len == 0 {
+            return Ok(arr.into());
+        }
+        let direction = if inverse {
+            FftDirection::Inverse
+        } else {
+            FftDirection::Forward
+        };
+        let fft = FftPlanner::new().plan_fft(len, direction);
+        // Transforming the whole buffer transforms each row along the last axis
+        let mut buffer: Vec<Complex64> = (arr.data.iter())
+            .map(|c| Complex64::new(c.re, c.im))
+            .collect();
+        fft.process(&mut buffer);
+        let scale = if inverse { 1.0 / len as f64 } else { 1.0 };
+        for (c, b) in arr.data.as_mut_slice().iter_mut().zip(buffer) {
+            *c = crate::Complex::new(b.re * scale, b.im * scale);
+        }
+        Ok(arr.into())
+    }
+    #[cfg(not(feature = "complex"))]
+    fn fft_impl(self, _inverse: bool, env: &Uiua) -> UiuaResult<Self> {
+        Err(env.error("fft is not available without the complex feature"))
+    }
+}
+
+impl Value {
+    /// Convert the strings in a value to uppercase
+    pub fn uppercase(self, env: &Uiua) -> UiuaResult<Self> {
+        self.map_strings("uppercase", str::to_uppercase, env)
+    }
+    /// Convert the strings in a value to lowercase
+    pub fn lowercase(self, env: &Uiua) -> UiuaResult<Self> {
+        self.map_strings("lowercase", str::to_lowercase, env)
+    }
+    /// Convert the strings in a value to titlecase
+    pub fn titlecase(self, env: &Uiua) -> UiuaResult<Self> {
+        self.map_strings("titlecase", titlecase, env)
+    }
+    /// Put the strings in a value into Unicode Normalization Form C
+    pub fn nfc(self, env: &Uiua) -> UiuaResult<Self> {
+        self.map_strings("normalize", |s| s.nfc().collect(), env)
+    }
+    /// Put the strings in a value into Unicode Normalization Form D
+    pub fn nfd(self, env: &Uiua) -> UiuaResult<Self> {
+        self.map_strings("normalize", |s| s.nfd().collect(), env)
+    }
+    /// Split a string into its grapheme clusters
+    pub fn graphemes(&self, env: &Uiua) -> UiuaResult<Self> {
+        let s = self.as_string(env, "Graphemes argument must be a string")?;
+        Ok(Array::<Boxed>::from_iter(s.graphemes(true).map(|g| Boxed(g.into()))).into())
+    }
+    /// Map each string in a character array or array of boxed strings
+    ///
+    /// The rows of a character array are padded with spaces if their lengths change differently.
+    fn map_strings(self, name: &str, f: fn(&str) -> String, env: &Uiua) -> UiuaResult<Self> {
+        Ok(match self {
+            Value::Char(arr) if arr.rank() == 0 => {
+                let mapped = f(&arr.data[0].to_string());
+                let mut chars = mapped.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Array::new(tiny_vec![], cowslice![c]).into(),
+                    _ => mapped.into(),
+                }
+            }
+            Value::Char(arr) => {
+                let row_len = arr.shape.last().copied().unwrap_or(1);
+                let strings: Vec<String> = (arr.data.chunks_exact(row_len.max(1)))
+                    .map(|row| f(&row.iter().collect::<String>()))
+                    .collect();
+                let max_len = if strings.is_empty() {
+                    row_len
+                } else {
+                    strings.iter().map(|s| s.chars().count()).max().unwrap()
+                };
+                let mut data = EcoVec::with_capacity(strings.len() * max_len);
+                for s in &strings {
+                    data.extend(s.chars().chain(repeat(' ')).take(max_len));
+                }
+                let mut shape = arr.shape;
+                *shape.last_mut().unwrap() = max_len;
+                Array::new(shape, data).into()
+            }
+            Value::Box(arr) => {
+                let mut data = EcoVec::with_capacity(arr.element_count());
+                for b in arr.data {
+                    data.push(Boxed(b.into_inner().map_strings(name, f, env)?));
+                }
+                Array::new(arr.shape, data).into()
+            }
+            value => return Err(env.error(format!("Cannot {name} {}", value.type_name_plural()))),
+        })
+    }
+}
+
+fn titlecase(s: &str) -> String {
+    let mut title = String::with_capacity(s.len());
+    let mut word_start = true;
+    for c in s.chars() {
+        if word_start {
+            title.extend(c.to_uppercase());
+        } else {
+            title.extend(c.to_lowercase());
+        }
+        word_start = !c.is_alphanumeric() && c != '\'';
+    }
+    title
+}
+
+impl Value {
+    /// Get the prime factors of a number
+    pub fn factor(&self, env: &Uiua) -> UiuaResult<Self> {
+        let nums = self.as_number_array(
+            env,
+            "Only natural numbers can be factored",
+            |_| true,
+            |n| n.fract() == 0.0 && n >= 0.0,
+            |n| n,
+        )?;
+        let factor = |n: f64| {
+            if n == 0.0 {
+                return Err(env.error("Cannot factor 0"));
+            }
+            if n > 2f64.powi(53) {
+                return Err(env.error(format!("{n} is too large to factor")));
+            }
+            Ok(Array::<f64>::from_iter(
+                prime_factors(n as u64).map(|f| f as f64),
+            ))
+        };
+        Ok(if let Some(&n) = nums.as_scalar() {
+            factor(n)?.into()
+        } else {
+            let mut factored = EcoVec::with_capacity(nums.element_count());
+            for &n in &nums.data {
+                factored.push(Boxed(factor(n)?.into()));
+            }
+            Array::new(nums.shape, factored).into()
+        })
+    }
+}
+
+/// Get the prime factors of a number in ascending order
+fn prime_factors(mut n: u64) -> impl Iterator<Item = u64> {
+    let mut factors = Vec::new();
+    for p in [2, 3] {
+        while n / p * p == n {
+            factors.push(p);
+            n /= p;
+        }
+    }
+    // Check 6k±1 up to the square root, stopping early once what is left is prime
+    let mut k = 5;
+    while k * k <= n && !is_prime(n) {
+        for p in [k, k + 2] {
+            while n / p * p == n {
+                factors.push(p);
+                n /= p;
+            }
+        }
+        k += 6;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors.into_iter()
+}
+
+impl Value {
+    /// Convert the numbers in a value to rationals
+    #[cfg(feature = "rational")]
+    pub fn rational(self, env: &Uiua) -> UiuaResult<Self> {
+        Ok(match self {
+            Value::Num(arr) => arr.convert::<crate::Rational>().into(),
+            #[cfg(feature = "bytes")]
+            Value::Byte(arr) => arr.convert::<crate::Rational>().into(),
+            Value::Rational(arr) => arr.into(),
+            Value::Box(mut arr) => {
+                for b in arr.data.as_mut_slice() {
+                    b.0 = take(&mut b.0).rational(env)?;
+                }
+                arr.into()
+            }
+            value => {
+                return Err(env.error(format!(
+                    "Cannot convert {} to rationals",
+                    value.type_name_plural()
+                )))
+            }
+        })
+    }
+    /// Convert the numbers in a value to rationals
+    ///
+    /// Without the `rational` feature, this is always an error.
+    #[cfg(not(feature = "rational"))]
+    pub fn rational(self, env: &Uiua) -> UiuaResult<Self> {
+        Err(env.error("rational is not available without the rational feature"))
+    }
+    /// Convert the rationals in a value back to numbers
+    pub fn inv_rational(self, env: &Uiua) -> UiuaResult<Self> {
+        Ok(match self {
+            #[cfg(feature = "rational")]
+            Value::Rational(arr) => arr.convert::<f64>().into(),
+            Value::Box(mut arr) => {
+                for b in arr.data.as_mut_slice() {
+                    b.0 = take(&mut b.0).inv_rational(env)?;
+                }
+                arr.into()
+            }
+            value @ Value::Num(_) => value,
+            #[cfg(feature = "bytes")]
+            value @ Value::Byte(_) => value,
+            value => {
+                return Err(env.error(format!(
+                    "Cannot convert {} to numbers",
+                    value.type_name_plural()
+                )))
+            }
+        })
+    }
+}
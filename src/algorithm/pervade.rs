@@ -10,7 +10,9 @@ use std::{
 
 #[cfg(feature = "complex")]
 use crate::Complex;
-use crate::{array::*, cowslice::CowSlice, Uiua, UiuaError, UiuaResult};
+#[cfg(feature = "rational")]
+use crate::Rational;
+use crate::{array::*, cowslice::CowSlice, ErrorKind, Uiua, UiuaError, UiuaResult};
 
 use super::fill_array_shapes;
 
@@ -363,6 +365,10 @@ pub mod not {
     pub fn com(a: Complex) -> Complex {
         1.0 - a
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> Rational {
+        Rational::ONE - a
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot not {a}"))
     }
@@ -381,6 +387,10 @@ pub mod neg {
     pub fn com(a: Complex) -> Complex {
         -a
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> Rational {
+        -a
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot negate {a}"))
     }
@@ -398,6 +408,10 @@ pub mod abs {
     pub fn com(a: Complex) -> f64 {
         a.abs()
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> Rational {
+        a.abs()
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot take the absolute value of {a}"))
     }
@@ -421,6 +435,10 @@ pub mod sign {
     pub fn com(a: Complex) -> Complex {
         a.signum()
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> Rational {
+        a.signum()
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the sign of {a}"))
     }
@@ -438,6 +456,10 @@ pub mod sqrt {
     pub fn com(a: Complex) -> Complex {
         a.sqrt()
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> f64 {
+        num(a.into())
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot take the square root of {a}"))
     }
@@ -455,6 +477,10 @@ pub mod sin {
     pub fn com(a: Complex) -> Complex {
         a.sin()
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> f64 {
+        num(a.into())
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the sine of {a}"))
     }
@@ -472,6 +498,10 @@ pub mod cos {
     pub fn com(a: Complex) -> Complex {
         a.cos()
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> f64 {
+        num(a.into())
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the cosine of {a}"))
     }
@@ -489,6 +519,10 @@ pub mod asin {
     pub fn com(a: Complex) -> Complex {
         a.asin()
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> f64 {
+        num(a.into())
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the arcsine of {a}"))
     }
@@ -506,6 +540,10 @@ pub mod acos {
     pub fn com(a: Complex) -> Complex {
         a.acos()
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> f64 {
+        num(a.into())
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the arccosine of {a}"))
     }
@@ -523,6 +561,10 @@ pub mod floor {
     pub fn com(a: Complex) -> Complex {
         a.floor()
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> Rational {
+        a.floor()
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the floor of {a}"))
     }
@@ -540,6 +582,10 @@ pub mod ceil {
     pub fn com(a: Complex) -> Complex {
         a.ceil()
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> Rational {
+        a.ceil()
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the ceiling of {a}"))
     }
@@ -557,10 +603,73 @@ pub mod round {
     pub fn com(a: Complex) -> Complex {
         a.round()
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> Rational {
+        a.round()
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the rounded value of {a}"))
     }
 }
+pub mod prime {
+    use super::*;
+    pub fn num(a: f64) -> f64 {
+        let natural = a.fract() == 0.0 && (0.0..u64::MAX as f64).contains(&a);
+        (natural && is_prime(a as u64)) as u8 as f64
+    }
+    #[cfg(feature = "bytes")]
+    pub fn byte(a: u8) -> u8 {
+        is_prime(a as u64) as u8
+    }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> f64 {
+        num(a.into())
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot check if {a} is prime"))
+    }
+}
+
+/// Check if a number is prime with a deterministic Miller-Rabin test
+pub(crate) fn is_prime(n: u64) -> bool {
+    const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    for p in WITNESSES {
+        if n / p * p == n {
+            return n == p;
+        }
+    }
+    let mul_mod = |a: u64, b: u64| (a as u128 * b as u128 % n as u128) as u64;
+    let pow_mod = |mut base: u64, mut exp: u64| {
+        let mut result = 1;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = mul_mod(result, base);
+            }
+            base = mul_mod(base, base);
+            exp >>= 1;
+        }
+        result
+    };
+    let d = (n - 1) >> (n - 1).trailing_zeros();
+    WITNESSES.iter().all(|&a| {
+        let mut x = pow_mod(a, d);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        let mut d = d;
+        while d != n - 1 {
+            x = mul_mod(x, x);
+            d <<= 1;
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
 
 pub mod complex_re {
     use super::*;
@@ -588,10 +697,54 @@ pub mod complex_im {
     pub fn byte(_a: u8) -> u8 {
         0
     }
+    #[cfg(feature = "rational")]
+    pub fn rat(_a: Rational) -> Rational {
+        Rational::ZERO
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the imaginary part of {a}"))
     }
 }
+pub mod complex_arg {
+    use super::*;
+    #[cfg(feature = "complex")]
+    pub fn com(a: Complex) -> f64 {
+        a.arg()
+    }
+    pub fn num(a: f64) -> f64 {
+        if a.is_nan() {
+            f64::NAN
+        } else if a < 0.0 {
+            std::f64::consts::PI
+        } else {
+            0.0
+        }
+    }
+    #[cfg(feature = "bytes")]
+    pub fn byte(_a: u8) -> u8 {
+        0
+    }
+    #[cfg(feature = "rational")]
+    pub fn rat(a: Rational) -> f64 {
+        num(a.into())
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the argument of {a}"))
+    }
+}
+pub mod complex_conj {
+    use super::*;
+    #[cfg(feature = "complex")]
+    pub fn com(a: Complex) -> Complex {
+        a.conj()
+    }
+    pub fn generic<T>(a: T) -> T {
+        a
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the conjugate of {a}"))
+    }
+}
 
 macro_rules! cmp_impl {
     ($name:ident $eq:tt $ordering:expr) => {
@@ -622,6 +775,14 @@ macro_rules! cmp_impl {
             pub fn num_byte(a: f64, b: u8) -> u8 {
                 (f64::from(b).array_cmp(&a) $eq $ordering) as u8
             }
+            #[cfg(feature = "rational")]
+            pub fn rat_x(a: Rational, b: impl Into<Rational>) -> u8 {
+                (b.into().array_cmp(&a) $eq $ordering) as u8
+            }
+            #[cfg(feature = "rational")]
+            pub fn x_rat(a: impl Into<Rational>, b: Rational) -> u8 {
+                (b.array_cmp(&a.into()) $eq $ordering) as u8
+            }
             pub fn generic<T: Ord>(a: T, b: T) -> u8 {
                 (b.cmp(&a) $eq $ordering).into()
             }
@@ -681,6 +842,14 @@ pub mod add {
     pub fn char_byte(a: char, b: u8) -> char {
         char::from_u32((b as i64 + a as i64) as u32).unwrap_or('\0')
     }
+    #[cfg(feature = "rational")]
+    pub fn rat_x(a: Rational, b: impl Into<Rational>) -> Rational {
+        b.into() + a
+    }
+    #[cfg(feature = "rational")]
+    pub fn x_rat(a: impl Into<Rational>, b: Rational) -> Rational {
+        b + a.into()
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot add {a} and {b}"))
     }
@@ -721,13 +890,31 @@ pub mod sub {
     pub fn byte_char(a: u8, b: char) -> char {
         char::from_u32(((b as i64) - (a as i64)) as u32).unwrap_or('\0')
     }
+    #[cfg(feature = "rational")]
+    pub fn rat_x(a: Rational, b: impl Into<Rational>) -> Rational {
+        b.into() - a
+    }
+    #[cfg(feature = "rational")]
+    pub fn x_rat(a: impl Into<Rational>, b: Rational) -> Rational {
+        b - a.into()
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot subtract {a} from {b}"))
     }
 }
 
 macro_rules! bin_op_mod {
-    ($name:ident, $a:ident, $b:ident, $byte_convert:expr, $byte_ret:ty, $f:expr, $err:literal) => {
+    (
+        $name:ident,
+        $a:ident,
+        $b:ident,
+        $byte_convert:expr,
+        $byte_ret:ty,
+        $rat_convert:expr,
+        $rat_ret:ty,
+        $f:expr,
+        $err:literal
+    ) => {
         pub mod $name {
             use super::*;
             pub fn num_num($a: f64, $b: f64) -> f64 {
@@ -759,6 +946,18 @@ macro_rules! bin_op_mod {
                 let $a = $a.into();
                 $f
             }
+            #[cfg(feature = "rational")]
+            pub fn rat_x($a: Rational, $b: impl Into<Rational>) -> $rat_ret {
+                let $a = $rat_convert($a);
+                let $b = $rat_convert($b.into());
+                $f
+            }
+            #[cfg(feature = "rational")]
+            pub fn x_rat($a: impl Into<Rational>, $b: Rational) -> $rat_ret {
+                let $a = $rat_convert($a.into());
+                let $b = $rat_convert($b);
+                $f
+            }
             pub fn error<T: Display>($a: T, $b: T, env: &Uiua) -> UiuaError {
                 env.error(format!($err))
             }
@@ -772,17 +971,31 @@ bin_op_mod!(
     b,
     f64::from,
     f64,
+    Rational::from,
+    Rational,
     b * a,
     "Cannot multiply {a} and {b}"
 );
 
-bin_op_mod!(div, a, b, f64::from, f64, b / a, "Cannot divide {b} by {a}");
+bin_op_mod!(
+    div,
+    a,
+    b,
+    f64::from,
+    f64,
+    Rational::from,
+    Rational,
+    b / a,
+    "Cannot divide {b} by {a}"
+);
 bin_op_mod!(
     modulus,
     a,
     b,
     f64::from,
     f64,
+    Rational::from,
+    Rational,
     (b % a + a) % a,
     "Cannot take the modulus of {a} by {b}"
 );
@@ -792,9 +1005,112 @@ bin_op_mod!(
     b,
     f64::from,
     f64,
+    f64::from,
+    f64,
     a.atan2(b),
     "Cannot get the atan2 of {a} and {b}"
 );
+pub mod gcd {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        let (mut a, mut b) = (a.abs(), b.abs());
+        if !(a.is_finite() && b.is_finite()) {
+            return f64::NAN;
+        }
+        while b != 0.0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    }
+    #[cfg(feature = "bytes")]
+    pub fn byte_byte(a: u8, b: u8) -> f64 {
+        num_num(a.into(), b.into())
+    }
+    #[cfg(feature = "bytes")]
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    #[cfg(feature = "bytes")]
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!(
+            "Cannot get the greatest common divisor of {a} and {b}"
+        ))
+    }
+}
+pub mod lcm {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        let gcd = gcd::num_num(a, b);
+        if gcd == 0.0 {
+            0.0
+        } else {
+            (a * b).abs() / gcd
+        }
+    }
+    #[cfg(feature = "bytes")]
+    pub fn byte_byte(a: u8, b: u8) -> f64 {
+        num_num(a.into(), b.into())
+    }
+    #[cfg(feature = "bytes")]
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    #[cfg(feature = "bytes")]
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!(
+            "Cannot get the least common multiple of {a} and {b}"
+        ))
+    }
+}
+pub mod round_to {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        if a == 0.0 {
+            return b;
+        }
+        // Steps like 0.01 are not exact, so divide by their exact reciprocal instead
+        let recip = (1.0 / a).round();
+        if recip != 0.0 && 1.0 / recip == a {
+            (b * recip).round() / recip
+        } else {
+            (b / a).round() * a
+        }
+    }
+    #[cfg(feature = "bytes")]
+    pub fn byte_byte(a: u8, b: u8) -> f64 {
+        num_num(a.into(), b.into())
+    }
+    #[cfg(feature = "bytes")]
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    #[cfg(feature = "bytes")]
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    #[cfg(feature = "rational")]
+    pub fn rat_x(a: Rational, b: impl Into<Rational>) -> Rational {
+        let b = b.into();
+        if a.num == 0 {
+            b
+        } else {
+            (b / a).round() * a
+        }
+    }
+    #[cfg(feature = "rational")]
+    pub fn x_rat(a: impl Into<Rational>, b: Rational) -> Rational {
+        rat_x(a.into(), b)
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot round {b} to a multiple of {a}"))
+    }
+}
 pub mod pow {
     use super::*;
     pub fn num_num(a: f64, b: f64) -> f64 {
@@ -826,6 +1142,14 @@ pub mod pow {
         let a = a.into();
         b.powc(a)
     }
+    #[cfg(feature = "rational")]
+    pub fn rat_x(a: Rational, b: impl Into<Rational>) -> Rational {
+        b.into().pow(a)
+    }
+    #[cfg(feature = "rational")]
+    pub fn x_rat(a: impl Into<Rational>, b: Rational) -> Rational {
+        b.pow(a.into())
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the power of {a} to {b}"))
     }
@@ -836,6 +1160,8 @@ bin_op_mod!(
     b,
     f64::from,
     f64,
+    f64::from,
+    f64,
     b.log(a),
     "Cannot get the log base {b} of {a}"
 );
@@ -903,6 +1229,14 @@ pub mod max {
     pub fn x_com(a: impl Into<Complex>, b: Complex) -> Complex {
         a.into().max(b)
     }
+    #[cfg(feature = "rational")]
+    pub fn rat_x(a: Rational, b: impl Into<Rational>) -> Rational {
+        a.max(b.into())
+    }
+    #[cfg(feature = "rational")]
+    pub fn x_rat(a: impl Into<Rational>, b: Rational) -> Rational {
+        a.into().max(b)
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the max of {a} and {b}"))
     }
@@ -936,6 +1270,14 @@ pub mod min {
     pub fn x_com(a: impl Into<Complex>, b: Complex) -> Complex {
         a.into().min(b)
     }
+    #[cfg(feature = "rational")]
+    pub fn rat_x(a: Rational, b: impl Into<Rational>) -> Rational {
+        a.min(b.into())
+    }
+    #[cfg(feature = "rational")]
+    pub fn x_rat(a: impl Into<Rational>, b: Rational) -> Rational {
+        a.into().min(b)
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the min of {a} and {b}"))
     }
@@ -1050,11 +1392,13 @@ fn bin_pervade_recursive_generic<A: PervasiveInput, B: PervasiveInput, C>(
             let a_cells = a_shape[0];
             let b_cells = b_shape[0];
             if a_cells != b_cells {
-                return Err(env.error(format!(
-                    "Shapes {} and {} do not match",
-                    FormatShape(a_shape),
-                    FormatShape(b_shape)
-                )));
+                return Err(env
+                    .error(format!(
+                        "Shapes {} and {} do not match",
+                        FormatShape(a_shape),
+                        FormatShape(b_shape)
+                    ))
+                    .with_kind(ErrorKind::Shape));
             }
             let a_chunk_size = a.len() / a_cells;
             let b_chunk_size = b.len() / b_cells;
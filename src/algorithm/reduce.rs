@@ -1,19 +1,19 @@
 //! Algorithms for reducing modifiers
 
-use std::sync::Arc;
+use std::{iter::repeat_n, sync::Arc};
 
 use ecow::EcoVec;
 
 use crate::{
     algorithm::{
-        loops::{flip, rank_list, rank_to_depth},
+        loops::{flip, rank_list, ranked_args},
         pervade::*,
     },
     array::{Array, ArrayValue, Shape},
     cowslice::cowslice,
-    function::{Function, Signature},
+    function::{Function, Instr, Signature},
     value::Value,
-    Primitive, Uiua, UiuaResult,
+    ErrorKind, Primitive, Uiua, UiuaResult,
 };
 
 pub fn reduce(env: &mut Uiua) -> UiuaResult {
@@ -147,6 +147,99 @@ where
     }
 }
 
+type AxisFn = fn(f64, f64) -> f64;
+
+/// Get the identity and function of a primitive that can be reduced or scanned along any axis
+fn axis_fn(prim: Primitive, flipped: bool) -> Option<(f64, AxisFn)> {
+    Some(match (prim, flipped) {
+        (Primitive::Add, _) => (0.0, add::num_num),
+        (Primitive::Sub, false) => (0.0, sub::num_num),
+        (Primitive::Sub, true) => (0.0, |a, b| sub::num_num(b, a)),
+        (Primitive::Mul, _) => (1.0, mul::num_num),
+        (Primitive::Div, false) => (1.0, div::num_num),
+        (Primitive::Div, true) => (1.0, |a, b| div::num_num(b, a)),
+        (Primitive::Mod, false) => (1.0, modulus::num_num),
+        (Primitive::Mod, true) => (1.0, |a, b| modulus::num_num(b, a)),
+        (Primitive::Atan, false) => (0.0, atan2::num_num),
+        (Primitive::Atan, true) => (0.0, |a, b| atan2::num_num(b, a)),
+        (Primitive::Max, _) => (f64::NEG_INFINITY, max::num_num),
+        (Primitive::Min, _) => (f64::INFINITY, min::num_num),
+        _ => return None,
+    })
+}
+
+/// Reduce or scan a number array along an axis without splitting it into rows
+///
+/// `f` must be a [reduce](Primitive::Reduce) or [scan](Primitive::Scan) of a simple primitive.
+/// The array is given back if the function cannot be applied this way.
+#[allow(clippy::result_large_err)]
+pub(crate) fn axis_reduce(f: &Function, xs: Value, axis: usize) -> Result<Value, Value> {
+    let [Instr::PushFunc(g, _), Instr::Prim(modifier @ (Primitive::Reduce | Primitive::Scan), _)] =
+        f.instrs.as_slice()
+    else {
+        return Err(xs);
+    };
+    let Some((identity, g)) = (g.as_flipped_primitive()).and_then(|(p, fl)| axis_fn(p, fl)) else {
+        return Err(xs);
+    };
+    if axis >= xs.rank() {
+        return Err(xs);
+    }
+    let arr = match xs {
+        Value::Num(arr) => arr,
+        #[cfg(feature = "bytes")]
+        Value::Byte(arr) => arr.convert(),
+        xs => return Err(xs),
+    };
+    Ok(if *modifier == Primitive::Reduce {
+        fast_axis_reduce(arr, axis, identity, g)
+    } else {
+        fast_axis_scan(arr, axis, g)
+    }
+    .into())
+}
+
+fn fast_axis_reduce(arr: Array<f64>, axis: usize, identity: f64, f: AxisFn) -> Array<f64> {
+    let outer: usize = arr.shape[..axis].iter().product();
+    let len = arr.shape[axis];
+    let inner: usize = arr.shape[axis + 1..].iter().product();
+    let mut shape = arr.shape.clone();
+    shape.remove(axis);
+    let mut data = EcoVec::with_capacity(outer * inner);
+    for o in 0..outer {
+        let block = &arr.data[o * len * inner..(o + 1) * len * inner];
+        if len == 0 {
+            data.extend(repeat_n(identity, inner));
+            continue;
+        }
+        if inner == 0 {
+            continue;
+        }
+        let mut acc = block[..inner].to_vec();
+        for row in block[inner..].chunks_exact(inner) {
+            for (a, &b) in acc.iter_mut().zip(row) {
+                *a = f(*a, b);
+            }
+        }
+        data.extend(acc);
+    }
+    Array::new(shape, data)
+}
+
+fn fast_axis_scan(mut arr: Array<f64>, axis: usize, f: AxisFn) -> Array<f64> {
+    let len = arr.shape[axis];
+    let inner: usize = arr.shape[axis + 1..].iter().product();
+    if len == 0 || inner == 0 {
+        return arr;
+    }
+    for block in arr.data.as_mut_slice().chunks_exact_mut(len * inner) {
+        for i in inner..block.len() {
+            block[i] = f(block[i - inner], block[i]);
+        }
+    }
+    arr
+}
+
 fn generic_fold_right_1(
     f: Arc<Function>,
     xs: Value,
@@ -344,16 +437,7 @@ pub fn fold(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     let ns = rank_list("Fold", env)?;
     let f = env.pop_function()?;
-    let mut args = Vec::with_capacity(ns.len());
-    for i in 0..ns.len() {
-        let arg = env.pop(i + 1)?;
-        args.push(arg);
-    }
-    let ns: Vec<usize> = ns
-        .into_iter()
-        .zip(&args)
-        .map(|(n, arg)| rank_to_depth(n, arg.rank()))
-        .collect();
+    let (args, ns) = ranked_args(&ns, env)?;
     let res = fold_recursive(f, args, &ns, env)?;
     for val in res.into_iter().rev() {
         env.push(val);
@@ -398,11 +482,13 @@ fn fold_recursive(
             .windows(2)
             .find(|w| w[0].row_count() != w[1].row_count())
         {
-            return Err(env.error(format!(
-                "Cannot fold arrays with shapes {} and {}",
-                w[0].format_shape(),
-                w[1].format_shape()
-            )));
+            return Err(env
+                .error(format!(
+                    "Cannot fold arrays with shapes {} and {}",
+                    w[0].format_shape(),
+                    w[1].format_shape()
+                ))
+                .with_kind(ErrorKind::Shape));
         }
         let row_count = arrays[0].row_count();
         let mut array_iters: Vec<_> = arrays.into_iter().map(|array| array.into_rows()).collect();
@@ -452,11 +538,13 @@ fn fold_recursive(
                     continue;
                 }
                 if arg.row_count() != arg2.row_count() {
-                    return Err(env.error(format!(
-                        "Cannot fold arrays with shapes {} and {}",
-                        arg.format_shape(),
-                        arg2.format_shape()
-                    )));
+                    return Err(env
+                        .error(format!(
+                            "Cannot fold arrays with shapes {} and {}",
+                            arg.format_shape(),
+                            arg2.format_shape()
+                        ))
+                        .with_kind(ErrorKind::Shape));
                 }
             }
             row_count = arg.row_count();
@@ -0,0 +1,133 @@
+//! Statistical reductions
+
+use ecow::EcoVec;
+
+use crate::{array::Array, value::Value, Uiua, UiuaResult};
+
+impl Value {
+    /// Get the `mean` of the rows of the array
+    pub fn mean(&self, env: &Uiua) -> UiuaResult<Array<f64>> {
+        let arr = self.stat_array("mean", env)?;
+        let data = columns(&arr, 1, |col, out| out[0] = mean(col));
+        Ok(Array::new(&arr.shape[1..], data))
+    }
+    /// Get the `variance` of the rows of the array
+    pub fn variance(&self, env: &Uiua) -> UiuaResult<Array<f64>> {
+        let arr = self.stat_array("variance", env)?;
+        let data = columns(&arr, 1, |col, out| out[0] = variance(col));
+        Ok(Array::new(&arr.shape[1..], data))
+    }
+    /// Get the `median` of the rows of the array
+    pub fn median(&self, env: &Uiua) -> UiuaResult<Array<f64>> {
+        let arr = self.stat_array("median", env)?;
+        let data = columns(&arr, 1, |col, out| quantiles(col, &[0.5], out));
+        Ok(Array::new(&arr.shape[1..], data))
+    }
+    /// Use this value as fractions to get the `quantile`s of the rows of another
+    pub fn quantile(&self, of: &Self, env: &Uiua) -> UiuaResult<Array<f64>> {
+        let qs = self.as_number_array(
+            env,
+            "Quantiles must be numbers between 0 and 1",
+            |_| true,
+            |n| (0.0..=1.0).contains(&n),
+            |n| n,
+        )?;
+        let arr = of.stat_array("quantile", env)?;
+        let data = columns(&arr, qs.data.len(), |col, out| {
+            quantiles(col, &qs.data, out)
+        });
+        let mut shape = qs.shape;
+        shape.extend_from_slice(&arr.shape[1..]);
+        Ok(Array::new(shape, data))
+    }
+    fn stat_array(&self, name: &str, env: &Uiua) -> UiuaResult<Array<f64>> {
+        if self.rank() == 0 {
+            return Err(env.error(format!("Cannot take {name} of a scalar")));
+        }
+        if self.row_count() == 0 {
+            return Err(env.error(format!("Cannot take {name} of an empty array")));
+        }
+        match self {
+            Value::Num(arr) => Ok(arr.clone()),
+            #[cfg(feature = "bytes")]
+            Value::Byte(arr) => Ok(arr.convert_ref()),
+            value => Err(env.error(format!(
+                "Cannot take {name} of {}",
+                value.type_name_plural()
+            ))),
+        }
+    }
+}
+
+/// Apply a function to each column of an array's rows
+///
+/// The function writes `k` results for each column.
+/// The results are laid out as `k` rows, each with one element per column.
+fn columns(arr: &Array<f64>, k: usize, mut f: impl FnMut(&mut [f64], &mut [f64])) -> EcoVec<f64> {
+    let row_count = arr.row_count();
+    let row_len = arr.row_len();
+    let mut data = EcoVec::from_iter(std::iter::repeat_n(0.0, k * row_len));
+    let slice = data.make_mut();
+    let mut col = Vec::with_capacity(row_count);
+    let mut out = vec![0.0; k];
+    for j in 0..row_len {
+        col.clear();
+        col.extend((0..row_count).map(|i| arr.data[i * row_len + j]));
+        f(&mut col, &mut out);
+        for (i, &x) in out.iter().enumerate() {
+            slice[i * row_len + j] = x;
+        }
+    }
+    data
+}
+
+/// Get the mean of some numbers, using Neumaier summation to limit rounding errors
+fn mean(nums: &[f64]) -> f64 {
+    let mut sum = 0.0;
+    let mut compensation = 0.0;
+    for &x in nums {
+        let t = sum + x;
+        compensation += if sum.abs() >= x.abs() {
+            (sum - t) + x
+        } else {
+            (x - t) + sum
+        };
+        sum = t;
+    }
+    (sum + compensation) / nums.len() as f64
+}
+
+/// Get the population variance of some numbers with Welford's algorithm
+fn variance(nums: &[f64]) -> f64 {
+    let mut mean = 0.0;
+    let mut m2 = 0.0;
+    for (i, &x) in nums.iter().enumerate() {
+        let delta = x - mean;
+        mean += delta / (i + 1) as f64;
+        m2 += delta * (x - mean);
+    }
+    m2 / nums.len() as f64
+}
+
+/// Get quantiles of some numbers, interpolating linearly between them
+///
+/// The numbers are reordered.
+fn quantiles(nums: &mut [f64], qs: &[f64], out: &mut [f64]) {
+    let last = nums.len() - 1;
+    let lerp = |a: f64, b: f64, t: f64| if t == 0.0 { a } else { a + (b - a) * t };
+    if let [q] = qs {
+        // A single quantile only needs a partial sort
+        let h = q * last as f64;
+        let lo = h.floor() as usize;
+        let (_, &mut a, rest) = nums.select_nth_unstable_by(lo, f64::total_cmp);
+        let b = rest.iter().copied().min_by(f64::total_cmp).unwrap_or(a);
+        out[0] = lerp(a, b, h - lo as f64);
+    } else {
+        nums.sort_unstable_by(f64::total_cmp);
+        for (o, &q) in out.iter_mut().zip(qs) {
+            let h = q * last as f64;
+            let lo = h.floor() as usize;
+            *o = lerp(nums[lo], nums[(lo + 1).min(last)], h - lo as f64);
+        }
+    }
+}
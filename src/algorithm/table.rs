@@ -7,7 +7,7 @@ use tinyvec::tiny_vec;
 
 use crate::{
     algorithm::{
-        loops::{rank_list, rank_to_depth},
+        loops::{rank_list, ranked_args},
         pervade::*,
     },
     array::{Array, ArrayValue, Shape},
@@ -21,8 +21,15 @@ use super::{loops::flip, multi_output};
 pub fn table(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     let f = env.pop_function()?;
+    if f.signature().args > 2 {
+        return generic_table_n(f, false, env);
+    }
     let xs = env.pop(1)?;
     let ys = env.pop(2)?;
+    env.validate_alloc(
+        xs.element_count().saturating_mul(ys.element_count()),
+        xs.element_size().max(ys.element_size()),
+    )?;
     match (f.as_flipped_primitive(), xs, ys) {
         (Some((prim, flipped)), Value::Num(xs), Value::Num(ys)) => {
             if let Err((xs, ys)) = table_nums(prim, flipped, xs, ys, env) {
@@ -249,6 +256,9 @@ fn generic_table(f: Arc<Function>, xs: Value, ys: Value, env: &mut Uiua) -> Uiua
 pub fn cross(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     let f = env.pop_function()?;
+    if f.signature().args > 2 {
+        return generic_table_n(f, true, env);
+    }
     let xs = env.pop(1)?;
     let ys = env.pop(2)?;
     let sig = f.signature();
@@ -282,6 +292,73 @@ pub fn cross(env: &mut Uiua) -> UiuaResult {
     Ok(())
 }
 
+/// Apply a function to each combination of the elements or rows of 3 or more arrays
+///
+/// The first array's elements or rows vary the slowest.
+fn generic_table_n(f: Arc<Function>, rows: bool, env: &mut Uiua) -> UiuaResult {
+    let sig = f.signature();
+    let (name, past) = if rows {
+        ("cross", "crossed")
+    } else {
+        ("table", "tabled")
+    };
+    let mut args = Vec::with_capacity(sig.args);
+    for i in 0..sig.args {
+        args.push(env.pop(i + 1)?);
+    }
+    let mut new_shape = Shape::new();
+    for arg in &args {
+        if rows {
+            new_shape.push(arg.row_count());
+        } else {
+            new_shape.extend_from_slice(arg.shape());
+        }
+    }
+    let count: usize = new_shape.iter().product();
+    env.validate_alloc(
+        count,
+        args.iter().map(Value::element_size).max().unwrap_or(0),
+    )?;
+    let items_per_arg: Vec<Vec<Value>> = (args.into_iter())
+        .map(|arg| {
+            if rows {
+                arg.into_rows().collect()
+            } else {
+                arg.into_elements().collect()
+            }
+        })
+        .collect();
+    let outputs = sig.outputs;
+    let mut items = multi_output(outputs, Value::builder(count));
+    let mut indices = vec![0; items_per_arg.len()];
+    for _ in 0..count {
+        for (arg_items, &i) in items_per_arg.iter().zip(&indices).rev() {
+            env.push(arg_items[i].clone());
+        }
+        env.call_error_on_break_with(f.clone(), || format!("break is not allowed in {name}"))?;
+        for i in 0..outputs {
+            items[i].add_row(env.pop(format!("{past} function result"))?, env)?;
+        }
+        // Go to the next combination
+        for (index, arg_items) in indices.iter_mut().zip(&items_per_arg).rev() {
+            *index += 1;
+            if *index < arg_items.len() {
+                break;
+            }
+            *index = 0;
+        }
+    }
+    for items in items.into_iter().rev() {
+        let mut tabled = items.finish();
+        let mut new_shape = new_shape.clone();
+        new_shape.extend_from_slice(&tabled.shape()[1..]);
+        *tabled.shape_mut() = new_shape;
+        tabled.validate_shape();
+        env.push(tabled);
+    }
+    Ok(())
+}
+
 pub fn combinate(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     let ns = rank_list("Combinate", env)?;
@@ -306,16 +383,7 @@ pub fn combinate(env: &mut Uiua) -> UiuaResult {
             f_sig.args
         )));
     }
-    let mut args = Vec::with_capacity(ns.len());
-    for i in 0..ns.len() {
-        let arg = env.pop(i + 1)?;
-        args.push(arg);
-    }
-    let ns: Vec<usize> = ns
-        .into_iter()
-        .zip(&args)
-        .map(|(n, arg)| rank_to_depth(n, arg.rank()))
-        .collect();
+    let (mut args, ns) = ranked_args(&ns, env)?;
     let res = multi_combinate_recursive(f, &mut args, &ns, 0, env)?;
     env.push(res);
     Ok(())
@@ -4,13 +4,14 @@ use std::sync::Arc;
 
 use crate::{
     algorithm::{
-        loops::{rank_list, rank_to_depth},
+        loops::{rank_list, rank_to_depth, ranked_args},
         pervade::bin_pervade_generic,
+        reduce,
     },
     array::{FormatShape, Shape},
     function::Function,
     value::Value,
-    Uiua, UiuaResult,
+    ErrorKind, Uiua, UiuaResult,
 };
 
 use super::{multi_output, MultiOutput};
@@ -37,20 +38,29 @@ fn each1(f: Arc<Function>, xs: Value, env: &mut Uiua) -> UiuaResult {
     let outputs = f.signature().outputs;
     let mut new_values = multi_output(outputs, Vec::with_capacity(xs.element_count()));
     let new_shape = Shape::from(xs.shape());
-    let mut old_values = xs.into_elements();
-    for val in old_values.by_ref() {
-        env.push(val);
-        let broke = env.call_catch_break(f.clone())?;
-        for i in 0..outputs {
-            new_values[i].push(env.pop("each's function result")?);
+    if env.can_run_in_parallel(&f, xs.element_count()) {
+        let args = xs.into_elements().map(|val| vec![val]).collect();
+        for results in env.par_call(&f, args)? {
+            for (i, result) in results.into_iter().enumerate() {
+                new_values[i].push(result);
+            }
         }
-        if broke {
-            for row in old_values {
-                for i in 0..outputs {
-                    new_values[i].push(row.clone());
+    } else {
+        let mut old_values = xs.into_elements();
+        for val in old_values.by_ref() {
+            env.push(val);
+            let broke = env.call_catch_break(f.clone())?;
+            for i in 0..outputs {
+                new_values[i].push(env.pop("each's function result")?);
+            }
+            if broke {
+                for row in old_values {
+                    for i in 0..outputs {
+                        new_values[i].push(row.clone());
+                    }
                 }
+                break;
             }
-            break;
         }
     }
     for new_values in new_values.into_iter().rev() {
@@ -108,7 +118,7 @@ fn eachn(f: Arc<Function>, args: Vec<Value>, env: &mut Uiua) -> UiuaResult {
                 If you want more flexibility, use rows.",
                 win[0].format_shape(),
                 win[1].format_shape()
-            )));
+            )).with_kind(ErrorKind::Shape));
         }
     }
     let elem_count = args[0].element_count();
@@ -147,20 +157,29 @@ pub fn rows(env: &mut Uiua) -> UiuaResult {
 fn rows1(f: Arc<Function>, xs: Value, env: &mut Uiua) -> UiuaResult {
     let outputs = f.signature().outputs;
     let mut new_rows = multi_output(outputs, Value::builder(xs.row_count()));
-    let mut old_rows = xs.into_rows();
-    for row in old_rows.by_ref() {
-        env.push(row);
-        let broke = env.call_catch_break(f.clone())?;
-        for i in 0..outputs {
-            new_rows[i].add_row(env.pop("rows' function result")?, env)?;
+    if env.can_run_in_parallel(&f, xs.row_count()) {
+        let args = xs.into_rows().map(|row| vec![row]).collect();
+        for results in env.par_call(&f, args)? {
+            for (i, result) in results.into_iter().enumerate() {
+                new_rows[i].add_row(result, env)?;
+            }
         }
-        if broke {
-            for row in old_rows {
-                for i in 0..outputs {
-                    new_rows[i].add_row(row.clone(), env)?;
+    } else {
+        let mut old_rows = xs.into_rows();
+        for row in old_rows.by_ref() {
+            env.push(row);
+            let broke = env.call_catch_break(f.clone())?;
+            for i in 0..outputs {
+                new_rows[i].add_row(env.pop("rows' function result")?, env)?;
+            }
+            if broke {
+                for row in old_rows {
+                    for i in 0..outputs {
+                        new_rows[i].add_row(row.clone(), env)?;
+                    }
                 }
+                break;
             }
-            break;
         }
     }
     for new_rows in new_rows.into_iter().rev() {
@@ -181,12 +200,21 @@ fn rows2(f: Arc<Function>, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
     let mut new_rows = multi_output(outputs, Vec::with_capacity(xs.row_count()));
     let x_rows = xs.into_rows();
     let y_rows = ys.into_rows();
-    for (x, y) in x_rows.into_iter().zip(y_rows) {
-        env.push(y);
-        env.push(x);
-        env.call_error_on_break(f.clone(), "break is not allowed in multi-argument rows")?;
-        for i in 0..outputs {
-            new_rows[i].push(env.pop("rows's function result")?);
+    if env.can_run_in_parallel(&f, x_rows.len()) {
+        let args = x_rows.zip(y_rows).map(|(x, y)| vec![x, y]).collect();
+        for results in env.par_call(&f, args)? {
+            for (i, result) in results.into_iter().enumerate() {
+                new_rows[i].push(result);
+            }
+        }
+    } else {
+        for (x, y) in x_rows.into_iter().zip(y_rows) {
+            env.push(y);
+            env.push(x);
+            env.call_error_on_break(f.clone(), "break is not allowed in multi-argument rows")?;
+            for i in 0..outputs {
+                new_rows[i].push(env.pop("rows's function result")?);
+            }
         }
     }
     for new_rows in new_rows.into_iter().rev() {
@@ -447,6 +475,14 @@ pub fn level(env: &mut Uiua) -> UiuaResult {
                 env.push(xs);
                 return Ok(());
             }
+            let axis = rank_to_depth(n, xs.rank());
+            let xs = match reduce::axis_reduce(&f, xs, axis) {
+                Ok(res) => {
+                    env.push(res);
+                    return Ok(());
+                }
+                Err(xs) => xs,
+            };
             match n {
                 Some(0) => return each1(f, xs, env),
                 Some(-1) => return rows1(f, xs, env),
@@ -457,7 +493,7 @@ pub fn level(env: &mut Uiua) -> UiuaResult {
                 Some(_) => {}
             }
             let n = rank_to_depth(n, xs.rank());
-            let res = monadic_level_recursive(f, xs, n, env)?;
+            let res = level_recursive(f, vec![xs], &[n], env)?;
             env.push(res);
         }
         &[xn, yn] => {
@@ -482,167 +518,72 @@ pub fn level(env: &mut Uiua) -> UiuaResult {
             }
             let xn = rank_to_depth(xn, xs.rank());
             let yn = rank_to_depth(yn, ys.rank());
-            let res = dyadic_level_recursive(f, xs, ys, xn, yn, env)?;
+            let res = level_recursive(f, vec![xs, ys], &[xn, yn], env)?;
             env.push(res);
         }
-        is => {
-            let mut args = Vec::with_capacity(is.len());
-            for i in 0..is.len() {
-                let arg = env.pop(i + 1)?;
-                args.push(arg);
-            }
-            let mut ns: Vec<usize> = Vec::with_capacity(is.len());
-            for (i, arg) in args.iter().enumerate() {
-                ns.push(rank_to_depth(is[i], arg.rank()));
-            }
-            let res = multi_level_recursive(f, args, &ns, env)?;
+        ns => {
+            let (args, depths) = ranked_args(ns, env)?;
+            let res = level_recursive(f, args, &depths, env)?;
             env.push(res);
         }
     }
     Ok(())
 }
 
-fn monadic_level_recursive(
+/// Apply a function at the given depths of its arguments
+///
+/// The frames of the arguments, the parts of their shapes above their depths, must agree where they overlap.
+/// An argument whose frame is shorter is passed whole to each call below it.
+fn level_recursive(
     f: Arc<Function>,
-    value: Value,
-    n: usize,
-    env: &mut Uiua,
-) -> UiuaResult<Value> {
-    if n == 0 {
-        env.push(value);
-        env.call(f)?;
-        Ok(env.pop("level's function result")?)
-    } else {
-        let mut rows = Vec::with_capacity(value.row_count());
-        for row in value.into_rows() {
-            rows.push(monadic_level_recursive(f.clone(), row, n - 1, env)?);
-        }
-        Value::from_row_values(rows, env)
-    }
-}
-
-fn dyadic_level_recursive(
-    f: Arc<Function>,
-    xs: Value,
-    ys: Value,
-    xn: usize,
-    yn: usize,
+    args: Vec<Value>,
+    depths: &[usize],
     env: &mut Uiua,
 ) -> UiuaResult<Value> {
-    let xs_prefix = &xs.shape()[..xn];
-    let ys_prefix = &ys.shape()[..yn];
-    if !xs_prefix.iter().zip(ys_prefix).all(|(a, b)| a == b) {
-        return Err(env.error(format!(
-            "Cannot level with ranks {} and {} arrays with shapes {} and {} \
-            because shape prefixes {} and {} are not compatible",
-            xs.rank() - xn,
-            ys.rank() - yn,
-            xs.format_shape(),
-            ys.format_shape(),
-            FormatShape(xs_prefix),
-            FormatShape(ys_prefix)
-        )));
-    }
-    Ok(match (xn, yn) {
-        (0, 0) => {
-            env.push(ys);
-            env.push(xs);
-            env.call(f)?;
-            env.pop("level's function result")?
-        }
-        (0, yn) => {
-            let mut new_rows = Vec::with_capacity(ys.row_count());
-            for y in ys.into_rows() {
-                new_rows.push(dyadic_level_recursive(
-                    f.clone(),
-                    xs.clone(),
-                    y,
-                    xn,
-                    yn - 1,
-                    env,
-                )?);
-            }
-            Value::from_row_values(new_rows, env)?
-        }
-        (xn, 0) => {
-            let mut new_rows = Vec::with_capacity(xs.row_count());
-            for x in xs.into_rows() {
-                new_rows.push(dyadic_level_recursive(
-                    f.clone(),
-                    x,
-                    ys.clone(),
-                    xn - 1,
-                    yn,
-                    env,
-                )?);
-            }
-            Value::from_row_values(new_rows, env)?
-        }
-        (xn, yn) => {
-            let mut new_rows = Vec::with_capacity(xs.row_count());
-            for (x, y) in xs.into_rows().zip(ys.into_rows()) {
-                new_rows.push(dyadic_level_recursive(
-                    f.clone(),
-                    x,
-                    y,
-                    xn - 1,
-                    yn - 1,
-                    env,
-                )?);
+    for (i, (a, &an)) in args.iter().zip(depths).enumerate() {
+        for (b, &bn) in args.iter().zip(depths).skip(i + 1) {
+            let a_prefix = &a.shape()[..an];
+            let b_prefix = &b.shape()[..bn];
+            if !a_prefix.iter().zip(b_prefix).all(|(a, b)| a == b) {
+                return Err(env
+                    .error(format!(
+                        "Cannot level with ranks {} and {} arrays with shapes {} and {} \
+                    because shape prefixes {} and {} are not compatible",
+                        a.rank() - an,
+                        b.rank() - bn,
+                        a.format_shape(),
+                        b.format_shape(),
+                        FormatShape(a_prefix),
+                        FormatShape(b_prefix)
+                    ))
+                    .with_kind(ErrorKind::Shape));
             }
-            Value::from_row_values(new_rows, env)?
         }
-    })
+    }
+    level_rows(f, args, depths, env)
 }
 
-fn multi_level_recursive(
+fn level_rows(
     f: Arc<Function>,
     args: Vec<Value>,
-    ns: &[usize],
+    depths: &[usize],
     env: &mut Uiua,
 ) -> UiuaResult<Value> {
-    if ns.iter().all(|&n| n == 0) {
+    let Some(i) = depths.iter().position(|&n| n > 0) else {
         for arg in args.into_iter().rev() {
             env.push(arg);
         }
         env.call_error_on_break(f, "break is not allowed in level")?;
-        Ok(env.pop("level's function result")?)
-    } else {
-        let (&n_with_max_row_count, arg_with_max_row_count) = ns
-            .iter()
-            .zip(&args)
-            .max_by_key(|&(&n, v)| if n == 0 { 1 } else { v.shape()[0] })
-            .unwrap();
-        for (n, arg) in ns.iter().zip(&args) {
-            if !arg.shape()[..*n]
-                .iter()
-                .zip(&arg_with_max_row_count.shape()[..n_with_max_row_count])
-                .all(|(a, b)| a == b)
-            {
-                return Err(env.error(format!(
-                    "Cannot level with ranks {} and {} arrays with shapes {} and {}",
-                    arg_with_max_row_count.rank() - n_with_max_row_count,
-                    arg.rank() - n,
-                    arg_with_max_row_count.format_shape(),
-                    arg.format_shape()
-                )));
-            }
-        }
-        let row_count = if n_with_max_row_count == 0 {
-            1
-        } else {
-            arg_with_max_row_count.shape()[0]
-        };
-        let mut rows = Vec::with_capacity(row_count);
-        let mut row_args = args.clone();
-        let dec_ns: Vec<usize> = ns.iter().map(|n| n.saturating_sub(1)).collect();
-        for i in 0..row_count {
-            for (j, (arg, n)) in args.iter().zip(ns).enumerate() {
-                row_args[j] = if *n == 0 { arg.clone() } else { arg.row(i) };
-            }
-            let row = multi_level_recursive(f.clone(), row_args.clone(), &dec_ns, env)?;
-            rows.push(row);
-        }
-        Value::from_row_values(rows, env)
+        return env.pop("level's function result");
+    };
+    let row_count = args[i].row_count();
+    let sub_depths: Vec<usize> = depths.iter().map(|n| n.saturating_sub(1)).collect();
+    let mut rows = Vec::with_capacity(row_count);
+    for r in 0..row_count {
+        let row_args = (args.iter().zip(depths))
+            .map(|(arg, &n)| if n == 0 { arg.clone() } else { arg.row(r) })
+            .collect();
+        rows.push(level_rows(f.clone(), row_args, &sub_depths, env)?);
     }
+    Value::from_row_values(rows, env)
 }
@@ -2,17 +2,20 @@ use std::{
     cmp::Ordering,
     fmt::{self, Debug, Display},
     hash::{Hash, Hasher},
+    mem::size_of,
+    sync::Arc,
 };
 
 use ecow::EcoVec;
 use tinyvec::{tiny_vec, TinyVec};
 
 use crate::{
+    algorithm::map::MapKeys,
     boxed::Boxed,
     cowslice::{cowslice, CowSlice},
     grid_fmt::GridFmt,
     value::Value,
-    Complex, Uiua,
+    Complex, Rational, Uiua,
 };
 
 /// Uiua's array type
@@ -20,6 +23,8 @@ use crate::{
 pub struct Array<T> {
     pub(crate) shape: Shape,
     pub(crate) data: CowSlice<T>,
+    /// The keys of the array's rows if it is a map
+    pub(crate) map_keys: Option<Arc<MapKeys>>,
 }
 
 /// Uiua's array shape type
@@ -30,6 +35,7 @@ impl<T: ArrayValue> Default for Array<T> {
         Self {
             shape: tiny_vec![0],
             data: CowSlice::new(),
+            map_keys: None,
         }
     }
 }
@@ -89,7 +95,11 @@ impl<T> Array<T> {
         let shape = shape.into();
         let data = data.into();
         validate_shape(&shape, &data);
-        Self { shape, data }
+        Self {
+            shape,
+            data,
+            map_keys: None,
+        }
     }
     #[track_caller]
     #[inline(always)]
@@ -109,6 +119,10 @@ impl<T> Array<T> {
     pub fn row_len(&self) -> usize {
         self.shape.iter().skip(1).product()
     }
+    /// Get the number of bytes taken up by the array's elements
+    pub(crate) fn data_size(&self) -> usize {
+        self.data.len() * size_of::<T>()
+    }
     /// Get the rank of the array
     pub fn rank(&self) -> usize {
         self.shape.len()
@@ -125,6 +139,10 @@ impl<T> Array<T> {
     pub fn row_slices(&self) -> impl ExactSizeIterator<Item = &[T]> + DoubleEndedIterator {
         (0..self.row_count()).map(move |row| self.row_slice(row))
     }
+    /// Get the keys of the array's rows if it is a map
+    pub(crate) fn map_keys(&self) -> Option<&MapKeys> {
+        (self.map_keys.as_deref()).filter(|keys| self.rank() > 0 && keys.len() == self.row_count())
+    }
     /// Get a slice of a row
     #[track_caller]
     pub fn row_slice(&self, row: usize) -> &[T] {
@@ -194,6 +212,7 @@ impl<T: ArrayValue> Array<T> {
         Array {
             shape: self.shape,
             data: self.data.into_iter().map(f).collect(),
+            map_keys: self.map_keys,
         }
     }
     /// Convert the elements of the array with a fallible function
@@ -204,6 +223,7 @@ impl<T: ArrayValue> Array<T> {
         Ok(Array {
             shape: self.shape,
             data: self.data.into_iter().map(f).collect::<Result<_, _>>()?,
+            map_keys: self.map_keys,
         })
     }
     /// Convert the elements of the array without consuming it
@@ -219,6 +239,7 @@ impl<T: ArrayValue> Array<T> {
         Array {
             shape: self.shape.clone(),
             data: self.data.iter().cloned().map(f).collect(),
+            map_keys: self.map_keys.clone(),
         }
     }
     /// Consume the array and get an iterator over its rows
@@ -260,6 +281,7 @@ impl<T: ArrayValue + ArrayCmp<U>, U: ArrayValue> PartialEq<Array<U>> for Array<T
             .iter()
             .zip(&other.data)
             .all(|(a, b)| a.array_eq(b))
+            && self.map_keys() == other.map_keys()
     }
 }
 
@@ -292,6 +314,9 @@ impl<T: ArrayValue> Hash for Array<T> {
     fn hash<H: Hasher>(&self, hasher: &mut H) {
         self.shape.hash(hasher);
         self.data.iter().for_each(|x| x.array_hash(hasher));
+        if let Some(keys) = self.map_keys() {
+            keys.hash(hasher);
+        }
     }
 }
 
@@ -375,6 +400,8 @@ pub trait ArrayValue: Clone + Debug + Display + GridFmt + ArrayCmp + Send + Sync
     fn get_fill(env: &Uiua) -> Option<Self>;
     /// Hash the value
     fn array_hash<H: Hasher>(&self, hasher: &mut H);
+    /// The value to fill with when an operation must fill but no fill is set
+    fn default_fill() -> Self;
     /// Delimiters for formatting
     fn format_delims() -> (&'static str, &'static str) {
         ("[", "]")
@@ -390,6 +417,9 @@ impl ArrayValue for f64 {
     fn get_fill(env: &Uiua) -> Option<Self> {
         env.num_fill()
     }
+    fn default_fill() -> Self {
+        0.0
+    }
     fn array_hash<H: Hasher>(&self, hasher: &mut H) {
         let v = if self.is_nan() {
             f64::NAN
@@ -407,6 +437,9 @@ impl ArrayValue for u8 {
     fn get_fill(env: &Uiua) -> Option<Self> {
         env.byte_fill()
     }
+    fn default_fill() -> Self {
+        0
+    }
     fn array_hash<H: Hasher>(&self, hasher: &mut H) {
         self.hash(hasher)
     }
@@ -423,6 +456,9 @@ impl ArrayValue for char {
     fn format_sep() -> &'static str {
         ""
     }
+    fn default_fill() -> Self {
+        ' '
+    }
     fn array_hash<H: Hasher>(&self, hasher: &mut H) {
         self.hash(hasher)
     }
@@ -433,6 +469,9 @@ impl ArrayValue for Boxed {
     fn get_fill(env: &Uiua) -> Option<Self> {
         env.box_fill()
     }
+    fn default_fill() -> Self {
+        Boxed(Value::default())
+    }
     fn array_hash<H: Hasher>(&self, hasher: &mut H) {
         self.hash(hasher)
     }
@@ -448,6 +487,9 @@ impl ArrayValue for Complex {
         #[cfg(not(feature = "complex"))]
         None
     }
+    fn default_fill() -> Self {
+        Complex::ZERO
+    }
     fn array_hash<H: Hasher>(&self, hasher: &mut H) {
         for n in [self.re, self.im] {
             n.array_hash(hasher);
@@ -455,6 +497,24 @@ impl ArrayValue for Complex {
     }
 }
 
+impl ArrayValue for Rational {
+    const NAME: &'static str = "rational";
+    fn get_fill(_env: &Uiua) -> Option<Self> {
+        #[cfg(feature = "rational")]
+        {
+            _env.rational_fill()
+        }
+        #[cfg(not(feature = "rational"))]
+        None
+    }
+    fn default_fill() -> Self {
+        Rational::ZERO
+    }
+    fn array_hash<H: Hasher>(&self, hasher: &mut H) {
+        self.hash(hasher)
+    }
+}
+
 /// Trait for comparing array elements
 pub trait ArrayCmp<U = Self> {
     /// Compare two elements
@@ -486,6 +546,12 @@ impl ArrayCmp for Complex {
     }
 }
 
+impl ArrayCmp for Rational {
+    fn array_cmp(&self, other: &Self) -> Ordering {
+        self.total_cmp(other)
+    }
+}
+
 impl ArrayCmp for char {
     fn array_cmp(&self, other: &Self) -> Ordering {
         self.cmp(other)
@@ -510,6 +576,30 @@ impl ArrayCmp<u8> for f64 {
     }
 }
 
+impl ArrayCmp<f64> for Rational {
+    fn array_cmp(&self, other: &f64) -> Ordering {
+        self.total_cmp(&Rational::from(*other))
+    }
+}
+
+impl ArrayCmp<Rational> for f64 {
+    fn array_cmp(&self, other: &Rational) -> Ordering {
+        Rational::from(*self).total_cmp(other)
+    }
+}
+
+impl ArrayCmp<u8> for Rational {
+    fn array_cmp(&self, other: &u8) -> Ordering {
+        self.total_cmp(&Rational::from(*other))
+    }
+}
+
+impl ArrayCmp<Rational> for u8 {
+    fn array_cmp(&self, other: &Rational) -> Ordering {
+        Rational::from(*self).total_cmp(other)
+    }
+}
+
 /// A formattable shape
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct FormatShape<'a>(pub &'a [usize]);
@@ -16,6 +16,8 @@ pub enum Item {
     Words(Vec<Sp<Word>>),
     /// A binding
     Binding(Binding),
+    /// A module import
+    Import(Import),
     /// A test scope
     TestScope(Sp<Vec<Item>>),
     /// Extra newlines between items
@@ -33,16 +35,39 @@ impl Item {
                 first.merge(last)
             }
             Item::Binding(binding) => binding.span(),
+            Item::Import(import) => import.span(),
             Item::ExtraNewlines(span) => span.clone(),
         }
     }
 }
 
+/// A module import
+///
+/// The module's bindings are accessible as `Name¤Binding`
+#[derive(Debug, Clone)]
+pub struct Import {
+    /// The name of the module
+    pub name: Sp<Ident>,
+    /// The span of the currency sign
+    pub currency_span: CodeSpan,
+    /// The path to the module's file
+    pub path: Sp<String>,
+}
+
+impl Import {
+    /// Get the span of this import
+    pub fn span(&self) -> CodeSpan {
+        (self.name.span.clone()).merge(self.path.span.clone())
+    }
+}
+
 /// A binding
 #[derive(Debug, Clone)]
 pub struct Binding {
     /// The name of the binding
     pub name: Sp<Ident>,
+    /// The names after the first if the binding destructures an array into its rows
+    pub more_names: Vec<Sp<Ident>>,
     /// The span of the arrow
     pub arrow_span: CodeSpan,
     /// The signature
@@ -52,6 +77,10 @@ pub struct Binding {
 }
 
 impl Binding {
+    /// Get all the names of this binding
+    pub fn names(&self) -> impl Iterator<Item = &Sp<Ident>> {
+        [&self.name].into_iter().chain(&self.more_names)
+    }
     /// Get the span of this binding
     pub fn span(&self) -> CodeSpan {
         (self.name.span.clone()).merge(if let Some(last_word) = self.words.last() {
@@ -80,6 +109,7 @@ pub enum Word {
     Primitive(Primitive),
     Modified(Box<Modified>),
     Placeholder(Signature),
+    Binding(Box<Binding>),
     Comment(String),
     Spaces,
 }
@@ -128,6 +158,7 @@ impl fmt::Debug for Word {
             Word::Spaces => write!(f, "' '"),
             Word::Comment(comment) => write!(f, "# {comment}"),
             Word::Placeholder(sig) => write!(f, "^{}.{}", sig.args, sig.outputs),
+            Word::Binding(binding) => binding.fmt(f),
         }
     }
 }
@@ -61,6 +61,8 @@ impl BasicValue {
                 Value::Byte(b) => b.data.iter().map(|b| BasicValue::Num(*b as f64)).collect(),
                 #[cfg(feature = "complex")]
                 Value::Complex(c) => c.data.iter().map(|_| BasicValue::Other).collect(),
+                #[cfg(feature = "rational")]
+                Value::Rational(r) => r.data.iter().map(|_| BasicValue::Other).collect(),
                 Value::Char(c) => c.data.iter().map(|_| BasicValue::Other).collect(),
                 Value::Box(b) => b.data.iter().map(|_| BasicValue::Other).collect(),
             })
@@ -89,8 +91,8 @@ impl<'a> VirtualEnv<'a> {
     fn instr(&mut self, instr: &'a Instr) -> Result<(), String> {
         use Primitive::*;
         match instr {
-            Instr::Push(val) => self.stack.push(BasicValue::from_val(val)),
-            Instr::BeginArray => self.array_stack.push(self.stack.len()),
+            Instr::Push(val, _) => self.stack.push(BasicValue::from_val(val)),
+            Instr::BeginArray(_) => self.array_stack.push(self.stack.len()),
             Instr::EndArray { .. } => {
                 let bottom = self
                     .array_stack
@@ -105,8 +107,8 @@ impl<'a> VirtualEnv<'a> {
                 self.handle_sig(sig)?
             }
             Instr::PushTemp { count, .. } => self.handle_args_outputs(*count, 0)?,
-            Instr::PushTempFunctions(_) | Instr::PopTempFunctions(_) => {}
-            Instr::GetTempFunction { sig, .. } => {
+            Instr::PushTempFunctions { .. } | Instr::PopTempFunctions { .. } => {}
+            Instr::GetTempFunction { sig, .. } | Instr::GetGlobalFunction { sig, .. } => {
                 self.function_stack.push(Cow::Owned(Function::new(
                     FunctionId::Unnamed,
                     Vec::new(),
@@ -116,7 +118,7 @@ impl<'a> VirtualEnv<'a> {
             Instr::PopTemp { count, .. } | Instr::CopyTemp { count, .. } => {
                 self.handle_args_outputs(0, *count)?
             }
-            Instr::PushFunc(f) => self.function_stack.push(Cow::Borrowed(f)),
+            Instr::PushFunc(f, _) => self.function_stack.push(Cow::Borrowed(f)),
             &Instr::Switch { count, .. } => {
                 let mut funcs = Vec::with_capacity(count);
                 for _ in 0..count {
@@ -146,7 +148,11 @@ impl<'a> VirtualEnv<'a> {
                     }
                 }
             }
-            Instr::Dynamic(f) => self.handle_sig(f.signature)?,
+            Instr::Dynamic(f, _) => self.handle_sig(f.signature)?,
+            Instr::Format { parts, .. } => self.handle_sig(Signature::new(parts.len() - 1, 1))?,
+            Instr::MatchFormatPattern { parts, .. } => {
+                self.handle_sig(Signature::new(1, parts.len() - 1))?
+            }
             Instr::DropTemp { .. } => {}
             Instr::Prim(prim, _) => match prim {
                 Reduce | Scan => {
@@ -183,10 +189,45 @@ impl<'a> VirtualEnv<'a> {
                     };
                     self.handle_args_outputs(args, outputs)?;
                 }
+                PartIndex => {
+                    let sig = self.pop_func()?.signature();
+                    if !(2..=3).contains(&sig.args) {
+                        return Err(format!(
+                            "{prim}'s function must take 2 or 3 arguments, \
+                            but its signature is {sig}",
+                        ));
+                    }
+                    self.handle_args_outputs(2, sig.outputs)?;
+                }
+                Comptime => {
+                    let sig = self.pop_func()?.signature();
+                    self.handle_sig(sig)?;
+                }
                 Spawn => {
                     let sig = self.pop_func()?.signature();
                     self.handle_args_outputs(sig.args, 1)?;
                 }
+                Stencil => {
+                    let sig = self.pop_func()?.signature();
+                    if sig != (1, 1) {
+                        return Err(format!("stencil's function's signature is {sig}"));
+                    }
+                    self.handle_args_outputs(2, 1)?;
+                }
+                SortWith => {
+                    let sig = self.pop_func()?.signature();
+                    if sig != (2, 1) {
+                        return Err(format!("{prim}'s function's signature is {sig}"));
+                    }
+                    self.handle_args_outputs(1, 1)?;
+                }
+                SortBy | SortByDown | DeduplicateBy | ClassifyBy | OccurrencesBy => {
+                    let sig = self.pop_func()?.signature();
+                    if sig != (1, 1) {
+                        return Err(format!("{prim}'s function's signature is {sig}"));
+                    }
+                    self.handle_args_outputs(1, 1)?;
+                }
                 Repeat => {
                     let f = self.pop_func()?;
                     let n = self.pop()?;
@@ -245,6 +286,23 @@ impl<'a> VirtualEnv<'a> {
                         }
                     }
                 }
+                Beneath | Skip => {
+                    let f = self.pop_func()?;
+                    // The count must be known to know how many values are kept or discarded
+                    let n = match self.pop()? {
+                        BasicValue::Num(n) if n.fract() == 0.0 && n >= 0.0 => n as usize,
+                        _ => return Err(format!("{prim} without a constant natural number")),
+                    };
+                    let mut kept = Vec::with_capacity(n);
+                    for _ in 0..n {
+                        kept.push(self.pop()?);
+                    }
+                    self.set_min_height();
+                    self.handle_sig(f.signature())?;
+                    if *prim == Beneath {
+                        self.stack.extend(kept.into_iter().rev());
+                    }
+                }
                 Do => {
                     let f = self.pop_func()?;
                     let g = self.pop_func()?;
@@ -348,6 +406,24 @@ impl<'a> VirtualEnv<'a> {
                         // but the "no inverse found" error is more useful.
                     }
                 }
+                SetInverse => {
+                    let f = self.pop_func()?;
+                    let inv = self.pop_func()?;
+                    let (sig, inv_sig) = (f.signature(), inv.signature());
+                    if inv_sig.args != sig.outputs || inv_sig.outputs != sig.args {
+                        return Err(format!(
+                            "setinv's inverse's signature {inv_sig} \
+                            is not the opposite of its function's {sig}"
+                        ));
+                    }
+                    self.handle_sig(sig)?;
+                }
+                SetUnder => {
+                    let f = self.pop_func()?;
+                    let _before = self.pop_func()?;
+                    let _after = self.pop_func()?;
+                    self.handle_sig(f.signature())?;
+                }
                 Under => {
                     let f = self.pop_func()?;
                     let g = self.pop_func()?;
@@ -518,7 +594,7 @@ fn instrs_contain_break(instrs: &[Instr]) -> bool {
     for instr in instrs.iter() {
         match instr {
             Instr::Prim(Primitive::Break, _) => return true,
-            Instr::PushFunc(f) => {
+            Instr::PushFunc(f, _) => {
                 if instrs_contain_break(&f.instrs) {
                     return true;
                 }
@@ -540,7 +616,7 @@ mod test {
     where
         T: Into<Value>,
     {
-        Push(val.into().into())
+        Instr::push(val, 0)
     }
     #[test]
     fn instrs_signature() {
@@ -564,7 +640,7 @@ mod test {
         assert_eq!(
             Ok(sig(0, 1)),
             check(&[
-                BeginArray,
+                BeginArray(0),
                 push(3),
                 push(2),
                 push(1),
@@ -577,7 +653,7 @@ mod test {
         assert_eq!(
             Ok(sig(1, 1)),
             check(&[
-                BeginArray,
+                BeginArray(0),
                 push(3),
                 push(2),
                 push(1),
@@ -1,5 +1,5 @@
 use std::{
-    collections::hash_map::DefaultHasher,
+    collections::{hash_map::DefaultHasher, HashMap},
     hash::{Hash, Hasher},
     sync::Arc,
 };
@@ -14,21 +14,27 @@ use crate::{
     lex::{CodeSpan, Sp, Span},
     parse::{count_placeholders, ident_modifier_args},
     primitive::{ImplPrimitive, Primitive},
-    run::{Global, RunMode},
+    run::{CurrentBinding, Global, OptLevel, RunMode},
     value::Value,
-    Diagnostic, DiagnosticKind, Ident, SysOp, UiuaError, UiuaResult,
+    Diagnostic, DiagnosticKind, ErrorKind, Ident, SysOp, UiuaError, UiuaResult,
 };
 
 use crate::Uiua;
 
 impl Uiua {
     pub(crate) fn items(&mut self, items: Vec<Item>, in_test: bool) -> UiuaResult {
-        for item in items {
-            self.item(item, in_test)?;
-        }
-        Ok(())
+        let later_len = self.later_bindings.len();
+        (self.later_bindings).extend(items.iter().flat_map(|item| match item {
+            Item::Binding(binding) => later_bindings(binding),
+            _ => Vec::new(),
+        }));
+        let res = items
+            .into_iter()
+            .try_for_each(|item| self.item(item, in_test));
+        self.later_bindings.truncate(later_len);
+        res
     }
-    fn item(&mut self, item: Item, in_test: bool) -> UiuaResult {
+    pub(crate) fn item(&mut self, item: Item, in_test: bool) -> UiuaResult {
         fn words_have_import(words: &[Sp<Word>]) -> bool {
             words
                 .iter()
@@ -36,7 +42,13 @@ impl Uiua {
         }
         match item {
             Item::TestScope(items) => {
-                self.in_scope(|env| env.items(items.value, true))?;
+                // Handles opened in a test scope are closed when it ends
+                let open_handles = self.open_handles.lock().clone();
+                let span = self.add_span(items.span);
+                let res =
+                    self.with_span(span, |env| env.in_scope(|env| env.items(items.value, true)));
+                self.close_handles_opened_since(&open_handles);
+                res?;
             }
             Item::Words(words) => {
                 let can_run = match self.mode {
@@ -57,7 +69,8 @@ impl Uiua {
                             .into());
                     }
                     let instrs = self.compile_words(words, true)?;
-                    self.exec_global_instrs(instrs)?;
+                    let span = self.add_span(span);
+                    self.exec_global_instrs(instrs, span)?;
                 }
             }
             Item::Binding(binding) => {
@@ -69,15 +82,43 @@ impl Uiua {
                     self.binding(binding)?;
                 }
             }
+            Item::Import(import) => self.import_module(import)?,
             Item::ExtraNewlines(_) => {}
         }
         Ok(())
     }
+    fn import_module(&mut self, import: Import) -> UiuaResult {
+        let span = self.add_span(import.path.span.clone());
+        let path = self.with_span(span, |env| env.load_module(&import.path.value))?;
+        let names = self.imports.lock()[&path].clone();
+        for (name, idx) in names {
+            let name = format!("{}¤{}", import.name.value, name);
+            self.scope.names.insert(name.into(), idx);
+        }
+        Ok(())
+    }
     fn binding(&mut self, binding: Binding) -> UiuaResult {
+        // The global reserved by functions that referred to this binding before it was bound
+        let mut forward_index = None;
+        for name in binding.names() {
+            if let Some(i) =
+                (self.later_bindings.iter()).rposition(|later| later.name == name.value)
+            {
+                forward_index = self.later_bindings.remove(i).global_index;
+            }
+        }
+        if !binding.more_names.is_empty() {
+            return self.destructure(binding);
+        }
+        if let Some(modifier) = alias_target(&binding.words) {
+            return self.bind_modifier_alias(binding.name, modifier);
+        }
+        let binding_span = binding.span();
         let name = binding.name.value;
         let span = &binding.name.span;
 
         let placeholder_count = count_placeholders(&binding.words);
+        let name_span = self.add_span(span.clone());
 
         let make_fn = |mut instrs: Vec<Instr>, sig: Signature, env: &mut Self| {
             // Diagnostic for function that doesn't consume its arguments
@@ -98,19 +139,36 @@ impl Uiua {
             // Handle placeholders
             if placeholder_count > 0 {
                 increment_placeholders(&mut instrs, &mut 0);
-                instrs.insert(0, Instr::PushTempFunctions(placeholder_count));
-                instrs.push(Instr::PopTempFunctions(placeholder_count));
+                instrs.insert(
+                    0,
+                    Instr::PushTempFunctions {
+                        count: placeholder_count,
+                        span: name_span,
+                    },
+                );
+                instrs.push(Instr::PopTempFunctions {
+                    count: placeholder_count,
+                    span: name_span,
+                });
             }
             Function::new(FunctionId::Named(name.clone()), instrs, sig)
         };
         // Compile the body
-        let instrs = self.compile_words(binding.words, true)?;
+        self.current_bindings.push(CurrentBinding {
+            name: name.clone(),
+            signature: binding.signature.as_ref().map(|sig| sig.value),
+            global_index: forward_index,
+        });
+        let instrs = self.compile_words(binding.words, true);
+        let current = self.current_bindings.pop().unwrap();
+        let recursive_index = current.global_index;
+        let instrs = instrs?;
         // Resolve signature
         match instrs_signature(&instrs) {
             Ok(mut sig) => {
                 // Validate signature
                 if let Some(declared_sig) = &binding.signature {
-                    let sig_to_check = if let [Instr::PushFunc(f)] = instrs.as_slice() {
+                    let sig_to_check = if let [Instr::PushFunc(f, _)] = instrs.as_slice() {
                         // If this is a function wrapped in parens, check the signature of the
                         // function rather than the signature of the binding's words
                         f.signature()
@@ -120,24 +178,22 @@ impl Uiua {
                     if declared_sig.value == sig_to_check {
                         sig = declared_sig.value;
                     } else {
-                        return Err(UiuaError::Run(Span::Code(declared_sig.span.clone()).sp(
-                            format!(
-                                "Function signature mismatch:  declared {} but inferred {}",
-                                declared_sig.value, sig_to_check
-                            ),
-                        )));
+                        return Err(signature_mismatch(declared_sig, sig_to_check));
                     }
                 }
-                if let [Instr::PushFunc(f)] = instrs.as_slice() {
+                if let [Instr::PushFunc(f, _)] = instrs.as_slice() {
                     // Binding is a single inline function
                     let func = make_fn(f.instrs.clone(), f.signature(), self);
                     self.compile_bind_function(name, func.into(), span.clone().into())?;
                 } else if sig.args == 0
                     && (sig.outputs > 0 || instrs.is_empty())
                     && placeholder_count == 0
+                    && recursive_index.is_none()
+                    && !instrs_get_global_functions(&instrs)
                 {
                     // Binding's instrs must be run
-                    self.exec_global_instrs(instrs)?;
+                    let binding_span = self.add_span(binding_span);
+                    self.exec_global_instrs(instrs, binding_span)?;
                     if let Some(f) = self.function_stack.pop() {
                         // Binding is an imported function
                         self.compile_bind_function(name, f, span.clone().into())?;
@@ -167,6 +223,56 @@ impl Uiua {
                 }
             }
         }
+        // Fill in the global that recursive calls refer to
+        if let Some(index) = recursive_index {
+            let Some(bound) = self.bound_global(&current.name) else {
+                return Err(span
+                    .clone()
+                    .sp(format!(
+                        "Recursive function `{}` was not bound",
+                        current.name
+                    ))
+                    .into());
+            };
+            self.globals.lock()[index] = bound;
+        }
+        Ok(())
+    }
+    /// Bind the rows of a binding's value to its names
+    fn destructure(&mut self, binding: Binding) -> UiuaResult {
+        let binding_span = binding.span();
+        let names: Vec<Sp<Ident>> = binding.names().cloned().collect();
+        let name_span = (names[0].span.clone()).merge(names.last().unwrap().span.clone());
+        let instrs = self.compile_words(binding.words, true)?;
+        let sig = instrs_signature(&instrs).map_err(|e| {
+            UiuaError::Run(Span::Code(name_span.clone()).sp(format!(
+                "Cannot infer destructuring binding's signature: {e}"
+            )))
+        })?;
+        if let Some(declared_sig) = &binding.signature {
+            if declared_sig.value != sig {
+                return Err(signature_mismatch(declared_sig, sig));
+            }
+        }
+        if sig != (0, 1) && !instrs.is_empty() {
+            return Err(UiuaError::Run(Span::Code(name_span).sp(format!(
+                "A destructuring binding must produce exactly 1 value, \
+                but its signature is {sig}"
+            ))));
+        }
+        let binding_span = self.add_span(binding_span);
+        self.exec_global_instrs(instrs, binding_span)?;
+        let value = self.pop("destructured value")?;
+        if value.rank() == 0 || value.row_count() != names.len() {
+            return Err(UiuaError::Run(Span::Code(name_span).sp(format!(
+                "Cannot destructure an array of shape {} into {} names",
+                value.format_shape(),
+                names.len()
+            ))));
+        }
+        for (name, row) in names.into_iter().zip(value.into_rows()) {
+            self.compile_bind_value(name.value, row, name.span.into())?;
+        }
         Ok(())
     }
     pub(crate) fn compile_bind_value(
@@ -177,10 +283,7 @@ impl Uiua {
     ) -> UiuaResult {
         self.validate_binding_name(&name, &[], span)?;
         value.compress();
-        let mut globals = self.globals.lock();
-        let idx = globals.len();
-        globals.push(Global::Val(value));
-        self.scope.names.insert(name, idx);
+        self.bind_global(name, Global::Val(value));
         Ok(())
     }
     pub(crate) fn compile_bind_function(
@@ -190,13 +293,92 @@ impl Uiua {
         span: Span,
     ) -> UiuaResult {
         self.validate_binding_name(&name, &function.instrs, span)?;
-        let mut globals = self.globals.lock();
-        let idx = globals.len();
-        globals.push(Global::Func(function));
-        self.scope.names.insert(name, idx);
+        self.bind_global(name, Global::Func(function));
         Ok(())
     }
-    fn validate_binding_name(&self, name: &Ident, instrs: &[Instr], span: Span) -> UiuaResult {
+    /// Bind a name in the innermost inline function being compiled, or globally if there is none
+    fn bind_global(&mut self, name: Ident, global: Global) {
+        self.scope.modifier_aliases.remove(&name);
+        if let Some(frame) = self.local_frames.last_mut() {
+            frame.insert(name, global);
+        } else {
+            let mut globals = self.globals.lock();
+            let idx = globals.len();
+            globals.push(global);
+            self.scope.names.insert(name, idx);
+        }
+    }
+    /// Get what a name is bound to, looking in local bindings first
+    fn bound_global(&self, name: &Ident) -> Option<Global> {
+        if let Some(global) = (self.local_frames.iter().rev()).find_map(|frame| frame.get(name)) {
+            return Some(global.clone());
+        }
+        let idx =
+            (self.scope.names.get(name)).or_else(|| self.higher_scopes.last()?.names.get(name))?;
+        Some(self.globals.lock()[*idx].clone())
+    }
+    /// Bind a name to another modifier, which is substituted wherever the name is used
+    fn bind_modifier_alias(&mut self, name: Sp<Ident>, modifier: Sp<Modifier>) -> UiuaResult {
+        let target = match modifier.value {
+            Modifier::Ident(ident) => {
+                if let Some(target) = self.modifier_alias(&ident) {
+                    target
+                } else if self.bound_global(&ident).is_some() {
+                    Modifier::Ident(ident)
+                } else {
+                    return Err(modifier
+                        .span
+                        .sp(format!("Unknown identifier `{ident}`"))
+                        .into());
+                }
+            }
+            prim => prim,
+        };
+        let name_args = ident_modifier_args(&name.value);
+        if name_args != target.args() {
+            let target_name = match &target {
+                Modifier::Primitive(prim) => prim.to_string(),
+                Modifier::Ident(ident) => ident.to_string(),
+            };
+            let this = format!(
+                "{}{}",
+                name.value.trim_end_matches('!'),
+                "!".repeat(target.args() as usize)
+            );
+            return Err(name
+                .span
+                .sp(format!(
+                    "The name {} implies {name_args} modifier arguments, \
+                    but {target_name} takes {}. Try `{this}`.",
+                    name.value,
+                    target.args()
+                ))
+                .into());
+        }
+        self.scope.names.remove(&name.value);
+        if let Some(frame) = self.local_frames.last_mut() {
+            frame.remove(&name.value);
+        }
+        self.scope.modifier_aliases.insert(name.value, target);
+        Ok(())
+    }
+    /// Get the modifier a name is an alias for
+    fn modifier_alias(&self, name: &Ident) -> Option<Modifier> {
+        if self.scope.names.contains_key(name)
+            || (self.local_frames.iter()).any(|frame| frame.contains_key(name))
+        {
+            return None;
+        }
+        (self.scope.modifier_aliases.get(name))
+            .or_else(|| self.higher_scopes.last()?.modifier_aliases.get(name))
+            .cloned()
+    }
+    pub(crate) fn validate_binding_name(
+        &self,
+        name: &Ident,
+        instrs: &[Instr],
+        span: Span,
+    ) -> UiuaResult {
         let temp_function_count = count_temp_functions(instrs);
         let name_marg_count = ident_modifier_args(name) as usize;
         if temp_function_count != name_marg_count {
@@ -232,7 +414,7 @@ impl Uiua {
         let mut instrs = self.compile_words(words, true)?;
         let mut sig = None;
         // Extract function instrs if possible
-        if let [Instr::PushFunc(f)] = instrs.as_slice() {
+        if let [Instr::PushFunc(f, _)] = instrs.as_slice() {
             sig = Some(f.signature());
             instrs = f.instrs.clone();
         }
@@ -257,6 +439,10 @@ impl Uiua {
         use ImplPrimitive::*;
         use Primitive::*;
         let instrs = self.new_functions.last_mut().unwrap();
+        if self.opt_level < OptLevel::Peephole {
+            instrs.push(instr);
+            return;
+        }
         // Optimizations
         match (instrs.as_mut_slice(), instr) {
             // Cosine
@@ -322,8 +508,28 @@ impl Uiua {
             ) if *a_stack == b_stack => {
                 *a_count += b_count;
             }
-            // // Coalesce inline stack ops
-            // ([.., Instr::])
+            // Call a pushed primitive directly
+            ([.., Instr::PushFunc(f, _)], Instr::Call(span))
+                if f.as_primitive().is_some() || f.as_impl_primitive().is_some() =>
+            {
+                let instr = match f.as_primitive() {
+                    Some((prim, _)) => Instr::Prim(prim, span),
+                    None => Instr::ImplPrim(f.as_impl_primitive().unwrap().0, span),
+                };
+                instrs.pop();
+                instrs.push(instr);
+            }
+            // Pushing a value and then popping it does nothing
+            ([.., Instr::Push(..)], Instr::Prim(Pop, _)) => {
+                instrs.pop();
+            }
+            // Flipping twice does nothing, as long as it doesn't change
+            // how many arguments the function takes
+            ([rest @ .., Instr::Prim(Flip, _)], Instr::Prim(Flip, _))
+                if instrs_signature(rest).is_ok_and(|sig| sig.outputs >= 2) =>
+            {
+                instrs.pop();
+            }
             (_, instr) => instrs.push(instr),
         }
     }
@@ -335,14 +541,18 @@ impl Uiua {
     fn word(&mut self, word: Sp<Word>, call: bool) -> UiuaResult {
         match word.value {
             Word::Number(_, n) => {
+                let span = self.add_span(word.span.clone());
                 if call {
-                    self.push_instr(Instr::push(n));
+                    self.push_instr(Instr::push(n, span));
                 } else {
-                    self.push_instr(Instr::push_func(Function::new(
-                        FunctionId::Anonymous(word.span.clone()),
-                        vec![Instr::push(n)],
-                        Signature::new(0, 1),
-                    )));
+                    self.push_instr(Instr::push_func(
+                        Function::new(
+                            FunctionId::Anonymous(word.span),
+                            vec![Instr::push(n, span)],
+                            Signature::new(0, 1),
+                        ),
+                        span,
+                    ));
                 }
             }
             Word::Char(c) => {
@@ -351,57 +561,47 @@ impl Uiua {
                 } else {
                     c.into()
                 };
+                let span = self.add_span(word.span.clone());
                 if call {
-                    self.push_instr(Instr::push(val));
+                    self.push_instr(Instr::push(val, span));
                 } else {
-                    self.push_instr(Instr::push_func(Function::new(
-                        FunctionId::Anonymous(word.span.clone()),
-                        vec![Instr::push(val)],
-                        Signature::new(0, 1),
-                    )));
+                    self.push_instr(Instr::push_func(
+                        Function::new(
+                            FunctionId::Anonymous(word.span),
+                            vec![Instr::push(val, span)],
+                            Signature::new(0, 1),
+                        ),
+                        span,
+                    ));
                 }
             }
             Word::String(s) => {
+                let span = self.add_span(word.span.clone());
                 if call {
-                    self.push_instr(Instr::push(s));
+                    self.push_instr(Instr::push(s, span));
                 } else {
-                    self.push_instr(Instr::push_func(Function::new(
-                        FunctionId::Anonymous(word.span.clone()),
-                        vec![Instr::push(s)],
-                        Signature::new(0, 1),
-                    )));
+                    self.push_instr(Instr::push_func(
+                        Function::new(
+                            FunctionId::Anonymous(word.span),
+                            vec![Instr::push(s, span)],
+                            Signature::new(0, 1),
+                        ),
+                        span,
+                    ));
                 }
             }
             Word::FormatString(frags) => {
                 let signature = Signature::new(frags.len() - 1, 1);
-                let f = Function::new(
-                    FunctionId::Anonymous(word.span.clone()),
-                    vec![Instr::Dynamic(DynamicFunction {
-                        id: {
-                            let mut hasher = DefaultHasher::new();
-                            frags.hash(&mut hasher);
-                            hasher.finish()
-                        },
-                        f: Arc::new(move |env| {
-                            let mut formatted = String::new();
-                            for (i, frag) in frags.iter().enumerate() {
-                                if i > 0 {
-                                    let val = env.pop(format!("format argument {i}"))?;
-                                    formatted.push_str(&format!("{}", val));
-                                }
-                                formatted.push_str(frag);
-                            }
-                            env.push(formatted);
-                            Ok(())
-                        }),
-                        signature,
-                    })],
-                    signature,
-                );
-                self.push_instr(Instr::push_func(f));
+                let span = self.add_span(word.span.clone());
+                let instr = Instr::Format {
+                    parts: frags.into_iter().map(Into::into).collect(),
+                    span,
+                };
                 if call {
-                    let span = self.add_span(word.span);
-                    self.push_instr(Instr::Call(span));
+                    self.push_instr(instr);
+                } else {
+                    let f = Function::new(FunctionId::Anonymous(word.span), vec![instr], signature);
+                    self.push_instr(Instr::push_func(f, span));
                 }
             }
             Word::MultilineString(lines) => {
@@ -409,40 +609,43 @@ impl Uiua {
                     lines.iter().map(|l| l.value.len().saturating_sub(1)).sum(),
                     1,
                 );
+                let span = self.add_span(word.span.clone());
                 let f = Function::new(
                     FunctionId::Anonymous(word.span.clone()),
-                    vec![Instr::Dynamic(DynamicFunction {
-                        id: {
-                            let mut hasher = DefaultHasher::new();
-                            lines.hash(&mut hasher);
-                            hasher.finish()
-                        },
-                        f: Arc::new(move |env| {
-                            let mut formatted = String::new();
-                            let mut i = 0;
-                            for (j, line) in lines.iter().enumerate() {
-                                if j > 0 {
-                                    formatted.push('\n');
-                                }
-                                for (k, frag) in line.value.iter().enumerate() {
-                                    if k > 0 {
-                                        let val = env.pop(format!("format argument {i}"))?;
-                                        formatted.push_str(&format!("{}", val));
+                    vec![Instr::Dynamic(
+                        DynamicFunction {
+                            id: {
+                                let mut hasher = DefaultHasher::new();
+                                lines.hash(&mut hasher);
+                                hasher.finish()
+                            },
+                            f: Arc::new(move |env| {
+                                let mut formatted = String::new();
+                                let mut i = 0;
+                                for (j, line) in lines.iter().enumerate() {
+                                    if j > 0 {
+                                        formatted.push('\n');
+                                    }
+                                    for (k, frag) in line.value.iter().enumerate() {
+                                        if k > 0 {
+                                            let val = env.pop(format!("format argument {i}"))?;
+                                            formatted.push_str(&format!("{}", val));
+                                        }
+                                        formatted.push_str(frag);
+                                        i += 1;
                                     }
-                                    formatted.push_str(frag);
-                                    i += 1;
                                 }
-                            }
-                            env.push(formatted);
-                            Ok(())
-                        }),
-                        signature,
-                    })],
+                                env.push(formatted);
+                                Ok(())
+                            }),
+                            signature,
+                        },
+                        span,
+                    )],
                     signature,
                 );
-                self.push_instr(Instr::push_func(f));
+                self.push_instr(Instr::push_func(f, span));
                 if call {
-                    let span = self.add_span(word.span);
                     self.push_instr(Instr::Call(span));
                 }
             }
@@ -451,17 +654,18 @@ impl Uiua {
                 if !call {
                     self.new_functions.push(Vec::new());
                 }
-                self.push_instr(Instr::BeginArray);
+                let span = self.add_span(word.span.clone());
+                self.push_instr(Instr::BeginArray(span));
                 let inner = self.compile_words(items, true)?;
                 let mut instrs = inner.iter();
                 while let Some(instr) = instrs.next() {
                     match instr {
-                        Instr::Push(_) => {}
+                        Instr::Push(..) => {}
                         Instr::Prim(p, _)
                             if p.args() == Some(0)
                                 && p.outputs() == Some(1)
                                 && p.modifier_args().is_none() => {}
-                        Instr::BeginArray => {
+                        Instr::BeginArray(_) => {
                             while (instrs.next())
                                 .is_some_and(|instr| !matches!(instr, Instr::EndArray { .. }))
                             {
@@ -475,17 +679,16 @@ impl Uiua {
                         }
                     }
                 }
-                let span = self.add_span(word.span.clone());
                 let instrs = self.new_functions.last_mut().unwrap();
-                if call && inner.iter().all(|instr| matches!(instr, Instr::Push(_))) {
+                if call && inner.iter().all(|instr| matches!(instr, Instr::Push(..))) {
                     // Inline constant arrays
                     instrs.pop();
                     let values = inner.into_iter().rev().map(|instr| match instr {
-                        Instr::Push(v) => *v,
+                        Instr::Push(v, _) => *v,
                         _ => unreachable!(),
                     });
                     let val = self.with_span(span, |env| Value::from_row_values(values, env))?;
-                    self.push_instr(Instr::push(val));
+                    self.push_instr(Instr::push(val, span));
                 } else {
                     // Normal case
                     instrs.extend(inner);
@@ -495,7 +698,7 @@ impl Uiua {
                         let sig =
                             instrs_signature(&instrs).unwrap_or_else(|_| Signature::new(0, 0));
                         let func = Function::new(FunctionId::Anonymous(word.span), instrs, sig);
-                        self.push_instr(Instr::push_func(func));
+                        self.push_instr(Instr::push_func(func, span));
                     }
                 }
             }
@@ -503,19 +706,19 @@ impl Uiua {
                 if !call {
                     self.new_functions.push(Vec::new());
                 }
-                self.push_instr(Instr::BeginArray);
+                let span = self.add_span(word.span.clone());
+                self.push_instr(Instr::BeginArray(span));
                 let mut inner = Vec::new();
                 for lines in arr.lines.into_iter().rev() {
                     inner.extend(self.compile_words(lines, true)?);
                 }
-                let span = self.add_span(word.span.clone());
                 let instrs = self.new_functions.last_mut().unwrap();
-                if call && inner.iter().all(|instr| matches!(instr, Instr::Push(_))) {
+                if call && inner.iter().all(|instr| matches!(instr, Instr::Push(..))) {
                     // Inline constant arrays
                     instrs.pop();
                     let empty = inner.is_empty();
                     let values = inner.into_iter().rev().map(|instr| match instr {
-                        Instr::Push(v) => *v,
+                        Instr::Push(v, _) => *v,
                         _ => unreachable!(),
                     });
                     let val = self.with_span(span, |env| {
@@ -532,7 +735,7 @@ impl Uiua {
                             Value::from_row_values(values, env)
                         }
                     })?;
-                    self.push_instr(Instr::push(val));
+                    self.push_instr(Instr::push(val, span));
                 } else {
                     instrs.extend(inner);
                     self.push_instr(Instr::EndArray {
@@ -544,11 +747,12 @@ impl Uiua {
                         let sig =
                             instrs_signature(&instrs).unwrap_or_else(|_| Signature::new(0, 0));
                         let func = Function::new(FunctionId::Anonymous(word.span), instrs, sig);
-                        self.push_instr(Instr::push_func(func));
+                        self.push_instr(Instr::push_func(func, span));
                     }
                 }
             }
             Word::Func(func) => self.func(func, word.span)?,
+            Word::Binding(binding) => self.binding(*binding)?,
             Word::Switch(sw) => self.switch(sw, word.span, call)?,
             Word::Ocean(prims) => self.ocean(prims, call)?,
             Word::Primitive(p) => self.primitive(p, word.span, call)?,
@@ -569,36 +773,92 @@ impl Uiua {
         Ok(())
     }
     fn ident(&mut self, ident: Ident, span: CodeSpan, call: bool) -> UiuaResult {
-        if let Some(idx) = self
-            .scope
-            .names
-            .get(&ident)
-            .or_else(|| self.higher_scopes.last()?.names.get(&ident))
-        {
+        if let Some(global) = self.bound_global(&ident) {
             // Name exists in scope
-            let global = self.globals.lock()[*idx].clone();
+            let span_idx = self.add_span(span.clone());
             match global {
-                Global::Val(val) if call => self.push_instr(Instr::push(val)),
+                Global::Val(val) if call => self.push_instr(Instr::push(val, span_idx)),
                 Global::Val(val) => {
-                    self.push_instr(Instr::push_func(Function::new(
-                        FunctionId::Anonymous(span),
-                        vec![Instr::push(val)],
-                        Signature::new(0, 1),
-                    )));
+                    self.push_instr(Instr::push_func(
+                        Function::new(
+                            FunctionId::Anonymous(span),
+                            vec![Instr::push(val, span_idx)],
+                            Signature::new(0, 1),
+                        ),
+                        span_idx,
+                    ));
                 }
                 Global::Func(f) => {
-                    self.push_instr(Instr::push_func(f));
+                    self.push_instr(Instr::push_func(f, span_idx));
                     if call {
-                        let span = self.add_span(span);
-                        self.push_instr(Instr::Call(span));
+                        self.push_instr(Instr::Call(span_idx));
                     }
                 }
             }
+        } else if let Some(binding) = self
+            .current_bindings
+            .iter_mut()
+            .rev()
+            .find(|binding| binding.name == ident)
+        {
+            // Name is a binding that is being compiled, so this is a recursive call
+            let Some(sig) = binding.signature else {
+                return Err(span
+                    .sp(format!(
+                        "Recursive function `{ident}` must have a declared signature, \
+                        as in `{ident} ← |1.1 ...`"
+                    ))
+                    .into());
+            };
+            let index = *binding.global_index.get_or_insert_with(|| {
+                let mut globals = self.globals.lock();
+                globals.push(Global::Val(Value::default()));
+                globals.len() - 1
+            });
+            let span = self.add_span(span);
+            self.push_instr(Instr::GetGlobalFunction { index, sig, span });
+            if call {
+                self.push_instr(Instr::Call(span));
+            }
+        } else if let Some(f) = self.host_function(&ident) {
+            // Name is a registered Rust function
+            let span = self.add_span(span);
+            self.push_instr(Instr::push_func(f, span));
+            if call {
+                self.push_instr(Instr::Call(span));
+            }
+        } else if let Some(later) = (self.later_bindings.iter_mut().rev())
+            .find(|later| later.name == ident)
+            .filter(|_| !self.current_bindings.is_empty())
+        {
+            // Name is bound after the binding being compiled, so this is a forward reference
+            let Some(sig) = later.signature else {
+                return Err(span
+                    .sp(format!(
+                        "`{ident}` is not bound until after `{}`, so it must have a \
+                        declared signature to be used here, as in `{ident} ← |1.1 ...`",
+                        self.current_bindings.last().unwrap().name
+                    ))
+                    .into());
+            };
+            let index = *later.global_index.get_or_insert_with(|| {
+                let mut globals = self.globals.lock();
+                globals.push(Global::Val(Value::default()));
+                globals.len() - 1
+            });
+            let span = self.add_span(span);
+            self.push_instr(Instr::GetGlobalFunction { index, sig, span });
+            if call {
+                self.push_instr(Instr::Call(span));
+            }
         } else {
             return Err(span.sp(format!("Unknown identifier `{ident}`")).into());
         }
         Ok(())
     }
+    fn host_function(&self, name: &str) -> Option<Arc<Function>> {
+        self.host_functions.lock().get(name).cloned()
+    }
     fn ocean(&mut self, prims: Vec<Sp<Primitive>>, call: bool) -> UiuaResult {
         if call {
             for prim in prims.into_iter().rev() {
@@ -616,16 +876,45 @@ impl Uiua {
                 self.primitive(prim.value, prim.span, true)?;
             }
             let instrs = self.new_functions.pop().unwrap();
+            let span_idx = self.add_span(span.clone());
             let function = Function::new(FunctionId::Anonymous(span), instrs, Signature::new(1, 1));
-            self.push_instr(Instr::push_func(function));
+            self.push_instr(Instr::push_func(function, span_idx));
         }
         Ok(())
     }
     fn func(&mut self, func: Func, span: CodeSpan) -> UiuaResult {
-        let mut instrs = Vec::new();
-        for line in func.lines {
-            instrs.extend(self.compile_words(line, true)?);
+        // Bindings in a function are only visible in that function,
+        // so they go in a frame that is dropped when it is done compiling
+        let has_bindings =
+            (func.lines.iter().flatten()).any(|word| matches!(word.value, Word::Binding(_)));
+        let names = has_bindings.then(|| {
+            (
+                self.scope.names.clone(),
+                self.scope.modifier_aliases.clone(),
+            )
+        });
+        let later_len = self.later_bindings.len();
+        (self.later_bindings).extend(func.lines.iter().flatten().flat_map(
+            |word| match &word.value {
+                Word::Binding(binding) => later_bindings(binding),
+                _ => Vec::new(),
+            },
+        ));
+        if has_bindings {
+            self.local_frames.push(HashMap::new());
+        }
+        let instrs = (func.lines.into_iter())
+            .map(|line| self.compile_words(line, true))
+            .collect::<UiuaResult<Vec<_>>>();
+        if has_bindings {
+            self.local_frames.pop();
         }
+        self.later_bindings.truncate(later_len);
+        if let Some((names, modifier_aliases)) = names {
+            self.scope.names = names;
+            self.scope.modifier_aliases = modifier_aliases;
+        }
+        let instrs: Vec<Instr> = instrs?.into_iter().flatten().collect();
 
         // Validate signature
         let sig = match instrs_signature(&instrs) {
@@ -634,12 +923,7 @@ impl Uiua {
                     if declared_sig.value == sig {
                         sig = declared_sig.value;
                     } else {
-                        return Err(UiuaError::Run(Span::Code(declared_sig.span.clone()).sp(
-                            format!(
-                                "Function signature mismatch: declared {} but inferred {}",
-                                declared_sig.value, sig
-                            ),
-                        )));
+                        return Err(signature_mismatch(declared_sig, sig));
                     }
                 }
                 sig
@@ -656,15 +940,16 @@ impl Uiua {
         };
 
         // De-nest function calls
-        if let [Instr::Push(val), Instr::Call(_)] = instrs.as_slice() {
+        let span_idx = self.add_span(span);
+        if let [Instr::Push(val, _), Instr::Call(_)] = instrs.as_slice() {
             if let Some(f) = val.as_box() {
-                self.push_instr(Instr::push(f.clone()));
+                self.push_instr(Instr::push(f.clone(), span_idx));
                 return Ok(());
             }
         }
 
         let function = Function::new(func.id, instrs, sig);
-        self.push_instr(Instr::push_func(function));
+        self.push_instr(Instr::push_func(function, span_idx));
         Ok(())
     }
     fn switch(&mut self, sw: Switch, span: CodeSpan, call: bool) -> UiuaResult {
@@ -691,11 +976,17 @@ impl Uiua {
                 }
             };
             let function = Function::new(FunctionId::Anonymous(span), instrs, sig);
-            self.push_instr(Instr::push_func(function));
+            self.push_instr(Instr::push_func(function, span_idx));
         }
         Ok(())
     }
-    fn modified(&mut self, modified: Modified, call: bool) -> UiuaResult {
+    fn modified(&mut self, mut modified: Modified, call: bool) -> UiuaResult {
+        // Substitute modifier aliases
+        if let Modifier::Ident(ident) = &modified.modifier.value {
+            if let Some(target) = self.modifier_alias(ident) {
+                modified.modifier.value = target;
+            }
+        }
         if let Modifier::Primitive(prim) = modified.modifier.value {
             // Give advice about redundancy
             match prim {
@@ -738,6 +1029,7 @@ impl Uiua {
 
             // Inlining
             match prim {
+                // These are always inlined, because invert and under rely on it
                 Primitive::Dip | Primitive::Gap | Primitive::Reach => {
                     let (mut instrs, sig) = self.compile_operand_words(modified.operands)?;
                     // Dip () . diagnostic
@@ -798,12 +1090,13 @@ impl Uiua {
                     } else {
                         match instrs_signature(&instrs) {
                             Ok(sig) => {
+                                let modifier_span = self.add_span(modified.modifier.span.clone());
                                 let func = Function::new(
                                     FunctionId::Anonymous(modified.modifier.span),
                                     instrs,
                                     sig,
                                 );
-                                self.push_instr(Instr::push_func(func));
+                                self.push_instr(Instr::push_func(func, modifier_span));
                                 Ok(())
                             }
                             Err(e) => Err(UiuaError::Run(
@@ -813,7 +1106,7 @@ impl Uiua {
                         }
                     };
                 }
-                Primitive::Fork => {
+                Primitive::Fork if self.opt_level >= OptLevel::Full => {
                     let mut operands = modified
                         .operands
                         .clone()
@@ -859,12 +1152,14 @@ impl Uiua {
                         } else {
                             match instrs_signature(&instrs) {
                                 Ok(sig) => {
+                                    let modifier_span =
+                                        self.add_span(modified.modifier.span.clone());
                                     let func = Function::new(
                                         FunctionId::Anonymous(modified.modifier.span),
                                         instrs,
                                         sig,
                                     );
-                                    self.push_instr(Instr::push_func(func));
+                                    self.push_instr(Instr::push_func(func, modifier_span));
                                     Ok(())
                                 }
                                 Err(e) => Err(UiuaError::Run(
@@ -875,6 +1170,58 @@ impl Uiua {
                         };
                     }
                 }
+                Primitive::Comptime => {
+                    let mut operands = modified
+                        .operands
+                        .clone()
+                        .into_iter()
+                        .filter(|word| word.value.is_code());
+                    let (instrs, sig) =
+                        self.compile_operand_words(vec![operands.next().unwrap()])?;
+                    let span = Span::Code(modified.modifier.span.clone());
+                    let sig = sig.map_err(|e| {
+                        UiuaError::Run(
+                            span.clone()
+                                .sp(format!("Cannot infer {prim}'s function's signature: {e}")),
+                        )
+                    })?;
+                    if sig.args > 0 {
+                        return Err(UiuaError::Run(span.sp(format!(
+                            "{prim}'s function must take no arguments, \
+                            but its signature is {sig}"
+                        ))));
+                    }
+                    // Call the function now and put its outputs in the code
+                    let height = self.stack.len();
+                    let function_height = self.function_stack.len();
+                    let span_index = self.add_span(span.clone());
+                    let res = self.exec_global_instrs(instrs, span_index);
+                    let values = self.stack.split_off(height.min(self.stack.len()));
+                    let functions = (self.function_stack)
+                        .split_off(function_height.min(self.function_stack.len()));
+                    res?;
+                    if !functions.is_empty() {
+                        return Err(UiuaError::Run(
+                            span.sp(format!("{prim}'s function cannot return functions")),
+                        ));
+                    }
+                    let count = values.len();
+                    let instrs: Vec<Instr> = (values.into_iter())
+                        .map(|val| Instr::push(val, span_index))
+                        .collect();
+                    if call {
+                        self.extend_instrs(instrs);
+                    } else {
+                        let modifier_span = self.add_span(modified.modifier.span.clone());
+                        let func = Function::new(
+                            FunctionId::Anonymous(modified.modifier.span),
+                            instrs,
+                            Signature::new(0, count),
+                        );
+                        self.push_instr(Instr::push_func(func, modifier_span));
+                    }
+                    return Ok(());
+                }
                 Primitive::Invert => {
                     let mut operands = modified
                         .operands
@@ -889,12 +1236,14 @@ impl Uiua {
                         } else {
                             match instrs_signature(&inverted) {
                                 Ok(sig) => {
+                                    let modifier_span =
+                                        self.add_span(modified.modifier.span.clone());
                                     let func = Function::new(
                                         FunctionId::Anonymous(modified.modifier.span),
                                         inverted,
                                         sig,
                                     );
-                                    self.push_instr(Instr::push_func(func));
+                                    self.push_instr(Instr::push_func(func, modifier_span));
                                     Ok(())
                                 }
                                 Err(e) => Err(UiuaError::Run(
@@ -926,12 +1275,14 @@ impl Uiua {
                             } else {
                                 match instrs_signature(&instrs) {
                                     Ok(sig) => {
+                                        let modifier_span =
+                                            self.add_span(modified.modifier.span.clone());
                                         let func = Function::new(
                                             FunctionId::Anonymous(modified.modifier.span),
                                             instrs,
                                             sig,
                                         );
-                                        self.push_instr(Instr::push_func(func));
+                                        self.push_instr(Instr::push_func(func, modifier_span));
                                         Ok(())
                                     }
                                     Err(e) => Err(UiuaError::Run(
@@ -967,9 +1318,10 @@ impl Uiua {
             let instrs = self.new_functions.pop().unwrap();
             match instrs_signature(&instrs) {
                 Ok(sig) => {
+                    let modifier_span = self.add_span(modified.modifier.span.clone());
                     let func =
                         Function::new(FunctionId::Anonymous(modified.modifier.span), instrs, sig);
-                    self.push_instr(Instr::push_func(func));
+                    self.push_instr(Instr::push_func(func, modifier_span));
                 }
                 Err(e) => {
                     return Err(UiuaError::Run(
@@ -1009,7 +1361,7 @@ impl Uiua {
             let instrs = [Instr::Prim(prim, span_i)];
             let func = Function::new_inferred(FunctionId::Primitive(prim), instrs);
             match func {
-                Ok(func) => self.push_instr(Instr::push_func(func)),
+                Ok(func) => self.push_instr(Instr::push_func(func, span_i)),
                 Err(e) => {
                     return Err(span
                         .sp(format!("Cannot infer function signature: {e}"))
@@ -1042,7 +1394,7 @@ fn increment_placeholders(instrs: &mut [Instr], curr: &mut usize) {
                 *offset = *curr;
                 *curr += 1;
             }
-            Instr::PushFunc(f) => {
+            Instr::PushFunc(f, _) => {
                 increment_placeholders(&mut Arc::make_mut(f).instrs, curr);
             }
             _ => (),
@@ -1055,7 +1407,7 @@ fn count_temp_functions(instrs: &[Instr]) -> usize {
     for instr in instrs {
         match instr {
             Instr::GetTempFunction { .. } => count += 1,
-            Instr::PushFunc(f) if matches!(f.id, FunctionId::Anonymous(_)) => {
+            Instr::PushFunc(f, _) if matches!(f.id, FunctionId::Anonymous(_)) => {
                 count += count_temp_functions(&f.instrs);
             }
             _ => (),
@@ -1063,3 +1415,52 @@ fn count_temp_functions(instrs: &[Instr]) -> usize {
     }
     count
 }
+
+/// Whether instructions refer to a function that may not be bound yet
+pub(crate) fn instrs_get_global_functions(instrs: &[Instr]) -> bool {
+    instrs.iter().any(|instr| match instr {
+        Instr::GetGlobalFunction { .. } => true,
+        Instr::PushFunc(f, _) => instrs_get_global_functions(&f.instrs),
+        _ => false,
+    })
+}
+
+/// The bindings a binding item adds to [`Uiua::later_bindings`]
+///
+/// Only a function with a declared signature can be referred to before it is bound.
+fn later_bindings(binding: &Binding) -> Vec<CurrentBinding> {
+    let signature = (binding.more_names.is_empty() && alias_target(&binding.words).is_none())
+        .then(|| binding.signature.as_ref().map(|sig| sig.value))
+        .flatten();
+    (binding.names())
+        .map(|name| CurrentBinding {
+            name: name.value.clone(),
+            signature,
+            global_index: None,
+        })
+        .collect()
+}
+
+/// The error for a function whose inferred signature does not match its declared one
+fn signature_mismatch(declared: &Sp<Signature>, inferred: Signature) -> UiuaError {
+    UiuaError::Run(Span::Code(declared.span.clone()).sp(format!(
+        "Function signature mismatch: declared {} but inferred {}",
+        declared.value, inferred
+    )))
+    .with_kind(ErrorKind::Signature)
+}
+
+/// Get the modifier a binding's words alias, if they are just a modifier with no operands
+fn alias_target(words: &[Sp<Word>]) -> Option<Sp<Modifier>> {
+    let mut code = words.iter().filter(|word| word.value.is_code());
+    let word = code.next()?;
+    if code.next().is_some() {
+        return None;
+    }
+    let modifier = match &word.value {
+        Word::Primitive(prim) if prim.is_modifier() => Modifier::Primitive(*prim),
+        Word::Ident(ident) if ident_modifier_args(ident) > 0 => Modifier::Ident(ident.clone()),
+        _ => return None,
+    };
+    Some(word.span.clone().sp(modifier))
+}
@@ -71,23 +71,20 @@ impl Complex {
         )
     }
     /// Get the sign of a complex number
+    ///
+    /// This is the complex number with the same argument and a magnitude of 1,
+    /// or 0 if the number is 0.
     pub fn signum(self) -> Self {
-        Self::new(
-            if self.re.is_nan() {
-                f64::NAN
-            } else if self.re == 0.0 {
-                0.0
-            } else {
-                self.re.signum()
-            },
-            if self.im.is_nan() {
-                f64::NAN
-            } else if self.im == 0.0 {
-                0.0
-            } else {
-                self.im.signum()
-            },
-        )
+        let abs = self.abs();
+        if abs == 0.0 {
+            Self::ZERO
+        } else {
+            self / abs
+        }
+    }
+    /// Get the complex conjugate of a complex number
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
     }
     /// Calculate the principal value of the complex number
     pub fn arg(self) -> f64 {
@@ -105,12 +102,10 @@ impl Complex {
     pub fn powc(self, power: impl Into<Self>) -> Self {
         let power = power.into();
         if power.im == 0.0 {
-            if self.im == 0.0 {
-                return Self::new(self.re.powf(power.re), 0.0);
-            }
-            if power.re == 0.0 {
-                return Self::ONE;
-            }
+            return self.powf(power.re);
+        }
+        if self == Self::ZERO {
+            return Self::ZERO;
         }
         let (r, theta) = self.to_polar();
         ((r.ln() + Self::I * theta) * power).exp()
@@ -120,8 +115,29 @@ impl Complex {
         if power == 0.0 {
             return Self::ONE;
         }
-        if power.fract() == 0.0 {
-            return Self::new(self.re.powf(power), self.im.powf(power));
+        if self.im == 0.0 && (self.re >= 0.0 || power.fract() == 0.0) {
+            return Self::new(self.re.powf(power), 0.0);
+        }
+        if power == 0.5 {
+            return self.sqrt();
+        }
+        // Exact integer powers by repeated squaring
+        if power.fract() == 0.0 && power.abs() <= 64.0 {
+            let mut base = self;
+            let mut exp = power.abs() as u32;
+            let mut result = Self::ONE;
+            while exp > 0 {
+                if exp & 1 == 1 {
+                    result = result * base;
+                }
+                base = base * base;
+                exp >>= 1;
+            }
+            return if power < 0.0 {
+                Self::ONE / result
+            } else {
+                result
+            };
         }
         let (r, theta) = self.to_polar();
         Self::from_polar(r.powf(power), theta * power)
@@ -142,8 +158,16 @@ impl Complex {
     }
     /// Calculate the square root of a complex number
     pub fn sqrt(self) -> Self {
-        let (r, theta) = self.to_polar();
-        Self::from_polar(r.sqrt(), theta / 2.0)
+        if self == Self::ZERO {
+            return Self::ZERO;
+        }
+        // This avoids the rounding error of going through polar coordinates
+        let t = ((self.re.abs() + self.abs()) / 2.0).sqrt();
+        if self.re >= 0.0 {
+            Self::new(t, self.im / (2.0 * t))
+        } else {
+            Self::new(self.im.abs() / (2.0 * t), t.copysign(self.im))
+        }
     }
     /// Calculate the sine of a complex number
     pub fn sin(self) -> Self {
@@ -161,13 +185,11 @@ impl Complex {
     }
     /// Calculate the arc sine of a complex number
     pub fn asin(self) -> Self {
-        let z = Self::new(-self.im, self.re);
-        (Self::ONE - (Self::ONE - self * self).sqrt()).log(z) / z
+        -Self::I * (Self::I * self + (Self::ONE - self * self).sqrt()).ln()
     }
     /// Calculate the arc cosine of a complex number
     pub fn acos(self) -> Self {
-        let z = Self::new(-self.im, self.re);
-        (z - (self - Self::ONE) * (self + Self::ONE).sqrt()).log(z) / z
+        -Self::I * (self + Self::I * (Self::ONE - self * self).sqrt()).ln()
     }
 }
 
@@ -177,6 +199,12 @@ impl From<f64> for Complex {
     }
 }
 
+impl From<crate::Rational> for Complex {
+    fn from(r: crate::Rational) -> Self {
+        f64::from(r).into()
+    }
+}
+
 impl From<u8> for Complex {
     fn from(value: u8) -> Self {
         f64::from(value).into()
@@ -106,7 +106,9 @@ impl<T: Clone> CowSlice<T> {
     where
         F: FnOnce(&mut EcoVec<T>) -> R,
     {
-        if self.data.is_unique() && self.start == 0 && self.end == self.data.len() as u32 {
+        if self.data.is_unique() && self.start == 0 {
+            // A unique buffer that has only been truncated can be reused
+            self.data.truncate(self.end as usize);
             let res = f(&mut self.data);
             self.end = self.data.len() as u32;
             res
@@ -135,6 +137,12 @@ fn cow_slice_modify() {
     sub.modify(|vec| vec.push(5));
     assert_eq!(slice, [1, 2, 3, 4]);
     assert_eq!(sub, [2, 3, 5]);
+
+    let ptr = slice.as_ptr();
+    slice.truncate(2);
+    slice.modify(|vec| vec.push(6));
+    assert_eq!(slice, [1, 2, 6]);
+    assert_eq!(slice.as_ptr(), ptr);
 }
 
 impl<T> Default for CowSlice<T> {
@@ -3,6 +3,8 @@ use std::{convert::Infallible, error::Error, fmt, io, path::PathBuf, sync::Arc};
 use colored::*;
 
 use crate::{
+    array::Array,
+    boxed::Boxed,
     function::FunctionId,
     lex::{Sp, Span},
     parse::ParseError,
@@ -33,8 +35,46 @@ pub enum UiuaError {
     Break(usize, Span),
     /// Maximum execution time exceeded
     Timeout(Span),
+    /// The instruction limit set with [`Uiua::with_fuel`] was reached
+    ///
+    /// [`Uiua::with_fuel`]: crate::Uiua::with_fuel
+    OutOfFuel(Span),
+    /// Execution was interrupted with an [`InterruptHandle`]
+    ///
+    /// [`InterruptHandle`]: crate::InterruptHandle
+    Interrupted(Span),
     /// A wrapper marking this error as being fill-related
     Fill(Box<Self>),
+    /// A wrapper giving this error a more specific kind
+    Kind(ErrorKind, Box<Self>),
+}
+
+/// A specific kind of runtime error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// A value had the wrong type
+    Type,
+    /// Arrays had incompatible shapes
+    Shape,
+    /// An index was out of bounds
+    Index,
+    /// A system function failed to interact with the outside world
+    Io,
+    /// A function's signature did not match the one it was declared or expected to have
+    Signature,
+}
+
+impl ErrorKind {
+    /// Get the `"kind"` of a caught error of this kind
+    pub fn str(&self) -> &'static str {
+        match self {
+            ErrorKind::Type => "type",
+            ErrorKind::Shape => "shape",
+            ErrorKind::Index => "index",
+            ErrorKind::Io => "io",
+            ErrorKind::Signature => "signature",
+        }
+    }
 }
 
 /// Uiua's result type
@@ -80,14 +120,16 @@ impl fmt::Display for UiuaError {
             UiuaError::Traced { error, trace } => {
                 write!(f, "{error}")?;
                 for line in format_trace(trace) {
-                    writeln!(f, "{line}")?;
+                    write!(f, "\n{line}")?;
                 }
                 Ok(())
             }
             UiuaError::Throw(value, span) => write!(f, "{span}: {value}"),
             UiuaError::Break(_, span) => write!(f, "{span}: Break amount exceeded loop depth"),
             UiuaError::Timeout(_) => write!(f, "Maximum execution time exceeded"),
-            UiuaError::Fill(error) => error.fmt(f),
+            UiuaError::OutOfFuel(_) => write!(f, "Maximum instruction count exceeded"),
+            UiuaError::Interrupted(_) => write!(f, "Program interrupted"),
+            UiuaError::Fill(error) | UiuaError::Kind(_, error) => error.fmt(f),
         }
     }
 }
@@ -100,12 +142,84 @@ impl UiuaError {
             error => error.to_string(),
         }
     }
-    /// Get the value of the error if it was thrown by `assert`
+    /// Get the value of a caught error
+    ///
+    /// Errors thrown by `assert` are the thrown value.
+    /// Other errors are a map of their `"kind"`, `"message"`, `"span"`, and `"trace"`.
     pub fn value(self) -> Value {
         match self {
             UiuaError::Throw(value, _) => *value,
-            UiuaError::Traced { error, .. } => error.value(),
-            error => error.message().into(),
+            UiuaError::Traced { error, .. } if matches!(*error, UiuaError::Throw(..)) => {
+                error.value()
+            }
+            error => {
+                let span = error
+                    .span()
+                    .map_or_else(String::new, |span| span.to_string());
+                let trace: Vec<_> = (error.trace().iter())
+                    .filter(|frame| frame.id != FunctionId::Main)
+                    .map(|frame| Boxed(format!("{} at {}", frame.id, frame.span).into()))
+                    .collect();
+                Value::string_map([
+                    ("kind", error.kind().into()),
+                    ("message", error.bare_message().into()),
+                    ("span", span.into()),
+                    ("trace", Array::from_iter(trace).into()),
+                ])
+            }
+        }
+    }
+    /// Get the kind of the error
+    ///
+    /// This is the `"kind"` of a caught error.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            UiuaError::Load(..) => "load",
+            UiuaError::Format(..) => "format",
+            UiuaError::Parse(_) => "parse",
+            UiuaError::Run(_) => "run",
+            UiuaError::Traced { error, .. } | UiuaError::Fill(error) => error.kind(),
+            UiuaError::Kind(kind, _) => kind.str(),
+            UiuaError::Throw(..) => "assert",
+            UiuaError::Break(..) => "break",
+            UiuaError::Timeout(_) => "timeout",
+            UiuaError::OutOfFuel(_) => "fuel",
+            UiuaError::Interrupted(_) => "interrupted",
+        }
+    }
+    /// Get the span where the error occurred, if there is one
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            UiuaError::Run(error) => Some(&error.span),
+            UiuaError::Traced { error, .. }
+            | UiuaError::Fill(error)
+            | UiuaError::Kind(_, error) => error.span(),
+            UiuaError::Throw(_, span)
+            | UiuaError::Break(_, span)
+            | UiuaError::Timeout(span)
+            | UiuaError::OutOfFuel(span)
+            | UiuaError::Interrupted(span) => Some(span),
+            UiuaError::Load(..) | UiuaError::Format(..) | UiuaError::Parse(_) => None,
+        }
+    }
+    /// Get the stack trace of the error
+    pub fn trace(&self) -> &[TraceFrame] {
+        match self {
+            UiuaError::Traced { trace, .. } => trace,
+            UiuaError::Fill(error) | UiuaError::Kind(_, error) => error.trace(),
+            _ => &[],
+        }
+    }
+    /// Get the message of the error without its span
+    fn bare_message(&self) -> String {
+        match self {
+            UiuaError::Run(error) => error.value.clone(),
+            UiuaError::Traced { error, .. }
+            | UiuaError::Fill(error)
+            | UiuaError::Kind(_, error) => error.bare_message(),
+            UiuaError::Throw(value, _) => value.to_string(),
+            UiuaError::Break(..) => "Break amount exceeded loop depth".into(),
+            error => error.message(),
         }
     }
     pub(crate) fn break_data(self) -> Result<(usize, Span), Self> {
@@ -123,7 +237,7 @@ impl UiuaError {
     /// Check if the error is fill-related
     pub(crate) fn is_fill(&self) -> bool {
         match self {
-            UiuaError::Traced { error, .. } => error.is_fill(),
+            UiuaError::Traced { error, .. } | UiuaError::Kind(_, error) => error.is_fill(),
             UiuaError::Fill(_) => true,
             _ => false,
         }
@@ -132,17 +246,22 @@ impl UiuaError {
     pub(crate) fn fill(self) -> Self {
         UiuaError::Fill(Box::new(self))
     }
+    /// Give the error a more specific kind
+    pub(crate) fn with_kind(self, kind: ErrorKind) -> Self {
+        UiuaError::Kind(kind, Box::new(self))
+    }
 }
 
 fn format_trace(trace: &[TraceFrame]) -> Vec<String> {
     let max_id_length = trace
         .iter()
-        .filter(|frame| frame.span != Span::Builtin)
+        .filter(|frame| frame.span != Span::Builtin && frame.id != FunctionId::Main)
         .map(|frame| frame.id.to_string().chars().count())
         .max()
         .unwrap_or(0);
     let max_span_length = trace
         .iter()
+        .filter(|frame| frame.id != FunctionId::Main)
         .map(|frame| match &frame.span {
             Span::Code(span) => span.to_string().chars().count(),
             Span::Builtin => 0,
@@ -163,7 +282,38 @@ fn format_trace(trace: &[TraceFrame]) -> Vec<String> {
             Span::Builtin => format!("  in {:max_id_length$}", frame.id.to_string()),
         });
     }
-    lines
+    collapse_repeated_lines(lines)
+}
+
+/// The longest cycle of frames that is collapsed in a trace
+const MAX_REPEATED_FRAMES: usize = 4;
+
+/// Collapse runs of repeated trace lines, such as those left by deep recursion
+fn collapse_repeated_lines(lines: Vec<String>) -> Vec<String> {
+    let mut collapsed = Vec::new();
+    let mut i = 0;
+    'lines: while i < lines.len() {
+        for period in (1..=MAX_REPEATED_FRAMES).take_while(|period| i + period <= lines.len()) {
+            let block = &lines[i..i + period];
+            let repeats = lines[i + period..]
+                .chunks_exact(period)
+                .take_while(|chunk| *chunk == block)
+                .count();
+            if repeats >= 2 {
+                collapsed.extend_from_slice(block);
+                collapsed.push(if period == 1 {
+                    format!("  (repeated {repeats} more times)")
+                } else {
+                    format!("  (previous {period} frames repeated {repeats} more times)")
+                });
+                i += period * (repeats + 1);
+                continue 'lines;
+            }
+        }
+        collapsed.push(lines[i].clone());
+        i += 1;
+    }
+    collapsed
 }
 
 impl From<Vec<Sp<ParseError>>> for UiuaError {
@@ -200,7 +350,13 @@ impl UiuaError {
             UiuaError::Timeout(span) => {
                 Report::new_multi(kind, [("Maximum execution time exceeded", span.clone())])
             }
-            UiuaError::Fill(error) => error.report(),
+            UiuaError::OutOfFuel(span) => {
+                Report::new_multi(kind, [("Maximum instruction count exceeded", span.clone())])
+            }
+            UiuaError::Interrupted(span) => {
+                Report::new_multi(kind, [("Program interrupted", span.clone())])
+            }
+            UiuaError::Fill(error) | UiuaError::Kind(_, error) => error.report(),
             UiuaError::Load(..) | UiuaError::Format(..) => Report::new(kind, self.to_string()),
         }
     }
@@ -206,6 +206,10 @@ create_config!(
     (align_comments, bool, true),
     /// Whether to indent item imports
     (indent_item_imports, bool, true),
+    /// Whether to remove comments and optional whitespace and to use the shortest spellings of numbers and glyphs.
+    ///
+    /// This produces the smallest equivalent code and overrides most other options.
+    (golf, bool, false),
 );
 
 /// The source from which to populate the formatter configuration.
@@ -405,6 +409,9 @@ struct Formatter<'a> {
 impl<'a> Formatter<'a> {
     fn format_items(&mut self, items: &[Item]) {
         for item in items {
+            if self.config.golf && item_is_golfed_away(item) {
+                continue;
+            }
             self.format_item(item);
             self.output.push('\n');
         }
@@ -469,7 +476,9 @@ impl<'a> Formatter<'a> {
                         self.prev_import_function = Some(binding.name.value.clone());
                     }
                     Some(Word::Ident(ident)) => {
-                        if (self.prev_import_function.as_ref()).is_some_and(|prev| prev == ident) {
+                        if (self.prev_import_function.as_ref()).is_some_and(|prev| prev == ident)
+                            && !self.config.golf
+                        {
                             for _ in 0..self.config.multiline_indent {
                                 self.output.push(' ');
                             }
@@ -479,22 +488,42 @@ impl<'a> Formatter<'a> {
                     }
                     _ => self.prev_import_function = None,
                 }
-
-                self.output.push_str(&binding.name.value);
-                self.output.push_str(" ←");
-                if !binding.words.is_empty() || binding.signature.is_some() {
-                    self.output.push(' ');
-                }
-                if let Some(sig) = &binding.signature {
-                    self.format_signature('|', sig.value, true);
-                }
-                self.format_words(&binding.words, true, 0);
+                self.format_binding(binding, false, 0);
+            }
+            Item::Import(import) => {
+                self.prev_import_function = None;
+                self.output.push_str(&import.name.value);
+                self.output
+                    .push_str(if self.config.golf { "¤" } else { " ¤ " });
+                self.output.push_str(import.path.span.as_str());
             }
             Item::ExtraNewlines(_) => {
                 self.prev_import_function = None;
             }
         }
     }
+    fn format_binding(&mut self, binding: &Binding, local: bool, depth: usize) {
+        for (i, name) in binding.names().enumerate() {
+            if i > 0 {
+                self.output.push(' ');
+            }
+            self.output.push_str(&name.value);
+        }
+        if self.config.golf {
+            // Local and destructuring bindings must use `←`
+            let arrow = local || !binding.more_names.is_empty();
+            self.output.push(if arrow { '←' } else { '=' });
+        } else {
+            self.output.push_str(" ←");
+            if !binding.words.is_empty() || binding.signature.is_some() {
+                self.output.push(' ');
+            }
+        }
+        if let Some(sig) = &binding.signature {
+            self.format_signature('|', sig.value, true);
+        }
+        self.format_words(&binding.words, true, depth);
+    }
     fn format_signature(&mut self, init_char: char, sig: Signature, trailing_space: bool) {
         self.output.push(init_char);
         self.output.push_str(&sig.args.to_string());
@@ -502,7 +531,7 @@ impl<'a> Formatter<'a> {
             self.output.push('.');
             self.output.push_str(&sig.outputs.to_string());
         }
-        if trailing_space {
+        if trailing_space && !self.config.golf {
             self.output.push(' ');
         }
     }
@@ -512,10 +541,25 @@ impl<'a> Formatter<'a> {
         }
     }
     fn format_word(&mut self, word: &Sp<Word>, depth: usize) {
+        let start = self.output.len();
+        self.format_word_impl(word, depth);
+        if self.config.golf {
+            self.separate_golfed_word(start);
+        }
+    }
+    fn format_word_impl(&mut self, word: &Sp<Word>, depth: usize) {
         match &word.value {
             Word::Number(s, n) => {
                 let grid_str = n.grid_string();
-                let formatted = if grid_str.len() < s.trim_end_matches('i').len() {
+                let formatted = if self.config.golf {
+                    let s = s.replace('¯', "`");
+                    let grid_str = grid_str.replace('¯', "`");
+                    if grid_str.len() < s.trim_end_matches('i').len() {
+                        grid_str
+                    } else {
+                        s
+                    }
+                } else if grid_str.len() < s.trim_end_matches('i').len() {
                     grid_str
                 } else {
                     s.replace('`', "¯")
@@ -548,8 +592,10 @@ impl<'a> Formatter<'a> {
                 for (i, line) in lines.iter().enumerate() {
                     if i > 0 {
                         self.output.push('\n');
-                        for _ in 0..curr_line_pos {
-                            self.output.push(' ');
+                        if !self.config.golf {
+                            for _ in 0..curr_line_pos {
+                                self.output.push(' ');
+                            }
                         }
                     }
                     self.output.push_str(line.span.as_str());
@@ -617,22 +663,25 @@ impl<'a> Formatter<'a> {
             }
             Word::Ocean(prims) => {
                 for prim in prims {
-                    self.push(&prim.span, &prim.value.to_string());
+                    self.push(&prim.span, &self.prim_string(prim.value));
                 }
             }
-            Word::Primitive(prim) => self.push(&word.span, &prim.to_string()),
+            Word::Primitive(prim) => self.push(&word.span, &self.prim_string(*prim)),
             Word::Modified(m) => {
                 self.push(
                     &m.modifier.span,
                     &match &m.modifier.value {
-                        Modifier::Primitive(prim) => prim.to_string(),
+                        Modifier::Primitive(prim) => self.prim_string(*prim),
                         Modifier::Ident(ident) => ident.to_string(),
                     },
                 );
                 self.format_words(&m.operands, true, depth);
             }
             Word::Placeholder(sig) => self.format_signature('^', *sig, false),
+            Word::Binding(binding) => self.format_binding(binding, true, depth),
+            Word::Spaces if self.config.golf => {}
             Word::Spaces => self.push(&word.span, " "),
+            Word::Comment(_) if self.config.golf => {}
             Word::Comment(comment) => {
                 let beginning_of_line = self
                     .output
@@ -667,6 +716,16 @@ impl<'a> Formatter<'a> {
         if lines.is_empty() {
             return;
         }
+        if self.config.golf {
+            let lines = (lines.iter()).filter(|line| !words_are_golfed_away(line));
+            for (i, line) in lines.enumerate() {
+                if i > 0 {
+                    self.output.push('\n');
+                }
+                self.format_words(line, true, depth);
+            }
+            return;
+        }
         let last_word_comment = lines
             .last()
             .unwrap()
@@ -719,6 +778,39 @@ impl<'a> Formatter<'a> {
             }
         }
     }
+    /// Get the spelling of a primitive, which is the shortest one when golfing
+    fn prim_string(&self, prim: Primitive) -> String {
+        let glyph = prim.to_string();
+        match prim.ascii() {
+            Some(ascii) if self.config.golf && ascii.to_string().len() < glyph.len() => {
+                ascii.to_string()
+            }
+            _ => glyph,
+        }
+    }
+    /// Insert a space before a golfed word if it would otherwise merge with the code before it
+    fn separate_golfed_word(&mut self, start: usize) {
+        let (before, after) = self.output.split_at(start);
+        let mut before_chars = before.chars().rev();
+        let (Some(prev), Some(next)) = (before_chars.next(), after.chars().next()) else {
+            return;
+        };
+        let prev_prev = before_chars.next();
+        let line = &before[before.rfind('\n').map_or(0, |i| i + 1)..];
+        let needs_space = is_ident_char(prev) && is_ident_char(next)
+            // Numbers
+            || prev.is_ascii_digit() && (next.is_ascii_digit() || "eE".contains(next))
+            || "`¯".contains(prev) && next.is_ascii_digit()
+            || "./".contains(prev)
+                && next.is_ascii_digit()
+                && prev_prev.is_some_and(|c| c.is_ascii_digit())
+            // Multi-character ASCII glyphs
+            || "<>!".contains(prev) && next == '='
+            || line == "--" && next == '-';
+        if needs_space {
+            self.output.insert(start, ' ');
+        }
+    }
     fn push(&mut self, span: &CodeSpan, formatted: &str) {
         let start = end_loc(&self.output);
         self.output.push_str(formatted);
@@ -754,6 +846,20 @@ fn trim_spaces(words: &[Sp<Word>], trim_end: bool) -> &[Sp<Word>] {
     &words[start..end]
 }
 
+/// Whether a line of words produces no code when golfing
+fn words_are_golfed_away(words: &[Sp<Word>]) -> bool {
+    (words.iter()).all(|word| matches!(word.value, Word::Spaces | Word::Comment(_)))
+}
+
+/// Whether an item produces no code when golfing
+fn item_is_golfed_away(item: &Item) -> bool {
+    match item {
+        Item::Words(words) => words_are_golfed_away(words),
+        Item::ExtraNewlines(_) => true,
+        _ => false,
+    }
+}
+
 fn word_is_multiline(word: &Word) -> bool {
     match word {
         Word::Number(..) => false,
@@ -783,6 +889,7 @@ fn word_is_multiline(word: &Word) -> bool {
         Word::Primitive(_) => false,
         Word::Modified(m) => m.operands.iter().any(|word| word_is_multiline(&word.value)),
         Word::Placeholder(_) => false,
+        Word::Binding(binding) => (binding.words.iter()).any(|word| word_is_multiline(&word.value)),
         Word::Comment(_) => false,
         Word::Spaces => false,
     }
@@ -6,11 +6,12 @@ use std::{
     sync::Arc,
 };
 
+use ecow::EcoString;
 use enum_iterator::Sequence;
 
 use crate::{
     check::instrs_signature,
-    lex::CodeSpan,
+    lex::{CodeSpan, Span},
     primitive::{ImplPrimitive, Primitive},
     value::Value,
     Ident, Uiua, UiuaResult,
@@ -22,9 +23,9 @@ use crate::{
 #[allow(missing_docs)]
 pub enum Instr {
     /// Push a value onto the stack
-    Push(Box<Value>) = 0,
+    Push(Box<Value>, usize) = 0,
     /// Begin an array
-    BeginArray,
+    BeginArray(usize),
     /// End an array
     EndArray {
         boxed: bool,
@@ -37,21 +38,46 @@ pub enum Instr {
     /// Call a function
     Call(usize),
     /// Push a function onto the function stack
-    PushFunc(Arc<Function>),
+    PushFunc(Arc<Function>, usize),
     /// Execute a switch function
     Switch {
         count: usize,
         span: usize,
     },
     /// Call a dynamic function
-    Dynamic(DynamicFunction),
-    PushTempFunctions(usize),
-    PopTempFunctions(usize),
+    Dynamic(DynamicFunction, usize),
+    /// Format values into a string, putting them between the parts
+    Format {
+        parts: Arc<[EcoString]>,
+        span: usize,
+    },
+    /// Split a string into the values that were formatted between the parts
+    MatchFormatPattern {
+        parts: Arc<[EcoString]>,
+        span: usize,
+    },
+    PushTempFunctions {
+        count: usize,
+        span: usize,
+    },
+    PopTempFunctions {
+        count: usize,
+        span: usize,
+    },
     GetTempFunction {
         offset: usize,
         sig: Signature,
         span: usize,
     },
+    /// Push a function bound to a global, looking it up when the instruction is run
+    ///
+    /// This is how a function refers to itself or to functions bound after it,
+    /// since they are not yet bound when it is compiled.
+    GetGlobalFunction {
+        index: usize,
+        sig: Signature,
+        span: usize,
+    },
     PushTemp {
         stack: TempStack,
         count: usize,
@@ -96,11 +122,16 @@ impl fmt::Display for TempStack {
 impl PartialEq for Instr {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Self::Push(a), Self::Push(b)) => a == b,
-            (Self::BeginArray, Self::BeginArray) => true,
+            (Self::Push(a, _), Self::Push(b, _)) => a == b,
+            (Self::BeginArray(_), Self::BeginArray(_)) => true,
             (Self::EndArray { .. }, Self::EndArray { .. }) => true,
             (Self::Prim(a, s_span), Self::Prim(b, b_span)) => a == b && s_span == b_span,
             (Self::Call(a), Self::Call(b)) => a == b,
+            (Self::Format { parts: a, .. }, Self::Format { parts: b, .. })
+            | (
+                Self::MatchFormatPattern { parts: a, .. },
+                Self::MatchFormatPattern { parts: b, .. },
+            ) => a == b,
             (Self::PushTemp { count: a, .. }, Self::PushTemp { count: b, .. }) => a == b,
             (Self::PopTemp { count: a, .. }, Self::PopTemp { count: b, .. }) => a == b,
             (
@@ -132,7 +163,7 @@ impl PartialOrd for Instr {
 impl Ord for Instr {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
-            (Self::Push(a), Self::Push(b)) => a.cmp(b),
+            (Self::Push(a, _), Self::Push(b, _)) => a.cmp(b),
             (a, b) => {
                 if a == b {
                     Ordering::Equal
@@ -151,18 +182,22 @@ impl Hash for Instr {
         let disc: u8 = unsafe { transmute(discriminant(self)) };
         disc.hash(state);
         match self {
-            Instr::Push(val) => val.hash(state),
-            Instr::BeginArray => {}
+            Instr::Push(val, _) => val.hash(state),
+            Instr::BeginArray(_) => {}
             Instr::EndArray { .. } => {}
             Instr::Prim(p, _) => p.hash(state),
             Instr::ImplPrim(p, _) => p.hash(state),
             Instr::Call(_) => {}
-            Instr::PushFunc(f) => f.id.hash(state),
+            Instr::PushFunc(f, _) => f.id.hash(state),
             Instr::Switch { count, .. } => count.hash(state),
-            Instr::PushTempFunctions(count) => count.hash(state),
-            Instr::PopTempFunctions(count) => count.hash(state),
+            Instr::PushTempFunctions { count, .. } => count.hash(state),
+            Instr::PopTempFunctions { count, .. } => count.hash(state),
             Instr::GetTempFunction { offset, .. } => offset.hash(state),
-            Instr::Dynamic(f) => f.id.hash(state),
+            Instr::GetGlobalFunction { index, .. } => index.hash(state),
+            Instr::Dynamic(f, _) => f.id.hash(state),
+            Instr::Format { parts, .. } | Instr::MatchFormatPattern { parts, .. } => {
+                parts.hash(state)
+            }
             Instr::PushTemp { count, .. } => count.hash(state),
             Instr::PopTemp { count, .. } => count.hash(state),
             Instr::CopyTemp { offset, count, .. } => {
@@ -176,12 +211,62 @@ impl Hash for Instr {
 
 impl Instr {
     /// Create a new push instruction
-    pub fn push(val: impl Into<Value>) -> Self {
-        Self::Push(Box::new(val.into()))
+    pub fn push(val: impl Into<Value>, span: usize) -> Self {
+        Self::Push(Box::new(val.into()), span)
     }
     /// Create a new push function instruction
-    pub fn push_func(f: impl Into<Arc<Function>>) -> Self {
-        Self::PushFunc(f.into())
+    pub fn push_func(f: impl Into<Arc<Function>>, span: usize) -> Self {
+        Self::PushFunc(f.into(), span)
+    }
+    /// Get the index of the span this instruction was compiled from
+    ///
+    /// The span can be looked up with [`Uiua::get_span`].
+    /// Instructions that do not come from source code have the index of [`Span::Builtin`].
+    pub fn span(&self) -> usize {
+        match self {
+            Self::Push(_, span)
+            | Self::BeginArray(span)
+            | Self::PushFunc(_, span)
+            | Self::Dynamic(_, span)
+            | Self::PushTempFunctions { span, .. }
+            | Self::PopTempFunctions { span, .. }
+            | Self::EndArray { span, .. }
+            | Self::Prim(_, span)
+            | Self::ImplPrim(_, span)
+            | Self::Call(span)
+            | Self::Switch { span, .. }
+            | Self::Format { span, .. }
+            | Self::MatchFormatPattern { span, .. }
+            | Self::GetTempFunction { span, .. }
+            | Self::GetGlobalFunction { span, .. }
+            | Self::PushTemp { span, .. }
+            | Self::PopTemp { span, .. }
+            | Self::CopyTemp { span, .. }
+            | Self::DropTemp { span, .. } => *span,
+        }
+    }
+    pub(crate) fn span_mut(&mut self) -> &mut usize {
+        match self {
+            Self::Push(_, span)
+            | Self::BeginArray(span)
+            | Self::PushFunc(_, span)
+            | Self::Dynamic(_, span)
+            | Self::PushTempFunctions { span, .. }
+            | Self::PopTempFunctions { span, .. }
+            | Self::EndArray { span, .. }
+            | Self::Prim(_, span)
+            | Self::ImplPrim(_, span)
+            | Self::Call(span)
+            | Self::Switch { span, .. }
+            | Self::Format { span, .. }
+            | Self::MatchFormatPattern { span, .. }
+            | Self::GetTempFunction { span, .. }
+            | Self::GetGlobalFunction { span, .. }
+            | Self::PushTemp { span, .. }
+            | Self::PopTemp { span, .. }
+            | Self::CopyTemp { span, .. }
+            | Self::DropTemp { span, .. } => span,
+        }
     }
     pub(crate) fn is_temp(&self) -> bool {
         matches!(
@@ -197,7 +282,7 @@ impl Instr {
 impl fmt::Debug for Instr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Instr::Push(val) => {
+            Instr::Push(val, _) => {
                 if val.element_count() < 50 && val.shape().len() <= 1 {
                     write!(f, "push {val:?}")
                 } else {
@@ -212,19 +297,24 @@ impl fmt::Debug for Instr {
 impl fmt::Display for Instr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Instr::Push(val) => write!(f, "{val:?}"),
-            Instr::BeginArray => write!(f, "]"),
+            Instr::Push(val, _) => write!(f, "{val:?}"),
+            Instr::BeginArray(_) => write!(f, "]"),
             Instr::EndArray { .. } => write!(f, "["),
             Instr::Prim(prim @ Primitive::Over, _) => write!(f, "`{prim}`"),
             Instr::Prim(prim, _) => write!(f, "{prim}"),
             Instr::ImplPrim(prim, _) => write!(f, "{prim}"),
             Instr::Call(_) => write!(f, "call"),
-            Instr::PushFunc(func) => write!(f, "push({func})"),
+            Instr::PushFunc(func, _) => write!(f, "push({func})"),
             Instr::Switch { count, .. } => write!(f, "<switch {count}>"),
-            Instr::PushTempFunctions(count) => write!(f, "<push {count} functions>"),
-            Instr::PopTempFunctions(count) => write!(f, "<pop {count} functions>"),
+            Instr::PushTempFunctions { count, .. } => write!(f, "<push {count} functions>"),
+            Instr::PopTempFunctions { count, .. } => write!(f, "<pop {count} functions>"),
             Instr::GetTempFunction { offset, .. } => write!(f, "<get function at {offset}>"),
-            Instr::Dynamic(df) => write!(f, "{df:?}"),
+            Instr::GetGlobalFunction { index, .. } => write!(f, "<get global function {index}>"),
+            Instr::Dynamic(df, _) => write!(f, "{df:?}"),
+            Instr::Format { parts, .. } => write!(f, "<format {}>", parts.join("_")),
+            Instr::MatchFormatPattern { parts, .. } => {
+                write!(f, "<match format {}>", parts.join("_"))
+            }
             Instr::PushTemp { stack, count, .. } => write!(f, "<push {stack} {count}>"),
             Instr::PopTemp { stack, count, .. } => write!(f, "<pop {stack} {count}>"),
             Instr::CopyTemp {
@@ -240,6 +330,82 @@ impl fmt::Display for Instr {
     }
 }
 
+/// The instructions of a function and of all the functions it pushes
+///
+/// Created with [`Uiua::disassemble`].
+/// Its [`Display`](fmt::Display) implementation prints one instruction per line.
+#[derive(Clone)]
+pub struct Disassembly {
+    /// The instructions, in the order they appear in the function
+    ///
+    /// A pushed function's instructions come right after the instruction that pushes it.
+    pub instrs: Vec<DisassembledInstr>,
+}
+
+/// An instruction in a [`Disassembly`]
+#[derive(Clone)]
+pub struct DisassembledInstr {
+    /// The index of the instruction in its function
+    pub index: usize,
+    /// How many pushed functions deep the instruction is
+    pub depth: usize,
+    /// The instruction
+    pub instr: Instr,
+    /// The span the instruction was compiled from
+    pub span: Span,
+    /// The id of the function the instruction belongs to
+    pub function: FunctionId,
+}
+
+impl Disassembly {
+    pub(crate) fn new(f: &Function, get_span: impl Fn(usize) -> Span) -> Self {
+        fn recur(
+            f: &Function,
+            depth: usize,
+            get_span: &impl Fn(usize) -> Span,
+            instrs: &mut Vec<DisassembledInstr>,
+        ) {
+            for (index, instr) in f.instrs.iter().enumerate() {
+                instrs.push(DisassembledInstr {
+                    index,
+                    depth,
+                    instr: instr.clone(),
+                    span: get_span(instr.span()),
+                    function: f.id.clone(),
+                });
+                if let Instr::PushFunc(f, _) = instr {
+                    recur(f, depth + 1, get_span, instrs);
+                }
+            }
+        }
+        let mut instrs = Vec::new();
+        recur(f, 0, &get_span, &mut instrs);
+        Disassembly { instrs }
+    }
+}
+
+impl IntoIterator for Disassembly {
+    type Item = DisassembledInstr;
+    type IntoIter = std::vec::IntoIter<DisassembledInstr>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.instrs.into_iter()
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for instr in &self.instrs {
+            let indent = "  ".repeat(instr.depth);
+            write!(f, "{indent}{:>3} {:?}", instr.index, instr.instr)?;
+            if let Span::Code(span) = &instr.span {
+                write!(f, " @ {span}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
 /// A Uiua function
 #[derive(Clone)]
 pub struct Function {
@@ -444,6 +610,20 @@ impl Function {
             },
         }
     }
+    /// Check if this function's outputs depend only on its arguments and it has no side effects
+    ///
+    /// Functions that are looked up at runtime are assumed to be impure.
+    pub(crate) fn is_pure(&self) -> bool {
+        self.instrs.iter().all(|instr| match instr {
+            Instr::Prim(prim, _) => prim.is_pure(),
+            Instr::ImplPrim(prim, _) => *prim != ImplPrimitive::InvTrace,
+            Instr::PushFunc(f, _) => f.is_pure(),
+            Instr::Dynamic(..)
+            | Instr::GetTempFunction { .. }
+            | Instr::GetGlobalFunction { .. } => false,
+            _ => true,
+        })
+    }
     /// `invert` this function
     pub fn invert(&self, context: &str, env: &Uiua) -> UiuaResult<Self> {
         self.inverse()
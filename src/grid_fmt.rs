@@ -14,7 +14,7 @@ use crate::{
     array::{Array, ArrayValue},
     boxed::Boxed,
     value::Value,
-    Complex, Primitive,
+    Complex, Primitive, Rational,
 };
 
 type Grid<T = char> = Vec<Vec<T>>;
@@ -89,6 +89,22 @@ impl GridFmt for Complex {
     }
 }
 
+impl GridFmt for Rational {
+    fn fmt_grid(&self, boxed: bool) -> Grid {
+        if self.den <= 0 {
+            return f64::from(*self).fmt_grid(boxed);
+        }
+        let minus = if self.num < 0 { "¯" } else { "" };
+        let num = self.num.unsigned_abs();
+        let s = if self.den == 1 {
+            format!("{minus}{num}")
+        } else {
+            format!("{minus}{num}/{}", self.den)
+        };
+        vec![boxed_scalar(boxed).chain(s.chars()).collect()]
+    }
+}
+
 pub fn format_char_inner(c: char) -> String {
     if c == char::MAX {
         return '_'.to_string();
@@ -120,17 +136,25 @@ impl GridFmt for char {
     }
 }
 
-impl GridFmt for Boxed {
+impl GridFmt for Value {
     fn fmt_grid(&self, boxed: bool) -> Grid {
-        let mut grid = match self.as_value() {
-            Value::Num(array) => array.fmt_grid(true),
+        match self {
+            Value::Num(array) => array.fmt_grid(boxed),
             #[cfg(feature = "bytes")]
-            Value::Byte(array) => array.fmt_grid(true),
+            Value::Byte(array) => array.fmt_grid(boxed),
             #[cfg(feature = "complex")]
-            Value::Complex(array) => array.fmt_grid(true),
-            Value::Char(array) => array.fmt_grid(true),
-            Value::Box(array) => array.fmt_grid(true),
-        };
+            Value::Complex(array) => array.fmt_grid(boxed),
+            #[cfg(feature = "rational")]
+            Value::Rational(array) => array.fmt_grid(boxed),
+            Value::Char(array) => array.fmt_grid(boxed),
+            Value::Box(array) => array.fmt_grid(boxed),
+        }
+    }
+}
+
+impl GridFmt for Boxed {
+    fn fmt_grid(&self, boxed: bool) -> Grid {
+        let mut grid = self.as_value().fmt_grid(true);
         if boxed && grid.len() == 1 {
             grid = vec![boxed_scalar(true)
                 .chain(grid.into_iter().flatten())
@@ -142,6 +166,26 @@ impl GridFmt for Boxed {
 
 impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
     fn fmt_grid(&self, boxed: bool) -> Grid {
+        if let Some(keys) = self.map_keys() {
+            // Maps are shown as the code that creates them
+            let mut values = self.clone();
+            values.map_keys = None;
+            let keys = keys.keys.fmt_grid(boxed);
+            let values = values.fmt_grid(boxed);
+            let height = keys.len().max(values.len());
+            let mut grid = vec![vec![]; height];
+            for (i, mut part) in [vec![Primitive::Map.name().chars().collect()], keys, values]
+                .into_iter()
+                .enumerate()
+            {
+                let width = part[0].len() + (i > 0) as usize;
+                pad_grid_center(width, height, false, &mut part);
+                for (row, part_row) in grid.iter_mut().zip(part) {
+                    row.extend(part_row);
+                }
+            }
+            return grid;
+        }
         if self.shape.is_empty() {
             return self.data[0].fmt_grid(boxed);
         }
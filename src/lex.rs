@@ -321,6 +321,7 @@ pub enum Token {
     Simple(AsciiToken),
     Glyph(Primitive),
     LeftArrow,
+    Currency,
     Newline,
     Spaces,
 }
@@ -550,6 +551,7 @@ impl<'a> Lexer<'a> {
                 ">" if self.next_char_exact("=") => self.end(GreaterEqual, start),
                 "!" if self.next_char_exact("=") => self.end(BangEqual, start),
                 "←" => self.end(LeftArrow, start),
+                "¤" => self.end(Currency, start),
                 // Comments
                 "#" => {
                     let mut comment = String::new();
@@ -642,6 +644,18 @@ impl<'a> Lexer<'a> {
                     while let Some(c) = self.next_char_if_all(is_ident_char) {
                         ident.push_str(c);
                     }
+                    // Module member access
+                    while ident.starts_with(|c: char| c.is_uppercase())
+                        && self.peek_char() == Some("¤")
+                        && (self.input_segments.get(self.loc.char_pos + 1))
+                            .is_some_and(|c| c.chars().all(is_ident_char))
+                    {
+                        self.next_char();
+                        ident.push('¤');
+                        while let Some(c) = self.next_char_if_all(is_ident_char) {
+                            ident.push_str(c);
+                        }
+                    }
                     let mut exclam_count = 0;
                     while self.next_char_exact("!") {
                         ident.push('!');
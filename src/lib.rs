@@ -44,6 +44,26 @@ uiua.load_str("MyAdd 2 3").unwrap();
 let res = uiua.pop_num().unwrap();
 assert_eq!(res, 5.0);
 ```
+Rust functions can call back into Uiua code with [`Uiua::call_with_args`].
+```rust
+use uiua::*;
+
+let mut uiua = Uiua::with_native_sys();
+uiua.load_str("Double ← ×2").unwrap();
+
+uiua.create_bind_function("Quadruple", (1, 1), |uiua| {
+    let double = uiua.bound_function("Double").unwrap();
+    let x = uiua.pop(1)?;
+    let doubled = uiua.call_with_args(double.clone(), [x])?;
+    let quadrupled = uiua.call_with_args(double, doubled)?;
+    uiua.push(quadrupled[0].clone());
+    Ok(())
+}).unwrap();
+
+uiua.load_str("Quadruple 5").unwrap();
+let res = uiua.pop_num().unwrap();
+assert_eq!(res, 20.0);
+```
 You can format Uiua code with the [`mod@format`] module.
 ```rust
 use uiua::format::*;
@@ -58,6 +78,7 @@ assert_eq!(formatted, "↯3_4⇡12");
 
 The `uiua` crate has the following feature flags:
 - `bytes`: Enables a byte array type. This type is semantically equivalent to a numeric array, but takes up less space. It is returned by certain file and network functions, as well as some comparison functions.
+- `native_sys`: Enables the [`NativeSys`] backend. Without it, the interpreter has no access to the filesystem, network, or terminal unless a custom [`SysBackend`] is provided. It is enabled by the default features and by any feature that extends the native backend.
 - `audio`: Enables audio features in the [`NativeSys`] backend.
 */
 
@@ -82,11 +103,14 @@ mod parse;
 mod primitive;
 #[doc(hidden)]
 pub mod profile;
+mod rational;
+mod record;
 mod run;
 #[cfg(feature = "stand")]
 #[doc(hidden)]
 pub mod stand;
 mod sys;
+#[cfg(feature = "native_sys")]
 mod sys_native;
 mod value;
 
@@ -102,15 +126,21 @@ pub use self::{
     lsp::{spans, SpanKind},
     parse::{parse, ParseError},
     primitive::*,
+    record::*,
     run::*,
     sys::*,
-    sys_native::*,
     value::*,
 };
 #[cfg(feature = "complex")]
 pub use complex::*;
 #[cfg(not(feature = "complex"))]
 use complex::*;
+#[cfg(feature = "rational")]
+pub use rational::*;
+#[cfg(not(feature = "rational"))]
+use rational::*;
+#[cfg(feature = "native_sys")]
+pub use sys_native::*;
 
 /// A Uiua identifier
 pub type Ident = Arc<str>;
@@ -131,6 +161,678 @@ fn suite() {
     }
 }
 
+#[test]
+fn unoptimized_suite() {
+    for entry in std::fs::read_dir("tests").unwrap() {
+        let entry = entry.unwrap();
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|s| s == "ua") {
+            let mut env = Uiua::with_native_sys().with_opt_level(OptLevel::None);
+            if let Err(e) = env.load_file(&path) {
+                panic!("Test failed in {}:\n{}", path.display(), e.report());
+            }
+        }
+    }
+}
+
+#[test]
+fn golf_suite() {
+    let config = format::FormatConfig::default().with_golf(true);
+    for entry in std::fs::read_dir("tests").unwrap() {
+        let entry = entry.unwrap();
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|s| s == "ua") {
+            let input = std::fs::read_to_string(&path).unwrap();
+            let golfed = format::format(&input, &path, &config).unwrap().output;
+            assert!(golfed.len() < input.len());
+            let mut env = Uiua::with_native_sys();
+            if let Err(e) = env.load_str_path(&golfed, &path) {
+                panic!(
+                    "Golfed test failed in {}:\n{}\n{golfed}",
+                    path.display(),
+                    e.report()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn reload() {
+    let mut env = Uiua::with_native_sys();
+    env.reload("A ← 1\nB ← +1 A\nB").unwrap();
+    let globals_len = env.globals.lock().len();
+    env.reload("A ← 1\nB ← +2 A\nB").unwrap();
+    assert_eq!(env.stack, [Value::from(3.0)]);
+    assert_eq!(env.globals.lock().len(), globals_len);
+    env.reload("A ← 1\nB ← +2 A\nB").unwrap();
+    assert_eq!(env.stack, [Value::from(3.0)]);
+    assert!(env.reload("A ← 1\nB ← +2 A\n+1 C").is_err());
+    assert!(env.stack.is_empty());
+    env.reload("A ← 1\nB ← +2 A\n+1 B").unwrap();
+    assert_eq!(env.stack, [Value::from(4.0)]);
+}
+
+#[test]
+fn reload_keeps_independent_bindings() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let mut env = Uiua::with_native_sys();
+    let counter = ticks.clone();
+    env.register_fn("Tick", (1, 1), move |_| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    })
+    .unwrap();
+    let code = |a: &str| format!("A ← {a}\nB ← Tick +1 A\nC ← Tick 5\nF ← |1.1 +1⊢⇌\n[B C]\nF []");
+    assert!(env.reload(&code("1")).is_err());
+    assert_eq!(ticks.load(Ordering::Relaxed), 2);
+    let error = env.reload(&code("2")).unwrap_err().to_string();
+    // Only the binding that depends on the changed one is run again
+    assert_eq!(ticks.load(Ordering::Relaxed), 3);
+    assert_eq!(env.pop_nums().unwrap(), [3.0, 5.0]);
+    // The kept function still points at its own code
+    assert!(error.contains("4:12"), "{error}");
+    // Removing a binding runs the bindings that referred to it again
+    assert!(env.reload("B ← Tick +1 A\nC ← Tick 5").is_err());
+    env.reload("A ← 3\nB ← Tick +1 A\nC ← Tick 5\n[B C]")
+        .unwrap();
+    assert_eq!(env.pop_nums().unwrap(), [4.0, 5.0]);
+}
+
+#[test]
+fn run_many() {
+    let mut env = Uiua::with_native_sys();
+    env.load_str("A ← 5").unwrap();
+    let results = env.run_many(&["B ← 1\n+A B", "B", "×2 A"]);
+    assert_eq!(results[0].as_ref().unwrap(), &[Value::from(6.0)]);
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap(), &[Value::from(10.0)]);
+}
+
+#[test]
+fn snapshot_rollback() {
+    let mut env = Uiua::with_native_sys();
+    env.load_str("A ← 1\n2").unwrap();
+    let snapshot = env.snapshot();
+    env.load_str("A ← 5\nB ← 3\n+A B").unwrap();
+    assert_eq!(env.stack, [Value::from(2.0), Value::from(8.0)]);
+    env.rollback(snapshot);
+    assert_eq!(env.stack, [Value::from(2.0)]);
+    env.load_str("A").unwrap();
+    assert_eq!(env.stack, [Value::from(2.0), Value::from(1.0)]);
+    assert!(env.load_str("B").is_err());
+}
+
+#[test]
+fn eval_releases_globals() {
+    let mut env = Uiua::with_native_sys();
+    env.load_str("F ← eval\nF \"X ← 5\\nY ← ×2 X\\nY\"")
+        .unwrap();
+    let globals_len = env.globals.lock().len();
+    env.load_str("F \"X ← 5\\nY ← ×2 X\\nY\"").unwrap();
+    assert_eq!(env.globals.lock().len(), globals_len);
+    assert!(env.load_str("X").is_err());
+}
+
+#[test]
+fn profile() {
+    let mut env = Uiua::with_native_sys().with_profiling(true);
+    env.load_str("F ← +1\n≡F ⇡5").unwrap();
+    let profile = env.profile().unwrap();
+    let (_, f_stats) = profile.functions.iter().find(|(id, _)| *id == "F").unwrap();
+    assert_eq!(f_stats.count, 10);
+    assert!(profile.spans.iter().any(|(_, stats)| stats.count == 5));
+}
+
+#[test]
+fn fuel() {
+    let mut env = Uiua::with_native_sys().with_fuel(1000);
+    let error = env.load_str("⍥(+1)∞ 0").unwrap_err();
+    assert_eq!(error.message(), "Maximum instruction count exceeded");
+    assert_eq!(env.remaining_fuel(), Some(0));
+
+    let mut env = Uiua::with_native_sys().with_fuel(1000);
+    env.load_str("⍥(+1)10 0").unwrap();
+    assert!(env.remaining_fuel().is_some_and(|fuel| fuel > 900));
+
+    // Tail calls do not take call frames, so only fuel stops this
+    let mut env = Uiua::with_native_sys().with_fuel(1000);
+    let error = env.load_str("F ← |1.1 F\nF 0").unwrap_err();
+    assert_eq!(error.message(), "Maximum instruction count exceeded");
+
+    // Spawned threads burn the same fuel
+    let mut env = Uiua::with_native_sys().with_fuel(1000);
+    let error = env.load_str("⊐≡wait ≡spawn(⍥(+1)∞) [0 0 0 0]").unwrap_err();
+    assert_eq!(error.message(), "Maximum instruction count exceeded");
+    assert_eq!(env.remaining_fuel(), Some(0));
+
+    let mut env = Uiua::with_native_sys().with_fuel(1000);
+    env.load_str("wait spawn(⍥(+1)100) 0").unwrap();
+    assert!(env.remaining_fuel().is_some_and(|fuel| fuel < 900));
+}
+
+#[test]
+fn error_trace() {
+    let mut env = Uiua::with_native_sys();
+    let error = env.load_str("F ← ⊢⊢\nG ← F\n≡G [[] []]").unwrap_err();
+    let UiuaError::Traced { trace, .. } = &error else {
+        panic!("error has no trace: {error}");
+    };
+    let ids: Vec<String> = trace.iter().map(|frame| frame.id.to_string()).collect();
+    assert_eq!(ids, ["`F`", "`G`", "≡", "main"]);
+    assert_eq!(error.to_string().lines().count(), 4);
+}
+
+#[test]
+fn interrupt() {
+    let mut env = Uiua::with_native_sys();
+    let handle = env.interrupt_handle();
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        handle.interrupt();
+    });
+    let error = env.load_str("F ← ×1+1×2\n⍥F∞ 0").unwrap_err();
+    assert_eq!(error.message(), "Program interrupted");
+    env.take_stack();
+    env.load_str("5").unwrap();
+    assert_eq!(env.take_stack(), [Value::from(5.0)]);
+}
+
+#[test]
+fn memory_limit() {
+    for code in [
+        "⇡1e12",
+        "⇡[1e6 1e6]",
+        "↯[1e6 1e6]0",
+        "↯1e12 1",
+        "▽1e12 1",
+        "⊞+.⇡1e6",
+        "⊚[1e12]",
+        "⍘⊚[1e12]",
+        "⬚0↙1e12 [1]",
+        "⇡1e6",
+    ] {
+        let mut env = Uiua::with_native_sys().with_memory_limit(1 << 20);
+        let error = env.load_str(code).unwrap_err();
+        assert!(
+            error.message().contains("memory limit"),
+            "{code}: {}",
+            error.message()
+        );
+    }
+    let mut env = Uiua::with_native_sys().with_memory_limit(1 << 20);
+    env.load_str("⇡1e5 ⇡1e5 ⇡1e5").unwrap();
+}
+
+#[test]
+fn repeated_idents_share_an_allocation() {
+    let (items, _, _) = parse("Shared ← 1\nShared ← 2", None);
+    let [ast::Item::Binding(a), ast::Item::Binding(b)] = &items[..] else {
+        panic!("expected bindings");
+    };
+    assert!(Arc::ptr_eq(&a.name.value, &b.name.value));
+}
+
+#[test]
+fn signature_mismatch() {
+    for code in ["F ← |2.1 +1", "F ← (|2.1 +1)", "A B ← |0.2 [1 2]"] {
+        let error = Uiua::with_native_sys().load_str(code).unwrap_err();
+        assert_eq!(error.kind(), "signature", "{code}: {error}");
+        assert!(
+            error.message().contains("Function signature mismatch"),
+            "{code}: {error}"
+        );
+    }
+}
+
+#[test]
+fn mutual_recursion() {
+    for (code, expected) in [
+        (
+            "Even ← |1.1 (Odd-1|1;)=0.\nOdd ← |1.1 (Even-1|0;)=0.\n[Even 10 Even 7 Odd 3]",
+            vec![1.0, 0.0, 1.0],
+        ),
+        (
+            "F ← |1.1 (\n  G ← |1.1 (∘|H-1)>0.\n  H ← |1.1 (∘|G-1)>0.\n  G\n)\n[F 5]",
+            vec![0.0],
+        ),
+        ("A ← |0.1 B\nB ← |0.1 5\n[A]", vec![5.0]),
+    ] {
+        let mut env = Uiua::with_native_sys();
+        env.load_str(code).unwrap();
+        assert_eq!(env.pop_nums().unwrap(), expected, "{code}");
+    }
+    let error = (Uiua::with_native_sys().load_str("A ← |1.1 +B\nB ← 5"))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("must have a declared signature"), "{error}");
+    let error = (Uiua::with_native_sys().load_str("A ← |1.1 B\nA 1\nB ← |1.1 +1"))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("called before it was bound"), "{error}");
+    let error = Uiua::with_native_sys().load_str("F ← |1.1 G").unwrap_err();
+    assert!(
+        error.message().contains("Unknown identifier `G`"),
+        "{error}"
+    );
+}
+
+#[test]
+fn capabilities() {
+    let mut env = Uiua::with_native_sys().with_capabilities([Capability::Audio]);
+    for code in [
+        "&fras \"readme.md\"",
+        "&tcpc \"localhost:0\"",
+        "&runc \"ls\"",
+        "X ¤ \"x.ua\"",
+    ] {
+        let error = env.load_str(code).unwrap_err();
+        assert!(
+            error.message().contains("access is disabled"),
+            "{code}: {error}"
+        );
+    }
+    env.load_str("&p \"hi\"").unwrap();
+    // Downloads are cached, so they need filesystem access as well
+    let mut env = Uiua::with_native_sys().with_capabilities([Capability::Network]);
+    let error = env.load_str("&dl \"http://localhost:0\"").unwrap_err();
+    assert!(error.message().contains("filesystem access is disabled"));
+}
+
+#[test]
+fn leftover_handles_are_closed() {
+    fn is_open(handle: Value) -> bool {
+        let mut env = Uiua::with_native_sys();
+        env.push(handle);
+        env.load_str("&cl").is_ok()
+    }
+    // When a program fails
+    let mut env = Uiua::with_native_sys();
+    assert!(env.load_str("&fo \"readme.md\"\n⊢[]").is_err());
+    assert!(!is_open(env.pop(()).unwrap()));
+    // When a test scope ends
+    let mut test_env = Uiua::with_native_sys().with_mode(RunMode::All);
+    test_env
+        .load_str("---\n&fo \"readme.md\"\n---\n&fo \"readme.md\"")
+        .unwrap();
+    assert_eq!(test_env.open_handles.lock().len(), 1);
+    // When the runtime is dropped
+    env.load_str("&fo \"readme.md\"").unwrap();
+    let handle = env.pop(()).unwrap();
+    drop(env);
+    assert!(!is_open(handle));
+}
+
+#[test]
+fn host_callback() {
+    use std::cmp::Ordering;
+    let mut env = Uiua::with_native_sys();
+    env.load_str("Cmp ← >").unwrap();
+    env.create_bind_function("HostSort", (1, 1), |env| {
+        let cmp = env.bound_function("Cmp").unwrap();
+        let mut rows: Vec<Value> = env.pop(1)?.into_rows().collect();
+        let mut error = None;
+        rows.sort_by(
+            |a, b| match env.call_with_args(cmp.clone(), [a.clone(), b.clone()]) {
+                Ok(res) if res[0] == Value::from(1.0) => Ordering::Greater,
+                Ok(_) => Ordering::Less,
+                Err(e) => {
+                    error = Some(e);
+                    Ordering::Equal
+                }
+            },
+        );
+        if let Some(error) = error {
+            return Err(error);
+        }
+        env.push(Value::from_row_values_infallible(rows));
+        Ok(())
+    })
+    .unwrap();
+    env.load_str("⍤∶≍, [1 2 3] HostSort [3 1 2]").unwrap();
+    env.load_str("⍤∶≍, [4_5_6 1_2_3] HostSort ≡HostSort [3_1_2 6_4_5]")
+        .unwrap();
+    let cmp = env.bound_function("Cmp").unwrap();
+    assert!(env.call_with_args(cmp, [Value::from(1.0)]).is_err());
+    assert!(env.stack.is_empty());
+}
+
+#[test]
+fn duplicated_arrays_share_data() {
+    let mut env = Uiua::with_native_sys();
+    env.load_str("⍥.2,1⇡1e6").unwrap();
+    let Value::Num(a) = env.pop(()).unwrap() else {
+        panic!("expected numbers")
+    };
+    let mut copies = 0;
+    for val in env.take_stack() {
+        let Value::Num(mut b) = val else {
+            continue;
+        };
+        if b.shape == a.shape {
+            assert!(b.data.is_copy_of(&a.data));
+            b.data.as_mut_slice()[0] = 1.0;
+            assert!(!b.data.is_copy_of(&a.data));
+            assert_eq!(a.data[0], 0.0);
+            copies += 1;
+        }
+    }
+    assert_eq!(copies, 3);
+}
+
+#[test]
+fn unique_arrays_are_mutated_in_place() {
+    let env = Uiua::with_native_sys();
+    let a: Array<f64> = (0..1000).map(f64::from).collect();
+    let ptr = a.data.as_ptr();
+    let val = Value::from(a).add(2.0.into(), &env).unwrap();
+    let val = Value::from(3.0).mul(val, &env).unwrap();
+    let val = val.neg(&env).unwrap();
+    let Value::Num(a) = val else {
+        panic!("expected numbers")
+    };
+    assert_eq!(a.data.as_ptr(), ptr);
+    assert_eq!(a.data[1], -9.0);
+}
+
+#[test]
+fn primitive_stats() {
+    let mut env = Uiua::with_native_sys().with_stats(true);
+    env.load_str("⍥(+1)10 0\n⇡1000").unwrap();
+    let stats = env.stats().unwrap();
+    let get = |prim| {
+        (stats.primitives.iter())
+            .find(|(p, _)| *p == prim)
+            .map(|(_, stats)| *stats)
+            .unwrap()
+    };
+    assert_eq!(get(Primitive::Add).count, 10);
+    assert_eq!(get(Primitive::Repeat).count, 1);
+    assert_eq!(get(Primitive::Range).bytes, 8000);
+    assert!(Uiua::with_native_sys().stats().is_none());
+}
+
+#[test]
+fn registered_functions() {
+    let mut env = Uiua::with_native_sys();
+    env.register_fn("Triple", (1, 1), |env| {
+        let x = env.pop(1)?;
+        env.push(x.mul(3.into(), env)?);
+        Ok(())
+    })
+    .unwrap();
+    env.register_fn("Fail", (0, 0), |env| Err(env.error("Oh no")))
+        .unwrap();
+    assert!(env.register_fn("Bad!", (0, 0), |_| Ok(())).is_err());
+    env.load_str("⍤∶≍, 6 Triple 2").unwrap();
+    // Registered functions are visible from imported modules
+    let module = std::env::temp_dir().join("uiua_registered_functions.ua");
+    std::fs::write(&module, "F ← Triple Triple").unwrap();
+    env.load_str(&format!(
+        "M ¤ {:?}\n⍤∶≍, 18 M¤F 2",
+        module.to_string_lossy()
+    ))
+    .unwrap();
+    _ = std::fs::remove_file(module);
+    // Bindings shadow them
+    env.load_str("Triple ← +1\n⍤∶≍, 3 Triple 2").unwrap();
+    let error = env.load_str("Fail").unwrap_err().to_string();
+    assert!(
+        error.contains("Oh no") && error.contains("in `Fail`"),
+        "{error}"
+    );
+}
+
+#[test]
+fn waiting_for_a_packet_does_not_block_closing() {
+    use std::{thread, time::*};
+    let mut env = Uiua::with_native_sys();
+    env.load_str("&udpb \"127.0.0.1:0\"").unwrap();
+    let handle = env.pop_nat().unwrap();
+    let receiver = thread::spawn(move || {
+        let mut env = Uiua::with_native_sys().with_execution_limit(Duration::from_secs(1));
+        env.load_str(&format!("&udpr 16 {handle}")).unwrap_err();
+    });
+    thread::sleep(Duration::from_millis(200));
+    let start = Instant::now();
+    env.load_str(&format!("&cl {handle}")).unwrap();
+    assert!(start.elapsed() < Duration::from_millis(500));
+    receiver.join().unwrap();
+}
+
+#[test]
+fn udp_receive_count_is_capped() {
+    let mut env =
+        Uiua::with_native_sys().with_execution_limit(std::time::Duration::from_millis(200));
+    let error = env
+        .load_str("&udpr 1e15 &udpb \"127.0.0.1:0\"")
+        .unwrap_err();
+    assert_eq!(error.kind(), "timeout", "{error}");
+    let mut env = Uiua::with_native_sys().with_memory_limit(1 << 20);
+    env.load_str("&udpr 1e15 &udpb \"127.0.0.1:0\"")
+        .unwrap_err();
+}
+
+#[test]
+fn waiting_for_a_connection_does_not_block_closing() {
+    use std::{thread, time::*};
+    let mut env = Uiua::with_native_sys();
+    env.load_str("&tcpl \"127.0.0.1:0\"").unwrap();
+    let handle = env.pop_nat().unwrap();
+    let acceptor = thread::spawn(move || {
+        let mut env = Uiua::with_native_sys().with_execution_limit(Duration::from_secs(1));
+        env.load_str(&format!("&tcpa {handle}")).unwrap_err();
+    });
+    thread::sleep(Duration::from_millis(200));
+    let start = Instant::now();
+    env.load_str(&format!("&cl {handle}")).unwrap();
+    assert!(start.elapsed() < Duration::from_millis(500));
+    acceptor.join().unwrap();
+}
+
+#[test]
+fn blocking_io_times_out() {
+    for code in ["&sl 10", "&tcpa &tcpl \"127.0.0.1:0\""] {
+        let start = std::time::Instant::now();
+        let mut env =
+            Uiua::with_native_sys().with_execution_limit(std::time::Duration::from_millis(200));
+        let error = env.load_str(code).unwrap_err().to_string();
+        assert!(
+            error.contains("Maximum execution time exceeded"),
+            "{code}: {error}"
+        );
+        assert!(start.elapsed().as_secs() < 5, "{code}");
+    }
+}
+
+#[cfg(feature = "https")]
+#[test]
+fn download() {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        let mut requests = Vec::new();
+        for response in [
+            "HTTP/1.1 302 Found\r\nLocation: data.txt\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5\r\nHello\r\n7\r\n, world\r\n0\r\n\r\n",
+        ] {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            while !request.ends_with("\r\n\r\n") {
+                reader.read_line(&mut request).unwrap();
+            }
+            requests.push(request.lines().next().unwrap().to_string());
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+        requests
+    });
+    let mut env = Uiua::with_native_sys();
+    env.load_str(&format!("&dl \"http://{addr}/files/start\""))
+        .unwrap();
+    let path = env.pop_string().unwrap();
+    assert!(std::path::Path::new(&path).starts_with(std::env::temp_dir()));
+    let contents = std::fs::read_to_string(&path).unwrap();
+    _ = std::fs::remove_file(&path);
+    assert_eq!(contents, "Hello, world");
+    // The relative redirect is resolved against the requested URL
+    assert_eq!(
+        server.join().unwrap(),
+        ["GET /files/start HTTP/1.1", "GET /files/data.txt HTTP/1.1"]
+    );
+}
+
+#[test]
+fn call_stack_spans() {
+    use std::sync::{Arc, Mutex};
+    let spans = Arc::new(Mutex::new(Vec::new()));
+    let mut env = Uiua::with_native_sys().with_mode(RunMode::All);
+    let recorded = spans.clone();
+    env.register_fn("Spans", (0, 0), move |env| {
+        assert_eq!(env.call_depth(), env.call_stack().len());
+        *recorded.lock().unwrap() = (env.call_stack().into_iter())
+            .map(|frame| frame.span)
+            .collect();
+        Ok(())
+    })
+    .unwrap();
+    env.load_str("---\nF ← |1.1 (F-1|Spans)=0.\nF 3\n---")
+        .unwrap();
+    let spans = spans.lock().unwrap();
+    assert_eq!(spans.len(), 5, "{spans:?}");
+    assert!(
+        spans.iter().all(|span| *span != lex::Span::Builtin),
+        "{spans:?}"
+    );
+}
+
+#[test]
+fn project_modules() {
+    let dir = std::env::temp_dir().join(format!("uiua-project-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (name, code) in [
+        ("main.ua", "B ¤ \"b.ua\"\n&i \"c.ua\" \"X\"\nB¤Y"),
+        ("b.ua", "C ¤ \"c.ua\"\nY ← +1 C¤X"),
+        ("c.ua", "X ← 5"),
+        ("x.ua", "Y ¤ \"y.ua\""),
+        ("y.ua", "5\nX ¤ \"x.ua\""),
+    ] {
+        std::fs::write(dir.join(name), code).unwrap();
+    }
+    let file_names = |paths: &[std::path::PathBuf]| -> Vec<String> {
+        (paths.iter())
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    };
+
+    let mut env = Uiua::with_native_sys();
+    let graph = env.load_project(dir.join("main.ua")).unwrap();
+    assert_eq!(file_names(&graph.modules), ["c.ua", "b.ua", "main.ua"]);
+    assert_eq!(
+        file_names(&graph.imports[&graph.modules[2]]),
+        ["b.ua", "c.ua"]
+    );
+    assert_eq!(env.pop_num().unwrap(), 6.0);
+    assert_eq!(env.pop_num().unwrap(), 5.0);
+
+    let mut env = Uiua::with_native_sys();
+    let error = env.load_project(dir.join("x.ua")).unwrap_err().to_string();
+    assert!(error.contains("Cycle detected"), "{error}");
+    assert!(error.contains("y.ua:2:"), "{error}");
+    assert!(env.stack().is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn local_bindings_are_not_globals() {
+    let mut env = Uiua::with_native_sys();
+    let before = env.globals.lock().len();
+    env.load_str("F ← (\n  X ← 5\n  G ← ×2\n  G+X\n)\nF 1")
+        .unwrap();
+    assert_eq!(env.globals.lock().len(), before + 1);
+    assert_eq!(env.pop_num().unwrap(), 12.0);
+    assert!(env.load_str("X").is_err());
+}
+
+#[test]
+fn instruction_spans() {
+    let mut env = Uiua::with_native_sys();
+    env.load_str("F ← ⊂ 9 ≡(⊂ [1 2] ⊂ 3 .) ; $\"_ x\" \"y\"")
+        .unwrap();
+    let f = env.bound_function("F").unwrap();
+    let instrs = env.disassemble(&f).instrs;
+    assert!(instrs
+        .iter()
+        .any(|instr| matches!(instr.instr, Instr::PushFunc(..))));
+    for instr in instrs {
+        assert_ne!(instr.span, lex::Span::Builtin, "{}", instr.instr);
+    }
+}
+
+#[test]
+fn hooks() {
+    use std::sync::Mutex;
+    #[derive(Default)]
+    struct Events(Mutex<Vec<String>>);
+    impl Hooks for Events {
+        fn on_call(&self, _: &Uiua, f: &Function) {
+            self.0.lock().unwrap().push(format!("call {}", f.id));
+        }
+        fn on_return(&self, _: &Uiua, f: &Function) {
+            self.0.lock().unwrap().push(format!("return {}", f.id));
+        }
+        fn on_push(&self, _: &Uiua, val: &Value) {
+            self.0.lock().unwrap().push(format!("push {val}"));
+        }
+        fn on_error(&self, env: &Uiua, error: &UiuaError) {
+            let id = env.call_stack().remove(0).id;
+            (self.0.lock().unwrap()).push(format!("error in {id}: {}", error.message()));
+        }
+    }
+    let events = Arc::new(Events::default());
+    let mut env = Uiua::with_native_sys()
+        .with_opt_level(OptLevel::None)
+        .with_hooks(events.clone())
+        .with_hooks(events.clone());
+    env.load_str("F ← |1 ⊢\nF [5]").unwrap();
+    let expected = [
+        "call main",
+        "call main",
+        "push [5]",
+        "push [5]",
+        "call `F`",
+        "call `F`",
+        "push 5",
+        "push 5",
+        "return `F`",
+        "return `F`",
+        "return main",
+        "return main",
+    ];
+    assert_eq!(*events.0.lock().unwrap(), expected);
+    events.0.lock().unwrap().clear();
+    assert!(env.load_str("F []").is_err());
+    let events = events.0.lock().unwrap();
+    assert_eq!(
+        events.iter().filter(|e| e.starts_with("error")).count(),
+        2,
+        "{events:?}"
+    );
+    assert!(
+        events.last().unwrap().starts_with("error in `F`"),
+        "{events:?}"
+    );
+}
+
 #[test]
 fn no_dbgs() {
     fn recurse_dirs(dir: &std::path::Path, f: &impl Fn(&std::path::Path)) {
@@ -1,7 +1,7 @@
 use std::slice;
 
 use crate::{
-    ast::{Item, Modifier, Word},
+    ast::{Binding, Item, Modifier, Word},
     lex::{CodeSpan, Loc, Sp},
     parse::parse,
     Primitive,
@@ -35,13 +35,11 @@ fn items_spans(items: &[Item]) -> Vec<Sp<SpanKind>> {
         match item {
             Item::TestScope(items) => spans.extend(items_spans(&items.value)),
             Item::Words(words) => spans.extend(words_spans(words)),
-            Item::Binding(binding) => {
-                spans.push(binding.name.span.clone().sp(SpanKind::Ident));
-                spans.push(binding.arrow_span.clone().sp(SpanKind::Delimiter));
-                if let Some(sig) = &binding.signature {
-                    spans.push(sig.span.clone().sp(SpanKind::Signature));
-                }
-                spans.extend(words_spans(&binding.words));
+            Item::Binding(binding) => spans.extend(binding_spans(binding)),
+            Item::Import(import) => {
+                spans.push(import.name.span.clone().sp(SpanKind::Ident));
+                spans.push(import.currency_span.clone().sp(SpanKind::Delimiter));
+                spans.push(import.path.span.clone().sp(SpanKind::String));
             }
             Item::ExtraNewlines(span) => spans.push(span.clone().sp(SpanKind::Whitespace)),
         }
@@ -49,6 +47,18 @@ fn items_spans(items: &[Item]) -> Vec<Sp<SpanKind>> {
     spans
 }
 
+fn binding_spans(binding: &Binding) -> Vec<Sp<SpanKind>> {
+    let mut spans: Vec<_> = (binding.names())
+        .map(|name| name.span.clone().sp(SpanKind::Ident))
+        .collect();
+    spans.push((binding.arrow_span.clone()).sp(SpanKind::Delimiter));
+    if let Some(sig) = &binding.signature {
+        spans.push(sig.span.clone().sp(SpanKind::Signature));
+    }
+    spans.extend(words_spans(&binding.words));
+    spans
+}
+
 fn words_spans(words: &[Sp<Word>]) -> Vec<Sp<SpanKind>> {
     let mut spans = Vec::new();
     for word in words {
@@ -143,6 +153,7 @@ fn words_spans(words: &[Sp<Word>]) -> Vec<Sp<SpanKind>> {
             Word::Spaces => spans.push(word.span.clone().sp(SpanKind::Whitespace)),
             Word::Comment(_) => spans.push(word.span.clone().sp(SpanKind::Comment)),
             Word::Placeholder(_) => spans.push(word.span.clone().sp(SpanKind::Placeholder)),
+            Word::Binding(binding) => spans.extend(binding_spans(binding)),
         }
     }
     spans
@@ -235,16 +246,18 @@ mod server {
                 }
                 Item::Binding(binding) => {
                     let comment = last_comment.take();
-                    bindings.insert(
-                        binding.name.clone(),
-                        BindingInfo {
-                            comment,
-                            span: binding.name.span.clone(),
-                        }
-                        .into(),
-                    );
+                    for name in binding.names() {
+                        bindings.insert(
+                            name.clone(),
+                            BindingInfo {
+                                comment: comment.clone(),
+                                span: name.span.clone(),
+                            }
+                            .into(),
+                        );
+                    }
                 }
-                Item::ExtraNewlines(_) => {}
+                Item::Import(_) | Item::ExtraNewlines(_) => {}
             }
         }
         scope_bindings.push(bindings);
@@ -6,7 +6,7 @@ use std::{
     io::{self, stderr, Write},
     path::{Path, PathBuf},
     process::{exit, Child, Command, Stdio},
-    sync::mpsc::channel,
+    sync::{mpsc::channel, Arc},
     thread::sleep,
     time::Duration,
 };
@@ -20,13 +20,19 @@ use parking_lot::Mutex;
 use rustyline::{error::ReadlineError, DefaultEditor};
 use uiua::{
     format::{format_file, format_str, FormatConfig, FormatConfigSource},
-    spans, PrimClass, RunMode, SpanKind, Uiua, UiuaError, UiuaResult, Value,
+    spans, InterruptHandle, PrimClass, Recorder, Recording, RunMode, SpanKind, Uiua, UiuaError,
+    UiuaResult, Value,
 };
 
 fn main() {
     color_backtrace::install();
 
     let _ = ctrlc::set_handler(|| {
+        // The first interrupt stops the running program so it can report where it was
+        if let Some(handle) = INTERRUPT_HANDLE.lock().take() {
+            handle.interrupt();
+            return;
+        }
         let mut child = WATCH_CHILD.lock();
         if let Some(ch) = &mut *child {
             _ = ch.kill();
@@ -48,6 +54,12 @@ fn main() {
 }
 
 static WATCH_CHILD: Lazy<Mutex<Option<Child>>> = Lazy::new(Default::default);
+static INTERRUPT_HANDLE: Lazy<Mutex<Option<InterruptHandle>>> = Lazy::new(Default::default);
+
+/// Make Ctrl-C interrupt the runtime
+fn interrupt_on_ctrlc(rt: &Uiua) {
+    *INTERRUPT_HANDLE.lock() = Some(rt.interrupt_handle());
+}
 
 fn run() -> UiuaResult {
     if cfg!(feature = "profile") {
@@ -94,6 +106,9 @@ fn run() -> UiuaResult {
                 no_color,
                 formatter_options,
                 time_instrs,
+                profile,
+                stats,
+                record,
                 mode,
                 #[cfg(feature = "audio")]
                 audio_options,
@@ -125,9 +140,28 @@ fn run() -> UiuaResult {
                     .with_file_path(&path)
                     .with_args(args)
                     .print_diagnostics(true)
-                    .time_instrs(time_instrs);
-                rt.load_file(path)?;
+                    .time_instrs(time_instrs)
+                    .with_profiling(profile)
+                    .with_stats(stats);
+                let recorder = record.as_ref().map(|_| Arc::new(Recorder::default()));
+                if let Some(recorder) = &recorder {
+                    rt = rt.with_debugger(recorder.clone());
+                }
+                interrupt_on_ctrlc(&rt);
+                let res = rt.load_file(path);
+                // The recording is written even if the program fails
+                if let Some((record, recorder)) = record.zip(recorder) {
+                    fs::write(&record, recorder.recording().to_string())
+                        .map_err(|e| UiuaError::Load(record, e.into()))?;
+                }
+                res?;
                 print_stack(&rt.take_stack(), !no_color);
+                if let Some(profile) = rt.profile() {
+                    eprintln!("{profile}");
+                }
+                if let Some(stats) = rt.stats() {
+                    eprintln!("{stats}");
+                }
             }
             App::Eval {
                 code,
@@ -142,6 +176,7 @@ fn run() -> UiuaResult {
                     .with_mode(RunMode::Normal)
                     .with_args(args)
                     .print_diagnostics(true);
+                interrupt_on_ctrlc(&rt);
                 rt.load_str(&code)?;
                 print_stack(&rt.take_stack(), !no_color);
             }
@@ -210,6 +245,7 @@ fn run() -> UiuaResult {
                     .print_diagnostics(true);
                 repl(rt, true, config);
             }
+            App::Replay { path } => replay(path)?,
             App::CheckUpdate => show_update_message(),
             #[cfg(feature = "stand")]
             App::Stand { main, name } => {
@@ -491,6 +527,12 @@ enum App {
         formatter_options: FormatterOptions,
         #[clap(long, help = "Emit the duration of each instruction's execution")]
         time_instrs: bool,
+        #[clap(long, help = "Emit how much time was spent on each span of code")]
+        profile: bool,
+        #[clap(long, help = "Emit how often each primitive ran and how long it took")]
+        stats: bool,
+        #[clap(long, help = "Record every executed instruction to a file")]
+        record: Option<PathBuf>,
         #[clap(long, help = "Run the file in a specific mode")]
         mode: Option<RunMode>,
         #[cfg(feature = "audio")]
@@ -550,6 +592,8 @@ enum App {
         #[clap(trailing_var_arg = true)]
         args: Vec<String>,
     },
+    #[clap(about = "Step through a recording made with `uiua run --record`")]
+    Replay { path: PathBuf },
     #[clap(about = "Check for updates")]
     CheckUpdate,
     #[cfg(feature = "stand")]
@@ -775,7 +819,10 @@ fn repl(mut rt: Uiua, color: bool, config: FormatConfig) {
         }
         println!();
 
-        rt.load_str(&code)?;
+        interrupt_on_ctrlc(rt);
+        let res = rt.load_str(&code);
+        INTERRUPT_HANDLE.lock().take();
+        res?;
         print_stack(&rt.take_stack(), color);
         Ok(true)
     };
@@ -791,3 +838,58 @@ fn repl(mut rt: Uiua, color: bool, config: FormatConfig) {
         }
     }
 }
+
+fn replay(path: PathBuf) -> UiuaResult {
+    let input = fs::read_to_string(&path).map_err(|e| UiuaError::Load(path.clone(), e.into()))?;
+    let recording: Recording = match input.parse() {
+        Ok(recording) => recording,
+        Err(e) => {
+            eprintln!("{e}");
+            return Ok(());
+        }
+    };
+    let steps = &recording.steps;
+    if steps.is_empty() {
+        println!("The recording is empty");
+        return Ok(());
+    }
+    println!(
+        "Press enter to step forward, b to step back, a number to jump to a step, or q to quit\n"
+    );
+    let mut i = 0;
+    let mut line = String::new();
+    loop {
+        let step = &steps[i];
+        println!(
+            "{} {}  depth {}  stack {}",
+            "step".bright_black(),
+            format!("{}/{}", i + 1, steps.len()).bold(),
+            step.call_depth,
+            step.stack_height
+        );
+        if step.location.is_empty() {
+            println!("  {}", step.instr);
+        } else {
+            println!(
+                "  {}  {} {}",
+                step.instr,
+                step.location.bright_black(),
+                step.code
+            );
+        }
+        line.clear();
+        if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+        match line.trim() {
+            "" => i = (i + 1).min(steps.len() - 1),
+            "b" => i = i.saturating_sub(1),
+            "q" => break,
+            n => match n.parse::<usize>() {
+                Ok(n) => i = n.clamp(1, steps.len()) - 1,
+                Err(_) => eprintln!("Unknown command: {n}"),
+            },
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::{error::Error, fmt, iter::once, path::Path};
+use std::{collections::HashSet, error::Error, fmt, iter::once, path::Path};
 
 use crate::{
     ast::*,
@@ -106,6 +106,7 @@ pub fn parse(
         index: 0,
         errors,
         diagnostics: Vec::new(),
+        idents: HashSet::new(),
     };
     let items = parser.items(true);
     if parser.errors.is_empty() && parser.index < parser.tokens.len() {
@@ -124,6 +125,8 @@ struct Parser {
     index: usize,
     errors: Vec<Sp<ParseError>>,
     diagnostics: Vec<Diagnostic>,
+    /// Identifiers that have already been parsed, so that repeated names share an allocation
+    idents: HashSet<Ident>,
 }
 
 type FunctionContents = (Option<Sp<Signature>>, Vec<Vec<Sp<Word>>>, Option<CodeSpan>);
@@ -201,7 +204,9 @@ impl Parser {
     }
     fn try_item(&mut self, parse_scopes: bool) -> Option<Item> {
         self.try_spaces();
-        Some(if let Some(binding) = self.try_binding() {
+        Some(if let Some(import) = self.try_import() {
+            Item::Import(import)
+        } else if let Some(binding) = self.try_binding() {
             Item::Binding(binding)
         } else if let Some(words) = self.try_words() {
             self.validate_words(&words, false);
@@ -226,17 +231,71 @@ impl Parser {
         let s = s.strip_prefix('#').unwrap_or(s).into();
         Some(span.sp(s))
     }
+    fn try_import(&mut self) -> Option<Import> {
+        let start = self.index;
+        let name = self.try_ident()?;
+        self.try_spaces();
+        let Some(currency_span) = self.try_exact(Currency) else {
+            self.index = start;
+            return None;
+        };
+        self.try_spaces();
+        let Some(path) = self.next_token_map(|t| t.as_string().map(Into::into)) else {
+            self.index = start;
+            return None;
+        };
+        // Only a lone path is an import
+        let end = self.index;
+        self.try_spaces();
+        if !matches!(
+            self.tokens.get(self.index).map(|t| &t.value),
+            None | Some(Newline)
+        ) {
+            self.index = start;
+            return None;
+        }
+        self.index = end;
+        if name.value.contains('&') {
+            self.errors
+                .push(name.span.clone().sp(ParseError::AmpersandBindingName));
+        }
+        Some(Import {
+            name,
+            currency_span,
+            path,
+        })
+    }
     fn try_binding(&mut self) -> Option<Binding> {
         let start = self.index;
         Some(if let Some(name) = self.try_ident() {
+            // More names to destructure into
+            let mut more_names = Vec::new();
+            loop {
+                let before = self.index;
+                self.try_spaces();
+                if let Some(name) = self.try_ident() {
+                    more_names.push(name);
+                } else {
+                    self.index = before;
+                    break;
+                }
+            }
             // Check for invalid binding names
-            if name.value.contains('&') {
-                self.errors
-                    .push(self.prev_span().sp(ParseError::AmpersandBindingName));
+            for name in [&name].into_iter().chain(&more_names) {
+                if name.value.contains('&') {
+                    self.errors
+                        .push(name.span.clone().sp(ParseError::AmpersandBindingName));
+                }
             }
             // Left arrow
             let mut arrow_span = self.try_spaces().map(|w| w.span);
-            if let Some(span) = self.try_exact(Equal).or_else(|| self.try_exact(LeftArrow)) {
+            // Destructuring bindings must use `←`, because `A B = C` is valid code
+            let arrow = if more_names.is_empty() {
+                self.try_exact(Equal).or_else(|| self.try_exact(LeftArrow))
+            } else {
+                self.try_exact(LeftArrow)
+            };
+            if let Some(span) = arrow {
                 arrow_span = Some(if let Some(arrow_span) = arrow_span {
                     arrow_span.merge(span)
                 } else {
@@ -253,7 +312,11 @@ impl Parser {
             // Signature
             let signature = self.try_signature(Bar);
             // Words
-            let words = self.try_words().unwrap_or_default();
+            let mut words = Vec::new();
+            if ident_modifier_args(&name.value) > 0 {
+                words.extend(self.try_modifier_alias());
+            }
+            words.extend(self.try_words().into_iter().flatten());
             // Validate words
             if let (1, Some(Word::Func(func))) = (
                 words.iter().filter(|w| w.value.is_code()).count(),
@@ -266,9 +329,12 @@ impl Parser {
                 self.validate_words(&words, false)
             }
             // Check for uncapitalized binding names
-            if name.value.trim_end_matches('!').chars().count() >= 2
-                && name.value.chars().next().unwrap().is_ascii_lowercase()
-            {
+            for name in [&name].into_iter().chain(&more_names) {
+                if name.value.trim_end_matches('!').chars().count() < 2
+                    || !name.value.chars().next().unwrap().is_ascii_lowercase()
+                {
+                    continue;
+                }
                 let captialized: String = name
                     .value
                     .chars()
@@ -290,6 +356,7 @@ impl Parser {
             }
             Binding {
                 name,
+                more_names,
                 arrow_span,
                 words,
                 signature,
@@ -298,10 +365,57 @@ impl Parser {
             return None;
         })
     }
+    /// Try to parse a binding that is local to a function
+    ///
+    /// Unlike top-level bindings, these must use `←`, because `=` at the start
+    /// of a line in a function is just the `equals` function.
+    fn try_local_binding(&mut self) -> Option<Sp<Word>> {
+        let start = self.index;
+        self.try_ident()?;
+        while self.try_spaces().is_some() && self.try_ident().is_some() {}
+        let is_binding = self.try_exact(LeftArrow).is_some();
+        self.index = start;
+        if !is_binding {
+            return None;
+        }
+        let binding = self.try_binding()?;
+        Some(binding.span().sp(Word::Binding(binding.into())))
+    }
     fn try_ident(&mut self) -> Option<Sp<Ident>> {
         let span = self.try_exact(Token::Ident)?;
-        let s: Ident = span.as_str().into();
-        Some(span.sp(s))
+        let s = span.as_str();
+        let ident = if let Some(ident) = self.idents.get(s) {
+            ident.clone()
+        } else {
+            let ident: Ident = s.into();
+            self.idents.insert(ident.clone());
+            ident
+        };
+        Some(span.sp(ident))
+    }
+    /// Parse a modifier with no operands, which a modifier binding can alias
+    fn try_modifier_alias(&mut self) -> Option<Sp<Word>> {
+        let start = self.index;
+        let word = if let Some(prim) =
+            Primitive::all()
+                .filter(|prim| prim.is_modifier())
+                .find_map(|prim| {
+                    self.try_exact(prim)
+                        .or_else(|| prim.ascii().and_then(|simple| self.try_exact(simple)))
+                        .map(|span| span.sp(prim))
+                }) {
+            prim.map(Word::Primitive)
+        } else {
+            self.try_modifier_ident()?.map(Word::Ident)
+        };
+        // The modifier must be the end of the line
+        let end = self.index;
+        self.try_spaces();
+        let at_end = self.index == self.tokens.len()
+            || self.try_exact(Newline).is_some()
+            || self.try_exact(Token::Comment).is_some();
+        self.index = if at_end { end } else { start };
+        at_end.then_some(word)
     }
     fn try_modifier_ident(&mut self) -> Option<Sp<Ident>> {
         let start = self.index;
@@ -402,11 +516,18 @@ impl Parser {
             Some(words)
         }
     }
-    fn multiline_words(&mut self) -> Vec<Vec<Sp<Word>>> {
+    fn multiline_words(&mut self, allow_bindings: bool) -> Vec<Vec<Sp<Word>>> {
         let mut lines = Vec::new();
         while self.try_exact(Newline).is_some() || self.try_spaces().is_some() {}
-        while let Some(words) = self.try_words() {
-            lines.push(words);
+        loop {
+            let binding = allow_bindings.then(|| self.try_local_binding()).flatten();
+            if let Some(binding) = binding {
+                lines.push(vec![binding]);
+            } else if let Some(words) = self.try_words() {
+                lines.push(words);
+            } else {
+                break;
+            }
             let mut newlines = 0;
             while self.try_exact(Newline).is_some() {
                 newlines += 1;
@@ -615,7 +736,7 @@ impl Parser {
             let span = start.merge(end);
             span.sp(Word::MultilineString(lines))
         } else if let Some(start) = self.try_exact(OpenBracket) {
-            let items = self.multiline_words();
+            let items = self.multiline_words(false);
             let end = self.expect_close(CloseBracket);
             let span = start.merge(end.span);
             span.sp(Word::Array(Arr {
@@ -624,7 +745,7 @@ impl Parser {
                 closed: end.value,
             }))
         } else if let Some(start) = self.try_exact(OpenCurly) {
-            let items = self.multiline_words();
+            let items = self.multiline_words(false);
             let end = self.expect_close(CloseCurly);
             let span = start.merge(end.span);
             span.sp(Word::Array(Arr {
@@ -742,7 +863,7 @@ impl Parser {
     fn func_contents(&mut self) -> FunctionContents {
         while self.try_exact(Newline).is_some() || self.try_spaces().is_some() {}
         let signature = self.try_signature(Bar);
-        let lines = self.multiline_words();
+        let lines = self.multiline_words(true);
         let start = signature
             .as_ref()
             .map(|sig| sig.span.clone())
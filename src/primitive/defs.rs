@@ -236,6 +236,8 @@ primitive!(
     /// ex: ± ¯5
     /// ex: ± 0
     /// ex: ± [¯2 ¯1 0 1 2]
+    /// The sign of a complex number is the number with the same direction and a magnitude of 1.
+    /// ex: ± ℂ4 3
     (1, Sign, MonadicPervasive, ("sign", '±')),
     /// Negate a number
     ///
@@ -299,6 +301,44 @@ primitive!(
     /// ex: ⁅1.5
     /// ex: ⁅[0.1 π 2 9.9 7.5]
     (1, Round, MonadicPervasive, ("round", '⁅')),
+    /// Check if a number is prime
+    ///
+    /// ex: prime 7
+    /// ex: prime 8
+    /// ex: ▽prime.⇡30
+    /// Numbers that are not natural numbers are never prime.
+    /// ex: prime [¯7 1 2.5]
+    (1, Prime, MonadicPervasive, "prime"),
+    /// Get the real part of a complex number
+    ///
+    /// ex: real ℂ3 5
+    /// ex: real [1 ℂ2 3]
+    /// Real numbers are unchanged.
+    /// ex: real 5
+    (1, Real, MonadicPervasive, "real"),
+    /// Get the imaginary part of a complex number
+    ///
+    /// ex: imaginary ℂ3 5
+    /// ex: imaginary [1 ℂ2 3]
+    /// The imaginary part of a real number is `0`.
+    /// ex: imaginary 5
+    (1, Imaginary, MonadicPervasive, "imaginary"),
+    /// Get the argument of a complex number
+    ///
+    /// This is the angle from the positive real axis, between `¯π` and `π`.
+    /// ex: argument ℂ1 0
+    /// ex: argument ℂ1 1
+    /// ex: argument [1 ¯1 i]
+    /// The argument and the [absolute value] are the polar coordinates of a complex number.
+    /// ex: [⊃argument⌵] ℂ4 3
+    (1, Argument, MonadicPervasive, "argument"),
+    /// Get the conjugate of a complex number
+    ///
+    /// This negates the imaginary part.
+    /// ex: conjugate ℂ3 5
+    /// Multiplying a number by its conjugate gives the square of its magnitude.
+    /// ex: ×conjugate. ℂ4 3
+    (1, Conjugate, MonadicPervasive, "conjugate"),
     /// Compare for equality
     ///
     /// ex: =1 2
@@ -430,6 +470,10 @@ primitive!(
     /// ex: ⁿ2 3
     /// ex: ⁿ2 [1 2 3]
     /// ex: ⁿ [1 2 3] [4 5 6]
+    /// Complex numbers can be raised to any power.
+    /// ex: ⁿ2 i
+    /// ex: ⁿ0.5 ℂ0 ¯1
+    /// ex: ⁿi i
     (2, Pow, DyadicPervasive, ("power", 'ⁿ')),
     /// Get the based logarithm of a number
     ///
@@ -476,6 +520,41 @@ primitive!(
     /// ex: [⍘ℂ] i
     /// ex: [⍘ℂ] ×. ℂ3 4
     (2, Complex, DyadicPervasive, ("complex", 'ℂ')),
+    /// Get the greatest common divisor of two numbers
+    ///
+    /// ex: gcd 12 18
+    /// ex: gcd 10 [4 5 6 7]
+    /// [reduce][gcd] gets the greatest common divisor of a list.
+    /// ex: /gcd [12 30 42]
+    (2, Gcd, DyadicPervasive, "gcd"),
+    /// Get the least common multiple of two numbers
+    ///
+    /// ex: lcm 4 6
+    /// ex: lcm 10 [4 5 6 7]
+    /// [reduce][lcm] gets the least common multiple of a list.
+    /// ex: /lcm +1⇡10
+    (2, Lcm, DyadicPervasive, "lcm"),
+    /// Round a number to the nearest multiple of another
+    ///
+    /// The first argument is the multiple, and the second argument is the number to round.
+    /// ex: roundto 5 23
+    /// ex: roundto 0.25 [1.1 1.2 1.4]
+    /// To round to some number of decimal places, use a power of 10.
+    /// ex: roundto 0.01 π
+    /// ex: roundto ⁿ¯3 10 π
+    /// Rounding to a multiple of `1` is the same as [round].
+    /// ex: roundto 1 [1.4 1.5 ¯2.5]
+    (2, RoundTo, DyadicPervasive, "roundto"),
+    /// Restrict a number to a range
+    ///
+    /// The first argument is the lower bound, the second is the upper bound, and the third is the number to restrict.
+    /// ex: clamp 0 10 [¯5 3 15]
+    /// ex: clamp 0 1 [0.5 1.5 ¯0.5]
+    /// The bounds are pervasive as well.
+    /// ex: clamp [0 5] [3 8] [4 4]
+    /// [clamp] is equivalent to `minimum``maximum`, but the bounds are in a more natural order.
+    /// ex: ↧10 ↥0 [¯5 3 15]
+    (3, Clamp, Misc, "clamp"),
     /// Get the number of rows in an array
     ///
     /// ex: ⧻5
@@ -580,6 +659,43 @@ primitive!(
     ///
     /// [first][fall] and [first][reverse][fall] are optimized in the interpreter to be O(n).
     (1, Fall, MonadicArray, ("fall", '⍖')),
+    /// Get the mean of the rows of an array
+    ///
+    /// ex: mean [1 2 3 4]
+    /// For higher-rank arrays, this gives the mean of each column.
+    /// ex: mean [1_2 3_4 5_9]
+    /// Use [rows] to get the mean of each row.
+    /// ex: ≡mean [1_2 3_4 5_9]
+    ///
+    /// The result is the same as `divide``length``flip``reduce``add``duplicate`, but it is computed in a way that limits rounding errors.
+    /// ex: mean [1e16 1 ¯1e16 3]
+    /// ex: ÷⧻∶/+. [1e16 1 ¯1e16 3]
+    (1, Mean, MonadicArray, "mean"),
+    /// Get the population variance of the rows of an array
+    ///
+    /// ex: variance [2 4 4 4 5 5 7 9]
+    /// ex: variance [1_2 3_4 5_9]
+    /// For the standard deviation, use [sqrt].
+    /// ex: √variance [2 4 4 4 5 5 7 9]
+    (1, Variance, MonadicArray, "variance"),
+    /// Get the median of the rows of an array
+    ///
+    /// ex: median [5 1 4 2 3]
+    /// If there is an even number of rows, the median is between the middle two.
+    /// ex: median [5 1 4 2]
+    /// ex: median [1_2 3_4 5_9]
+    /// [median] does not need to sort the whole array.
+    (1, Median, MonadicArray, "median"),
+    /// Get quantiles of the rows of an array
+    ///
+    /// The first argument is a number between `0` and `1` or an array of them.
+    /// Values between rows are interpolated linearly.
+    /// ex: quantile 0.25 [1 2 3 4 5]
+    /// ex: quantile [0 0.5 1] [7 1 9 3]
+    /// ex: quantile 0.9 [1_10 2_20 3_30]
+    /// `quantile``0.5` is equivalent to [median].
+    /// ex: ⊃(quantile 0.5)median [5 1 4 2]
+    (2, Quantile, DyadicArray, "quantile"),
     /// Get indices where array values are not equal to zero
     ///
     /// The most basic use is to convert a mask into a list of indices.
@@ -601,6 +717,11 @@ primitive!(
     /// ex: ⊚.[1_0_0 0_1_1 0_2_0]
     /// The inverse works as well
     /// ex: ⍘⊚[3_4 2_1 0_3]
+    /// Because trailing zeros leave no indices, [invert][where] cannot always recover the original shape.
+    /// [under][where] keeps it, so indices and counts can be modified in place.
+    /// ex: ⍘⊚⊚ [1_0_0 0_2_0 0_0_0]
+    /// ex: ⍜⊚∘ [1_0_0 0_2_0 0_0_0]
+    /// ex: ⍜⊚(+1) [1_0_0 0_2_0 0_0_0]
     ///
     /// [where] on a scalar is equivalent to [where] on a singleton array of that scalar, and so creates a list of `0`s.
     /// ex: ⊚3
@@ -743,6 +864,33 @@ primitive!(
     /// For index rank `2` or greater, it should hold that `pick``range``shape``duplicate``x` is equivalent to `x`.
     /// ex: ⊡⇡△. [1_2_3 4_5_6]
     (2, Pick, DyadicArray, ("pick", '⊡')),
+    /// Select the cells of an array where a mask is `1`
+    ///
+    /// The mask's [shape] must be a prefix of the array's.
+    /// The selected cells become the rows of the result.
+    /// ex: masked [1 0 1 1 0] [8 3 9 2 0]
+    /// ex: masked [1_0 0_1] [1_2 3_4]
+    /// ex: masked [1 0 1] [1_2 3_4 5_6]
+    /// ex! masked [1 0] [1 2 3]
+    /// It is equivalent to `pick``where`, but faster.
+    /// ex:      masked [1_0 0_1] ↯2_2_2⇡8
+    ///   : ⊡⊚ [1_0 0_1] ↯2_2_2⇡8
+    ///
+    /// [under][masked] updates only the selected cells.
+    /// ex: ⍜masked(×10) >5. [8 3 9 2 0]
+    (2, Masked, DyadicArray, "masked"),
+    /// Set the cells of a copy of an array at some indices
+    ///
+    /// The first argument is the indices. A list indexes rows, and a rank `2` array indexes cells like the result of [where].
+    /// The second argument is the values to put in, one row for each index. A single cell is put at every index.
+    /// ex: scatter [0 3] [10 20] [1 2 3 4 5]
+    /// ex: scatter [0_1 1_0] [10 20] [1_2 3_4]
+    /// ex: scatter ⊚[0_1 1_0] 9 [1_2 3_4]
+    /// ex: scatter [2] [[7 8]] [1_2 3_4 5_6]
+    /// ex! scatter [0 1] [1 2 3] [1 2 3 4]
+    /// If an index appears more than once, the last value is used.
+    /// ex: scatter [1 1] [5 6] [0 0 0]
+    (3, Scatter, Misc, "scatter"),
     /// Change the shape of an array
     ///
     /// ex: ↯ 2_3 [1 2 3 4 5 6]
@@ -827,7 +975,19 @@ primitive!(
     /// ex: ⬚0↻ 2 [1 2 3 4 5]
     ///   :   ↻ 2 [1 2 3 4 5]
     /// ex: ⬚0↻ 1_2 .↯4_5⇡20
+    /// [shift] does the same thing, but it fills even when no fill value is set.
     (2, Rotate, DyadicArray, ("rotate", '↻')),
+    /// Shift the elements of an array by n, filling in the vacated elements
+    ///
+    /// This is like [rotate], but elements do not wrap around.
+    /// ex: shift 1 [1 2 3 4 5]
+    /// ex: shift ¯2 [1 2 3 4 5]
+    /// ex: shift 1_¯1 .↯3_4⇡12
+    /// Numbers are filled with `0`, characters with spaces, and boxes with empty boxes.
+    /// ex: shift 2 "hello"
+    /// A different value can be used with [fill].
+    /// ex: ⬚∞shift 2 [1 2 3 4 5]
+    (2, Shift, DyadicArray, "shift"),
     /// The n-wise windows of an array
     ///
     /// ex: ◫2 .⇡4
@@ -835,6 +995,18 @@ primitive!(
     ///
     /// Multi-dimensional window sizes are supported.
     /// ex: ◫2_2 .[1_2_3 4_5_6 7_8_9]
+    ///
+    /// If the window size is a table, each row is the size, stride, and optionally the dilation of an axis.
+    /// The stride is how far apart the windows are.
+    /// ex: ◫[2_2] .⇡8
+    /// ex: ◫[2_2 2_2] .↯4_4⇡16
+    /// The dilation is how far apart the items in each window are.
+    /// ex: ◫[2_1_3] .⇡8
+    ///
+    /// [fill][windows] pads the array with the fill value so that there is a window at each row.
+    /// If the padding cannot be split evenly, the extra fill goes at the start.
+    /// ex: ⬚0◫3 .⇡5
+    /// ex: ⬚0◫2 .⇡5
     (2, Windows, DyadicArray, ("windows", '◫')),
     /// Discard or copy some rows of an array
     ///
@@ -898,6 +1070,19 @@ primitive!(
     ///
     /// [indexof] is closely related to [member].
     (2, IndexOf, DyadicArray, ("indexof", '⊗')),
+    /// Find the bin of each row of one array in a sorted array
+    ///
+    /// The bin of a row is the number of rows of the sorted array that are less than or equal to it.
+    /// This is the index at which the row could be inserted while keeping the array sorted.
+    /// ex: bins 5 [1 3 5 7]
+    /// ex: bins [0 2 3 8] [1 3 5 7]
+    /// Unlike [indexof], [bins] uses binary search, so the searched-in array must be sorted in ascending order.
+    /// ex! bins 2 [3 1 2]
+    /// [bins] is useful for bucketing values into ranges.
+    /// ex: bins [15 42 67 99] [0 20 40 60 80]
+    /// If the sorted array is multidimensional, its rows are compared lexicographically.
+    /// ex: bins [2_0 1_5] [1_2 2_1 3_0]
+    (2, Bins, DyadicArray, "bins"),
     /// Apply a reducing function to an array
     ///
     /// For reducing with an initial value, see [fold].
@@ -922,12 +1107,20 @@ primitive!(
     /// ex: /↧ []
     /// ex: /∠ []
     /// ex! /⊡ []
+    ///
+    /// [reduce] goes along the first axis. To reduce along a different axis, use [level].
+    /// Each array of the given rank is reduced along its first axis.
+    /// ex: ≑1/+ .↯2_3_4⇡24
+    /// ex: ≑2/+ .↯2_3_4⇡24
     (1[1], Reduce, AggregatingModifier, ("reduce", '/')),
     /// Reduce, but keep intermediate values
     ///
     /// ex: \+   1_2_3_4
     /// ex: \-   1_2_3_4
     /// ex: \(-∶) 1_2_3_4
+    ///
+    /// As with [reduce], [level] can be used to scan along a different axis.
+    /// ex: ≑1\+ .↯3_4⇡12
     (1[1], Scan, AggregatingModifier, ("scan", '\\')),
     /// Apply a function to each element of an array or arrays.
     ///
@@ -998,6 +1191,10 @@ primitive!(
     /// ex: △⊞⊂ 1_2 3_4_5
     /// ex: △⊞+ [1_2_3 4_5_6] [7 8 9 10]
     /// ex: △⊞⊂ [1_2_3 4_5_6] [7 8 9 10]
+    ///
+    /// If the function takes more than 2 arguments, it is applied to each combination of elements of all the arrays.
+    /// ex: ⊞(⊂⊂) 1_2 3_4 5_6
+    /// ex: △⊞(++) 1_2 [3_4_5 6_7_8] 9_10
     (2[1], Table, IteratingModifier, ("table", '⊞')),
     /// Apply a function to each combination of rows of two arrays
     ///
@@ -1006,6 +1203,9 @@ primitive!(
     /// ex: a ← .[1_2 3_4 5_6]
     ///   : b ← .[7_8 9_10]
     ///   : ⊠⊂ a b
+    ///
+    /// If the function takes more than 2 arguments, it is applied to each combination of rows of all the arrays.
+    /// ex: ⊠(⊂⊂) [1_2 3_4] 5_6 [7_8 9_10]
     (2[1], Cross, IteratingModifier, ("cross", '⊠')),
     /// Repeat a function a number of times
     ///
@@ -1079,6 +1279,14 @@ primitive!(
     ///
     /// [partition] is closely related to [group].
     (2[1], Partition, AggregatingModifier, ("partition", '⊜')),
+    /// Partition an array, also passing where each group starts
+    ///
+    /// Takes a function, markers, and an array, like [partition].
+    /// The function is called with each group and the index in the original array of the group's first row.
+    /// ex: partindex(⊟∶⧻) ≠@ . "Hey there friendo"
+    /// If the function takes 3 arguments, it is also passed the group's marker.
+    /// ex: partindex(□⊂⊂) [1 1 0 2 2 2 1] [4 5 6 7 8 9 10]
+    (2[1], PartIndex, AggregatingModifier, "partindex"),
     /// Apply a function with implicit (un)boxing
     ///
     /// When working with [box]ed data, [pack] will automatically [unbox] the data for functions like [join].
@@ -1122,6 +1330,14 @@ primitive!(
     /// While more inverses exists, most of them are not useful on their own.
     /// They are usually used within [under].
     ([1], Invert, OtherModifier, ("invert", '⍘')),
+    /// Call a function when the code is compiled rather than when it is run
+    ///
+    /// The function must take no arguments. It is called once, and its outputs are put in the code as constants.
+    /// ex: comptime(⇌⇡5)
+    /// This is useful for computing things like lookup tables inside functions that are called many times.
+    /// ex: F ← ⊏:comptime(ⁿ:2⇡10)
+    ///   : F [3 5 9]
+    ([1], Comptime, OtherModifier, "comptime"),
     /// Discard the top stack value then call a function
     ///
     /// See the [Advanced Stack Manipulation Tutorial](/docs/advancedstack) for a more complete understanding of why [gap] is useful.
@@ -1167,6 +1383,26 @@ primitive!(
     /// ex: [⊃⋅⊙∘(++) 3 5 10]
     /// ex: [⊃⊙∘(++) 3 5 10]
     ([1], Dip, Planet, ("dip", '⊙')),
+    /// Call a function below the top `n` values on the stack
+    ///
+    /// The count comes before the values it keeps.
+    /// ex: [beneath+ 2 1 2 3 4]
+    /// This is the same as chaining `n` [dip]s, but it is clearer when `n` is large.
+    /// ex: [⊙⊙+ 1 2 3 4]
+    /// The count must be a constant for the signature to be inferred.
+    /// ex: F ← beneath(×10) 3
+    ///   : [F 1 2 3 4]
+    ([1], Beneath, Planet, "beneath"),
+    /// Discard the top `n` values on the stack then call a function
+    ///
+    /// The count comes before the values it discards.
+    /// ex: skip+ 2 1 2 3 4
+    /// This is the same as chaining `n` [gap]s, but it is clearer when `n` is large.
+    /// ex: ⋅⋅+ 1 2 3 4
+    /// The count must be a constant for the signature to be inferred.
+    /// ex: F ← skip¯ 3
+    ///   : F 1 2 3 4
+    ([1], Skip, Planet, "skip"),
     /// Call a function on two sets of values
     ///
     /// For monadic functions, [both] calls its function on each of the top 2 values on the stack.
@@ -1258,6 +1494,29 @@ primitive!(
     ///
     /// [under] works with [&fo], [&fc], [&tcpa], and [&tcpc]. It calls [&cl] when `g` is done.
     ([2], Under, OtherModifier, ("under", '⍜')),
+    /// Set the inverse of a function
+    ///
+    /// The first function is called normally.
+    /// The second function is its inverse, which is used by [invert] and [under].
+    /// ex: F ← setinv(+1)(-1)
+    ///   : F 5
+    ///   : ⍘F 5
+    ///   : ⍜F(×10) 5
+    /// This makes it possible to invert functions that could not be inverted otherwise.
+    /// ex: Rot ← setinv(↻1)(↻¯1)
+    ///   : ⍘Rot [1 2 3 4]
+    /// The functions' signatures must be opposites.
+    /// ex! setinv(⊂)(⊢) 1 2
+    ([2], SetInverse, OtherModifier, "setinv"),
+    /// Set the behavior of a function when used with [under]
+    ///
+    /// The first function is called normally.
+    /// When used with [under], the second function is called before and the third function is called after.
+    /// Values that the second function returns below its first output are kept and passed to the third function above the result.
+    /// ex: F ← setund(⊢)(⊃⊢∘)(⍜⊢;)
+    ///   : F [1 2 3]
+    ///   : ⍜F(×10) [1 2 3]
+    ([3], SetUnder, OtherModifier, "setund"),
     /// Repeat a function while a condition holds
     ///
     /// The first function is the loop function, and it is run as long as the condition is true.
@@ -1324,6 +1583,9 @@ primitive!(
     /// ex: ⬚0↻ 2 [1 2 3 4 5]
     ///   :   ↻ 2 [1 2 3 4 5]
     ///
+    /// [fill][windows] pads the array so that there is a window at each row.
+    /// ex: ⬚0◫3 [1 2 3 4 5]
+    ///
     /// To [fill] with a value that is on the stack, use [identity].
     /// ex: F = ⬚∘+
     ///   : F 100 [1 2 3 4] [5 6]
@@ -1362,6 +1624,11 @@ primitive!(
     /// ex: ≑[1 1]⊂ ↯3_3⇡9 10_11_12 # Join two rank 1 arrays
     /// ex: ≑[1 0]⊂ ↯3_3⇡9 10_11_12 # Join rank 1 arrays with scalars
     ///
+    /// The parts of the arrays' shapes above their ranks must match where they overlap.
+    /// If one array runs out of axes before the others, it is reused for every row of them.
+    /// ex: ≑[0 1]+ [1_2_3 4_5_6] [1_2 3_4]
+    /// ex! ≑[1 1]⊂ ↯3_3⇡9 ↯2_3⇡6
+    ///
     /// [each] is equivalent to `level``0` or `level``[0 0 …]`.
     /// [rows] is equivalent to `level``¯1` or `level``[¯1 ¯1 …]`.
     /// [distribute] is equivalent to `level``[``infinity``¯1]` or `level``[``infinity``…``infinity``¯1]`.
@@ -1410,6 +1677,82 @@ primitive!(
     /// [table] is equivalent to `combinate``[0 0]`.
     /// [cross] is equivalent to `combinate``[¯1 ¯1]`.
     ([2], Combinate, IteratingModifier, ("combinate", '◳')),
+    /// Call a function on the neighborhood of each cell of an array
+    ///
+    /// Expects a radius and an array.
+    /// The function receives each cell's neighborhood, which extends the radius in each direction along the first axes of the array.
+    /// If the radius is a scalar, it applies to all axes.
+    /// A radius cannot be longer than the axis it applies to.
+    /// The function must have signature `|1.1`.
+    /// ex: stencil(/+♭) 1 ↯3_3 1
+    /// ex: stencil(⊢⇌) 1 [1 2 3 4 5]
+    ///
+    /// By default, neighborhoods wrap around the edges of the array.
+    /// Use [fill] to pad the edges instead.
+    /// ex: ⬚0stencil(/+♭) 1 ↯3_3 1
+    ///
+    /// Here is one generation of Conway's Game of Life.
+    /// ex: Life ← stencil(↥⊃(=3/+♭)(×⊃(⊡1_1)(=4/+♭)))1
+    ///   : Life [0_1_0_0 0_0_1_0 1_1_1_0 0_0_0_0]
+    (2[1], Stencil, IteratingModifier, "stencil"),
+    /// Sort the rows of an array by a key
+    ///
+    /// The function is called on each row to get its key, and the rows are sorted by their keys in ascending order.
+    /// The function must have signature `|1.1`.
+    /// ex: sortby⧻ {"banana" "fig" "apple"}
+    /// ex: sortby(⊡1) [3_1 1_2 2_0]
+    /// The sort is stable, so rows with equal keys keep their order.
+    /// ex: sortby(◿3) [5 3 4 1 6 2]
+    /// This does the same thing as `select``rise``rows``f``duplicate`, but it is easier to read.
+    /// To sort in descending order, use [sortbydown].
+    (1[1], SortBy, IteratingModifier, "sortby"),
+    /// Sort the rows of an array by a key in descending order
+    ///
+    /// This is like [sortby], but the rows with the largest keys come first.
+    /// The sort is stable, so rows with equal keys keep their order.
+    /// ex: sortbydown⧻ {"banana" "fig" "apple"}
+    /// ex: sortbydown(◿3) [5 3 4 1 6 2]
+    (1[1], SortByDown, IteratingModifier, "sortbydown"),
+    /// Sort the rows of an array with a comparison function
+    ///
+    /// The function is called with two rows and must return a number.
+    /// A positive number means the rows are out of order.
+    /// The rows are passed in the same order as for [subtract], so `sortwith``subtract` sorts in ascending order.
+    /// ex: sortwith- [3 1 4 1 5 9 2 6]
+    /// ex: sortwith(-∶) [3 1 4 1 5 9 2 6]
+    /// The function must have signature `|2.1`.
+    /// ex: sortwith(-∩(⊡1)) [3_1 1_2 2_0]
+    /// The sort is stable, so rows that compare as equal keep their order.
+    /// ex: sortwith(-∩⧻) {"cherry" "fig" "banana" "kiwi"}
+    /// If the order only depends on a key for each row, [sortby] is faster.
+    (1[1], SortWith, IteratingModifier, "sortwith"),
+    /// Remove rows of an array whose key has already been seen
+    ///
+    /// The function is called on each row to get its key. Only the first row with each key is kept.
+    /// The function must have signature `|1.1`.
+    /// ex: deduplicateby⌵ [1 ¯1 2 3 ¯2]
+    /// ex: deduplicateby⧻ {"cat" "dog" "bird" "fish" "ox"}
+    /// This is like [deduplicate], but rows only need to have equal keys, not be equal themselves.
+    /// Keys are hashed, so this takes linear time.
+    (1[1], DeduplicateBy, IteratingModifier, "deduplicateby"),
+    /// Assign a unique index to each row of an array based on a key
+    ///
+    /// The function is called on each row to get its key. Rows with equal keys get the same index.
+    /// The function must have signature `|1.1`.
+    /// ex: classifyby(◿3) [1 2 3 4 5 6 7]
+    /// ex: classifyby⧻ {"cat" "dog" "bird" "fish" "ox"}
+    /// This is useful with [group] for grouping rows by a key.
+    /// ex: ⊕□ classifyby⧻ . {"cat" "dog" "bird" "fish" "ox"}
+    (1[1], ClassifyBy, IteratingModifier, "classifyby"),
+    /// Count the rows of an array that have each key
+    ///
+    /// The function is called on each row to get its key.
+    /// The counts are in the order that each key is first seen, which is the same order as [deduplicateby].
+    /// The function must have signature `|1.1`.
+    /// ex: occurrencesby∘ "mississippi"
+    /// ex: deduplicateby∘ "mississippi"
+    /// ex: occurrencesby(◿3) [1 2 3 4 5 6 7]
+    (1[1], OccurrencesBy, IteratingModifier, "occurrencesby"),
     /// `join` `infinity` to an array
     ///
     /// See the [Ocean Notation tutorial](/docs/advancedarray#ocean-notation) to understand what this is for.
@@ -1487,9 +1830,13 @@ primitive!(
     ///
     /// If the first function errors, the second function is called with the original arguments and the error value below.
     ///
-    /// Normal runtime errors become strings.
-    /// ex: ⍣(+1 2)$"Error: _"
-    /// ex: ⍣(+@a @b)$"Error: _"
+    /// Normal runtime errors become [map]s with a `"kind"`, `"message"`, `"span"`, and `"trace"`.
+    /// The `"kind"` is `"type"`, `"shape"`, `"index"`, `"io"`, or `"signature"` for errors caused by those, and `"run"` for other runtime errors.
+    /// ex: ⍣(+@a @b)∘
+    /// The parts of the error can be gotten with [get].
+    /// ex: ⍣(+1 2)(⊔get "message")
+    /// ex: ⍣(+@a @b)(⊔get "message")
+    /// ex: ⍣(+@a @b)(⊔get "kind")
     /// Errors thrown with [assert] can be any value.
     /// ex: ⍣(⍤5 1 3)(×5)
     /// ex: ⍣(⍤5 0 3)(×5)
@@ -1588,6 +1935,7 @@ primitive!(
     /// Generate a random number in the range `[0, 1)`
     ///
     /// If you need a seeded random number, use [gen].
+    /// To make every [random] in a program reproducible, seed it with `&seed`.
     ///
     /// ex: ⚂
     /// ex: [⚂⚂⚂]
@@ -1595,6 +1943,30 @@ primitive!(
     /// Use [multiply] and [floor] to generate a random integer in a range.
     /// ex: ⌊×10 [⍥⚂5]
     (0, Rand, Misc, ("random", '⚂')),
+    /// Generate an array of random numbers in the range `[0, 1)`
+    ///
+    /// Expects a shape. A single number is the length of a list.
+    /// ex: uniform 5
+    /// ex: uniform 2_3
+    /// Like [random], this can be made reproducible with `&seed`.
+    /// ex: uniform 3 &seed 0
+    (1, Uniform, Misc, "uniform"),
+    /// Generate an array of random numbers from the standard normal distribution
+    ///
+    /// Expects a shape. A single number is the length of a list.
+    /// The numbers have a mean of `0` and a standard deviation of `1`.
+    /// ex: normal 5
+    /// Use [multiply] and [add] to change the standard deviation and mean.
+    /// ex: +10×2 normal 2_3
+    (1, Normal, Misc, "normal"),
+    /// Generate an array of random integers in a range
+    ///
+    /// Expects a range and a shape. A single number shape is the length of a list.
+    /// If the range is a single number `n`, the integers are in `[0, n)`.
+    /// ex: randint 10 8
+    /// If the range is a pair of numbers, the integers are at least the first and less than the second.
+    /// ex: randint ¯5_5 2_4
+    (2, RandInt, Misc, "randint"),
     /// Generate a random number between 0 and 1 from a seed, as well as the next seed
     ///
     /// If you don't care about a seed, you can use [random].
@@ -1621,6 +1993,251 @@ primitive!(
     /// ex: parse "3.1415926535897932"
     /// ex! parse "dog"
     (1, Parse, Misc, "parse"),
+    /// Format numbers as strings
+    ///
+    /// Expects a format string and an array of numbers.
+    /// The format looks like `[align][+][0][width][.precision][e]`, and every part is optional.
+    /// The precision is the number of digits after the decimal point.
+    /// ex: fmtnum ".2" π
+    /// ex: fmtnum "e" 1234.5
+    /// ex: fmtnum ".3e" 0.000123
+    /// A `+` shows the sign of positive numbers.
+    /// ex: fmtnum "+" [1 ¯2 0]
+    /// An array of numbers is formatted into an array of strings of the same width, so they line up.
+    /// The width can be made larger. A `0` pads with zeros instead of spaces.
+    /// ex: fmtnum ".1" [1 22.5 ¯333]
+    /// ex: fmtnum "05" [7 42 ¯3]
+    /// `<` aligns to the left and `^` aligns to the center.
+    /// ex: fmtnum "<6.1" [1 22.5 ¯333]
+    /// ex: fmtnum "^7" [1 22 333]
+    (2, FmtNum, Misc, "fmtnum"),
+    /// Convert a string to uppercase
+    ///
+    /// This works for any language that has case, not just English.
+    /// ex: upper "Hello, World!"
+    /// ex: upper "straße"
+    /// The rows of a character array are converted separately, and arrays of [box]ed strings are converted in each [box].
+    /// ex: upper {"abc" "δεζ"}
+    (1, Upper, Misc, "upper"),
+    /// Convert a string to lowercase
+    ///
+    /// ex: lower "Hello, World!"
+    /// ex: lower "ΑΒΓ"
+    (1, Lower, Misc, "lower"),
+    /// Convert a string to titlecase
+    ///
+    /// The first letter of each word is made uppercase, and the rest are made lowercase.
+    /// ex: title "the quick BROWN fox"
+    (1, Title, Misc, "title"),
+    /// Normalize a string to Unicode Normalization Form C
+    ///
+    /// Characters and combining marks are composed into single characters where possible.
+    /// ex: ⧻ nfc "e\u0301"
+    (1, Nfc, Misc, "nfc"),
+    /// Normalize a string to Unicode Normalization Form D
+    ///
+    /// Characters are decomposed into base characters and combining marks.
+    /// ex: ⧻ nfd "é"
+    (1, Nfd, Misc, "nfd"),
+    /// Split a string into its grapheme clusters
+    ///
+    /// A grapheme cluster is what a reader would think of as a single character, even if it is made of multiple [Unicode](https://unicode.org) characters.
+    /// ex: graphemes "e\u0301a"
+    /// ex: ⧻ graphemes "🏳️‍🌈!"
+    (1, Graphemes, Misc, "graphemes"),
+    /// Get the digits of numbers in a base
+    ///
+    /// Digits are listed from least to most significant.
+    /// ex: base 10 123
+    /// ex: base 2 10
+    /// ex: base 16 [255 256 257]
+    /// Negative numbers have negative digits.
+    /// ex: base 10 ¯123
+    /// If the base is a list, each digit uses the corresponding base. The last digit is whatever is left over.
+    /// This is useful for things like converting seconds to minutes, hours, and days.
+    /// ex: base [60 60 24] 100000
+    /// [invert][base] turns digits back into numbers.
+    /// ex: ⍘(base 10) [3 2 1]
+    /// ex: ⍘(base [60 60 24]) [40 46 3 1]
+    /// [under][base] can be used to change the digits of a number.
+    /// ex: ⍜(base 10)⇌ 1234
+    (2, Base, Misc, "base"),
+    /// Pack an array of bits into bytes
+    ///
+    /// The first argument is the bit order. With `1`, the first bit of each group of 8 is the most significant bit of its byte. With `0`, it is the least significant.
+    /// ex: packbits 1 [1 0 0 0 0 0 1 1]
+    /// ex: packbits 0 [1 0 0 0 0 0 1 1]
+    /// Bits are packed along the last axis. If its length is not a multiple of 8, the last byte of each row is padded with `0`s.
+    /// ex: packbits 1 [1 1 1 1 1 1 1 1 1]
+    /// ex: packbits 1 [1_0_1 0_1_0]
+    /// [invert][packbits] unpacks bytes into bits. Each byte becomes 8 bits.
+    /// ex: ⍘(packbits 1) [131 255]
+    /// ex: ⍘(packbits 0) [131 255]
+    (2, PackBits, Misc, "packbits"),
+    /// Run the Fast Fourier Transform on a signal
+    ///
+    /// The result is a [complex] array of the signal's frequency components.
+    /// ex: fft [1 0 1 0]
+    /// ex: fft [1 2 3 4]
+    /// The transform is done along the last axis, so each row of a rank `2` array is transformed separately.
+    /// ex: fft [1_0_1_0 1_1_1_1]
+    /// [invert][fft] runs the inverse transform.
+    /// ex: ⍘fft [4 0 0 0]
+    /// ex: ⍘fft fft [1 2 3 4]
+    /// Use [absolute value] to get the strength of each frequency.
+    /// ex: ⌵fft [0 1 2 3 4 3 2 1]
+    (1, Fft, Misc, "fft"),
+    /// Convolve an array with a kernel
+    ///
+    /// Expects a kernel and an array of numbers.
+    /// ex: convolve [1 1] [1 2 3 4]
+    /// ex: convolve [1 2 1] [0 0 1 0 0]
+    /// Without a fill, the result only has values where the kernel fits entirely inside the array, so it is smaller than the array.
+    /// [fill] pads the array so that the result has the same shape as the array.
+    /// ex: ⬚0convolve [1 1 1] [1 2 3 4 5]
+    /// Multi-dimensional kernels are supported, which is useful for image processing.
+    /// ex: convolve [1_0 0_¯1] .↯3_4⇡12
+    /// ex: ⬚0convolve ↯3_3 1 .↯4_4⇡16
+    /// Axes past those of the kernel are convolved separately, so the color channels of an image are not mixed.
+    /// ex: △ ⬚0convolve ÷9↯3_3 1 ↯[10 10 3] 0
+    /// Cross-correlation is convolution with a reversed kernel. Reversing a [deshape]d kernel reverses all of its axes.
+    /// ex: convolve ⍜♭⇌ [1_2 3_4] [1_2 3_4]
+    (2, Convolve, Misc, "convolve"),
+    /// Multiply and sum arrays along axes given by an index specification
+    ///
+    /// The first argument is the specification. The second is a list of [box]ed arrays to contract.
+    /// The specification gives a letter for each axis of each array, separated by commas, followed by `->` and the letters of the result's axes.
+    /// Axes with the same letter are multiplied together. Letters that are not in the result are summed over.
+    /// Here is matrix multiplication.
+    /// ex: einsum "ij,jk->ik" {[1_2 3_4] [5_6 7_8]}
+    /// Here is a dot product and an outer product.
+    /// ex: einsum "i,i->" {[1 2 3] [4 5 6]}
+    /// ex: einsum "i,j->ij" {[1 2] [3 4 5]}
+    /// A single array does not need to be boxed. Repeating a letter in one array uses its diagonal.
+    /// ex: einsum "ii->" [1_2 3_4]
+    /// ex: einsum "ii->i" [1_2 3_4]
+    /// ex: einsum "ij->ji" [1_2_3 4_5_6]
+    /// Extra axes can be used for batches.
+    /// ex: einsum "bij,bjk->bik" {↯2_2_2⇡8 ↯2_2_1 1}
+    /// If there is no `->`, the result has the letters that appear only once, in alphabetical order.
+    /// ex: einsum "ij,jk" {[1_2 3_4] [5_6 7_8]}
+    /// The specification can also be a list of boxed lists of numbers, where the last list is for the result.
+    /// ex: einsum {[0 1] [1 2] [0 2]} {[1_2 3_4] [5_6 7_8]}
+    (2, Einsum, Misc, "einsum"),
+    /// Replace each occurence of one array in another with a third array
+    ///
+    /// The first array is the one to search for. The second is its replacement. The third is the array to search in.
+    /// ex: replace "cat" "dog" "cats and more cats"
+    /// The replacement can have a different length.
+    /// ex: replace "the" "a" "the cat in the hat"
+    /// ex: replace " " "" "no more spaces"
+    /// ex: replace 2 [0 0] [1 2 3 2 1]
+    /// Occurences are found from the start and do not overlap.
+    /// ex: replace "aa" "b" "aaaaa"
+    /// Multidimensional arrays are searched along their first axis.
+    /// ex: replace [1_2 3_4] [0_0] [1_2 3_4 5_6 1_2 3_4]
+    (3, Replace, Misc, "replace"),
+    /// Split an array on a delimiter or any of several delimiters
+    ///
+    /// The first argument is the delimiter, or a list of boxed delimiters. The second is the array to split.
+    /// The parts are returned as a list of boxes.
+    /// ex: split ", " "apples, bananas, cherries"
+    /// ex: split {"," ";" " "} "a,b;c d"
+    /// Unlike `partition``box``≠`, the delimiters may be longer than a single row, and empty parts are kept.
+    /// ex: split "," "a,,b,"
+    /// ex: ⊜□≠@, . "a,,b,"
+    /// Where delimiters overlap, the longest one that matches is used.
+    /// ex: split {"\n" "\r\n"} "one\r\ntwo\nthree"
+    /// Arrays of numbers or higher rank can be split along their first axis.
+    /// ex: split 0 [1 2 0 3 0 4 5]
+    /// ex: split [0_0] [1_2 0_0 3_4]
+    ///
+    /// To make at most some number of splits, use [splitn].
+    (2, Split, Misc, "split"),
+    /// Split an array on delimiters at most some number of times
+    ///
+    /// The first argument is the maximum number of splits. The others are the same as for [split].
+    /// ex: splitn 1 "=" "key=value=more"
+    /// ex: splitn 2 {"," ";"} "a,b;c,d"
+    (3, SplitN, Misc, "splitn"),
+    /// Get the prime factors of a number
+    ///
+    /// The factors are in ascending order, and each appears as many times as it divides the number.
+    /// ex: factor 360
+    /// ex: factor 97
+    /// ex: factor 1
+    /// [reduce][multiply] turns the factors back into the number.
+    /// ex: /×factor 360
+    /// Factoring an array gives an array of [box]ed lists.
+    /// ex: factor [12 13 14]
+    (1, Factor, Misc, "factor"),
+    /// Convert numbers to rationals
+    ///
+    /// Rationals are exact fractions. Arithmetic on them does not accumulate error like it does on normal numbers.
+    /// ex: rational 0.5
+    /// ex: ÷3 rational 1
+    /// ex: + ÷3 rational 1 ÷6 rational 1
+    /// Each number becomes the simplest fraction that is equal to it.
+    /// ex: rational 0.1
+    /// ex: + rational 0.1 rational 0.2
+    /// ex: + 0.1 0.2
+    /// Operations with irrational results, like [sqrt], give normal numbers.
+    /// ex: √ rational 2
+    /// [invert][rational] turns rationals back into normal numbers.
+    /// ex: ⍘rational ÷3 rational 1
+    (1, Rational, Misc, "rational"),
+    /// Create a hashed map from a list of keys and list values
+    ///
+    /// A map is a normal array whose rows are looked up by key rather than by index.
+    /// Finding a row from its key takes constant time, unlike [indexof].
+    /// ex: map 1_2_3 4_5_6
+    /// Maps can be used with [get], [has], [insert], and [remove].
+    /// ex: get 2 map 1_2_3 4_5_6
+    /// ex: get "b" map {"a" "b"} [1 2]
+    /// If a key appears more than once, the last value wins.
+    /// ex: map [1 2 1] [3 4 5]
+    /// Operations that do not change the order or number of rows keep the keys.
+    /// ex: ×10 map 1_2_3 4_5_6
+    /// [invert][map] gives the keys and values.
+    /// ex: ⍘map map 1_2_3 4_5_6
+    (2, Map, Misc, "map"),
+    /// Insert a key-value pair into a map
+    ///
+    /// If the key is already in the map, its value is replaced.
+    /// ex: insert 3 10 map 1_2 4_5
+    /// ex: insert 1 10 map 1_2 4_5
+    /// An empty list is an empty map.
+    /// ex: insert 1 2 insert 3 4 []
+    /// Keys and values that do not fit with the existing ones are [box]ed.
+    /// ex: insert "cat" 3 map {"a" "b"} [1 2]
+    (3, Insert, Misc, "insert"),
+    /// Check if a map has a key
+    ///
+    /// ex: has 2 map 1_2_3 4_5_6
+    /// ex: has 5 map 1_2_3 4_5_6
+    (2, Has, Misc, "has"),
+    /// Get the value for a key in a map
+    ///
+    /// ex: get 2 map 1_2_3 4_5_6
+    /// It is an error if the key is not in the map.
+    /// ex! get 5 map 1_2_3 4_5_6
+    /// A [fill] value is used as the default for missing keys.
+    /// ex: ⬚0get 5 map 1_2_3 4_5_6
+    (2, Get, Misc, "get"),
+    /// Remove a key from a map
+    ///
+    /// ex: remove 2 map 1_2_3 4_5_6
+    /// Removing a key that is not in the map does nothing.
+    /// ex: remove 5 map 1_2_3 4_5_6
+    (2, Remove, Misc, "remove"),
+    /// Make a list of evenly spaced numbers
+    ///
+    /// Expects a pair of a start and a step, and a count.
+    /// ex: steps 0_0.1 6
+    /// ex: steps 10_¯2 5
+    /// The numbers do not accumulate floating point error like [multiply]ing a [range] can.
+    /// ex: ×0.1 ⇡6
+    (2, Steps, Misc, "steps"),
     /// Match a regex pattern
     ///
     /// Returns an list of [box]ed strings, with one string per matching group
@@ -1634,6 +2251,35 @@ primitive!(
     ///
     /// Uiua uses the [Rust regex crate](https://docs.rs/regex/latest/regex/) internally.
     (2, Regex, Misc, "regex"),
+    /// Check if strings match a glob pattern
+    ///
+    /// `?` matches any one character, and `*` matches any number of characters. Neither matches `/`.
+    /// `**` matches any number of characters, including `/`.
+    /// `[...]` matches one of the characters or ranges inside it. `[!...]` matches any other character.
+    /// A backslash makes the next character literal.
+    /// ex: glob "*.ua" "main.ua"
+    /// ex: glob "h?llo" "hello"
+    /// ex: glob "[a-c]*" "dog"
+    /// The target can be a list of [box]ed strings, such as paths, or a character array with a string in each row.
+    /// ex: glob "*.txt" {"notes.txt" "image.png" "todo.txt"}
+    /// Use [keep] to filter a list.
+    /// ex: ▽glob "*.txt" . {"notes.txt" "image.png" "todo.txt"}
+    /// ex: glob "src/*" {"src/main.rs" "src/algorithm/glob.rs"}
+    /// ex: glob "src/**" {"src/main.rs" "src/algorithm/glob.rs"}
+    /// ex: glob "[!abc]?" ["ab" "cd" "ef"]
+    (2, Glob, Misc, "glob"),
+    /// Run a string of Uiua code
+    ///
+    /// The code runs in its own scope with an empty stack. The values it leaves on the stack are returned as a list of [box]es, in the same order as if the code were put in `{}`.
+    /// ex: eval "+ 1 2"
+    /// ex: eval "⇡5 \"hi\""
+    /// Bindings made in the code are not visible outside of it.
+    /// ex: eval "X ← 5\n×2 X"
+    /// Errors in the code can be caught with [try].
+    /// ex: ⍣eval⋅(⊔get "message") "+1"
+    /// ex: ⍣eval⋅(⊔get "message") "[1 2"
+    /// The code has the same permissions as the rest of the program, so it can only use system functions that the program can.
+    (1, Eval, Misc, "eval"),
     /// Convert a string to UTF-8 bytes
     ///
     /// ex: utf "hello!"
@@ -1646,6 +2292,31 @@ primitive!(
     /// ex: -@\0 "👩🏽‍👩🏻‍👦🏻‍👧🏽"
     /// ex: utf "👩🏽‍👩🏻‍👦🏻‍👧🏽"
     (1, Utf, Misc, "utf"),
+    /// Convert UTF-8 bytes to a string, replacing invalid sequences
+    ///
+    /// This is like [invert][utf], but it does not fail on bytes that are not valid UTF-8.
+    /// Invalid sequences are replaced with `@\uFFFD`.
+    /// ex: utflossy [104 105 33]
+    /// ex: utflossy [104 255 105]
+    /// ex! ⍘utf [104 255 105]
+    (1, UtfLossy, Misc, "utflossy"),
+    /// Convert a string to UTF-16 code units
+    ///
+    /// ex: utfsixteen "hello!"
+    /// Characters outside the Basic Multilingual Plane take two code units.
+    /// ex: utfsixteen "😀"
+    /// You can use [invert] to convert UTF-16 code units back to a string.
+    /// ex: ⍘utfsixteen [55357 56832 33]
+    (1, Utf16, Misc, "utfsixteen"),
+    /// Convert a string to Latin-1 bytes
+    ///
+    /// Each character becomes a single byte, so only characters up to `@\xff` can be encoded.
+    /// ex: latin "café"
+    /// ex: utf "café"
+    /// ex! latin "😀"
+    /// You can use [invert] to convert Latin-1 bytes back to a string. Every byte is a valid Latin-1 character.
+    /// ex: ⍘latin [99 97 102 233]
+    (1, Latin1, Misc, "latin"),
     /// Generate a unique tag
     ///
     /// Tags are just numbers and are unique across multiple threads, but not across multiple runs.
@@ -1669,6 +2340,22 @@ primitive!(
     /// [under][now] can be used to time a function.
     /// ex: ⍜now(5&sl1)
     (0, Now, Misc, "now"),
+    /// Get the date and time information from a time
+    ///
+    /// Times are in seconds since the Unix epoch, like the ones from [now], and are in UTC.
+    /// The result is a list of the year, month, day, hour, minute, and second.
+    /// ex: datetime 0
+    /// ex: datetime 1700000000.5
+    /// Each time in an array gets its own row.
+    /// ex: datetime [0 1e9 2e9]
+    /// [invert][datetime] turns the components back into a time.
+    /// ex: ⍘datetime [2024 2 29 12 30 0]
+    /// Trailing components may be left out.
+    /// ex: ⍘datetime [2024 2 29]
+    /// Components that are out of range carry over into the larger ones, so you can do calendar math.
+    /// ex: datetime ⍘datetime +[0 1 0 0 0 0] [2023 12 31 0 0 0]
+    /// ex: datetime ⍘datetime +[0 0 0 36 0 0] datetime now
+    (1, DateTime, Misc, "datetime"),
     /// The number of radians in a quarter circle
     ///
     /// Equivalent to `divide``2``pi` or `divide``4``tau`
@@ -1776,15 +2463,26 @@ impl_primitive!(
     (1, InvWhere),
     (1(2), InvCouple),
     (1, InvUtf),
+    (1, InvUtf16),
+    (1, InvLatin1),
+    (1, InvDateTime),
+    (2, InvBase),
+    (2, InvPackBits),
+    (1, InvFft),
     (1, InvTrace),
     (1(2), InvAtan),
     (1(2), InvComplex),
+    (1, InvRational),
+    (1(2), InvMap),
     // Unders
     (3, Unselect),
     (3, Unpick),
     (3, Untake),
     (3, Undrop),
     (3, Unkeep),
+    (3, UnfillRotate),
+    (3, Unmasked),
+    (2, Unwhere),
     (3[1], Unpartition),
     (3[1], Ungroup),
     // Optimizations
@@ -15,6 +15,7 @@ use std::{
         INFINITY,
     },
     fmt::{self},
+    mem::size_of,
     sync::{
         atomic::{self, AtomicUsize},
         OnceLock,
@@ -28,8 +29,9 @@ use regex::Regex;
 
 use crate::{
     algorithm::{fork, loops, reduce, table, zip},
-    array::Array,
+    array::{Array, FormatShape, Shape},
     boxed::Boxed,
+    cowslice::CowSlice,
     lex::AsciiToken,
     sys::*,
     value::*,
@@ -137,10 +139,21 @@ impl fmt::Display for ImplPrimitive {
             InvCouple => write!(f, "⍘{Couple}"),
             InvAtan => write!(f, "⍘{Atan}"),
             InvComplex => write!(f, "⍘{Complex}"),
+            InvRational => write!(f, "⍘{Rational}"),
+            InvMap => write!(f, "⍘{Map}"),
+            InvDateTime => write!(f, "⍘{DateTime}"),
+            InvUtf16 => write!(f, "⍘{Utf16}"),
+            InvLatin1 => write!(f, "⍘{Latin1}"),
+            InvBase => write!(f, "⍘{Base}"),
+            InvPackBits => write!(f, "⍘{PackBits}"),
+            InvFft => write!(f, "⍘{Fft}"),
             Untake => write!(f, "⍘{Take}"),
+            UnfillRotate => write!(f, "⍘{Fill}{Rotate}"),
+            Unwhere => write!(f, "⍘{Where}"),
             Undrop => write!(f, "⍘{Drop}"),
             Unselect => write!(f, "⍘{Select}"),
             Unpick => write!(f, "⍘{Pick}"),
+            Unmasked => write!(f, "⍘{Masked}"),
             Unpartition => write!(f, "⍘{Partition}"),
             Cos => write!(f, "{Sin}{Add}{Eta}"),
             Asin => write!(f, "{Invert}{Sin}"),
@@ -238,6 +251,29 @@ impl Primitive {
             _ => None,
         }
     }
+    /// Check if this primitive's outputs depend only on its arguments and it has no side effects
+    pub(crate) fn is_pure(&self) -> bool {
+        use Primitive::*;
+        !matches!(
+            self,
+            Sys(_)
+                | Rand
+                | Uniform
+                | Normal
+                | RandInt
+                | Now
+                | Tag
+                | Trace
+                | Dump
+                | Spawn
+                | Wait
+                | Send
+                | Recv
+                | TryRecv
+                | Break
+                | Eval
+        )
+    }
     pub(crate) fn deprecation_suggestion(&self) -> Option<String> {
         match self {
             Primitive::Break => Some(format!(
@@ -364,8 +400,13 @@ impl Primitive {
             Primitive::Floor => env.monadic_env(Value::floor)?,
             Primitive::Ceil => env.monadic_env(Value::ceil)?,
             Primitive::Round => env.monadic_env(Value::round)?,
-            Primitive::Eq => env.dyadic_oo_env(Value::is_eq)?,
-            Primitive::Ne => env.dyadic_oo_env(Value::is_ne)?,
+            Primitive::Prime => env.monadic_env(Value::prime)?,
+            Primitive::Real => env.monadic_env(Value::complex_re)?,
+            Primitive::Imaginary => env.monadic_env(Value::complex_im)?,
+            Primitive::Argument => env.monadic_env(Value::complex_arg)?,
+            Primitive::Conjugate => env.monadic_env(Value::complex_conj)?,
+            Primitive::Eq => env.dyadic_oo_env(Value::is_eq_tolerant)?,
+            Primitive::Ne => env.dyadic_oo_env(Value::is_ne_tolerant)?,
             Primitive::Lt => env.dyadic_oo_env(Value::is_lt)?,
             Primitive::Le => env.dyadic_oo_env(Value::is_le)?,
             Primitive::Gt => env.dyadic_oo_env(Value::is_gt)?,
@@ -381,25 +422,67 @@ impl Primitive {
             Primitive::Max => env.dyadic_oo_env(Value::max)?,
             Primitive::Atan => env.dyadic_oo_env(Value::atan2)?,
             Primitive::Complex => env.dyadic_oo_env(Value::complex)?,
-            Primitive::Match => env.dyadic_rr(|a, b| a == b)?,
+            Primitive::Gcd => env.dyadic_oo_env(Value::gcd)?,
+            Primitive::Lcm => env.dyadic_oo_env(Value::lcm)?,
+            Primitive::RoundTo => env.dyadic_oo_env(Value::round_to)?,
+            Primitive::Clamp => {
+                let low = env.pop(1)?;
+                let high = env.pop(2)?;
+                let x = env.pop(3)?;
+                env.push(high.min(low.max(x, env)?, env)?);
+            }
+            Primitive::Match => env.dyadic_rr_env(|a, b, env| Ok(a.matches(b, env)))?,
             Primitive::Join => env.dyadic_oo_env(Value::join)?,
             Primitive::Transpose => env.monadic_mut(Value::transpose)?,
             Primitive::Keep => env.dyadic_ro_env(Value::keep)?,
             Primitive::Take => env.dyadic_oo_env(Value::take)?,
             Primitive::Drop => env.dyadic_oo_env(Value::drop)?,
             Primitive::Rotate => env.dyadic_ro_env(Value::rotate)?,
+            Primitive::Shift => env.dyadic_ro_env(Value::shift)?,
             Primitive::Couple => env.dyadic_oo_env(Value::couple)?,
             Primitive::Rise => env.monadic_ref_env(|v, env| v.rise(env).map(Array::from))?,
             Primitive::Fall => env.monadic_ref_env(|v, env| v.fall(env).map(Array::from))?,
             Primitive::Pick => env.dyadic_oo_env(Value::pick)?,
+            Primitive::Split => {
+                let delims = env.pop(1)?;
+                let searched = env.pop(2)?;
+                env.push(delims.split(searched, None, env)?);
+            }
+            Primitive::SplitN => {
+                let limit = env
+                    .pop(1)?
+                    .as_nat(env, "Split limit must be a natural number")?;
+                let delims = env.pop(2)?;
+                let searched = env.pop(3)?;
+                env.push(delims.split(searched, Some(limit), env)?);
+            }
+            Primitive::Glob => env.dyadic_rr_env(Value::glob)?,
+            Primitive::Eval => {
+                let code = env.pop(1)?.as_string(env, "Code must be a string")?;
+                let values = env.eval(&code)?;
+                env.push(Array::from_iter(values.into_iter().map(Boxed)));
+            }
+            Primitive::PackBits => env.dyadic_rr_env(Value::pack_bits)?,
+            Primitive::Mean => env.monadic_ref_env(Value::mean)?,
+            Primitive::Variance => env.monadic_ref_env(Value::variance)?,
+            Primitive::Median => env.monadic_ref_env(Value::median)?,
+            Primitive::Quantile => env.dyadic_rr_env(Value::quantile)?,
+            Primitive::Masked => env.dyadic_ro_env(Value::masked)?,
+            Primitive::Scatter => {
+                let indices = env.pop(1)?;
+                let values = env.pop(2)?;
+                let into = env.pop(3)?;
+                env.push(indices.scatter(values, into, env)?);
+            }
             Primitive::Select => env.dyadic_rr_env(Value::select)?,
             Primitive::Windows => env.dyadic_rr_env(Value::windows)?,
             Primitive::Where => env.monadic_ref_env(Value::wher)?,
             Primitive::Classify => env.monadic_ref_env(Value::classify)?,
-            Primitive::Deduplicate => env.monadic_mut(Value::deduplicate)?,
+            Primitive::Deduplicate => env.monadic_mut_env(Value::deduplicate)?,
             Primitive::Member => env.dyadic_rr_env(Value::member)?,
             Primitive::Find => env.dyadic_rr_env(Value::find)?,
             Primitive::IndexOf => env.dyadic_rr_env(Value::index_of)?,
+            Primitive::Bins => env.dyadic_rr_env(Value::bins)?,
             Primitive::Box => {
                 let val = env.pop(1)?;
                 env.push(Boxed(val));
@@ -415,7 +498,46 @@ impl Primitive {
                 env.push(val);
             }
             Primitive::Parse => env.monadic_ref_env(Value::parse_num)?,
+            Primitive::FmtNum => env.dyadic_rr_env(Value::fmtnum)?,
+            Primitive::Upper => env.monadic_env(Value::uppercase)?,
+            Primitive::Lower => env.monadic_env(Value::lowercase)?,
+            Primitive::Title => env.monadic_env(Value::titlecase)?,
+            Primitive::Nfc => env.monadic_env(Value::nfc)?,
+            Primitive::Nfd => env.monadic_env(Value::nfd)?,
+            Primitive::Graphemes => env.monadic_ref_env(Value::graphemes)?,
             Primitive::Utf => env.monadic_ref_env(Value::utf8)?,
+            Primitive::UtfLossy => env.monadic_ref_env(Value::utf8_lossy)?,
+            Primitive::Utf16 => env.monadic_ref_env(Value::utf16)?,
+            Primitive::Latin1 => env.monadic_ref_env(Value::latin1)?,
+            Primitive::Base => env.dyadic_rr_env(Value::base)?,
+            Primitive::Fft => env.monadic_env(Value::fft)?,
+            Primitive::Convolve => env.dyadic_rr_env(Value::convolve)?,
+            Primitive::Einsum => env.dyadic_rr_env(Value::einsum)?,
+            Primitive::Factor => env.monadic_ref_env(Value::factor)?,
+            Primitive::Rational => env.monadic_env(Value::rational)?,
+            Primitive::Map => env.dyadic_oo_env(Value::map)?,
+            Primitive::Replace => {
+                let pattern = env.pop(1)?;
+                let replacement = env.pop(2)?;
+                let searched = env.pop(3)?;
+                env.push(pattern.replace(replacement, searched, env)?);
+            }
+            Primitive::Insert => {
+                let key = env.pop(1)?;
+                let value = env.pop(2)?;
+                let mut map = env.pop(3)?;
+                map.insert(key, value, env)?;
+                env.push(map);
+            }
+            Primitive::Has => env.dyadic_rr_env(Value::has)?,
+            Primitive::Get => env.dyadic_rr_env(Value::get)?,
+            Primitive::Remove => {
+                let key = env.pop(1)?;
+                let mut map = env.pop(2)?;
+                map.remove(&key, env)?;
+                env.push(map);
+            }
+            Primitive::Steps => env.dyadic_rr_env(Value::steps)?,
             Primitive::Range => env.monadic_ref_env(Value::range)?,
             Primitive::Reverse => env.monadic_mut(Value::reverse)?,
             Primitive::Deshape => env.monadic_mut(Value::deshape)?,
@@ -427,6 +549,7 @@ impl Primitive {
                     Array::row_count,
                     Array::row_count,
                     Array::row_count,
+                    Array::row_count,
                 )
             })?,
             Primitive::Shape => env.monadic_ref(|v| {
@@ -436,6 +559,7 @@ impl Primitive {
                     Array::shape,
                     Array::shape,
                     Array::shape,
+                    Array::shape,
                 )
                 .iter()
                 .copied()
@@ -454,9 +578,17 @@ impl Primitive {
             Primitive::Cross => table::cross(env)?,
             Primitive::Combinate => table::combinate(env)?,
             Primitive::Repeat => loops::repeat(env)?,
+            Primitive::Stencil => loops::stencil(env)?,
+            Primitive::SortBy => loops::sort_by(false, env)?,
+            Primitive::SortByDown => loops::sort_by(true, env)?,
+            Primitive::DeduplicateBy => loops::deduplicate_by(env)?,
+            Primitive::ClassifyBy => loops::classify_by(env)?,
+            Primitive::OccurrencesBy => loops::occurrences_by(env)?,
+            Primitive::SortWith => loops::sort_with(env)?,
             Primitive::Do => loops::do_(env)?,
             Primitive::Group => loops::group(env)?,
             Primitive::Partition => loops::partition(env)?,
+            Primitive::PartIndex => loops::partition_index(env)?,
             Primitive::Reshape => {
                 let shape = env.pop(1)?;
                 let mut array = env.pop(2)?;
@@ -511,6 +643,26 @@ impl Primitive {
                 env.push(x);
                 env.call(f)?;
             }
+            Primitive::Beneath => {
+                let f = env.pop_function()?;
+                let n = (env.pop(1)?).as_nat(env, "Beneath's count must be a natural number")?;
+                let mut kept = Vec::with_capacity(n);
+                for i in 0..n {
+                    kept.push(env.pop(i + 2)?);
+                }
+                env.call(f)?;
+                for x in kept.into_iter().rev() {
+                    env.push(x);
+                }
+            }
+            Primitive::Skip => {
+                let f = env.pop_function()?;
+                let n = (env.pop(1)?).as_nat(env, "Skip's count must be a natural number")?;
+                for i in 0..n {
+                    env.pop(i + 2)?;
+                }
+                env.call(f)?;
+            }
             Primitive::Rock => {
                 let x = env.pop(1)?;
                 env.push(x.ocean(INFINITY, env)?);
@@ -531,11 +683,33 @@ impl Primitive {
                 let x = env.pop(1)?;
                 env.push(x.ocean(0.0, env)?);
             }
+            Primitive::Comptime => {
+                let f = env.pop_function()?;
+                env.call(f)?;
+            }
             Primitive::Invert => {
                 let f = env.pop_function()?;
                 let inv_f = f.invert("", env)?;
                 env.call(inv_f)?;
             }
+            Primitive::SetInverse => {
+                let f = env.pop_function()?;
+                let inv = env.pop_function()?;
+                let (sig, inv_sig) = (f.signature(), inv.signature());
+                if inv_sig.args != sig.outputs || inv_sig.outputs != sig.args {
+                    return Err(env.error(format!(
+                        "setinv's inverse's signature {inv_sig} \
+                        is not the opposite of its function's {sig}"
+                    )));
+                }
+                env.call(f)?;
+            }
+            Primitive::SetUnder => {
+                let f = env.pop_function()?;
+                let _before = env.pop_function()?;
+                let _after = env.pop_function()?;
+                env.call(f)?;
+            }
             Primitive::Under => {
                 let f = env.pop_function()?;
                 let g = env.pop_function()?;
@@ -583,10 +757,47 @@ impl Primitive {
                 }
             }
             Primitive::Rand => {
-                thread_local! {
-                    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::seed_from_u64(instant::now().to_bits()));
+                let val: f64 = env.rng.lock().gen();
+                env.push(val);
+            }
+            Primitive::Uniform => {
+                let shape = random_shape(env, 1)?;
+                let arr = random_array(env, shape, |rng| rng.gen::<f64>());
+                env.push(arr);
+            }
+            Primitive::Normal => {
+                let shape = random_shape(env, 1)?;
+                // The Box-Muller transform
+                let arr = random_array(env, shape, |rng| {
+                    let u = 1.0 - rng.gen::<f64>();
+                    (-2.0 * u.ln()).sqrt() * (TAU * rng.gen::<f64>()).cos()
+                });
+                env.push(arr);
+            }
+            Primitive::RandInt => {
+                let range = env.pop(1)?;
+                let range = match range.as_nums(env, "Range must be a number or pair of numbers")? {
+                    nums if range.rank() == 0 => [0.0, nums[0]],
+                    nums if nums.len() == 2 => [nums[0], nums[1]],
+                    nums => {
+                        return Err(env.error(format!(
+                            "Range must be a number or pair of numbers, but it has {} numbers",
+                            nums.len()
+                        )))
+                    }
+                };
+                let [low, high] = range.map(f64::ceil);
+                if !(low < high && low.is_finite() && high.is_finite()) {
+                    return Err(env.error(format!(
+                        "Range must contain at least one integer, but it is {}",
+                        Value::from_iter(range).show()
+                    )));
                 }
-                env.push(RNG.with(|rng| rng.borrow_mut().gen::<f64>()));
+                let shape = random_shape(env, 2)?;
+                let arr = random_array(env, shape, |rng| {
+                    (low + (rng.gen::<f64>() * (high - low)).floor()).min(high - 1.0)
+                });
+                env.push(arr);
             }
             Primitive::Gen => {
                 let seed = env.pop(1)?;
@@ -635,45 +846,33 @@ impl Primitive {
                 env.try_recv(id)?;
             }
             Primitive::Now => env.push(instant::now() / 1000.0),
+            Primitive::DateTime => env.monadic_ref_env(Value::datetime)?,
             Primitive::Trace => trace(env, false)?,
             Primitive::Dump => dump(env)?,
             Primitive::Sys(io) => io.run(env)?,
             Primitive::Regex => {
-                thread_local! {
-                    pub static REGEX_CACHE: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
-                }
                 let pattern = env.pop(1)?.as_string(env, "Pattern must be a string")?;
                 let target = env
                     .pop(1)?
                     .as_string(env, "Matching target must be a string")?;
-                REGEX_CACHE.with(|cache| -> UiuaResult {
-                    let mut cache = cache.borrow_mut();
-                    let regex = if let Some(regex) = cache.get(&pattern) {
-                        regex
-                    } else {
-                        let regex = Regex::new(&pattern)
-                            .map_err(|e| env.error(format!("Invalid pattern: {}", e)))?;
-                        cache.entry(pattern.clone()).or_insert(regex.clone())
-                    };
-                    let matches: EcoVec<Boxed> = if regex.captures_len() == 1 {
-                        regex
-                            .find_iter(&target)
-                            .map(|m| Boxed(Value::from(m.as_str())))
-                            .collect()
-                    } else {
-                        regex
-                            .captures(&target)
-                            .map(|caps| {
-                                caps.iter()
-                                    .flatten()
-                                    .map(|m| Boxed(Value::from(m.as_str())))
-                                    .collect()
-                            })
-                            .unwrap_or_default()
-                    };
-                    env.push(matches);
-                    Ok(())
-                })?
+                let regex = cached_regex(&pattern, env)?;
+                let matches: EcoVec<Boxed> = if regex.captures_len() == 1 {
+                    regex
+                        .find_iter(&target)
+                        .map(|m| Boxed(Value::from(m.as_str())))
+                        .collect()
+                } else {
+                    regex
+                        .captures(&target)
+                        .map(|caps| {
+                            caps.iter()
+                                .flatten()
+                                .map(|m| Boxed(Value::from(m.as_str())))
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                };
+                env.push(matches);
             }
         }
         Ok(())
@@ -698,6 +897,12 @@ impl ImplPrimitive {
                 let from = env.pop(3)?;
                 env.push(from.untake(index, into, env)?);
             }
+            ImplPrimitive::UnfillRotate => {
+                let by = env.pop(1)?;
+                let into = env.pop(2)?;
+                let from = env.pop(3)?;
+                env.push(from.unfill_rotate(by, into, env)?);
+            }
             ImplPrimitive::Undrop => {
                 let index = env.pop(1)?;
                 let into = env.pop(2)?;
@@ -716,6 +921,12 @@ impl ImplPrimitive {
                 let from = env.pop(3)?;
                 env.push(from.unpick(index, into, env)?);
             }
+            ImplPrimitive::Unmasked => {
+                let mask = env.pop(1)?;
+                let into = env.pop(2)?;
+                let values = env.pop(3)?;
+                env.push(mask.unmasked(values, into, env)?);
+            }
             ImplPrimitive::Unselect => {
                 let index = env.pop(1)?;
                 let into = env.pop(2)?;
@@ -723,7 +934,18 @@ impl ImplPrimitive {
                 env.push(from.unselect(index, into, env)?);
             }
             ImplPrimitive::InvWhere => env.monadic_ref_env(Value::inverse_where)?,
+            ImplPrimitive::Unwhere => {
+                let shape = env.pop(1)?;
+                let indices = env.pop(2)?;
+                env.push(indices.unwhere(&shape, env)?);
+            }
             ImplPrimitive::InvUtf => env.monadic_ref_env(Value::inv_utf8)?,
+            ImplPrimitive::InvUtf16 => env.monadic_ref_env(Value::inv_utf16)?,
+            ImplPrimitive::InvLatin1 => env.monadic_ref_env(Value::inv_latin1)?,
+            ImplPrimitive::InvDateTime => env.monadic_ref_env(Value::inv_datetime)?,
+            ImplPrimitive::InvBase => env.dyadic_rr_env(Value::inv_base)?,
+            ImplPrimitive::InvPackBits => env.dyadic_rr_env(Value::inv_pack_bits)?,
+            ImplPrimitive::InvFft => env.monadic_env(Value::inv_fft)?,
             ImplPrimitive::InverseBits => env.monadic_ref_env(Value::inv_bits)?,
             ImplPrimitive::Unpartition => loops::unpartition(env)?,
             ImplPrimitive::Ungroup => loops::ungroup(env)?,
@@ -742,6 +964,12 @@ impl ImplPrimitive {
                 env.push(re);
                 env.push(im);
             }
+            ImplPrimitive::InvRational => env.monadic_env(Value::inv_rational)?,
+            ImplPrimitive::InvMap => {
+                let (keys, values) = env.pop(1)?.unmap(env)?;
+                env.push(values);
+                env.push(keys);
+            }
             // Optimizations
             ImplPrimitive::Cos => env.monadic_env(Value::cos)?,
             ImplPrimitive::Last => env.monadic_env(Value::last)?,
@@ -755,6 +983,51 @@ impl ImplPrimitive {
     }
 }
 
+/// Pop the shape of a random array and check that it can be created
+fn random_shape(env: &mut Uiua, arg: usize) -> UiuaResult<Shape> {
+    let shape = env.pop(arg)?;
+    let requirement = "Shape must be a natural number or list of natural numbers";
+    let shape: Shape = if shape.rank() == 0 {
+        Some(shape.as_nat(env, requirement)?).into_iter().collect()
+    } else {
+        shape.as_nats(env, requirement)?.into_iter().collect()
+    };
+    let elements = (shape.iter().try_fold(1usize, |acc, &n| acc.checked_mul(n)))
+        .filter(|&elements| elements <= isize::MAX as usize / size_of::<f64>())
+        .ok_or_else(|| {
+            env.error(format!(
+                "Creating a random array of shape {} would be too large",
+                FormatShape(&shape)
+            ))
+        })?;
+    env.validate_alloc(elements, size_of::<f64>())?;
+    Ok(shape)
+}
+
+fn random_array(env: &Uiua, shape: Shape, mut gen: impl FnMut(&mut SmallRng) -> f64) -> Array<f64> {
+    let len = shape.iter().product();
+    let mut rng = env.rng.lock();
+    let data: CowSlice<f64> = (0..len).map(|_| gen(&mut rng)).collect();
+    Array::new(shape, data)
+}
+
+/// Compile a regex pattern, reusing it if it has been compiled before
+pub(crate) fn cached_regex(pattern: &str, env: &Uiua) -> UiuaResult<Regex> {
+    thread_local! {
+        pub static REGEX_CACHE: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
+    }
+    REGEX_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(regex) = cache.get(pattern) {
+            return Ok(regex.clone());
+        }
+        let regex =
+            Regex::new(pattern).map_err(|e| env.error(format!("Invalid pattern: {}", e)))?;
+        cache.insert(pattern.into(), regex.clone());
+        Ok(regex)
+    })
+}
+
 fn trace(env: &mut Uiua, inverse: bool) -> UiuaResult {
     let val = env.pop(1)?;
     let span: String = if inverse {
@@ -957,14 +1230,14 @@ impl PrimExample {
     }
     /// Check whether the example should run automatically in certain contexts
     pub fn should_run(&self) -> bool {
-        !["&sl", "&tcpc", "&ast"]
+        !["&sl", "&tcpc", "&tcpl", "&udp", "&ast", "&dl"]
             .iter()
             .any(|prim| self.input.contains(prim))
     }
     /// Get the example's output
     pub fn output(&self) -> &Result<Vec<String>, String> {
         self.output.get_or_init(|| {
-            let env = &mut Uiua::with_native_sys();
+            let env = &mut Uiua::default();
             match env.load_str(&self.input) {
                 Ok(()) => Ok(env.take_stack().into_iter().map(|val| val.show()).collect()),
                 Err(e) => Err(e
@@ -1153,6 +1426,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn class_arity() {
+        for prim in Primitive::all() {
+            let args = match prim.class() {
+                PrimClass::MonadicPervasive | PrimClass::MonadicArray => 1,
+                PrimClass::DyadicPervasive | PrimClass::DyadicArray => 2,
+                _ => continue,
+            };
+            assert_eq!(prim.args(), Some(args), "{prim:?} has the wrong class");
+        }
+    }
+
     #[test]
     fn primitive_from_name() {
         for prim in Primitive::all() {
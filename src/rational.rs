@@ -0,0 +1,336 @@
+use std::{cmp::Ordering, fmt, ops::*};
+
+/// Uiua's rational number type
+///
+/// Rationals are always in lowest terms with a non-negative denominator.
+/// A denominator of 0 is used for infinities, and `0/0` is NaN.
+///
+/// A number that does not fit is approximated with a denominator of -1 and
+/// the bits of an `f64` as the numerator. Arrays with approximations become
+/// normal number arrays when they are turned into values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rational {
+    /// The numerator
+    pub num: i64,
+    /// The denominator
+    pub den: i64,
+}
+
+impl Rational {
+    /// The rational number 0
+    pub const ZERO: Self = Self { num: 0, den: 1 };
+    /// The rational number 1
+    pub const ONE: Self = Self { num: 1, den: 1 };
+    /// The rational NaN
+    pub const NAN: Self = Self { num: 0, den: 0 };
+    /// Create a new rational number, reducing it to lowest terms
+    pub fn new(num: i64, den: i64) -> Self {
+        Self::from_i128(num as i128, den as i128)
+    }
+    /// Create an approximation of a number that does not fit in a rational
+    fn approx(n: f64) -> Self {
+        Self {
+            num: n.to_bits() as i64,
+            den: -1,
+        }
+    }
+    /// Check if the number is an approximation of one that does not fit in a rational
+    pub fn is_approx(self) -> bool {
+        self.den < 0
+    }
+    /// Create a rational number from wide parts, approximating it if it does not fit
+    fn from_i128(mut num: i128, mut den: i128) -> Self {
+        if den < 0 {
+            num = -num;
+            den = -den;
+        }
+        let gcd = gcd(num.unsigned_abs(), den.unsigned_abs()) as i128;
+        if gcd > 1 {
+            num /= gcd;
+            den /= gcd;
+        }
+        if den == 0 {
+            return Self {
+                num: num.signum() as i64,
+                den: 0,
+            };
+        }
+        match (i64::try_from(num), i64::try_from(den)) {
+            (Ok(num), Ok(den)) => Self { num, den },
+            _ => Self::approx(num as f64 / den as f64),
+        }
+    }
+    /// Check if the number is NaN
+    pub fn is_nan(self) -> bool {
+        self.num == 0 && self.den == 0
+    }
+    /// Check if the number is an integer
+    pub fn is_integer(self) -> bool {
+        self.den == 1
+    }
+    /// Get the absolute value of a rational number
+    pub fn abs(self) -> Self {
+        if self.is_approx() {
+            return Self::approx(f64::from(self).abs());
+        }
+        Self::from_i128((self.num as i128).abs(), self.den as i128)
+    }
+    /// Get the sign of a rational number
+    pub fn signum(self) -> Self {
+        if self.is_nan() {
+            self
+        } else if self.is_approx() {
+            Self::new(f64::from(self).signum() as i64, 1)
+        } else {
+            Self::new(self.num.signum(), 1)
+        }
+    }
+    /// Get the floor of a rational number
+    pub fn floor(self) -> Self {
+        if self.is_approx() {
+            f64::from(self).floor().into()
+        } else if self.den <= 1 {
+            self
+        } else {
+            Self::new(self.num.div_euclid(self.den), 1)
+        }
+    }
+    /// Get the ceiling of a rational number
+    pub fn ceil(self) -> Self {
+        -(-self).floor()
+    }
+    /// Round a rational number, rounding halves away from zero
+    pub fn round(self) -> Self {
+        if self.is_approx() {
+            f64::from(self).round().into()
+        } else if self.den <= 1 {
+            self
+        } else {
+            let half = Self::new(self.num.signum(), 2);
+            (self + half).abs().floor() * self.signum()
+        }
+    }
+    /// Get the minimum of two rational numbers
+    pub fn min(self, other: Self) -> Self {
+        if self.total_cmp(&other) == Ordering::Greater {
+            other
+        } else {
+            self
+        }
+    }
+    /// Get the maximum of two rational numbers
+    pub fn max(self, other: Self) -> Self {
+        if self.total_cmp(&other) == Ordering::Less {
+            other
+        } else {
+            self
+        }
+    }
+    /// Raise a rational number to a power
+    ///
+    /// Integer powers are exact. Other powers are approximated.
+    pub fn pow(self, power: Self) -> Self {
+        if !power.is_integer() || power.num.unsigned_abs() > 64 {
+            return f64::from(self).powf(power.into()).into();
+        }
+        let mut base = self;
+        let mut exp = power.num.unsigned_abs();
+        let mut result = Self::ONE;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            exp >>= 1;
+        }
+        if power.num < 0 {
+            Self::ONE / result
+        } else {
+            result
+        }
+    }
+    /// Compare two rational numbers, with NaN greater than everything
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        if self.is_approx() || other.is_approx() {
+            return (f64::from(*self).partial_cmp(&f64::from(*other)))
+                .unwrap_or_else(|| self.is_nan().cmp(&other.is_nan()));
+        }
+        match (self.is_nan(), other.is_nan()) {
+            (false, false) => {
+                let a = self.num as i128 * other.den as i128;
+                let b = other.num as i128 * self.den as i128;
+                if self.den == 0 && other.den == 0 {
+                    self.num.cmp(&other.num)
+                } else {
+                    a.cmp(&b)
+                }
+            }
+            (a, b) => a.cmp(&b),
+        }
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl From<f64> for Rational {
+    /// Convert a number to the simplest rational that converts back to it
+    fn from(n: f64) -> Self {
+        if n.is_nan() {
+            return Self::NAN;
+        }
+        if n.is_infinite() {
+            return Self::new(n.signum() as i64, 0);
+        }
+        if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+            return Self::new(n as i64, 1);
+        }
+        // Find the first continued fraction convergent that is exactly equal
+        let (mut h0, mut h1) = (0i128, 1i128);
+        let (mut k0, mut k1) = (1i128, 0i128);
+        let mut y = n;
+        // Numbers too large to be rationals are approximated
+        let mut best = Self::approx(n);
+        for _ in 0..64 {
+            let a = y.floor();
+            if a.abs() > i64::MAX as f64 {
+                break;
+            }
+            let a = a as i128;
+            let (h, k) = (a * h1 + h0, a * k1 + k0);
+            if h.abs() > i64::MAX as i128 || k > i64::MAX as i128 {
+                break;
+            }
+            best = Self::from_i128(h, k);
+            if h as f64 / k as f64 == n {
+                break;
+            }
+            (h0, h1, k0, k1) = (h1, h, k1, k);
+            y = 1.0 / (y - a as f64);
+            if !y.is_finite() {
+                break;
+            }
+        }
+        best
+    }
+}
+
+impl From<u8> for Rational {
+    fn from(n: u8) -> Self {
+        Self::new(n.into(), 1)
+    }
+}
+
+impl From<Rational> for f64 {
+    fn from(r: Rational) -> Self {
+        if r.is_approx() {
+            f64::from_bits(r.num as u64)
+        } else if r.den == 0 {
+            match r.num.signum() {
+                0 => f64::NAN,
+                sign => sign as f64 * f64::INFINITY,
+            }
+        } else {
+            r.num as f64 / r.den as f64
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (!self.is_nan() && !other.is_nan()).then(|| self.total_cmp(other))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_approx() {
+            return write!(f, "{}", f64::from(*self));
+        }
+        match (self.num, self.den) {
+            (0, 0) => write!(f, "NaN"),
+            (num, 0) => write!(f, "{}∞", if num < 0 { "-" } else { "" }),
+            (num, 1) => write!(f, "{num}"),
+            (num, den) => write!(f, "{num}/{den}"),
+        }
+    }
+}
+
+impl Add for Rational {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        if self.den <= 0 || rhs.den <= 0 {
+            return (f64::from(self) + f64::from(rhs)).into();
+        }
+        let (a, b, c, d) = (
+            self.num as i128,
+            self.den as i128,
+            rhs.num as i128,
+            rhs.den as i128,
+        );
+        Self::from_i128(a * d + c * b, b * d)
+    }
+}
+
+impl Sub for Rational {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Mul for Rational {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        if self.den <= 0 || rhs.den <= 0 {
+            return (f64::from(self) * f64::from(rhs)).into();
+        }
+        Self::from_i128(
+            self.num as i128 * rhs.num as i128,
+            self.den as i128 * rhs.den as i128,
+        )
+    }
+}
+
+impl Div for Rational {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self::Output {
+        if self.den <= 0 || rhs.den <= 0 || self.num == 0 && rhs.num == 0 {
+            return (f64::from(self) / f64::from(rhs)).into();
+        }
+        Self::from_i128(
+            self.num as i128 * rhs.den as i128,
+            self.den as i128 * rhs.num as i128,
+        )
+    }
+}
+
+impl Rem for Rational {
+    type Output = Self;
+    fn rem(self, rhs: Self) -> Self::Output {
+        if self.den <= 0 || rhs.den <= 0 || rhs.num == 0 {
+            return (f64::from(self) % f64::from(rhs)).into();
+        }
+        let quotient = self / rhs;
+        let truncated = if quotient.num < 0 {
+            quotient.ceil()
+        } else {
+            quotient.floor()
+        };
+        self - rhs * truncated
+    }
+}
+
+impl Neg for Rational {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        if self.is_approx() {
+            return Self::approx(-f64::from(self));
+        }
+        Self::from_i128(-(self.num as i128), self.den as i128)
+    }
+}
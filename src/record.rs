@@ -0,0 +1,158 @@
+use std::{fmt, str::FromStr};
+
+use parking_lot::Mutex;
+
+use crate::{function::Instr, lex::Span, Debugger, Uiua};
+
+/// A [`Debugger`] that records every instruction that is executed
+///
+/// The [`Recording`] can be written to a file and stepped through later,
+/// which helps with failures that are hard to reproduce.
+///
+/// # Example
+/// ```
+/// # use std::sync::Arc;
+/// # use uiua::*;
+/// let recorder = Arc::new(Recorder::default());
+/// let mut env = Uiua::with_native_sys().with_debugger(recorder.clone());
+/// env.load_str("+ 1 2").unwrap();
+/// let recording = recorder.recording();
+/// assert_eq!(recording.steps.last().unwrap().instr, "+");
+/// assert_eq!(recording.steps.last().unwrap().stack_height, 2);
+/// ```
+#[derive(Default)]
+pub struct Recorder {
+    steps: Mutex<Vec<Step>>,
+}
+
+impl Recorder {
+    /// Get the steps recorded so far
+    pub fn recording(&self) -> Recording {
+        Recording {
+            steps: self.steps.lock().clone(),
+        }
+    }
+}
+
+impl Debugger for Recorder {
+    fn step(&self, env: &Uiua, instr: &Instr) -> Result<(), String> {
+        let span = env.get_span(instr.span());
+        let (location, code) = match &span {
+            Span::Code(span) => (span.to_string(), span.as_str().to_string()),
+            Span::Builtin => (String::new(), String::new()),
+        };
+        self.steps.lock().push(Step {
+            call_depth: env.call_depth(),
+            stack_height: env.stack().len(),
+            location,
+            code,
+            instr: instr.to_string(),
+        });
+        Ok(())
+    }
+}
+
+/// A single executed instruction in a [`Recording`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// The number of functions being executed
+    pub call_depth: usize,
+    /// The height of the stack before the instruction was executed
+    pub stack_height: usize,
+    /// Where the instruction's code is, or an empty string if it has none
+    pub location: String,
+    /// The instruction's code
+    pub code: String,
+    /// The instruction itself
+    pub instr: String,
+}
+
+/// The instructions executed by a program, in order
+///
+/// Created with [`Recorder::recording`].
+/// Its [`Display`](fmt::Display) implementation writes one step per line,
+/// and its [`FromStr`] implementation reads them back.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording {
+    /// The steps
+    pub steps: Vec<Step>,
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}",
+                step.call_depth,
+                step.stack_height,
+                escape(&step.location),
+                escape(&step.instr),
+                escape(&step.code)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Recording {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut steps = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let invalid = || format!("Invalid recording step on line {}", i + 1);
+            let mut fields = line.split('\t');
+            let mut next = || fields.next().ok_or_else(invalid);
+            steps.push(Step {
+                call_depth: next()?.parse().map_err(|_| invalid())?,
+                stack_height: next()?.parse().map_err(|_| invalid())?,
+                location: unescape(next()?),
+                instr: unescape(next()?),
+                code: unescape(next()?),
+            });
+        }
+        Ok(Recording { steps })
+    }
+}
+
+/// Escape the characters that separate steps and fields
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+#[test]
+fn recording_round_trip() {
+    let recording = Recording {
+        steps: vec![Step {
+            call_depth: 2,
+            stack_height: 3,
+            location: "1:1".into(),
+            code: "\"a\\tb\"\n+".into(),
+            instr: "+".into(),
+        }],
+    };
+    assert_eq!(recording.to_string().lines().count(), 1);
+    assert_eq!(recording.to_string().parse(), Ok(recording));
+}
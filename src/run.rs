@@ -1,12 +1,15 @@
 use std::{
-    collections::{BTreeSet, HashMap},
-    fs,
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt, fs,
     hash::Hash,
     mem::{replace, take},
     panic::{catch_unwind, AssertUnwindSafe},
     path::{Path, PathBuf},
     str::FromStr,
-    sync::Arc,
+    sync::{
+        atomic::{self, AtomicBool, AtomicU64},
+        Arc,
+    },
 };
 
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
@@ -14,18 +17,51 @@ use enum_iterator::Sequence;
 use instant::Duration;
 use parking_lot::Mutex;
 use rand::prelude::*;
+use rayon::prelude::*;
 
 use crate::{
-    array::Array, boxed::Boxed, constants, function::*, lex::Span, parse::parse,
-    primitive::Primitive, value::Value, Diagnostic, DiagnosticKind, Ident, NativeSys, SysBackend,
-    SysOp, TraceFrame, UiuaError, UiuaResult,
+    algorithm::fork,
+    array::Array,
+    ast::{Item, Modifier, Word},
+    boxed::Boxed,
+    compile::instrs_get_global_functions,
+    constants,
+    function::*,
+    lex::{Loc, Sp, Span},
+    parse::parse,
+    primitive::Primitive,
+    sys::example_ua,
+    value::Value,
+    Capability, Diagnostic, DiagnosticKind, ErrorKind, Handle, Ident, SysBackend, SysOp,
+    TraceFrame, UiuaError, UiuaResult,
 };
 
+/// The maximum number of nested function calls
+const MAX_CALL_DEPTH: usize = 800;
+/// The native stack space below which a call allocates a new stack segment
+///
+/// Modifiers call functions through native Rust calls, so this has to be
+/// larger than the stack used between one call and the next.
+#[cfg(not(target_arch = "wasm32"))]
+const STACK_RED_ZONE: usize = 1024 * 1024;
+/// The size of newly allocated stack segments
+#[cfg(not(target_arch = "wasm32"))]
+const STACK_SEGMENT_SIZE: usize = 16 * 1024 * 1024;
+
+/// The minimum number of rows or elements for a loop to be run in parallel
+const PARALLEL_THRESHOLD: usize = 1024;
+
 /// The Uiua runtime
 #[derive(Clone)]
 pub struct Uiua {
     /// Functions which are under construction
     pub(crate) new_functions: Vec<Vec<Instr>>,
+    /// Bindings which are under construction
+    pub(crate) current_bindings: Vec<CurrentBinding>,
+    /// Bindings bound later in the blocks currently being compiled
+    pub(crate) later_bindings: Vec<CurrentBinding>,
+    /// The bindings local to each inline function currently being compiled
+    pub(crate) local_frames: Vec<HashMap<Ident, Global>>,
     /// Global values
     pub(crate) globals: Arc<Mutex<Vec<Global>>>,
     /// Indexable spans
@@ -44,14 +80,34 @@ pub struct Uiua {
     pub(crate) higher_scopes: Vec<Scope>,
     /// Determines which How test scopes are run
     pub(crate) mode: RunMode,
+    /// Determines which optimizations the compiler does
+    pub(crate) opt_level: OptLevel,
     /// A limit on the execution duration in milliseconds
     execution_limit: Option<f64>,
+    /// The number of instructions that may still be executed, shared with spawned threads
+    fuel: Option<Arc<AtomicU64>>,
+    /// Set to stop execution at the next instruction
+    interrupted: Arc<AtomicBool>,
+    /// The random number generator used by `random` and other nondeterministic functions
+    pub(crate) rng: Arc<Mutex<SmallRng>>,
+    /// A limit on the number of bytes a single array's data may take up
+    memory_limit: Option<usize>,
+    /// How much numbers may differ and still be considered equal
+    tolerance: f64,
+    /// The kinds of outside access system functions are allowed
+    capabilities: BTreeSet<Capability>,
     /// The time at which execution started
     execution_start: f64,
     /// The paths of files currently being imported (used to detect import cycles)
     current_imports: Arc<Mutex<Vec<PathBuf>>>,
     /// The bindings of imported files
-    imports: Arc<Mutex<HashMap<PathBuf, HashMap<Ident, usize>>>>,
+    pub(crate) imports: Arc<Mutex<HashMap<PathBuf, HashMap<Ident, usize>>>>,
+    /// The Rust functions registered with [`Uiua::register_fn`]
+    pub(crate) host_functions: Arc<Mutex<HashMap<Ident, Arc<Function>>>>,
+    /// The handles of files and sockets that were opened and not yet closed
+    pub(crate) open_handles: Arc<Mutex<BTreeSet<Handle>>>,
+    /// The items run by the last reload
+    reloaded: Reloaded,
     /// Accumulated diagnostics
     pub(crate) diagnostics: BTreeSet<Diagnostic>,
     /// Print diagnostics as they are encountered
@@ -60,6 +116,14 @@ pub struct Uiua {
     time_instrs: bool,
     /// The time at which the last instruction was executed
     last_time: f64,
+    /// The debugger to call before each instruction
+    debugger: Option<Arc<dyn Debugger>>,
+    /// The hooks to call when events happen
+    hooks: Vec<Arc<dyn Hooks>>,
+    /// Execution statistics, if profiling is enabled
+    profile: Option<ProfileData>,
+    /// Per-primitive execution statistics, if enabled
+    stats: Option<HashMap<Primitive, PrimitiveStats>>,
     /// Arguments passed from the command line
     cli_arguments: Vec<String>,
     /// File that was passed to the interpreter for execution
@@ -76,6 +140,15 @@ pub(crate) enum Global {
     Func(Arc<Function>),
 }
 
+/// A binding whose body is being compiled, or which is bound later in the current block
+#[derive(Clone)]
+pub(crate) struct CurrentBinding {
+    pub name: Ident,
+    pub signature: Option<Signature>,
+    /// The global index reserved for the binding if it is referred to before it is bound
+    pub global_index: Option<usize>,
+}
+
 #[derive(Clone)]
 pub(crate) struct Scope {
     /// The stack height at the start of each array currently being built
@@ -84,6 +157,8 @@ pub(crate) struct Scope {
     call: Vec<StackFrame>,
     /// Map local names to global indices
     pub names: HashMap<Ident, usize>,
+    /// Map modifier alias names to the modifiers they stand for
+    pub modifier_aliases: HashMap<Ident, Modifier>,
     /// The current fill values
     fills: Fills,
     /// The current clear state
@@ -103,19 +178,110 @@ impl Default for Scope {
                 call_span: 0,
                 pc: 0,
                 spans: Vec::new(),
+                tail: false,
             }],
             names: HashMap::new(),
+            modifier_aliases: HashMap::new(),
             fills: Fills::default(),
             pack_depth: 0,
         }
     }
 }
 
+/// The state needed to incrementally reload a file
+#[derive(Default, Clone)]
+struct Reloaded {
+    /// The names that were bound before the first item was run
+    base_names: HashMap<Ident, usize>,
+    /// The items that have been run, in order
+    items: Vec<ReloadedItem>,
+}
+
+#[derive(Clone)]
+struct ReloadedItem {
+    /// The start location and source of the item
+    key: (Loc, String),
+    /// The runtime state before the item was run
+    checkpoint: Checkpoint,
+    /// The names bound by the item
+    names: Vec<(Ident, usize)>,
+    /// The diagnostics emitted by the item
+    diagnostics: Vec<Diagnostic>,
+    /// The names a binding item refers to, if it can be kept as long as they are not rebound
+    depends_on: Option<HashSet<Ident>>,
+}
+
+/// The items after the first changed one in a reload, and the globals and spans they added
+struct OldReload {
+    items: Vec<ReloadedItem>,
+    globals_start: usize,
+    globals: Vec<Global>,
+    spans_start: usize,
+    spans: Vec<Span>,
+}
+
+#[derive(Clone)]
+struct Checkpoint {
+    stack: Vec<Value>,
+    function_stack: Vec<Arc<Function>>,
+    globals_len: usize,
+    spans_len: usize,
+    open_handles: BTreeSet<Handle>,
+}
+
+impl Drop for Uiua {
+    fn drop(&mut self) {
+        // Close the handles the program left open, unless another thread's runtime still shares them
+        if let Some(open_handles) = Arc::get_mut(&mut self.open_handles) {
+            for handle in open_handles.get_mut().iter() {
+                _ = self.backend.close(*handle);
+            }
+        }
+    }
+}
+
+/// A handle for interrupting a running [`Uiua`] runtime
+///
+/// Created with [`Uiua::interrupt_handle`].
+#[derive(Clone)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    /// Stop execution at the next instruction with an error
+    ///
+    /// The interruption is cleared when the runtime next loads code.
+    pub fn interrupt(&self) {
+        self.0.store(true, atomic::Ordering::Relaxed);
+    }
+}
+
+/// A saved runtime state that can be returned to with [`Uiua::rollback`]
+#[derive(Clone)]
+pub struct Snapshot {
+    checkpoint: Checkpoint,
+    names: HashMap<Ident, usize>,
+}
+
+/// The files that make up a project, and which of them import which
+///
+/// Created with [`Uiua::load_project`].
+#[derive(Debug, Clone, Default)]
+pub struct ModuleGraph {
+    /// The files in the order they were run, each after the files it imports
+    ///
+    /// The root file is last.
+    pub modules: Vec<PathBuf>,
+    /// The files imported by each file
+    pub imports: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
 #[derive(Default, Clone)]
 struct Fills {
     nums: Vec<f64>,
     #[cfg(feature = "complex")]
     complexes: Vec<crate::Complex>,
+    #[cfg(feature = "rational")]
+    rationals: Vec<crate::Rational>,
     chars: Vec<char>,
     boxes: Vec<Boxed>,
 }
@@ -129,7 +295,10 @@ struct StackFrame {
     /// The program counter for the function
     pc: usize,
     /// Additional spans for error reporting
-    spans: Vec<(usize, Option<Primitive>)>,
+    spans: Vec<usize>,
+    /// Whether the frame was entered from the end of the frame below it,
+    /// in the same call to [`Uiua::exec`]
+    tail: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -166,7 +335,196 @@ struct Thread {
 
 impl Default for Uiua {
     fn default() -> Self {
-        Self::with_native_sys()
+        #[cfg(feature = "native_sys")]
+        {
+            Self::with_native_sys()
+        }
+        #[cfg(not(feature = "native_sys"))]
+        {
+            Self::with_backend(crate::SafeSys)
+        }
+    }
+}
+
+/// Something that follows execution one instruction at a time
+///
+/// Set one with [`Uiua::with_debugger`].
+/// It is called before each instruction is executed. Execution waits for it to return,
+/// so a frontend can pause execution by blocking, for example on a channel.
+///
+/// # Example
+/// A debugger that records the stack whenever execution reaches line 2
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use uiua::*;
+/// #[derive(Default)]
+/// struct Breakpoint(Mutex<Vec<Vec<Value>>>);
+///
+/// impl Debugger for Breakpoint {
+///     fn step(&self, env: &Uiua, instr: &Instr) -> Result<(), String> {
+///         if env.get_span(instr.span()).to_string().starts_with("2:") {
+///             self.0.lock().unwrap().push(env.stack().to_vec());
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let breakpoint = Arc::new(Breakpoint::default());
+/// let mut env = Uiua::with_native_sys().with_debugger(breakpoint.clone());
+/// env.load_str("1 2\n+").unwrap();
+/// let stacks = breakpoint.0.lock().unwrap();
+/// assert_eq!(*stacks, [vec![Value::from(2.0), Value::from(1.0)]]);
+/// ```
+pub trait Debugger: Send + Sync {
+    /// Called before an instruction is executed
+    ///
+    /// The runtime can be inspected with methods like [`Uiua::stack`], [`Uiua::call_stack`],
+    /// and [`Uiua::all_values_is_scope`].
+    ///
+    /// Returning an error stops execution with that error.
+    fn step(&self, env: &Uiua, instr: &Instr) -> Result<(), String>;
+}
+
+/// Callbacks for events that happen during execution
+///
+/// Add some with [`Uiua::with_hooks`]. Every method does nothing by default,
+/// so implementors only need to handle the events they care about.
+/// Unlike a [`Debugger`], hooks cannot stop execution.
+///
+/// # Example
+/// Hooks that record the height of the stack after each push
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use uiua::*;
+/// #[derive(Default)]
+/// struct Heights(Mutex<Vec<usize>>);
+///
+/// impl Hooks for Heights {
+///     fn on_push(&self, env: &Uiua, _: &Value) {
+///         self.0.lock().unwrap().push(env.stack().len());
+///     }
+/// }
+///
+/// let heights = Arc::new(Heights::default());
+/// let mut env = Uiua::with_native_sys().with_hooks(heights.clone());
+/// env.load_str("+ 1 2").unwrap();
+/// assert_eq!(*heights.0.lock().unwrap(), [1, 2, 1]);
+/// ```
+#[allow(unused_variables)]
+pub trait Hooks: Send + Sync {
+    /// Called when a function starts executing
+    ///
+    /// The function is already on [`Uiua::call_stack`].
+    fn on_call(&self, env: &Uiua, f: &Function) {}
+    /// Called when a function finishes executing without an error
+    fn on_return(&self, env: &Uiua, f: &Function) {}
+    /// Called after a value is pushed onto the stack
+    fn on_push(&self, env: &Uiua, val: &Value) {}
+    /// Called when an error occurs, before it unwinds any functions
+    ///
+    /// The error may still be caught, for example by [`try` `⍣`](https://uiua.org/docs/try).
+    fn on_error(&self, env: &Uiua, error: &UiuaError) {}
+}
+
+#[derive(Clone, Default)]
+struct ProfileData {
+    spans: HashMap<usize, ProfileStats>,
+    functions: HashMap<FunctionId, ProfileStats>,
+}
+
+/// Execution statistics for some code
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProfileStats {
+    /// The number of instructions executed
+    pub count: usize,
+    /// The total time spent executing those instructions, in milliseconds
+    ///
+    /// This includes the time spent in any functions they call.
+    pub time: f64,
+}
+
+impl ProfileStats {
+    fn add(&mut self, time: f64) {
+        self.count += 1;
+        self.time += time;
+    }
+}
+
+/// Execution statistics collected by a profiling run
+///
+/// Created with [`Uiua::profile`].
+/// Its [`Display`](fmt::Display) implementation prints a report of the spans that took the most time.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    /// The statistics for each span of code, slowest first
+    pub spans: Vec<(Span, ProfileStats)>,
+    /// The statistics for the instructions directly in each function, slowest first
+    pub functions: Vec<(FunctionId, ProfileStats)>,
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>10} {:>10}  span", "time (ms)", "count")?;
+        for (span, stats) in &self.spans {
+            let code = match span {
+                Span::Code(span) => span.as_str(),
+                Span::Builtin => "",
+            };
+            writeln!(f, "{:>10.3} {:>10}  {span} {code}", stats.time, stats.count)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{:>10} {:>10}  function", "time (ms)", "count")?;
+        for (id, stats) in &self.functions {
+            writeln!(f, "{:>10.3} {:>10}  {id}", stats.time, stats.count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Execution statistics for a primitive
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PrimitiveStats {
+    /// The number of times the primitive was executed
+    pub count: usize,
+    /// The total time spent executing the primitive, in milliseconds
+    ///
+    /// For modifiers, this includes the time spent in the functions they call.
+    pub time: f64,
+    /// The total size in bytes of the arrays the primitive left on the stack
+    /// that do not reuse the buffer of one of its arguments
+    pub bytes: usize,
+}
+
+/// Per-primitive execution statistics
+///
+/// Created with [`Uiua::stats`].
+/// Its [`Display`](fmt::Display) implementation prints a report of the primitives that took the most time.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    /// The statistics for each primitive that was executed, slowest first
+    pub primitives: Vec<(Primitive, PrimitiveStats)>,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>10} {:>10} {:>12}  primitive",
+            "time (ms)", "count", "bytes"
+        )?;
+        for (prim, stats) in &self.primitives {
+            write!(
+                f,
+                "{:>10.3} {:>10} {:>12}  ",
+                stats.time, stats.count, stats.bytes
+            )?;
+            if prim.glyph().is_some() {
+                writeln!(f, "{prim} {}", prim.name())?;
+            } else {
+                writeln!(f, "{}", prim.name())?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -184,6 +542,20 @@ pub enum RunMode {
     All,
 }
 
+/// Which optimizations the compiler does
+///
+/// Lower levels compile slightly faster, but the compiled code may run slower.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum OptLevel {
+    /// Compile code as it is written
+    None,
+    /// Fuse adjacent instructions into faster ones and remove redundant ones
+    Peephole,
+    /// Also inline the functions of some modifiers
+    #[default]
+    Full,
+}
+
 impl FromStr for RunMode {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -198,7 +570,12 @@ impl FromStr for RunMode {
 
 impl Uiua {
     /// Create a new Uiua runtime with the standard IO backend
+    #[cfg(feature = "native_sys")]
     pub fn with_native_sys() -> Self {
+        Self::with_backend(crate::NativeSys)
+    }
+    /// Create a new Uiua runtime with a custom IO backend
+    pub fn with_backend(backend: impl SysBackend) -> Self {
         let mut scope = Scope::default();
         let mut globals = Vec::new();
         for def in constants() {
@@ -215,28 +592,40 @@ impl Uiua {
             higher_scopes: Vec::new(),
             globals: Arc::new(Mutex::new(globals)),
             new_functions: Vec::new(),
+            current_bindings: Vec::new(),
+            later_bindings: Vec::new(),
+            local_frames: Vec::new(),
             current_imports: Arc::new(Mutex::new(Vec::new())),
             imports: Arc::new(Mutex::new(HashMap::new())),
+            host_functions: Arc::new(Mutex::new(HashMap::new())),
+            open_handles: Arc::new(Mutex::new(BTreeSet::new())),
+            reloaded: Reloaded::default(),
             mode: RunMode::Normal,
+            opt_level: OptLevel::Full,
             diagnostics: BTreeSet::new(),
-            backend: Arc::new(NativeSys),
+            backend: Arc::new(backend),
             print_diagnostics: false,
             time_instrs: false,
+            debugger: None,
+            hooks: Vec::new(),
+            profile: None,
+            stats: None,
             last_time: 0.0,
             cli_arguments: Vec::new(),
             cli_file_path: PathBuf::new(),
             execution_limit: None,
+            fuel: None,
+            interrupted: Arc::new(AtomicBool::new(false)),
+            rng: Arc::new(Mutex::new(SmallRng::seed_from_u64(
+                instant::now().to_bits(),
+            ))),
+            memory_limit: None,
+            tolerance: 0.0,
+            capabilities: Capability::all().collect(),
             execution_start: 0.0,
             thread: ThisThread::default(),
         }
     }
-    /// Create a new Uiua runtime with a custom IO backend
-    pub fn with_backend(backend: impl SysBackend) -> Self {
-        Uiua {
-            backend: Arc::new(backend),
-            ..Default::default()
-        }
-    }
     /// Get a reference to the system backend
     pub fn backend(&self) -> &dyn SysBackend {
         &*self.backend
@@ -266,11 +655,99 @@ impl Uiua {
         self.time_instrs = time_instrs;
         self
     }
+    /// Set a [`Debugger`] to call before each instruction is executed
+    pub fn with_debugger(mut self, debugger: Arc<dyn Debugger>) -> Self {
+        self.debugger = Some(debugger);
+        self
+    }
+    /// Add [`Hooks`] to call when events happen during execution
+    ///
+    /// This can be called more than once. Hooks are called in the order they were added.
+    pub fn with_hooks(mut self, hooks: Arc<dyn Hooks>) -> Self {
+        self.hooks.push(hooks);
+        self
+    }
+    /// Set whether to count the instructions executed and the time spent on each span of code
+    ///
+    /// The results can be retrieved with [`Uiua::profile`].
+    pub fn with_profiling(mut self, profiling: bool) -> Self {
+        self.profile = profiling.then(ProfileData::default);
+        self
+    }
+    /// Set whether to collect execution statistics for each primitive
+    ///
+    /// The results can be retrieved with [`Uiua::stats`].
+    pub fn with_stats(mut self, stats: bool) -> Self {
+        self.stats = stats.then(HashMap::new);
+        self
+    }
     /// Limit the execution duration
     pub fn with_execution_limit(mut self, limit: Duration) -> Self {
         self.execution_limit = Some(limit.as_millis() as f64);
         self
     }
+    /// Limit the number of instructions that can be executed
+    ///
+    /// Once the limit is reached, execution stops with an error.
+    /// Threads spawned by the program draw from the same limit.
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.fuel = Some(Arc::new(AtomicU64::new(fuel)));
+        self
+    }
+    /// Get the number of instructions that can still be executed, if it is limited
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        (self.fuel.as_ref()).map(|fuel| fuel.load(atomic::Ordering::Relaxed))
+    }
+    /// Seed the random number generator
+    ///
+    /// After seeding, [`random`](Primitive::Rand) produces the same sequence of numbers
+    /// every time a program is run.
+    pub fn seed_rng(&mut self, seed: u64) {
+        *self.rng.lock() = SmallRng::seed_from_u64(seed);
+    }
+    /// Set how much numbers may differ and still be considered equal
+    ///
+    /// This affects [`equals` `=`](Primitive::Eq), [`not equals` `≠`](Primitive::Ne),
+    /// [`match` `≍`](Primitive::Match), [`classify` `⊛`](Primitive::Classify),
+    /// and [`deduplicate` `⊝`](Primitive::Deduplicate).
+    /// The default is `0`, which means numbers must be exactly equal.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.set_tolerance(tolerance);
+        self
+    }
+    /// Set how much numbers may differ and still be considered equal
+    ///
+    /// See [`Uiua::with_tolerance`].
+    pub fn set_tolerance(&mut self, tolerance: f64) {
+        self.tolerance = tolerance.abs();
+    }
+    /// Get how much numbers may differ and still be considered equal
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+    /// Get a handle that can interrupt execution from another thread
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle(self.interrupted.clone())
+    }
+    /// Limit the number of bytes that the data of a single array may take up
+    ///
+    /// Operations that would create an array past the limit fail with an error instead of allocating.
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+    /// Set the kinds of outside access system functions are allowed
+    ///
+    /// System functions that need a missing [`Capability`] fail with an error.
+    /// By default, all capabilities are allowed.
+    pub fn with_capabilities(mut self, capabilities: impl IntoIterator<Item = Capability>) -> Self {
+        self.capabilities = capabilities.into_iter().collect();
+        self
+    }
+    /// Get the kinds of outside access system functions are allowed
+    pub fn capabilities(&self) -> &BTreeSet<Capability> {
+        &self.capabilities
+    }
     /// Set the [`RunMode`]
     ///
     /// Default is [`RunMode::Normal`]
@@ -278,6 +755,17 @@ impl Uiua {
         self.mode = mode;
         self
     }
+    /// Set the [`OptLevel`]
+    ///
+    /// Default is [`OptLevel::Full`]
+    pub fn with_opt_level(mut self, opt_level: OptLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+    /// Get the [`OptLevel`]
+    pub fn opt_level(&self) -> OptLevel {
+        self.opt_level
+    }
     /// Get the [`RunMode`]
     pub fn mode(&self) -> RunMode {
         self.mode
@@ -314,6 +802,274 @@ impl Uiua {
     pub fn load_str_path<P: AsRef<Path>>(&mut self, input: &str, path: P) -> UiuaResult {
         self.load_impl(input, Some(path.as_ref()))
     }
+    /// Reload a Uiua file from a string, only running what has changed
+    ///
+    /// The top-level items are compared with those run by the previous reload.
+    /// Items before the first changed one are kept as they are, along with their bindings
+    /// and the values they left on the stack. Everything from the first changed item on is
+    /// rolled back. After that, an unchanged binding is kept if none of the names it refers
+    /// to were bound by an item that was run again or removed. Every other item is run again.
+    ///
+    /// Items are compared by their source and location, so an unchanged item that imports a
+    /// changed file will not be run again.
+    ///
+    /// If an item fails, the runtime is left in the state it was in before that item.
+    pub fn reload(&mut self, input: &str) -> UiuaResult {
+        self.reload_impl(input, None)
+    }
+    /// Reload a Uiua file from a string with a path for error reporting
+    ///
+    /// See [`Uiua::reload`] for details.
+    pub fn reload_path<P: AsRef<Path>>(&mut self, input: &str, path: P) -> UiuaResult {
+        self.reload_impl(input, Some(path.as_ref()))
+    }
+    fn reload_impl(&mut self, input: &str, path: Option<&Path>) -> UiuaResult {
+        self.execution_start = instant::now();
+        self.interrupted.store(false, atomic::Ordering::Relaxed);
+        let (items, errors, diagnostics) = parse(input, path);
+        if !errors.is_empty() {
+            return Err(errors.into());
+        }
+        let items: Vec<(Item, (Loc, String))> = (items.into_iter())
+            .filter(|item| !matches!(item, Item::ExtraNewlines(_)))
+            .map(|item| {
+                let span = item.span();
+                let key = (span.start, span.as_str().to_string());
+                (item, key)
+            })
+            .collect();
+        // Roll back to just before the first changed item
+        let unchanged = (self.reloaded.items.iter())
+            .zip(&items)
+            .take_while(|(reloaded, (_, key))| reloaded.key == *key)
+            .count();
+        let mut old = None;
+        if let Some(item) = self.reloaded.items.get(unchanged) {
+            let checkpoint = item.checkpoint.clone();
+            old = Some(OldReload {
+                items: self.reloaded.items.split_off(unchanged),
+                globals_start: checkpoint.globals_len,
+                globals: self.globals.lock()[checkpoint.globals_len..].to_vec(),
+                spans_start: checkpoint.spans_len,
+                spans: self.spans.lock()[checkpoint.spans_len..].to_vec(),
+            });
+            self.rollback_reload(checkpoint);
+        } else if self.reloaded.items.is_empty() {
+            self.reloaded.base_names = self.scope.names.clone();
+        }
+        // Parse diagnostics are only reported for changed items
+        let changed_start = items.get(unchanged).map(|(_, (start, _))| *start);
+        let diagnostics = diagnostics.into_iter().filter(|diag| {
+            changed_start.is_some_and(|start| match &diag.span {
+                Span::Code(span) => span.start >= start,
+                Span::Builtin => true,
+            })
+        });
+        if self.print_diagnostics {
+            for diagnostic in diagnostics {
+                println!("{}", diagnostic.report());
+            }
+        } else {
+            self.diagnostics.extend(diagnostics);
+        }
+        for item in &self.reloaded.items {
+            self.diagnostics.extend(item.diagnostics.iter().cloned());
+        }
+        if let Some(path) = path {
+            self.current_imports.lock().push(path.into());
+        }
+        // Names that were rebound or unbound since the previous reload
+        let mut dirty: HashSet<Ident> = HashSet::new();
+        let mut old_by_key = HashMap::new();
+        if let Some(old) = &old {
+            let keys: HashSet<_> = items.iter().map(|(_, key)| key).collect();
+            for item in &old.items {
+                if keys.contains(&item.key) {
+                    old_by_key.entry(item.key.clone()).or_insert(item);
+                } else {
+                    dirty.extend(item.names.iter().map(|(name, _)| name.clone()));
+                }
+            }
+        }
+        let res = match catch_unwind(AssertUnwindSafe(|| {
+            for (item, key) in items.into_iter().skip(unchanged) {
+                let checkpoint = self.checkpoint();
+                // Keep a binding if nothing it refers to has changed
+                if let Some(old_item) = old_by_key.get(&key).filter(|item| {
+                    (item.depends_on.as_ref()).is_some_and(|deps| deps.is_disjoint(&dirty))
+                }) {
+                    let names = self.reuse_reloaded_item(old_item, old.as_ref().unwrap());
+                    self.diagnostics
+                        .extend(old_item.diagnostics.iter().cloned());
+                    self.reloaded.items.push(ReloadedItem {
+                        key,
+                        checkpoint,
+                        names,
+                        diagnostics: old_item.diagnostics.clone(),
+                        depends_on: old_item.depends_on.clone(),
+                    });
+                    continue;
+                }
+                let mut depends_on = None;
+                if let Item::Binding(binding) = &item {
+                    dirty.extend(binding.names().map(|name| name.value.clone()));
+                    let mut idents = HashSet::new();
+                    word_idents(&binding.words, &mut idents);
+                    depends_on = Some(idents);
+                }
+                let diagnostics_before = self.diagnostics.clone();
+                if let Err(e) = self.item(item, false) {
+                    self.rollback_reload(checkpoint);
+                    return Err(e);
+                }
+                let names: Vec<(Ident, usize)> = (self.scope.names.iter())
+                    .filter(|(_, idx)| **idx >= checkpoint.globals_len)
+                    .map(|(name, idx)| (name.clone(), *idx))
+                    .collect();
+                dirty.extend(names.iter().map(|(name, _)| name.clone()));
+                // A binding can only be kept if it does nothing but bind its names
+                let globals = self.globals.lock();
+                let keepable = !names.is_empty()
+                    && self.stack.len() == checkpoint.stack.len()
+                    && self.function_stack.len() == checkpoint.function_stack.len()
+                    && names.iter().all(|(_, idx)| match &globals[*idx] {
+                        Global::Func(f) => !instrs_get_global_functions(&f.instrs),
+                        Global::Val(_) => true,
+                    });
+                drop(globals);
+                let diagnostics = (self.diagnostics.difference(&diagnostics_before))
+                    .cloned()
+                    .collect();
+                self.reloaded.items.push(ReloadedItem {
+                    key,
+                    checkpoint,
+                    names,
+                    diagnostics,
+                    depends_on: depends_on.filter(|_| keepable),
+                });
+            }
+            Ok(())
+        })) {
+            Ok(res) => res,
+            Err(_) => Err(self.error(format!(
+                "\
+The interpreter has crashed!
+Hooray! You found a bug!
+Please report this at http://github.com/uiua-lang/uiua/issues/new
+
+code:
+{}
+{}",
+                self.span(),
+                input
+            ))),
+        };
+        if path.is_some() {
+            self.current_imports.lock().pop();
+        }
+        res
+    }
+    /// Run many independent Uiua programs from strings, returning the final stack of each
+    ///
+    /// Each program is run on top of the current state, which is restored after it finishes.
+    /// This avoids paying the setup cost of a new runtime for every program, which adds up
+    /// when checking many small snippets.
+    ///
+    /// Diagnostics from all the programs are accumulated.
+    pub fn run_many(&mut self, inputs: &[&str]) -> Vec<UiuaResult<Vec<Value>>> {
+        let checkpoint = self.checkpoint();
+        let scope = self.scope.clone();
+        (inputs.iter())
+            .map(|input| {
+                let res = self.load_str(input).map(|()| self.take_stack());
+                self.restore_checkpoint(checkpoint.clone());
+                self.scope = scope.clone();
+                res
+            })
+            .collect()
+    }
+    /// Save the current state of the runtime
+    ///
+    /// The state can be returned to later with [`Uiua::rollback`]. This is useful for undoing
+    /// the last line evaluated in a REPL.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            checkpoint: self.checkpoint(),
+            names: self.scope.names.clone(),
+        }
+    }
+    /// Return to a state saved with [`Uiua::snapshot`]
+    ///
+    /// The stack and bindings are restored, and files and sockets opened since the
+    /// snapshot are closed.
+    pub fn rollback(&mut self, snapshot: Snapshot) {
+        self.restore_checkpoint(snapshot.checkpoint);
+        self.scope.names = snapshot.names;
+    }
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            stack: self.stack.clone(),
+            function_stack: self.function_stack.clone(),
+            globals_len: self.globals.lock().len(),
+            spans_len: self.spans.lock().len(),
+            open_handles: self.open_handles.lock().clone(),
+        }
+    }
+    fn restore_checkpoint(&mut self, checkpoint: Checkpoint) {
+        self.stack = checkpoint.stack;
+        self.function_stack = checkpoint.function_stack;
+        self.globals.lock().truncate(checkpoint.globals_len);
+        self.spans.lock().truncate(checkpoint.spans_len);
+        // Modules loaded after the checkpoint have to be loaded again
+        (self.imports.lock())
+            .retain(|_, names| names.values().all(|idx| *idx < checkpoint.globals_len));
+        self.close_handles_opened_since(&checkpoint.open_handles);
+    }
+    /// Close the handles that were opened since the given ones were open
+    pub(crate) fn close_handles_opened_since(&self, open_handles: &BTreeSet<Handle>) {
+        let mut current = self.open_handles.lock();
+        for handle in current.difference(open_handles) {
+            _ = self.backend.close(*handle);
+        }
+        current.retain(|handle| open_handles.contains(handle));
+    }
+    /// Bind the names of an item from a previous reload again without running it
+    ///
+    /// The item's globals are copied to new slots, and the spans of their functions
+    /// are copied to new indices.
+    fn reuse_reloaded_item(&mut self, item: &ReloadedItem, old: &OldReload) -> Vec<(Ident, usize)> {
+        let mut span_map = HashMap::new();
+        let mut remap = |span: &mut usize| {
+            if *span >= old.spans_start {
+                *span = *span_map.entry(*span).or_insert_with(|| {
+                    let mut spans = self.spans.lock();
+                    spans.push(old.spans[*span - old.spans_start].clone());
+                    spans.len() - 1
+                });
+            }
+        };
+        let mut names = Vec::with_capacity(item.names.len());
+        for (name, idx) in &item.names {
+            let mut global = old.globals[idx - old.globals_start].clone();
+            if let Global::Func(f) = &mut global {
+                remap_function_spans(Arc::make_mut(f), &mut remap);
+            }
+            let mut globals = self.globals.lock();
+            globals.push(global);
+            names.push((name.clone(), globals.len() - 1));
+        }
+        self.scope.names.extend(names.iter().cloned());
+        names
+    }
+    /// Restore a reload checkpoint, keeping the names bound by the remaining reloaded items
+    fn rollback_reload(&mut self, checkpoint: Checkpoint) {
+        self.restore_checkpoint(checkpoint);
+        let mut names = self.reloaded.base_names.clone();
+        for item in &self.reloaded.items {
+            names.extend(item.names.iter().cloned());
+        }
+        self.scope.names = names;
+    }
     /// Run in a scoped context. Names defined in this context will be removed when the scope ends.
     ///
     /// While names defined in this context will be removed when the scope ends, values *bound* to
@@ -324,7 +1080,10 @@ impl Uiua {
         &mut self,
         f: impl FnOnce(&mut Self) -> UiuaResult<T>,
     ) -> UiuaResult<HashMap<Ident, usize>> {
+        let call_span = self.span_index();
         self.higher_scopes.push(take(&mut self.scope));
+        // The new scope's code runs from wherever the scope was entered
+        self.scope.call[0].call_span = call_span;
         let start_height = self.stack.len();
         let res = f(self);
         let scope = replace(&mut self.scope, self.higher_scopes.pop().unwrap());
@@ -340,6 +1099,12 @@ impl Uiua {
     }
     fn load_impl(&mut self, input: &str, path: Option<&Path>) -> UiuaResult {
         self.execution_start = instant::now();
+        // Imported modules are loaded as part of the same run
+        let top_level = self.current_imports.lock().is_empty();
+        if top_level {
+            self.interrupted.store(false, atomic::Ordering::Relaxed);
+        }
+        let open_handles = self.open_handles.lock().clone();
         let (items, errors, diagnostics) = parse(input, path);
         if self.print_diagnostics {
             for diagnostic in diagnostics {
@@ -372,45 +1137,176 @@ code:
         if path.is_some() {
             self.current_imports.lock().pop();
         }
+        // A failed program cannot close the handles it opened
+        if res.is_err() && top_level {
+            self.close_handles_opened_since(&open_handles);
+        }
         res
     }
-    fn trace_error(&self, mut error: UiuaError, frame: StackFrame) -> UiuaError {
-        let mut frames = Vec::new();
-        for (span, prim) in &frame.spans {
-            if let Some(prim) = prim {
-                frames.push(TraceFrame {
-                    id: FunctionId::Primitive(*prim),
-                    span: self.spans.lock()[*span].clone(),
-                });
+    /// Pop the current function off the call stack because of an error, and trace the error
+    fn unwind(&mut self, mut error: UiuaError) -> UiuaError {
+        self.error_hooks(&error);
+        // Frames entered at the end of another frame were run by the same `exec`
+        loop {
+            let frame = self.scope.call.pop().unwrap();
+            let tail = frame.tail;
+            error = self.trace_error(error, frame);
+            if !tail {
+                break error;
             }
         }
-        frames.push(TraceFrame {
-            id: frame.function.id.clone(),
-            span: self.spans.lock()[frame.call_span].clone(),
-        });
+    }
+    /// Call the error hooks if an error has not already been reported
+    fn error_hooks(&self, error: &UiuaError) {
+        // Errors that have been traced have already been reported,
+        // and breaks are not really errors
+        if !matches!(error, UiuaError::Traced { .. } | UiuaError::Break(..)) {
+            for hooks in &self.hooks {
+                hooks.on_error(self, error);
+            }
+        }
+    }
+    fn trace_error(&self, error: UiuaError, frame: StackFrame) -> UiuaError {
+        self.add_trace_frame(
+            error,
+            TraceFrame {
+                id: frame.function.id.clone(),
+                span: self.get_span(frame.call_span),
+            },
+        )
+    }
+    /// Add a frame to the end of an error's trace
+    fn add_trace_frame(&self, mut error: UiuaError, frame: TraceFrame) -> UiuaError {
         if let UiuaError::Traced { trace, .. } = &mut error {
-            trace.extend(frames);
+            trace.push(frame);
             error
         } else {
             UiuaError::Traced {
                 error: error.into(),
-                trace: frames,
+                trace: vec![frame],
             }
         }
     }
-    pub(crate) fn import(&mut self, input: &str, path: &Path, item: &str) -> UiuaResult {
-        if self.current_imports.lock().iter().any(|p| p == path) {
+    /// Load a module from a path, running it if it has not been loaded yet
+    ///
+    /// Returns the resolved path, which is the module's key in the imports map
+    pub(crate) fn load_module(&mut self, path: &str) -> UiuaResult<PathBuf> {
+        self.require_capability(Capability::Filesystem, "Importing")?;
+        let resolved_path = self.resolve_import_path(path.as_ref());
+        let current_imports = self.current_imports.lock().clone();
+        if let Some(i) = current_imports.iter().position(|p| p == &resolved_path) {
+            let cycle: Vec<_> = current_imports[i..]
+                .iter()
+                .chain([&resolved_path])
+                .map(|p| p.to_string_lossy())
+                .collect();
             return Err(self.error(format!(
-                "Cycle detected importing {}",
-                path.to_string_lossy()
+                "Cycle detected importing {}: {}",
+                resolved_path.to_string_lossy(),
+                cycle.join(" → ")
             )));
         }
-        if !self.imports.lock().contains_key(path) {
+        if !self.imports.lock().contains_key(&resolved_path) {
+            let input = (self.read_module(path, &resolved_path)).map_err(|e| self.error(e))?;
+            let import =
+                self.in_scope(|env| env.load_str_path(&input, &resolved_path).map(drop))?;
+            self.imports.lock().insert(resolved_path.clone(), import);
+        }
+        Ok(resolved_path)
+    }
+    /// Read the source of a module that is being imported
+    fn read_module(&self, path: &str, resolved_path: &Path) -> Result<String, String> {
+        let bytes = (self.backend.file_read_all(resolved_path)).or_else(|e| {
+            if path == "example.ua" {
+                Ok(example_ua(|ex| ex.as_bytes().to_vec()))
+            } else {
+                Err(e)
+            }
+        })?;
+        String::from_utf8(bytes).map_err(|e| format!("Failed to read file: {e}"))
+    }
+    /// Load a project from its root file, loading the files it imports first
+    ///
+    /// Imports with literal paths are found before anything is run, so an import cycle is
+    /// reported at the import that closes it. Each imported file is then run once, after the
+    /// files it imports, and the root file is run last.
+    /// Imports whose paths are computed at runtime are loaded when they are run, as usual.
+    ///
+    /// Returns the graph of the files that were found.
+    pub fn load_project<P: AsRef<Path>>(&mut self, root: P) -> UiuaResult<ModuleGraph> {
+        let root = resolve_path(None, root.as_ref());
+        let input =
+            fs::read_to_string(&root).map_err(|e| UiuaError::Load(root.clone(), e.into()))?;
+        let mut graph = ModuleGraph::default();
+        let mut sources = HashMap::new();
+        self.add_to_module_graph(&root, input, &mut Vec::new(), &mut graph, &mut sources)?;
+        for path in &graph.modules[..graph.modules.len() - 1] {
+            let input = &sources[path];
             let import = self.in_scope(|env| env.load_str_path(input, path).map(drop))?;
-            self.imports.lock().insert(path.into(), import);
+            self.imports.lock().insert(path.clone(), import);
         }
+        self.load_str_path(&sources[&root], &root)?;
+        Ok(graph)
+    }
+    /// Add a file and the files it imports to a module graph, depth first
+    fn add_to_module_graph(
+        &self,
+        path: &Path,
+        input: String,
+        importers: &mut Vec<PathBuf>,
+        graph: &mut ModuleGraph,
+        sources: &mut HashMap<PathBuf, String>,
+    ) -> UiuaResult {
+        let (items, errors, _) = parse(&input, Some(path));
+        if !errors.is_empty() {
+            return Err(errors.into());
+        }
+        let mut imports = Vec::new();
+        literal_imports(&items, &mut imports);
+        importers.push(path.into());
+        let mut imported = Vec::new();
+        for import in imports {
+            let resolved_path = resolve_path(Some(path), import.value.as_ref());
+            let error =
+                |message: String| UiuaError::Run(Span::Code(import.span.clone()).sp(message));
+            if let Some(i) = importers.iter().position(|p| p == &resolved_path) {
+                let cycle: Vec<_> = importers[i..]
+                    .iter()
+                    .chain([&resolved_path])
+                    .map(|p| p.to_string_lossy())
+                    .collect();
+                return Err(error(format!(
+                    "Cycle detected importing {}: {}",
+                    resolved_path.to_string_lossy(),
+                    cycle.join(" → ")
+                )));
+            }
+            if imported.contains(&resolved_path) {
+                continue;
+            }
+            imported.push(resolved_path.clone());
+            if sources.contains_key(&resolved_path)
+                || self.imports.lock().contains_key(&resolved_path)
+            {
+                continue;
+            }
+            self.require_capability(Capability::Filesystem, "Importing")
+                .map_err(|e| error(e.message()))?;
+            let input = self
+                .read_module(&import.value, &resolved_path)
+                .map_err(error)?;
+            self.add_to_module_graph(&resolved_path, input, importers, graph, sources)?;
+        }
+        importers.pop();
+        graph.imports.insert(path.into(), imported);
+        graph.modules.push(path.into());
+        sources.insert(path.into(), input);
+        Ok(())
+    }
+    pub(crate) fn import(&mut self, path: &str, item: &str) -> UiuaResult {
+        let path = self.load_module(path)?;
         let imports_gaurd = self.imports.lock();
-        let imports = &imports_gaurd[path];
+        let imports = &imports_gaurd[&path];
         let idx = imports.get(item).ok_or_else(|| {
             self.error(format!("Item `{}` not found in {}", item, path.display()))
         })?;
@@ -422,39 +1318,77 @@ code:
         }
         Ok(())
     }
+    /// Run a string of code in a child scope with its own stack
+    ///
+    /// Returns the values left on the child's stack, top first.
+    /// The globals bound by the code are released when it finishes.
+    /// Unlike loading, this does not restart the execution time limit.
+    pub(crate) fn eval(&mut self, input: &str) -> UiuaResult<Vec<Value>> {
+        let (items, errors, _) = parse(input, None);
+        if !errors.is_empty() {
+            return Err(errors.into());
+        }
+        let stack = take(&mut self.stack);
+        let globals_len = self.globals.lock().len();
+        let sharers = Arc::strong_count(&self.globals);
+        let mut values = Vec::new();
+        let res = self.in_scope(|env| {
+            env.items(items, false)?;
+            values = take(&mut env.stack);
+            values.reverse();
+            Ok(())
+        });
+        self.stack = stack;
+        // Release the globals bound by the code, unless a thread it spawned may still use them
+        if Arc::strong_count(&self.globals) == sharers {
+            self.globals.lock().truncate(globals_len);
+            (self.imports.lock()).retain(|_, names| names.values().all(|idx| *idx < globals_len));
+        }
+        res?;
+        Ok(values)
+    }
     /// Resolve a declared import path relative to the path of the file that is being executed
     pub(crate) fn resolve_import_path(&self, path: &Path) -> PathBuf {
-        let target =
-            if let Some(parent) = self.current_imports.lock().last().and_then(|p| p.parent()) {
-                parent.join(path)
-            } else {
-                path.to_path_buf()
-            };
-        let base = Path::new(".");
-        if let (Ok(canon_target), Ok(canon_base)) = (target.canonicalize(), base.canonicalize()) {
-            pathdiff::diff_paths(canon_target, canon_base).unwrap_or(target)
-        } else {
-            pathdiff::diff_paths(&target, base).unwrap_or(target)
-        }
+        let importer = self.current_imports.lock().last().cloned();
+        resolve_path(importer.as_deref(), path)
     }
-    pub(crate) fn exec_global_instrs(&mut self, instrs: Vec<Instr>) -> UiuaResult {
+    /// Run top-level instructions
+    ///
+    /// The span should be that of the code the instructions were compiled from,
+    /// so that errors from instructions without their own span still point somewhere useful.
+    pub(crate) fn exec_global_instrs(&mut self, instrs: Vec<Instr>, span: usize) -> UiuaResult {
         let func = Function::new(FunctionId::Main, instrs, Signature::new(0, 0));
         self.exec(StackFrame {
             function: Arc::new(func),
-            call_span: 0,
+            call_span: span,
             spans: Vec::new(),
             pc: 0,
+            tail: false,
         })?;
         Ok(())
     }
     fn exec(&mut self, frame: StackFrame) -> UiuaResult<Arc<Function>> {
+        for hooks in &self.hooks {
+            hooks.on_call(self, &frame.function);
+        }
         self.scope.call.push(frame);
         let mut formatted_instr = String::new();
         Ok(loop {
             let frame = self.scope.call.last().unwrap();
             let Some(instr) = frame.function.instrs.get(frame.pc) else {
-                break self.scope.call.pop().unwrap().function;
+                let frame = self.scope.call.pop().unwrap();
+                for hooks in &self.hooks {
+                    hooks.on_return(self, &frame.function);
+                }
+                if frame.tail {
+                    // Finish the instruction that entered the frame
+                    self.scope.call.last_mut().unwrap().pc += 1;
+                    continue;
+                }
+                break frame.function;
             };
+            let tail = frame.pc + 1 == frame.function.instrs.len();
+            let mut advance = true;
             // Uncomment to debug
             // if !self.scope.array.is_empty() {
             //     print!("array: ");
@@ -479,20 +1413,51 @@ code:
             // println!();
             // println!("  {:?}", instr);
 
+            if let Some(fuel) = &self.fuel {
+                let burned = fuel.fetch_update(
+                    atomic::Ordering::Relaxed,
+                    atomic::Ordering::Relaxed,
+                    |fuel| fuel.checked_sub(1),
+                );
+                if burned.is_err() {
+                    let error = UiuaError::OutOfFuel(self.span());
+                    return Err(self.unwind(error));
+                }
+            }
+            if self.interrupted.load(atomic::Ordering::Relaxed) {
+                let error = UiuaError::Interrupted(self.get_span(instr.span()));
+                return Err(self.unwind(error));
+            }
+            if let Some(debugger) = &self.debugger {
+                if let Err(message) = debugger.step(self, instr) {
+                    let error = UiuaError::Run(self.get_span(instr.span()).sp(message));
+                    return Err(self.unwind(error));
+                }
+            }
+            let profile_start = self
+                .profile
+                .is_some()
+                .then(|| (instr.span(), frame.function.id.clone(), instant::now()));
             if self.time_instrs {
                 formatted_instr = format!("{instr:?}");
                 self.last_time = instant::now();
             }
             let res = match instr {
                 &Instr::Prim(prim, span) => {
-                    self.with_prim_span(span, Some(prim), |env| prim.run(env))
+                    if self.stats.is_some() {
+                        self.run_prim_with_stats(prim, span)
+                    } else if self.hooks.is_empty() && self.scalar_math(prim) {
+                        Ok(())
+                    } else {
+                        self.run_prim(prim, span)
+                    }
                 }
                 &Instr::ImplPrim(prim, span) => self.with_span(span, |env| prim.run(env)),
-                Instr::Push(val) => {
-                    self.stack.push(Value::clone(val));
+                Instr::Push(val, _) => {
+                    self.push(Value::clone(val));
                     Ok(())
                 }
-                Instr::BeginArray => {
+                Instr::BeginArray(_) => {
                     self.scope.array.push(self.stack.len());
                     Ok(())
                 }
@@ -512,43 +1477,39 @@ code:
                     env.push(val);
                     Ok(())
                 }),
-                &Instr::Call(span) => self
-                    .pop_function()
-                    .and_then(|f| self.call_with_span(f, span)),
-                Instr::PushFunc(f) => {
+                &Instr::Call(span) => match self.pop_function() {
+                    Ok(f) if tail && self.restart_tail_recursion(&f, span) => {
+                        advance = false;
+                        Ok(())
+                    }
+                    Ok(f) => self.call_with_span(f, span),
+                    Err(e) => Err(e),
+                },
+                Instr::PushFunc(f, _) => {
                     self.function_stack.push(f.clone());
                     Ok(())
                 }
-                &Instr::Switch { count, span } => self.with_span(span, |env| {
-                    let i = env
-                        .pop("switch index")?
-                        .as_nat(env, "Switch index mut be a natural number")?;
-                    if i >= count {
-                        return Err(env.error(format!(
-                            "Switch index {i} is out of bounds for switch of size {count}"
-                        )));
+                &Instr::Switch { count, span } => {
+                    match self.with_span(span, |env| fork::switch_branch(count, env)) {
+                        Ok(Some(f)) if tail => {
+                            // Enter the branch without a native call, so that
+                            // a recursive call at its end can restart this frame
+                            advance = false;
+                            self.enter_tail_frame(f, span)
+                        }
+                        Ok(Some(f)) => self.call_with_span(f, span),
+                        Ok(None) => Ok(()),
+                        Err(e) => Err(e),
                     }
-                    let f = env
-                        .function_stack
-                        .drain(env.function_stack.len() - count..)
-                        .nth(i);
-                    if let Some(f) = f {
-                        env.call(f)
-                    } else {
-                        Err(env.error(
-                            "Function stack was empty when getting switch function. \
-                            This is a bug in the interpreter.",
-                        ))
-                    }
-                }),
-                &Instr::PushTempFunctions(n) => (|| {
+                }
+                &Instr::PushTempFunctions { count: n, .. } => (|| {
                     for _ in 0..n {
                         let f = self.pop_function()?;
                         self.temp_function_stack.push(f);
                     }
                     Ok(())
                 })(),
-                &Instr::PopTempFunctions(n) => {
+                &Instr::PopTempFunctions { count: n, .. } => {
                     self.temp_function_stack
                         .truncate(self.temp_function_stack.len() - n);
                     Ok(())
@@ -565,15 +1526,52 @@ code:
                         })?;
                     let f_sig = f.signature();
                     if f_sig != sig {
-                        return Err(env.error(format!(
-                            "Function signature {f_sig} does not match \
+                        return Err(env
+                            .error(format!(
+                                "Function signature {f_sig} does not match \
                             placeholder signature {sig}"
-                        )));
+                            ))
+                            .with_kind(ErrorKind::Signature));
                     }
                     env.function_stack.push(f.clone());
                     Ok(())
                 }),
-                Instr::Dynamic(df) => df.f.clone()(self),
+                &Instr::GetGlobalFunction { index, sig, span } => {
+                    self.with_span(span, |env| match &env.globals.lock()[index] {
+                        Global::Func(f) if f.signature() == sig => {
+                            env.function_stack.push(f.clone());
+                            Ok(())
+                        }
+                        _ => Err(env.error("Function was called before it was bound")),
+                    })
+                }
+                Instr::Dynamic(df, _) => df.f.clone()(self),
+                Instr::Format { parts, span } => {
+                    let parts = parts.clone();
+                    self.with_span(*span, |env| {
+                        let mut formatted = String::new();
+                        for (i, part) in parts.iter().enumerate() {
+                            if i > 0 {
+                                let val = env.pop(format!("format argument {i}"))?;
+                                formatted.push_str(&val.to_string());
+                            }
+                            formatted.push_str(part);
+                        }
+                        env.push(formatted);
+                        Ok(())
+                    })
+                }
+                Instr::MatchFormatPattern { parts, span } => {
+                    let parts = parts.clone();
+                    self.with_span(*span, |env| {
+                        let value = env.pop(1)?;
+                        let fields = value.match_format_pattern(&parts, env)?;
+                        for field in fields.into_iter().rev() {
+                            env.push(field);
+                        }
+                        Ok(())
+                    })
+                }
                 &Instr::PushTemp { stack, count, span } => self.with_span(span, |env| {
                     for _ in 0..count {
                         let value = env.pop("value to save")?;
@@ -627,45 +1625,181 @@ code:
                 );
                 self.last_time = instant::now();
             }
+            if let Some((span, id, start)) = profile_start {
+                let time = instant::now() - start;
+                let profile = self.profile.as_mut().unwrap();
+                profile.spans.entry(span).or_default().add(time);
+                profile.functions.entry(id).or_default().add(time);
+            }
             if let Err(err) = res {
                 // Trace errors
-                let frame = self.scope.call.pop().unwrap();
-                return Err(self.trace_error(err, frame));
+                return Err(self.unwind(err));
             } else {
                 // Go to next instruction
-                self.scope.call.last_mut().unwrap().pc += 1;
-                if let Some(limit) = self.execution_limit {
-                    if instant::now() - self.execution_start > limit {
-                        return Err(UiuaError::Timeout(self.span()));
-                    }
+                if advance {
+                    self.scope.call.last_mut().unwrap().pc += 1;
+                }
+                if self.timed_out() {
+                    let error = UiuaError::Timeout(self.span());
+                    self.error_hooks(&error);
+                    return Err(error);
                 }
             }
         })
     }
     pub(crate) fn with_span<T>(&mut self, span: usize, f: impl FnOnce(&mut Self) -> T) -> T {
-        self.with_prim_span(span, None, f)
-    }
-    fn with_prim_span<T>(
-        &mut self,
-        span: usize,
-        prim: Option<Primitive>,
-        f: impl FnOnce(&mut Self) -> T,
-    ) -> T {
-        self.scope.call.last_mut().unwrap().spans.push((span, prim));
+        self.scope.call.last_mut().unwrap().spans.push(span);
         let res = f(self);
         self.scope.call.last_mut().unwrap().spans.pop();
         res
     }
+    /// Run a primitive, tracing errors that occur in the functions a modifier calls
+    fn run_prim(&mut self, prim: Primitive, span: usize) -> UiuaResult {
+        self.with_span(span, |env| prim.run(env)).map_err(|error| {
+            if prim.is_modifier() {
+                self.error_hooks(&error);
+                self.add_trace_frame(
+                    error,
+                    TraceFrame {
+                        id: FunctionId::Primitive(prim),
+                        span: self.get_span(span),
+                    },
+                )
+            } else {
+                error
+            }
+        })
+    }
+    /// Run a primitive and add to its execution statistics
+    fn run_prim_with_stats(&mut self, prim: Primitive, span: usize) -> UiuaResult {
+        let args = prim.args().unwrap_or(0) as usize;
+        let arg_start = self.stack.len().saturating_sub(args);
+        let arg_data: Vec<*const u8> = self.stack[arg_start..]
+            .iter()
+            .map(Value::data_ptr)
+            .collect();
+        let start = instant::now();
+        let res = self.run_prim(prim, span);
+        let time = instant::now() - start;
+        let bytes = (self.stack.iter().skip(arg_start))
+            .filter(|val| !arg_data.contains(&val.data_ptr()))
+            .map(Value::data_size)
+            .sum::<usize>();
+        let stats = self.stats.as_mut().unwrap().entry(prim).or_default();
+        stats.count += 1;
+        stats.time += time;
+        stats.bytes += bytes;
+        res
+    }
+    /// Run a dyadic math primitive on two scalar numbers in place
+    ///
+    /// This skips the general pervasive machinery, which dominates the cost
+    /// of scalar-heavy loops. Returns `false` if the primitive or its
+    /// arguments are not eligible, in which case nothing is changed.
+    fn scalar_math(&mut self, prim: Primitive) -> bool {
+        use crate::algorithm::pervade::*;
+        let f: fn(f64, f64) -> f64 = match prim {
+            Primitive::Add => add::num_num,
+            Primitive::Sub => sub::num_num,
+            Primitive::Mul => mul::num_num,
+            Primitive::Div => div::num_num,
+            Primitive::Mod => modulus::num_num,
+            Primitive::Pow => pow::num_num,
+            Primitive::Log => log::num_num,
+            Primitive::Min => min::num_num,
+            Primitive::Max => max::num_num,
+            Primitive::Atan => atan2::num_num,
+            Primitive::RoundTo => round_to::num_num,
+            _ => return false,
+        };
+        let height = self.stack.len();
+        if height < 2 || self.scope.array.iter().any(|&bottom| bottom > height - 2) {
+            return false;
+        }
+        let [Value::Num(b), Value::Num(a)] = &mut self.stack[height - 2..] else {
+            return false;
+        };
+        let Some(&a) = a.as_scalar() else {
+            return false;
+        };
+        let Some(b) = b.as_scalar_mut() else {
+            return false;
+        };
+        *b = f(a, *b);
+        self.stack.pop();
+        true
+    }
+    /// Enter a function called at the end of the current frame
+    fn enter_tail_frame(&mut self, function: Arc<Function>, call_span: usize) -> UiuaResult {
+        if self.scope.call.len() >= MAX_CALL_DEPTH {
+            return Err(self.spans.lock()[call_span]
+                .clone()
+                .sp("Call stack overflow".into())
+                .into());
+        }
+        for hooks in &self.hooks {
+            hooks.on_call(self, &function);
+        }
+        self.scope.call.push(StackFrame {
+            function,
+            call_span,
+            spans: Vec::new(),
+            pc: 0,
+            tail: true,
+        });
+        Ok(())
+    }
+    /// Restart a function instead of calling it again if a call to it is a tail call
+    ///
+    /// This is the case if the function calls itself at its end, or if it ends with
+    /// a switch whose branch calls the function at its end. Returns whether the
+    /// function was restarted.
+    fn restart_tail_recursion(&mut self, f: &Arc<Function>, call_span: usize) -> bool {
+        let len = self.scope.call.len();
+        let frame = &self.scope.call[len - 1];
+        if !Arc::ptr_eq(f, &frame.function) {
+            if !frame.tail || !Arc::ptr_eq(f, &self.scope.call[len - 2].function) {
+                return false;
+            }
+            let branch = self.scope.call.pop().unwrap();
+            for hooks in &self.hooks {
+                hooks.on_return(self, &branch.function);
+            }
+        }
+        for hooks in &self.hooks {
+            hooks.on_return(self, f);
+            hooks.on_call(self, f);
+        }
+        let frame = self.scope.call.last_mut().unwrap();
+        frame.call_span = call_span;
+        frame.pc = 0;
+        frame.spans.clear();
+        true
+    }
     fn call_with_span(&mut self, f: impl Into<Arc<Function>>, call_span: usize) -> UiuaResult {
+        if self.scope.call.len() >= MAX_CALL_DEPTH {
+            return Err(self.spans.lock()[call_span]
+                .clone()
+                .sp("Call stack overflow".into())
+                .into());
+        }
         let function = f.into();
         let sig = function.signature();
         let start_height = self.stack.len();
-        let function = self.exec(StackFrame {
+        let frame = StackFrame {
             function,
             call_span,
             spans: Vec::new(),
             pc: 0,
-        })?;
+            tail: false,
+        };
+        // Deep recursion through modifiers would otherwise overflow the native stack
+        // before reaching the call depth limit
+        #[cfg(not(target_arch = "wasm32"))]
+        let function =
+            stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || self.exec(frame))?;
+        #[cfg(target_arch = "wasm32")]
+        let function = self.exec(frame)?;
         let height_diff = self.stack.len() as isize - start_height as isize;
         let sig_diff = sig.outputs as isize - sig.args as isize;
         if height_diff != sig_diff
@@ -674,13 +1808,13 @@ code:
                 .iter()
                 .any(|instr| matches!(instr, Instr::Prim(Primitive::Sys(SysOp::Import), _)))
         {
-            return Err(self.spans.lock()[call_span]
-                .clone()
-                .sp(format!(
+            return Err(
+                UiuaError::from(self.spans.lock()[call_span].clone().sp(format!(
                     "Function modified the stack by {height_diff} values, but its \
-                    signature of {sig} implies a change of {sig_diff}"
-                ))
-                .into());
+                signature of {sig} implies a change of {sig_diff}"
+                )))
+                .with_kind(ErrorKind::Signature),
+            );
         }
         Ok(())
     }
@@ -690,6 +1824,39 @@ code:
         let call_span = self.span_index();
         self.call_with_span(f, call_span)
     }
+    /// Call a function on some arguments and return its outputs
+    ///
+    /// The arguments are pushed in order, so the last one is on top of the stack when the
+    /// function is called. The outputs are returned in the order they are on the stack.
+    /// The rest of the stack is not touched, so this is safe to use from inside a function
+    /// created with [`Uiua::create_function`] to call back into Uiua code.
+    pub fn call_with_args(
+        &mut self,
+        f: impl Into<Arc<Function>>,
+        args: impl IntoIterator<Item = Value>,
+    ) -> UiuaResult<Vec<Value>> {
+        let f = f.into();
+        let start_height = self.stack.len();
+        for arg in args {
+            self.push(arg);
+        }
+        let arg_count = self.stack.len() - start_height;
+        let sig = f.signature();
+        if arg_count != sig.args {
+            self.stack.truncate(start_height);
+            return Err(self
+                .error(format!(
+                    "Function with signature {sig} was called with {arg_count} argument{}",
+                    if arg_count == 1 { "" } else { "s" }
+                ))
+                .with_kind(ErrorKind::Signature));
+        }
+        if let Err(error) = self.call(f) {
+            self.stack.truncate(start_height);
+            return Err(error);
+        }
+        Ok(self.stack.split_off(start_height))
+    }
     /// Call a function and catch a `break`
     pub fn call_catch_break(&mut self, f: impl Into<Arc<Function>>) -> UiuaResult<bool> {
         match self.call(f) {
@@ -726,11 +1893,7 @@ code:
     }
     pub(crate) fn span_index(&self) -> usize {
         self.scope.call.last().map_or(0, |frame| {
-            frame
-                .spans
-                .last()
-                .map(|(i, _)| *i)
-                .unwrap_or(frame.call_span)
+            frame.spans.last().copied().unwrap_or(frame.call_span)
         })
     }
     /// Get the span of the current function call
@@ -752,6 +1915,42 @@ code:
     pub fn error(&self, message: impl ToString) -> UiuaError {
         UiuaError::Run(self.span().clone().sp(message.to_string()))
     }
+    /// Check that the runtime has a capability
+    ///
+    /// `action` names what needs the capability in the error message.
+    pub(crate) fn require_capability(&self, capability: Capability, action: &str) -> UiuaResult {
+        if self.capabilities.contains(&capability) {
+            Ok(())
+        } else {
+            Err(self.error(format!(
+                "{action} is not allowed because {capability} access is disabled"
+            )))
+        }
+    }
+    /// Check whether the execution limit has been reached
+    pub(crate) fn timed_out(&self) -> bool {
+        (self.execution_limit).is_some_and(|limit| instant::now() - self.execution_start > limit)
+    }
+    /// Get the time at which the execution limit will be reached, if there is one
+    pub(crate) fn deadline(&self) -> Option<instant::Instant> {
+        let limit = self.execution_limit?;
+        let left = (limit - (instant::now() - self.execution_start)).max(0.0);
+        Some(instant::Instant::now() + Duration::from_secs_f64(left / 1000.0))
+    }
+    /// Check that creating an array of `elements` elements of `element_size` bytes each
+    /// would not exceed the memory limit
+    pub(crate) fn validate_alloc(&self, elements: usize, element_size: usize) -> UiuaResult {
+        let Some(limit) = self.memory_limit else {
+            return Ok(());
+        };
+        if elements.saturating_mul(element_size) > limit {
+            return Err(self.error(format!(
+                "Creating an array of {elements} elements would exceed \
+                the memory limit of {limit} bytes"
+            )));
+        }
+        Ok(())
+    }
     /// Construct and add a diagnostic with the current span
     pub fn diagnostic(&mut self, message: impl Into<String>, kind: DiagnosticKind) {
         self.diagnostic_with_span(message, kind, self.span());
@@ -833,6 +2032,9 @@ code:
     /// Push a value onto the stack
     pub fn push(&mut self, val: impl Into<Value>) {
         self.stack.push(val.into());
+        for hooks in &self.hooks {
+            hooks.on_push(self, self.stack.last().unwrap());
+        }
     }
     /// Push a function onto the function stack
     pub fn push_func(&mut self, f: impl Into<Arc<Function>>) {
@@ -847,11 +2049,14 @@ code:
         let signature = signature.into();
         Function::new(
             FunctionId::Unnamed,
-            vec![Instr::Dynamic(DynamicFunction {
-                id: SmallRng::seed_from_u64(instant::now().to_bits()).gen(),
-                f: Arc::new(f),
-                signature,
-            })],
+            vec![Instr::Dynamic(
+                DynamicFunction {
+                    id: SmallRng::seed_from_u64(instant::now().to_bits()).gen(),
+                    f: Arc::new(f),
+                    signature,
+                },
+                0,
+            )],
             signature,
         )
     }
@@ -879,6 +2084,27 @@ code:
         let function = self.create_function(signature, f);
         self.bind_function(name, function)
     }
+    /// Register a Rust function that code loaded afterwards can call by name
+    ///
+    /// Unlike [`Uiua::create_bind_function`], this does not bind the function in the current scope.
+    /// It can be called from any scope, including imported modules,
+    /// unless a binding with the same name shadows it.
+    ///
+    /// # Errors
+    /// Returns an error if the name is not valid
+    pub fn register_fn(
+        &mut self,
+        name: impl Into<Ident>,
+        signature: impl Into<Signature>,
+        f: impl Fn(&mut Uiua) -> UiuaResult + Send + Sync + 'static,
+    ) -> UiuaResult {
+        let name = name.into();
+        let mut function = self.create_function(signature, f);
+        function.id = FunctionId::Named(name.clone());
+        self.validate_binding_name(&name, &function.instrs, Span::Builtin)?;
+        self.host_functions.lock().insert(name, function.into());
+        Ok(())
+    }
     /// Take the entire stack
     pub fn take_stack(&mut self) -> Vec<Value> {
         take(&mut self.stack)
@@ -902,6 +2128,95 @@ code:
                 )
             })
     }
+    /// Get the values on the stack, with the top of the stack last
+    pub fn stack(&self) -> &[Value] {
+        &self.stack
+    }
+    /// Get the functions currently being executed, along with the spans they were called from
+    ///
+    /// The innermost function is first.
+    pub fn call_stack(&self) -> Vec<TraceFrame> {
+        let spans = self.spans.lock();
+        self.scope
+            .call
+            .iter()
+            .rev()
+            .map(|frame| TraceFrame {
+                id: frame.function.id.clone(),
+                span: spans[frame.call_span].clone(),
+            })
+            .collect()
+    }
+    /// Get the number of functions currently being executed
+    ///
+    /// This is the length of [`Uiua::call_stack`], without building it.
+    pub fn call_depth(&self) -> usize {
+        self.scope.call.len()
+    }
+    /// Get the execution statistics collected so far, if profiling is enabled
+    ///
+    /// Profiling is enabled with [`Uiua::with_profiling`].
+    pub fn profile(&self) -> Option<Profile> {
+        let data = self.profile.as_ref()?;
+        let spans = self.spans.lock();
+        let mut profile = Profile {
+            spans: data
+                .spans
+                .iter()
+                .map(|(&span, &stats)| (spans[span].clone(), stats))
+                .collect(),
+            functions: data
+                .functions
+                .iter()
+                .map(|(id, &stats)| (id.clone(), stats))
+                .collect(),
+        };
+        profile
+            .spans
+            .sort_by(|(_, a), (_, b)| b.time.total_cmp(&a.time));
+        profile
+            .functions
+            .sort_by(|(_, a), (_, b)| b.time.total_cmp(&a.time));
+        Some(profile)
+    }
+    /// Get the per-primitive execution statistics collected so far, if enabled
+    ///
+    /// Statistics are enabled with [`Uiua::with_stats`].
+    pub fn stats(&self) -> Option<Stats> {
+        let mut primitives: Vec<_> = (self.stats.as_ref()?)
+            .iter()
+            .map(|(&prim, &stats)| (prim, stats))
+            .collect();
+        primitives.sort_by(|(_, a), (_, b)| b.time.total_cmp(&a.time));
+        Some(Stats { primitives })
+    }
+    /// Get the function bound to a name in the current scope
+    pub fn bound_function(&self, name: &str) -> Option<Arc<Function>> {
+        let idx = self.scope.names.get(name)?;
+        match &self.globals.lock()[*idx] {
+            Global::Func(f) => Some(f.clone()),
+            Global::Val(_) => None,
+        }
+    }
+    /// List a function's instructions, along with those of all the functions it pushes
+    ///
+    /// # Example
+    /// ```
+    /// # use uiua::*;
+    /// let mut env = Uiua::with_native_sys();
+    /// env.load_str("F ← +1").unwrap();
+    /// let f = env.bound_function("F").unwrap();
+    /// let prims: Vec<String> = env
+    ///     .disassemble(&f)
+    ///     .into_iter()
+    ///     .map(|instr| instr.instr.to_string())
+    ///     .collect();
+    /// assert_eq!(prims, ["1", "+"]);
+    /// ```
+    pub fn disassemble(&self, f: &Function) -> Disassembly {
+        let spans = self.spans.lock();
+        Disassembly::new(f, |i| spans[i].clone())
+    }
     /// Get the values for all bindings in the current scope
     pub fn all_values_is_scope(&self) -> HashMap<Ident, Value> {
         let mut bindings = HashMap::new();
@@ -960,10 +2275,10 @@ code:
         self.push(a);
         Ok(())
     }
-    pub(crate) fn dyadic_rr<V: Into<Value>>(&mut self, f: fn(&Value, &Value) -> V) -> UiuaResult {
-        let a = self.pop(1)?;
-        let b = self.pop(2)?;
-        self.push(f(&a, &b));
+    pub(crate) fn monadic_mut_env(&mut self, f: fn(&mut Value, &Self)) -> UiuaResult {
+        let mut a = self.pop(1)?;
+        f(&mut a, self);
+        self.push(a);
         Ok(())
     }
     pub(crate) fn dyadic_oo_env<V: Into<Value>>(
@@ -1016,6 +2331,10 @@ code:
     pub(crate) fn complex_fill(&self) -> Option<crate::Complex> {
         self.scope.fills.complexes.last().copied()
     }
+    #[cfg(feature = "rational")]
+    pub(crate) fn rational_fill(&self) -> Option<crate::Rational> {
+        self.scope.fills.rationals.last().copied()
+    }
     /// Do something with the fill context set
     pub(crate) fn with_fill(
         &mut self,
@@ -1044,6 +2363,13 @@ code:
                     set = true;
                 }
             }
+            #[cfg(feature = "rational")]
+            Value::Rational(r) => {
+                if let Some(&r) = r.as_scalar() {
+                    self.scope.fills.rationals.push(r);
+                    set = true;
+                }
+            }
             Value::Char(c) => {
                 if let Some(&c) = c.as_scalar() {
                     self.scope.fills.chars.push(c);
@@ -1076,6 +2402,10 @@ code:
             Value::Complex(_) => {
                 self.scope.fills.complexes.pop();
             }
+            #[cfg(feature = "rational")]
+            Value::Rational(_) => {
+                self.scope.fills.rationals.pop();
+            }
             Value::Char(_) => {
                 self.scope.fills.chars.pop();
             }
@@ -1094,55 +2424,125 @@ code:
     pub(crate) fn pack_boxes(&self) -> bool {
         self.scope.pack_depth > 0
     }
-    /// Spawn a thread
-    pub(crate) fn spawn(
-        &mut self,
-        capture_count: usize,
-        f: impl FnOnce(&mut Self) -> UiuaResult + Send + 'static,
-    ) -> UiuaResult {
-        if self.stack.len() < capture_count {
-            return Err(self.error(format!(
-                "Expected at least {} value(s) on the stack, but there are {}",
-                capture_count,
-                self.stack.len()
-            )))?;
-        }
-        let (this_send, child_recv) = crossbeam_channel::unbounded();
-        let (child_send, this_recv) = crossbeam_channel::unbounded();
-        let thread = ThisThread {
-            parent: Some(Channel {
-                send: child_send,
-                recv: child_recv,
-            }),
-            ..ThisThread::default()
-        };
-        let mut env = Uiua {
+    /// Create a runtime for another thread that shares this one's globals and settings
+    fn child(&self, stack: Vec<Value>, thread: ThisThread) -> Self {
+        Uiua {
             new_functions: Vec::new(),
+            current_bindings: Vec::new(),
+            later_bindings: Vec::new(),
+            local_frames: Vec::new(),
             globals: self.globals.clone(),
             spans: self.spans.clone(),
-            stack: self
-                .stack
-                .drain(self.stack.len() - capture_count..)
-                .collect(),
+            stack,
             function_stack: Vec::new(),
             temp_stacks: [Vec::new(), Vec::new()],
             temp_function_stack: Vec::new(),
             scope: self.scope.clone(),
             higher_scopes: self.higher_scopes.last().cloned().into_iter().collect(),
             mode: self.mode,
+            opt_level: self.opt_level,
             current_imports: self.current_imports.clone(),
             imports: self.imports.clone(),
+            host_functions: self.host_functions.clone(),
+            open_handles: self.open_handles.clone(),
+            reloaded: Reloaded::default(),
             diagnostics: BTreeSet::new(),
             print_diagnostics: self.print_diagnostics,
             time_instrs: self.time_instrs,
+            debugger: self.debugger.clone(),
+            hooks: self.hooks.clone(),
+            profile: None,
+            stats: None,
             last_time: self.last_time,
             cli_arguments: self.cli_arguments.clone(),
             cli_file_path: self.cli_file_path.clone(),
             backend: self.backend.clone(),
             execution_limit: self.execution_limit,
+            fuel: self.fuel.clone(),
+            interrupted: self.interrupted.clone(),
+            rng: self.rng.clone(),
+            memory_limit: self.memory_limit,
+            tolerance: self.tolerance,
+            capabilities: self.capabilities.clone(),
             execution_start: self.execution_start,
             thread,
+        }
+    }
+    /// Check if a function can be called on `count` rows or elements in parallel
+    ///
+    /// This is only done for pure functions, and only when nothing is observing execution
+    /// one instruction at a time.
+    pub(crate) fn can_run_in_parallel(&self, f: &Function, count: usize) -> bool {
+        !cfg!(target_arch = "wasm32")
+            && count >= PARALLEL_THRESHOLD
+            && self.fuel.is_none()
+            && self.debugger.is_none()
+            && self.hooks.is_empty()
+            && self.profile.is_none()
+            && self.stats.is_none()
+            && !self.time_instrs
+            && f.is_pure()
+    }
+    /// Call a function on each of some argument lists in parallel
+    ///
+    /// The outputs for each argument list are returned in the order they are popped.
+    pub(crate) fn par_call(
+        &self,
+        f: &Arc<Function>,
+        args: Vec<Vec<Value>>,
+    ) -> UiuaResult<Vec<Vec<Value>>> {
+        let outputs = f.signature().outputs;
+        let chunk_size = args.len().div_ceil(rayon::current_num_threads()).max(1);
+        let mut chunks = Vec::new();
+        let mut args = args.into_iter().peekable();
+        while args.peek().is_some() {
+            let chunk: Vec<_> = args.by_ref().take(chunk_size).collect();
+            chunks.push((self.child(Vec::new(), ThisThread::default()), chunk));
+        }
+        let results = (chunks.into_par_iter())
+            .map(|(mut env, chunk)| {
+                let mut results = Vec::with_capacity(chunk.len());
+                for args in chunk {
+                    for arg in args.into_iter().rev() {
+                        env.push(arg);
+                    }
+                    env.call(f.clone())?;
+                    let outputs = (0..outputs)
+                        .map(|_| env.pop("function result"))
+                        .collect::<UiuaResult<_>>()?;
+                    results.push(outputs);
+                }
+                Ok(results)
+            })
+            .collect::<UiuaResult<Vec<_>>>()?;
+        Ok(results.into_iter().flatten().collect())
+    }
+    /// Spawn a thread
+    pub(crate) fn spawn(
+        &mut self,
+        capture_count: usize,
+        f: impl FnOnce(&mut Self) -> UiuaResult + Send + 'static,
+    ) -> UiuaResult {
+        if self.stack.len() < capture_count {
+            return Err(self.error(format!(
+                "Expected at least {} value(s) on the stack, but there are {}",
+                capture_count,
+                self.stack.len()
+            )))?;
+        }
+        let (this_send, child_recv) = crossbeam_channel::unbounded();
+        let (child_send, this_recv) = crossbeam_channel::unbounded();
+        let thread = ThisThread {
+            parent: Some(Channel {
+                send: child_send,
+                recv: child_recv,
+            }),
+            ..ThisThread::default()
         };
+        let stack = (self.stack)
+            .drain(self.stack.len() - capture_count..)
+            .collect();
+        let mut env = self.child(stack, thread);
         #[cfg(not(target_arch = "wasm32"))]
         let handle = std::thread::Builder::new()
             .spawn(move || {
@@ -1171,51 +2571,33 @@ code:
         self.push(id);
         Ok(())
     }
+    /// Wait for a thread to finish and get its final stack
+    ///
+    /// If the thread failed, its error is returned.
+    fn join_thread(&mut self, handle: usize) -> UiuaResult<Vec<Value>> {
+        let thread = (self.thread.children.remove(&handle))
+            .ok_or_else(|| self.error("Invalid thread id"))?;
+        #[cfg(not(target_arch = "wasm32"))]
+        return Arc::into_inner(thread.handle)
+            .ok_or_else(|| self.error("Cannot wait on thread spawned in cloned environment"))?
+            .join()
+            .unwrap();
+        #[cfg(target_arch = "wasm32")]
+        thread.result
+    }
     /// Wait for a thread to finish
     pub(crate) fn wait(&mut self, id: Value) -> UiuaResult {
         let ids = id.as_natural_array(self, "Thread id must be an array of natural numbers")?;
         if ids.shape.is_empty() {
             let handle = ids.data.into_iter().next().unwrap();
-            #[cfg(not(target_arch = "wasm32"))]
-            let thread_stack = Arc::into_inner(
-                self.thread
-                    .children
-                    .remove(&handle)
-                    .ok_or_else(|| self.error("Invalid thread id"))?
-                    .handle,
-            )
-            .ok_or_else(|| self.error("Cannot wait on thread spawned in cloned environment"))?
-            .join()
-            .unwrap()?;
-            #[cfg(target_arch = "wasm32")]
-            let thread_stack = self
-                .thread
-                .children
-                .remove(&handle)
-                .ok_or_else(|| self.error("Invalid thread id"))?
-                .result?;
-            self.stack.extend(thread_stack);
+            let thread_stack = self.join_thread(handle)?;
+            for val in thread_stack {
+                self.push(val);
+            }
         } else {
             let mut rows = Vec::new();
             for handle in ids.data {
-                #[cfg(not(target_arch = "wasm32"))]
-                let thread_stack = Arc::into_inner(
-                    self.thread
-                        .children
-                        .remove(&handle)
-                        .ok_or_else(|| self.error("Invalid thread id"))?
-                        .handle,
-                )
-                .ok_or_else(|| self.error("Cannot wait on thread spawned in cloned environment"))?
-                .join()
-                .unwrap()?;
-                #[cfg(target_arch = "wasm32")]
-                let thread_stack = self
-                    .thread
-                    .children
-                    .remove(&handle)
-                    .ok_or_else(|| self.error("Invalid thread id"))?
-                    .result?;
+                let thread_stack = self.join_thread(handle)?;
                 let row = if thread_stack.len() == 1 {
                     thread_stack.into_iter().next().unwrap()
                 } else {
@@ -1336,3 +2718,83 @@ where
         self().arg_name()
     }
 }
+
+/// Resolve an import path relative to the file that imports it
+fn resolve_path(importer: Option<&Path>, path: &Path) -> PathBuf {
+    let target = if let Some(parent) = importer.and_then(|p| p.parent()) {
+        parent.join(path)
+    } else {
+        path.to_path_buf()
+    };
+    let base = Path::new(".");
+    if let (Ok(canon_target), Ok(canon_base)) = (target.canonicalize(), base.canonicalize()) {
+        pathdiff::diff_paths(canon_target, canon_base).unwrap_or(target)
+    } else {
+        pathdiff::diff_paths(&target, base).unwrap_or(target)
+    }
+}
+
+/// Collect the paths of the imports in some items that are string literals
+fn literal_imports(items: &[Item], imports: &mut Vec<Sp<String>>) {
+    for item in items {
+        match item {
+            Item::Import(import) => imports.push(import.path.clone()),
+            Item::TestScope(items) => literal_imports(&items.value, imports),
+            Item::Words(words) => literal_sys_imports(words, imports),
+            Item::Binding(binding) => literal_sys_imports(&binding.words, imports),
+            Item::ExtraNewlines(_) => {}
+        }
+    }
+}
+
+/// Collect the paths of the `&i` calls in some words that are string literals
+fn literal_sys_imports(words: &[Sp<Word>], imports: &mut Vec<Sp<String>>) {
+    let mut words =
+        (words.iter()).filter(|word| !matches!(word.value, Word::Spaces | Word::Comment(_)));
+    while let Some(word) = words.next() {
+        if let Word::Primitive(Primitive::Sys(SysOp::Import)) = word.value {
+            if let Some(Sp {
+                value: Word::String(path),
+                span,
+            }) = words.clone().next()
+            {
+                imports.push(span.clone().sp(path.clone()));
+            }
+        }
+    }
+}
+
+/// Collect the names that some words refer to
+fn word_idents(words: &[Sp<Word>], idents: &mut HashSet<Ident>) {
+    for word in words {
+        match &word.value {
+            Word::Ident(ident) => {
+                idents.insert(ident.clone());
+            }
+            Word::Strand(items) => word_idents(items, idents),
+            Word::Array(arr) => (arr.lines.iter()).for_each(|line| word_idents(line, idents)),
+            Word::Func(func) => (func.lines.iter()).for_each(|line| word_idents(line, idents)),
+            Word::Switch(sw) => (sw.branches.iter())
+                .flat_map(|branch| &branch.value.lines)
+                .for_each(|line| word_idents(line, idents)),
+            Word::Modified(modified) => {
+                if let Modifier::Ident(ident) = &modified.modifier.value {
+                    idents.insert(ident.clone());
+                }
+                word_idents(&modified.operands, idents);
+            }
+            Word::Binding(binding) => word_idents(&binding.words, idents),
+            _ => {}
+        }
+    }
+}
+
+/// Change the span indices of a function's instructions, including those of the functions it pushes
+fn remap_function_spans(f: &mut Function, remap: &mut impl FnMut(&mut usize)) {
+    for instr in &mut f.instrs {
+        remap(instr.span_mut());
+        if let Instr::PushFunc(f, _) = instr {
+            remap_function_spans(Arc::make_mut(f), remap);
+        }
+    }
+}
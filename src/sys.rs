@@ -1,18 +1,35 @@
 use std::{
     any::Any,
     collections::{HashMap, HashSet},
+    f64::consts::TAU,
+    fmt,
     io::{stderr, stdin, Cursor, Read, Write},
+    mem::size_of,
     path::Path,
     sync::OnceLock,
     time::Duration,
 };
 
+use base64::prelude::*;
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc,
+};
 use ecow::EcoVec;
 use enum_iterator::{all, Sequence};
+use hmac::{
+    digest::{core_api::BlockSizeUser, Digest},
+    Mac, SimpleHmac,
+};
 use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
 use image::{DynamicImage, ImageOutputFormat};
+use md5::Md5;
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
+use percent_encoding::{percent_decode_str, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rand::prelude::*;
+use sha1::Sha1;
+use sha2::{Sha224, Sha256, Sha384, Sha512};
 use tinyvec::tiny_vec;
 
 use crate::{
@@ -20,9 +37,9 @@ use crate::{
     boxed::Boxed,
     cowslice::{cowslice, CowSlice},
     function::Signature,
-    primitive::PrimDoc,
+    primitive::{cached_regex, PrimDoc},
     value::Value,
-    Uiua, UiuaError, UiuaResult,
+    ErrorKind, Uiua, UiuaError, UiuaResult,
 };
 
 /// Access the built-in `example.ua` file
@@ -132,6 +149,7 @@ pub enum SysOpClass {
     Images,
     Gifs,
     Tcp,
+    Udp,
     Misc,
 }
 
@@ -142,6 +160,40 @@ impl SysOpClass {
     }
 }
 
+/// Kinds of access to the outside world that system functions need
+///
+/// System functions that need a capability the runtime does not have fail with an error.
+/// See [`Uiua::with_capabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Sequence)]
+pub enum Capability {
+    /// Reading and writing files and importing modules
+    Filesystem,
+    /// Making network connections
+    Network,
+    /// Running commands and opening files in other programs
+    Process,
+    /// Playing audio
+    Audio,
+}
+
+impl Capability {
+    /// All capabilities
+    pub fn all() -> impl Iterator<Item = Self> {
+        all()
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Capability::Filesystem => write!(f, "filesystem"),
+            Capability::Network => write!(f, "network"),
+            Capability::Process => write!(f, "process"),
+            Capability::Audio => write!(f, "audio"),
+        }
+    }
+}
+
 sys_op! {
     /// Print a nicely formatted representation of a value to stdout
     (1(0), Show, StdIO, "&s", "show"),
@@ -193,6 +245,108 @@ sys_op! {
     /// On the web, this example will hang for 1 second.
     /// ex: ⚂ &sl 1
     (1(0), Sleep, Misc, "&sl", "sleep"),
+    /// Seed the random number generator
+    ///
+    /// After seeding, [random] produces the same sequence of numbers every time the program is run.
+    /// ex: [⍥⚂3] &seed 0
+    (1(0), Seed, Misc, "&seed", "seed random"),
+    /// Set how much numbers may differ and still be considered equal
+    ///
+    /// This affects [equals], [not equals], [match], [classify], and [deduplicate].
+    /// The default tolerance is `0`, so numbers must be exactly equal.
+    /// ex: = 0.3 +0.1 0.2
+    /// ex: = 0.3 +0.1 0.2 &tol 1e-9
+    (1(0), Tolerance, Misc, "&tol", "set comparison tolerance"),
+    /// Check if a regex pattern matches anywhere in a string
+    ///
+    /// Expects a pattern and a string.
+    /// ex: &rem "h[io]" "hihaho"
+    /// ex: &rem "\\d+" "no numbers here"
+    (2, RegexMatch, Misc, "&rem", "regex match"),
+    /// Find all the places a regex pattern matches in a string
+    ///
+    /// Expects a pattern and a string.
+    /// Returns a rank `2` array with a row for each match. Each row is the start index and length of the match in characters.
+    /// ex: &ref "[a-z]+" "hello world"
+    /// For the matched strings themselves, use [regex].
+    (2, RegexFind, Misc, "&ref", "regex find"),
+    /// Replace all matches of a regex pattern in a string
+    ///
+    /// Expects a pattern, a replacement, and a string.
+    /// The replacement may refer to capture groups with `$1`, `$2`, etc.
+    /// ex: &rer "[aeiou]" "_" "hello world"
+    /// ex: &rer "(\\w+) (\\w+)" "$2 $1" "hello world"
+    (3, RegexReplace, Misc, "&rer", "regex replace"),
+    /// Encode bytes as base64 text
+    ///
+    /// Expects a list of bytes or a string. Strings are encoded as UTF-8.
+    /// ex: &benc "hello!"
+    /// ex: &benc [0 1 2 254 255]
+    (1, Base64Encode, Misc, "&benc", "base64 encode"),
+    /// Decode base64 text into bytes
+    ///
+    /// ex: &bdec "aGVsbG8h"
+    /// ex: ⍘utf &bdec "aGVsbG8h"
+    (1, Base64Decode, Misc, "&bdec", "base64 decode"),
+    /// Encode bytes as hexadecimal text
+    ///
+    /// Expects a list of bytes or a string. Strings are encoded as UTF-8.
+    /// ex: &henc [0 1 2 254 255]
+    /// ex: &henc "hello!"
+    (1, HexEncode, Misc, "&henc", "hex encode"),
+    /// Decode hexadecimal text into bytes
+    ///
+    /// Both uppercase and lowercase digits are accepted.
+    /// ex: &hdec "0001fEFF"
+    (1, HexDecode, Misc, "&hdec", "hex decode"),
+    /// Percent-encode bytes for use in a URL
+    ///
+    /// Expects a list of bytes or a string. Strings are encoded as UTF-8.
+    /// Every byte except ASCII letters, digits, and `-._~` is encoded.
+    /// ex: &uenc "a b&c=d/é"
+    (1, UrlEncode, Misc, "&uenc", "url encode"),
+    /// Decode percent-encoded text into bytes
+    ///
+    /// ex: &udec "a%20b%26c"
+    /// ex: ⍘utf &udec "a%20b%26c"
+    (1, UrlDecode, Misc, "&udec", "url decode"),
+    /// Hash bytes with a cryptographic hash function
+    ///
+    /// Expects the name of an algorithm and a list of bytes or a string. Strings are hashed as UTF-8.
+    /// Numbers that are not integers from 0 to 255 are not bytes, so they cannot be hashed.
+    /// The algorithm must be one of `"md5"`, `"sha1"`, `"sha224"`, `"sha256"`, `"sha384"`, or `"sha512"`.
+    /// Returns the hash as a list of bytes.
+    /// ex: &hash "sha256" "hello!"
+    /// Use [&henc] to show the hash as hex.
+    /// ex: &henc &hash "md5" "hello!"
+    (2, Hash, Misc, "&hash", "hash"),
+    /// Compute an HMAC of bytes with a key
+    ///
+    /// Expects the name of a hash algorithm, a key, and a message.
+    /// The key and message may each be a list of bytes or a string.
+    /// The algorithms are the same as for [&hash].
+    /// ex: &henc &hmac "sha256" "key" "message"
+    (3, Hmac, Misc, "&hmac", "hmac"),
+    /// Format a time as a string
+    ///
+    /// Expects a pattern and a time in seconds since the Unix epoch, like the ones from [now].
+    /// Times are formatted in UTC.
+    /// The pattern uses [strftime](https://docs.rs/chrono/latest/chrono/format/strftime/index.html) syntax.
+    /// ex: &dtf "%Y-%m-%d %H:%M:%S" 1700000000
+    /// ex: &dtf "%A, %B %e" 1700000000
+    /// An array of times gives an array of [box]ed strings.
+    /// ex: &dtf "%Y" [0 1e9 2e9]
+    (2, DateTimeFormat, Misc, "&dtf", "format datetime"),
+    /// Parse an ISO 8601 date and time
+    ///
+    /// Returns the time in seconds since the Unix epoch.
+    /// Times without a UTC offset are taken to be in UTC. The time of day may be left out.
+    /// ex: &dtp "2023-11-14T22:13:20Z"
+    /// ex: &dtp "2023-11-14T23:13:20+01:00"
+    /// ex: &dtp "2023-11-14"
+    /// An array of [box]ed strings gives an array of times.
+    /// ex: &dtp {"1970-01-01" "2001-09-09T01:46:40.5"}
+    (1, DateTimeParse, Misc, "&dtp", "parse datetime"),
     /// Read at most n bytes from a stream
     ///
     /// Expects a count and a stream handle.
@@ -203,6 +357,15 @@ sys_op! {
     /// Expects a count and a stream handle.
     /// The stream handle `0` is stdin.
     (2, ReadBytes, Stream, "&rb", "read to bytes"),
+    /// Read a stream in chunks of bytes, folding each one into an accumulator
+    ///
+    /// Expects a chunk size, a stream handle, and an initial accumulator.
+    /// The function is called with each chunk on top of the current accumulator and must return the new accumulator.
+    /// The last chunk may be shorter than the chunk size. Reading stops at the end of the stream.
+    /// Because only one chunk is in memory at a time, this can aggregate data larger than memory.
+    /// The stream handle `0` is stdin.
+    /// ex! &rbf(+/+) 65536 &fo "example.txt" 0
+    (3[1], ReadBytesFold, Stream, "&rbf", "read bytes and fold"),
     /// Read from a stream until a delimiter is reached
     ///
     /// Expects a delimiter and a stream handle.
@@ -317,6 +480,59 @@ sys_op! {
     ///
     /// See also: [&ime]
     (1(0), ImShow, Images, "&ims", "image - show"),
+    /// Convert an image to grayscale
+    ///
+    /// The image must conform to the format described in [&ime].
+    /// Colors are weighted according to their perceived brightness.
+    /// An alpha channel is kept.
+    ///
+    /// ex: &imgr [[[1 0 0] [0 1 0]] [[0 0 1] [1 1 1]]]
+    ///
+    /// See also: [&imhsv]
+    (1, ImGrayscale, Images, "&imgr", "image - grayscale"),
+    /// Convert RGB colors to HSV colors
+    ///
+    /// The last axis of the array must be length 3 or 4.
+    /// Hue, saturation, and value are all between 0 and 1.
+    /// An alpha channel is kept.
+    ///
+    /// ex: &imhsv [1 0.5 0]
+    /// ex: &imhsv [[0 0 1] [0.5 0.5 0.5]]
+    ///
+    /// See also: [&imrgb]
+    (1, ImRgbToHsv, Images, "&imhsv", "image - rgb to hsv"),
+    /// Convert HSV colors to RGB colors
+    ///
+    /// The last axis of the array must be length 3 or 4.
+    /// Hue, saturation, and value are all between 0 and 1.
+    /// An alpha channel is kept.
+    ///
+    /// ex: &imrgb [0.5 1 1]
+    /// ex: &imrgb &imhsv [0.2 0.4 0.6]
+    ///
+    /// See also: [&imhsv]
+    (1, ImHsvToRgb, Images, "&imrgb", "image - hsv to rgb"),
+    /// Resize an image
+    ///
+    /// The first argument is the resampling method, either `"nearest"` or `"bilinear"`.
+    /// The second argument is the new height and width.
+    /// The third argument is the image, which must conform to the format described in [&ime].
+    ///
+    /// ex: &imrs "nearest" [4 4] [[0 1] [1 0]]
+    /// ex: &imrs "bilinear" [3 3] [[0 1] [1 0]]
+    (3, ImResize, Images, "&imrs", "image - resize"),
+    /// Composite an image over another
+    ///
+    /// The first argument is the top image, and the second is the bottom image.
+    /// Both images must conform to the format described in [&ime] and have the same height and width.
+    ///
+    /// The alpha channel of the top image determines how much of the bottom image shows through.
+    /// If either image is RGB, the result is RGB.
+    /// The result only has an alpha channel if the bottom image does.
+    ///
+    /// ex: &imov [[[1 0 0 0.5]]] [[[0 0 1]]]
+    /// ex: &imov [[[1 0.5] [0 1]]] [[[0.5 0.5] [1 0]]]
+    (2, ImOverlay, Images, "&imov", "image - overlay"),
     /// Decode a gif from a byte array
     ///
     /// Returns a framerate in seconds and a rank 4 array of RGBA frames.
@@ -381,6 +597,39 @@ sys_op! {
     /// Pass that to a periodic function, and you get a nice tone!
     /// ex: ÷4○×τ×220 ÷∶⇡×, 4 &asr
     (0, AudioSampleRate, Audio, "&asr", "audio - sample rate"),
+    /// Generate a waveform
+    ///
+    /// The first argument is the waveform, which is one of `"sine"`, `"square"`, `"saw"`, `"triangle"`, or `"noise"`.
+    /// The second argument is the frequency in Hz.
+    /// The third argument is the duration in seconds.
+    ///
+    /// Returns a list of samples between -1 and 1 at the rate [&asr].
+    /// ex: ⧻ &aosc "sine" 440 1
+    /// ex: ÷2 &aosc "square" 220 2
+    ///
+    /// See also: [&afl] [&aenv]
+    (3, AudioOscillator, Audio, "&aosc", "audio - oscillator"),
+    /// Filter audio with a biquad filter
+    ///
+    /// The first argument is the filter type, which is one of `"lowpass"`, `"highpass"`, `"bandpass"`, or `"notch"`.
+    /// The second argument is a list of the cutoff frequency in Hz and the Q factor.
+    /// The third argument is the audio samples, which must conform to the format described in [&ae].
+    ///
+    /// ex: &afl "lowpass" [800 0.7] &aosc "saw" 220 2
+    ///
+    /// See also: [&aosc]
+    (3, AudioFilter, Audio, "&afl", "audio - filter"),
+    /// Apply an ADSR envelope to audio
+    ///
+    /// The first argument is a list of the attack time, decay time, sustain level, and release time.
+    /// Times are in seconds.
+    /// The second argument is the audio samples, which must conform to the format described in [&ae].
+    ///
+    /// The release happens at the end of the samples.
+    /// ex: &aenv [0.01 0.1 0.6 0.3] &aosc "triangle" 330 1
+    ///
+    /// See also: [&aosc]
+    (2, AudioEnvelope, Audio, "&aenv", "audio - envelope"),
     /// Synthesize and stream audio
     ///
     /// Expects a function that takes a list of sample times and returns a list of samples.
@@ -401,16 +650,26 @@ sys_op! {
     /// How long the audio is can be configure in the editor settings.
     (0(0)[1], AudioStream, Audio, "&ast", "audio - stream"),
     /// Create a TCP listener and bind it to an address
+    ///
+    /// Returns a listener handle, which can be passed to [&tcpa].
+    /// [under][&tcpl] calls [&cl] automatically.
     (1, TcpListen, Tcp, "&tcpl", "tcp - listen"),
     /// Accept a connection with a TCP listener
     ///
     /// Returns a stream handle
-    /// [under][&tcpl] calls [&cl] automatically.
+    /// The stream can be read from with [&rs], [&rb], or [&ru].
+    /// The stream can be written to with [&w].
+    /// [under][&tcpa] calls [&cl] automatically.
+    /// A simple server that greets one client:
+    /// ex: ⍜&tcpl(⍜&tcpa(&w "Hello!\n")) "0.0.0.0:8080"
     (1, TcpAccept, Tcp, "&tcpa", "tcp - accept"),
     /// Create a TCP socket and connect it to an address
     ///
     /// Returns a stream handle
+    /// The stream can be read from with [&rs], [&rb], or [&ru].
+    /// The stream can be written to with [&w].
     /// [under][&tcpc] calls [&cl] automatically.
+    /// ex: ⍜&tcpc(&ru "\n") "localhost:8080"
     (1, TcpConnect, Tcp, "&tcpc", "tcp - connect"),
     /// Set a TCP socket to non-blocking mode
     (1, TcpSetNonBlocking, Tcp, "&tcpsnb", "tcp - set non-blocking"),
@@ -420,6 +679,27 @@ sys_op! {
     (2(0), TcpSetWriteTimeout, Tcp, "&tcpswt", "tcp - set write timeout"),
     /// Get the connection address of a TCP socket
     (1, TcpAddr, Tcp, "&tcpaddr", "tcp - address"),
+    /// Create a UDP socket and bind it to an address
+    ///
+    /// Returns a socket handle
+    /// Bind to port `0` to let the system pick a free port.
+    /// Packets can be sent with [&udps] and received with [&udpr].
+    /// [under][&udpb] calls [&cl] automatically.
+    /// ex: ⍜&udpb(&udps "ping" "127.0.0.1:9000") "0.0.0.0:0"
+    (1, UdpBind, Udp, "&udpb", "udp - bind"),
+    /// Send a packet to an address with a UDP socket
+    ///
+    /// Expects the contents of the packet, the address to send it to, and a socket handle.
+    /// The contents can be a string or a byte array.
+    (3(0), UdpSend, Udp, "&udps", "udp - send to"),
+    /// Receive a packet with a UDP socket
+    ///
+    /// Expects the maximum number of bytes to receive and a socket handle.
+    /// Waits for a packet, then returns the address it came from and its contents as bytes, with the contents on top.
+    /// Any bytes past the maximum are discarded.
+    /// A simple server that echoes one packet back to its sender:
+    /// ex: ⍜&udpb(&udps &udpr 1024 .) "0.0.0.0:9000"
+    (2(2), UdpReceive, Udp, "&udpr", "udp - receive from"),
     /// Make an HTTP request
     ///
     /// Takes in an 1.x HTTP request and returns an HTTP response.
@@ -441,6 +721,17 @@ sys_op! {
     /// - The HTTP version
     /// - The `Host` header (if not defined)
     (2, HttpsWrite, Tcp, "&httpsw", "http - Make an HTTP request"),
+    /// Download a file to a local cache and return its path
+    ///
+    /// Expects a URL.
+    /// If the file has been downloaded before, it is not downloaded again.
+    /// Downloaded files are stored in a `uiua` directory in your user cache directory.
+    /// Redirects are followed, except from HTTPS to plain HTTP, and responses larger than 1 GiB are rejected.
+    ///
+    /// ex: &frab &dl "https://example.com/data.csv"
+    ///
+    /// See also: [&httpsw]
+    (1, Download, Tcp, "&dl", "download to cache"),
 }
 
 /// A handle to an IO stream
@@ -502,6 +793,12 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn scan_line_stdin(&self) -> Result<Option<String>, String> {
         Err("Reading from stdin is not supported in this environment".into())
     }
+    /// Set the time after which blocking operations on the current thread should give up
+    ///
+    /// The runtime calls this before each system function with the time at which its
+    /// [execution limit](crate::Uiua::with_execution_limit) is reached, or `None` if it has none.
+    /// Backends should make sleeps, network operations, and other waits end by then and return an error.
+    fn set_deadline(&self, deadline: Option<instant::Instant>) {}
     /// Set the terminal to raw mode
     fn set_raw_mode(&self, raw_mode: bool) -> Result<(), String> {
         Err("Setting raw mode is not supported in this environment".into())
@@ -639,6 +936,22 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     ) -> Result<(), String> {
         Err("TCP sockets are not supported in this environment".into())
     }
+    /// Create a UDP socket and bind it to an address
+    fn udp_bind(&self, addr: &str) -> Result<Handle, String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    /// Send a packet to an address with a UDP socket
+    fn udp_send_to(&self, handle: Handle, contents: &[u8], addr: &str) -> Result<(), String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    /// Receive a packet with a UDP socket, returning its contents and the address it came from
+    fn udp_receive_from(
+        &self,
+        handle: Handle,
+        max_len: usize,
+    ) -> Result<(Vec<u8>, String), String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
     /// Close a stream
     fn close(&self, handle: Handle) -> Result<(), String> {
         Ok(())
@@ -667,49 +980,139 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn https_get(&self, request: &str, handle: Handle) -> Result<String, String> {
         Err("Making HTTPS requests is not supported in this environment".into())
     }
+    /// Download a URL to a local cache, returning the path of the cached file
+    fn download(&self, url: &str) -> Result<String, String> {
+        Err("Downloading files is not supported in this environment".into())
+    }
+}
+
+/// A system backend with no access to the outside world
+///
+/// Every system function that does IO will return an error.
+#[derive(Default)]
+pub struct SafeSys;
+
+impl SysBackend for SafeSys {
+    fn any(&self) -> &dyn Any {
+        self
+    }
+    fn any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Read at most `count` bytes from a stream
+fn read_bytes(handle: Handle, count: usize, env: &Uiua) -> UiuaResult<Vec<u8>> {
+    match handle {
+        Handle::STDOUT => Err(env.error("Cannot read from stdout")),
+        Handle::STDERR => Err(env.error("Cannot read from stderr")),
+        Handle::STDIN => stdin()
+            .lock()
+            .bytes()
+            .take(count)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| env.error(e).with_kind(ErrorKind::Io)),
+        _ => env
+            .backend
+            .read(handle, count)
+            .map_err(|e| env.error(e).with_kind(ErrorKind::Io)),
+    }
+}
+
+/// Get the bytes to write for a value
+fn write_bytes(data: Value, env: &Uiua) -> UiuaResult<Vec<u8>> {
+    Ok(match data {
+        Value::Num(arr) => arr.data.iter().map(|&x| x as u8).collect(),
+        #[cfg(feature = "bytes")]
+        Value::Byte(arr) => arr.data.into(),
+        #[cfg(feature = "complex")]
+        Value::Complex(_) => return Err(env.error("Cannot write complex array to file")),
+        #[cfg(feature = "rational")]
+        Value::Rational(arr) => arr.data.iter().map(|&x| f64::from(x) as u8).collect(),
+        Value::Char(arr) => arr.data.iter().collect::<String>().into(),
+        Value::Box(_) => return Err(env.error("Cannot write box array to file")),
+    })
 }
 
 impl SysOp {
+    /// Get the capabilities the system function needs
+    pub fn capabilities(&self) -> &'static [Capability] {
+        use SysOp::*;
+        match self {
+            ChangeDirectory | Import | FOpen | FCreate | FDelete | FTrash | FExists | FListDir
+            | FIsFile | FReadAllStr | FReadAllBytes | FWriteAll => &[Capability::Filesystem],
+            TcpListen | TcpAccept | TcpConnect | TcpSetNonBlocking | TcpSetReadTimeout
+            | TcpSetWriteTimeout | TcpAddr | UdpBind | UdpSend | UdpReceive | HttpsWrite => {
+                &[Capability::Network]
+            }
+            // Downloads are cached in the working directory
+            Download => &[Capability::Network, Capability::Filesystem],
+            RunInherit | RunCapture | Invoke => &[Capability::Process],
+            AudioPlay | AudioStream => &[Capability::Audio],
+            _ => &[],
+        }
+    }
     pub(crate) fn run(&self, env: &mut Uiua) -> UiuaResult {
+        for &capability in self.capabilities() {
+            env.require_capability(capability, self.name())?;
+        }
+        env.backend.set_deadline(env.deadline());
+        let res = self.run_impl(env);
+        // A blocking operation that failed because it reached the deadline is a timeout
+        if res.is_err() && env.timed_out() {
+            return Err(UiuaError::Timeout(env.span()));
+        }
+        res
+    }
+    fn run_impl(&self, env: &mut Uiua) -> UiuaResult {
         match self {
             SysOp::Show => {
                 let s = env.pop(1)?.show();
-                env.backend.print_str_stdout(&s).map_err(|e| env.error(e))?;
+                env.backend
+                    .print_str_stdout(&s)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                 env.backend
                     .print_str_stdout("\n")
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::Prin => {
                 let val = env.pop(1)?;
                 env.backend
                     .print_str_stdout(&val.to_string())
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::Print => {
                 let val = env.pop(1)?;
                 env.backend
                     .print_str_stdout(&val.to_string())
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                 env.backend
                     .print_str_stdout("\n")
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::ScanLine => {
-                if let Some(line) = env.backend.scan_line_stdin().map_err(|e| env.error(e))? {
+                if let Some(line) = env
+                    .backend
+                    .scan_line_stdin()
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?
+                {
                     env.push(line);
                 } else {
                     env.push(0u8);
                 }
             }
             SysOp::TermSize => {
-                let (width, height) = env.backend.term_size().map_err(|e| env.error(e))?;
+                let (width, height) = env
+                    .backend
+                    .term_size()
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                 env.push(cowslice![height as f64, width as f64])
             }
             SysOp::RawMode => {
                 let raw_mode = env.pop(1)?.as_bool(env, "Raw mode must be a boolean")?;
                 env.backend
                     .set_raw_mode(raw_mode)
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::Args => {
                 let mut args = Vec::new();
@@ -729,7 +1132,8 @@ impl SysOp {
                 let handle = env
                     .backend
                     .open_file(path.as_ref())
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+                env.open_handles.lock().insert(handle);
                 env.push(handle);
             }
             SysOp::FCreate => {
@@ -737,16 +1141,21 @@ impl SysOp {
                 let handle = env
                     .backend
                     .create_file(path.as_ref())
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+                env.open_handles.lock().insert(handle);
                 env.push(handle.0 as f64);
             }
             SysOp::FDelete => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
-                env.backend.delete(&path).map_err(|e| env.error(e))?;
+                env.backend
+                    .delete(&path)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::FTrash => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
-                env.backend.trash(&path).map_err(|e| env.error(e))?;
+                env.backend
+                    .trash(&path)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::ReadStr => {
                 let count = env.pop(1)?.as_nat(env, "Count must be an integer")?;
@@ -754,17 +1163,7 @@ impl SysOp {
                     .pop(2)?
                     .as_nat(env, "Handle must be an natural number")?
                     .into();
-                let bytes = match handle {
-                    Handle::STDOUT => return Err(env.error("Cannot read from stdout")),
-                    Handle::STDERR => return Err(env.error("Cannot read from stderr")),
-                    Handle::STDIN => stdin()
-                        .lock()
-                        .bytes()
-                        .take(count)
-                        .collect::<Result<Vec<_>, _>>()
-                        .map_err(|e| env.error(e))?,
-                    _ => env.backend.read(handle, count).map_err(|e| env.error(e))?,
-                };
+                let bytes = read_bytes(handle, count, env)?;
                 let s = String::from_utf8(bytes).map_err(|e| env.error(e))?;
                 env.push(s);
             }
@@ -774,19 +1173,39 @@ impl SysOp {
                     .pop(2)?
                     .as_nat(env, "Handle must be an natural number")?
                     .into();
-                let bytes = match handle {
-                    Handle::STDOUT => return Err(env.error("Cannot read from stdout")),
-                    Handle::STDERR => return Err(env.error("Cannot read from stderr")),
-                    Handle::STDIN => stdin()
-                        .lock()
-                        .bytes()
-                        .take(count)
-                        .collect::<Result<Vec<_>, _>>()
-                        .map_err(|e| env.error(e))?,
-                    _ => env.backend.read(handle, count).map_err(|e| env.error(e))?,
-                };
+                let bytes = read_bytes(handle, count, env)?;
                 env.push(Array::from(bytes.as_slice()));
             }
+            SysOp::ReadBytesFold => {
+                let f = env.pop_function()?;
+                if f.signature() != (2, 1) {
+                    return Err(env.error(format!(
+                        "&rbf's function's signature must be {}, but it is {}",
+                        Signature::new(2, 1),
+                        f.signature()
+                    )));
+                }
+                let count = env.pop(1)?.as_nat(env, "Chunk size must be an integer")?;
+                if count == 0 {
+                    return Err(env.error("Chunk size must be positive"));
+                }
+                let handle = env
+                    .pop(2)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                let mut acc = env.pop(3)?;
+                loop {
+                    let bytes = read_bytes(handle, count, env)?;
+                    if bytes.is_empty() {
+                        break;
+                    }
+                    env.push(acc);
+                    env.push(Array::from(bytes.as_slice()));
+                    env.call(f.clone())?;
+                    acc = env.pop("&rbf's accumulator")?;
+                }
+                env.push(acc);
+            }
             SysOp::ReadUntil => {
                 let delim = env.pop(1)?;
                 let handle = env
@@ -814,7 +1233,7 @@ impl SysOp {
                         let mut buffer = Vec::new();
                         let stdin = stdin().lock();
                         for byte in stdin.bytes() {
-                            let byte = byte.map_err(|e| env.error(e))?;
+                            let byte = byte.map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                             buffer.push(byte);
                             if buffer.ends_with(&delim_bytes) {
                                 break;
@@ -833,7 +1252,7 @@ impl SysOp {
                             let bytes = env
                                 .backend
                                 .read_until(handle, &delim)
-                                .map_err(|e| env.error(e))?;
+                                .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                             env.push(Array::from(bytes.as_slice()));
                         }
                         #[cfg(feature = "bytes")]
@@ -842,7 +1261,7 @@ impl SysOp {
                             let bytes = env
                                 .backend
                                 .read_until(handle, &delim)
-                                .map_err(|e| env.error(e))?;
+                                .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                             env.push(Array::from(bytes.as_slice()));
                         }
                         Value::Char(arr) => {
@@ -850,7 +1269,7 @@ impl SysOp {
                             let bytes = env
                                 .backend
                                 .read_until(handle, &delim)
-                                .map_err(|e| env.error(e))?;
+                                .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                             let s = String::from_utf8(bytes).map_err(|e| env.error(e))?;
                             env.push(s);
                         }
@@ -859,36 +1278,25 @@ impl SysOp {
                 }
             }
             SysOp::Write => {
-                let data = env.pop(1)?;
+                let bytes = write_bytes(env.pop(1)?, env)?;
                 let handle = env
                     .pop(2)?
                     .as_nat(env, "Handle must be an natural number")?
                     .into();
-                let bytes: Vec<u8> = match data {
-                    Value::Num(arr) => arr.data.iter().map(|&x| x as u8).collect(),
-                    #[cfg(feature = "bytes")]
-                    Value::Byte(arr) => arr.data.into(),
-                    #[cfg(feature = "complex")]
-                    Value::Complex(_) => {
-                        return Err(env.error("Cannot write complex array to file"))
-                    }
-                    Value::Char(arr) => arr.data.iter().collect::<String>().into(),
-                    Value::Box(_) => return Err(env.error("Cannot write box array to file")),
-                };
                 match handle {
                     Handle::STDOUT => env
                         .backend
                         .print_str_stdout(&String::from_utf8_lossy(&bytes))
-                        .map_err(|e| env.error(e))?,
+                        .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?,
                     Handle::STDERR => env
                         .backend
                         .print_str_stderr(&String::from_utf8_lossy(&bytes))
-                        .map_err(|e| env.error(e))?,
+                        .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?,
                     Handle::STDIN => return Err(env.error("Cannot write to stdin")),
                     _ => env
                         .backend
                         .write(handle, &bytes)
-                        .map_err(|e| env.error(e))?,
+                        .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?,
                 }
             }
             SysOp::FReadAllStr => {
@@ -903,7 +1311,7 @@ impl SysOp {
                             Err(e)
                         }
                     })
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                 let s = String::from_utf8(bytes).map_err(|e| env.error(e))?;
                 env.push(s);
             }
@@ -919,7 +1327,7 @@ impl SysOp {
                             Err(e)
                         }
                     })
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                 let bytes = bytes.into_iter().map(Into::into);
                 env.push(Array::<u8>::from_iter(bytes));
             }
@@ -934,6 +1342,8 @@ impl SysOp {
                     Value::Complex(_) => {
                         return Err(env.error("Cannot write complex array to file"))
                     }
+                    #[cfg(feature = "rational")]
+                    Value::Rational(arr) => arr.data.iter().map(|&x| f64::from(x) as u8).collect(),
                     Value::Char(arr) => arr.data.iter().collect::<String>().into(),
                     Value::Box(_) => return Err(env.error("Cannot write box array to file")),
                 };
@@ -948,7 +1358,7 @@ impl SysOp {
                             Err(e)
                         }
                     })
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::FExists => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
@@ -957,36 +1367,30 @@ impl SysOp {
             }
             SysOp::FListDir => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
-                let paths = env.backend.list_dir(&path).map_err(|e| env.error(e))?;
+                let paths = env
+                    .backend
+                    .list_dir(&path)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                 env.push(Array::<Boxed>::from_iter(paths));
             }
             SysOp::FIsFile => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
-                let is_file = env.backend.is_file(&path).map_err(|e| env.error(e))?;
+                let is_file = env
+                    .backend
+                    .is_file(&path)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                 env.push(is_file);
             }
             SysOp::Import => {
                 let path = env.pop(1)?.as_string(env, "Import path must be a string")?;
                 let item = env.pop(2)?.as_string(env, "Item name must be a string")?;
-                let resolved_path = env.resolve_import_path(path.as_ref());
-                let input = String::from_utf8(
-                    env.backend
-                        .file_read_all(&resolved_path)
-                        .or_else(|e| {
-                            if path == "example.ua" {
-                                Ok(example_ua(|ex| ex.as_bytes().to_vec()))
-                            } else {
-                                Err(e)
-                            }
-                        })
-                        .map_err(|e| env.error(e))?,
-                )
-                .map_err(|e| env.error(format!("Failed to read file: {e}")))?;
-                env.import(&input, resolved_path.as_ref(), &item)?;
+                env.import(&path, &item)?;
             }
             SysOp::Invoke => {
                 let path = env.pop(1)?.as_string(env, "Invoke path must be a string")?;
-                env.backend.invoke(&path).map_err(|e| env.error(e))?;
+                env.backend
+                    .invoke(&path)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::ImDecode => {
                 let bytes: CowSlice<u8> = match env.pop(1)? {
@@ -1045,7 +1449,59 @@ impl SysOp {
             SysOp::ImShow => {
                 let value = env.pop(1)?;
                 let image = value_to_image(&value).map_err(|e| env.error(e))?;
-                env.backend.show_image(image).map_err(|e| env.error(e))?;
+                env.backend
+                    .show_image(image)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+            }
+            SysOp::ImGrayscale => {
+                let value = env.pop(1)?;
+                let image = PixelBuffer::from_value(&value, env)?;
+                env.push(image.grayscale());
+            }
+            SysOp::ImRgbToHsv => {
+                let value = env.pop(1)?;
+                let array = map_colors(&value, rgb_to_hsv, env)?;
+                env.push(array);
+            }
+            SysOp::ImHsvToRgb => {
+                let value = env.pop(1)?;
+                let array = map_colors(&value, hsv_to_rgb, env)?;
+                env.push(array);
+            }
+            SysOp::ImResize => {
+                let method = env
+                    .pop(1)?
+                    .as_string(env, "Resize method must be a string")?;
+                let size = env
+                    .pop(2)?
+                    .as_nats(env, "Size must be a list of naturals")?;
+                let value = env.pop(3)?;
+                let [height, width] = size[..] else {
+                    return Err(env.error(format!(
+                        "Size must be a height and width, but it has {} elements",
+                        size.len()
+                    )));
+                };
+                let image = PixelBuffer::from_value(&value, env)?;
+                let resized = match method.as_str() {
+                    "nearest" => image.resize_nearest(height, width),
+                    "bilinear" => image.resize_bilinear(height, width),
+                    method => return Err(env.error(format!("Invalid resize method: {method}"))),
+                };
+                env.push(resized.into_array(value.rank() == 2));
+            }
+            SysOp::ImOverlay => {
+                let top = env.pop(1)?;
+                let bottom = env.pop(2)?;
+                let top = PixelBuffer::from_value(&top, env)?;
+                let bottom = PixelBuffer::from_value(&bottom, env)?;
+                if top.height != bottom.height || top.width != bottom.width {
+                    return Err(env.error(format!(
+                        "Cannot overlay a {}×{} image over a {}×{} image",
+                        top.height, top.width, bottom.height, bottom.width
+                    )));
+                }
+                env.push(top.over(&bottom));
             }
             SysOp::GifDecode => {
                 let bytes = env
@@ -1065,7 +1521,9 @@ impl SysOp {
                 let delay = env.pop(1)?.as_num(env, "Delay must be a number")?;
                 let value = env.pop(2)?;
                 let bytes = value_to_gif_bytes(&value, delay).map_err(|e| env.error(e))?;
-                env.backend.show_gif(bytes).map_err(|e| env.error(e))?;
+                env.backend
+                    .show_gif(bytes)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::AudioDecode => {
                 let bytes: CowSlice<u8> = match env.pop(1)? {
@@ -1100,7 +1558,7 @@ impl SysOp {
                 let value = env.pop(2)?;
                 let bytes = match format.as_str() {
                     "wav" => value_to_wav_bytes(&value, env.backend.audio_sample_rate())
-                        .map_err(|e| env.error(e))?,
+                        .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?,
                     format => return Err(env.error(format!("Invalid audio format: {}", format))),
                 };
                 env.push(Array::<u8>::from(bytes.as_slice()));
@@ -1108,13 +1566,103 @@ impl SysOp {
             SysOp::AudioPlay => {
                 let value = env.pop(1)?;
                 let bytes = value_to_wav_bytes(&value, env.backend.audio_sample_rate())
-                    .map_err(|e| env.error(e))?;
-                env.backend.play_audio(bytes).map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+                env.backend
+                    .play_audio(bytes)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::AudioSampleRate => {
                 let sample_rate = env.backend.audio_sample_rate();
                 env.push(f64::from(sample_rate));
             }
+            SysOp::AudioOscillator => {
+                let wave = env.pop(1)?.as_string(env, "Waveform must be a string")?;
+                let freq = env.pop(2)?.as_num(env, "Frequency must be a number")?;
+                let duration = env.pop(3)?.as_num(env, "Duration must be a number")?;
+                if duration < 0.0 || !duration.is_finite() {
+                    return Err(env.error(format!(
+                        "Duration must be a finite non-negative number, but it is {duration}"
+                    )));
+                }
+                let sample_rate = f64::from(env.backend.audio_sample_rate());
+                let len = (duration * sample_rate).round() as usize;
+                if len > isize::MAX as usize / size_of::<f64>() {
+                    return Err(env.error(format!(
+                        "A duration of {duration} seconds is too long to synthesize"
+                    )));
+                }
+                env.validate_alloc(len, size_of::<f64>())?;
+                // Phase of each sample, in cycles
+                let phase = (0..len).map(|i| (i as f64 * freq / sample_rate).rem_euclid(1.0));
+                let samples: CowSlice<f64> = match wave.as_str() {
+                    "sine" => phase.map(|p| (p * TAU).sin()).collect(),
+                    "square" => phase.map(|p| if p < 0.5 { 1.0 } else { -1.0 }).collect(),
+                    "saw" => phase.map(|p| 2.0 * p - 1.0).collect(),
+                    "triangle" => phase.map(|p| 1.0 - 4.0 * (p - 0.5).abs()).collect(),
+                    "noise" => {
+                        let mut rng = env.rng.lock();
+                        (0..len).map(|_| rng.gen_range(-1.0..=1.0)).collect()
+                    }
+                    wave => return Err(env.error(format!("Invalid waveform: {wave}"))),
+                };
+                env.push(Array::<f64>::from(samples));
+            }
+            SysOp::AudioFilter => {
+                let kind = env.pop(1)?.as_string(env, "Filter type must be a string")?;
+                let params = env
+                    .pop(2)?
+                    .as_nums(env, "Filter parameters must be a list of numbers")?;
+                let audio = env.pop(3)?;
+                let [freq, q] = params[..] else {
+                    return Err(env.error(format!(
+                        "Filter parameters must be a frequency and a Q factor, \
+                        but there are {} of them",
+                        params.len()
+                    )));
+                };
+                let sample_rate = f64::from(env.backend.audio_sample_rate());
+                let filter = Biquad::new(&kind, freq, q, sample_rate).map_err(|e| env.error(e))?;
+                let array = map_audio_channels(&audio, env, |samples| filter.apply(samples))?;
+                env.push(array);
+            }
+            SysOp::AudioEnvelope => {
+                let params = env
+                    .pop(1)?
+                    .as_nums(env, "Envelope must be a list of numbers")?;
+                let audio = env.pop(2)?;
+                let [attack, decay, sustain, release] = params[..] else {
+                    return Err(env.error(format!(
+                        "Envelope must be an attack, decay, sustain, and release, \
+                        but there are {} values",
+                        params.len()
+                    )));
+                };
+                let sample_rate = f64::from(env.backend.audio_sample_rate());
+                let array = map_audio_channels(&audio, env, |samples| {
+                    let len = samples.len();
+                    let attack = attack * sample_rate;
+                    let decay = decay * sample_rate;
+                    let release = release * sample_rate;
+                    let release_start = (len as f64 - release).max(0.0);
+                    for (i, sample) in samples.iter_mut().enumerate() {
+                        let t = i as f64;
+                        let level = if t < attack {
+                            t / attack
+                        } else if t < attack + decay {
+                            1.0 - (1.0 - sustain) * (t - attack) / decay
+                        } else {
+                            sustain
+                        };
+                        let level = if t >= release_start {
+                            level * (1.0 - (t - release_start) / release)
+                        } else {
+                            level
+                        };
+                        *sample *= level;
+                    }
+                })?;
+                env.push(array);
+            }
             SysOp::AudioStream => {
                 let f = env.pop_function()?;
                 if f.signature() != (1, 1) {
@@ -1154,11 +1702,159 @@ impl SysOp {
                     .pop(1)?
                     .as_num(env, "Sleep time must be a number")?
                     .max(0.0);
-                env.backend.sleep(seconds).map_err(|e| env.error(e))?;
+                env.backend
+                    .sleep(seconds)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+            }
+            SysOp::Seed => {
+                let seed = env.pop(1)?.as_num(env, "Seed must be a number")?.to_bits();
+                env.seed_rng(seed);
+            }
+            SysOp::RegexMatch => {
+                let pattern = env.pop(1)?.as_string(env, "Pattern must be a string")?;
+                let target = env.pop(2)?.as_string(env, "Target must be a string")?;
+                let regex = cached_regex(&pattern, env)?;
+                env.push(regex.is_match(&target));
+            }
+            SysOp::RegexFind => {
+                let pattern = env.pop(1)?.as_string(env, "Pattern must be a string")?;
+                let target = env.pop(2)?.as_string(env, "Target must be a string")?;
+                let regex = cached_regex(&pattern, env)?;
+                // Regex matches are byte ranges, but strings are indexed by character.
+                // Matches come in order, so count characters from the last byte counted.
+                let (mut counted_bytes, mut counted_chars) = (0, 0);
+                let mut char_index = |byte: usize| {
+                    counted_chars += target[counted_bytes..byte].chars().count();
+                    counted_bytes = byte;
+                    counted_chars as f64
+                };
+                let mut data = EcoVec::new();
+                for m in regex.find_iter(&target) {
+                    let start = char_index(m.start());
+                    data.push(start);
+                    data.push(char_index(m.end()) - start);
+                }
+                env.push(Array::<f64>::new(tiny_vec![data.len() / 2, 2], data));
+            }
+            SysOp::RegexReplace => {
+                let pattern = env.pop(1)?.as_string(env, "Pattern must be a string")?;
+                let replacement = env.pop(2)?.as_string(env, "Replacement must be a string")?;
+                let target = env.pop(3)?.as_string(env, "Target must be a string")?;
+                let regex = cached_regex(&pattern, env)?;
+                env.push(
+                    regex
+                        .replace_all(&target, replacement.as_str())
+                        .into_owned(),
+                );
+            }
+            SysOp::Base64Encode => {
+                let bytes = env
+                    .pop(1)?
+                    .into_bytes(env, "Data must be bytes or a string")?;
+                env.push(BASE64_STANDARD.encode(bytes));
+            }
+            SysOp::Base64Decode => {
+                let text = env.pop(1)?.as_string(env, "Base64 text must be a string")?;
+                let bytes = (BASE64_STANDARD.decode(text))
+                    .map_err(|e| env.error(format!("Invalid base64: {e}")))?;
+                env.push(Array::<u8>::from_iter(bytes));
+            }
+            SysOp::HexEncode => {
+                let bytes = env
+                    .pop(1)?
+                    .into_bytes(env, "Data must be bytes or a string")?;
+                let text: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+                env.push(text);
+            }
+            SysOp::HexDecode => {
+                let text = env.pop(1)?.as_string(env, "Hex text must be a string")?;
+                let bytes = hex_decode(&text).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from_iter(bytes));
+            }
+            SysOp::UrlEncode => {
+                let bytes = env
+                    .pop(1)?
+                    .into_bytes(env, "Data must be bytes or a string")?;
+                env.push(percent_encode(&bytes, URL_ENCODE_SET).to_string());
+            }
+            SysOp::UrlDecode => {
+                let text = env.pop(1)?.as_string(env, "URL text must be a string")?;
+                let bytes: Vec<u8> = percent_decode_str(&text).collect();
+                env.push(Array::<u8>::from_iter(bytes));
+            }
+            SysOp::Hash => {
+                let algorithm = env.pop(1)?.as_string(env, "Algorithm must be a string")?;
+                let data = env
+                    .pop(2)?
+                    .into_bytes(env, "Data must be bytes or a string")?;
+                let hash = hash_bytes(&algorithm, &data).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from_iter(hash));
+            }
+            SysOp::Hmac => {
+                let algorithm = env.pop(1)?.as_string(env, "Algorithm must be a string")?;
+                let key = env
+                    .pop(2)?
+                    .into_bytes(env, "Key must be bytes or a string")?;
+                let message = (env.pop(3)?).into_bytes(env, "Message must be bytes or a string")?;
+                let mac = hmac_bytes(&algorithm, &key, &message).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from_iter(mac));
+            }
+            SysOp::DateTimeFormat => {
+                let pattern = env.pop(1)?.as_string(env, "Pattern must be a string")?;
+                let items: Vec<Item> = StrftimeItems::new(&pattern).collect();
+                if items.contains(&Item::Error) {
+                    return Err(env.error(format!("Invalid datetime pattern {pattern:?}")));
+                }
+                let times = match env.pop(2)? {
+                    Value::Num(arr) => arr,
+                    #[cfg(feature = "bytes")]
+                    Value::Byte(arr) => arr.convert(),
+                    value => {
+                        return Err(env.error(format!(
+                            "Cannot format a {} array as a datetime",
+                            value.type_name()
+                        )))
+                    }
+                };
+                let format = |time: f64| format_time(time, &items).map_err(|e| env.error(e));
+                if let Some(&time) = times.as_scalar() {
+                    env.push(format(time)?);
+                } else {
+                    let mut formatted = EcoVec::with_capacity(times.element_count());
+                    for &time in &times.data {
+                        formatted.push(Boxed(format(time)?.into()));
+                    }
+                    env.push(Array::new(times.shape.clone(), formatted));
+                }
+            }
+            SysOp::DateTimeParse => {
+                let value = env.pop(1)?;
+                match value {
+                    Value::Box(arr) => {
+                        let mut times = EcoVec::with_capacity(arr.element_count());
+                        for b in &arr.data {
+                            let s = b.as_value().as_string(env, "Datetime must be a string")?;
+                            times.push(parse_time(&s).map_err(|e| env.error(e))?);
+                        }
+                        env.push(Array::<f64>::new(arr.shape.clone(), times));
+                    }
+                    value => {
+                        let s = value.as_string(env, "Datetime must be a string")?;
+                        env.push(parse_time(&s).map_err(|e| env.error(e))?);
+                    }
+                }
+            }
+            SysOp::Tolerance => {
+                let tolerance = env.pop(1)?.as_num(env, "Tolerance must be a number")?;
+                env.set_tolerance(tolerance);
             }
             SysOp::TcpListen => {
                 let addr = env.pop(1)?.as_string(env, "Address must be a string")?;
-                let handle = env.backend.tcp_listen(&addr).map_err(|e| env.error(e))?;
+                let handle = env
+                    .backend
+                    .tcp_listen(&addr)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+                env.open_handles.lock().insert(handle);
                 env.push(handle);
             }
             SysOp::TcpAccept => {
@@ -1166,12 +1862,20 @@ impl SysOp {
                     .pop(1)?
                     .as_nat(env, "Handle must be an natural number")?
                     .into();
-                let new_handle = env.backend.tcp_accept(handle).map_err(|e| env.error(e))?;
+                let new_handle = env
+                    .backend
+                    .tcp_accept(handle)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+                env.open_handles.lock().insert(new_handle);
                 env.push(new_handle);
             }
             SysOp::TcpConnect => {
                 let addr = env.pop(1)?.as_string(env, "Address must be a string")?;
-                let handle = env.backend.tcp_connect(&addr).map_err(|e| env.error(e))?;
+                let handle = env
+                    .backend
+                    .tcp_connect(&addr)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+                env.open_handles.lock().insert(handle);
                 env.push(handle);
             }
             SysOp::TcpAddr => {
@@ -1179,7 +1883,10 @@ impl SysOp {
                     .pop(1)?
                     .as_nat(env, "Handle must be an natural number")?
                     .into();
-                let addr = env.backend.tcp_addr(handle).map_err(|e| env.error(e))?;
+                let addr = env
+                    .backend
+                    .tcp_addr(handle)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                 env.push(addr);
             }
             SysOp::TcpSetNonBlocking => {
@@ -1189,7 +1896,7 @@ impl SysOp {
                     .into();
                 env.backend
                     .tcp_set_non_blocking(handle, true)
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::TcpSetReadTimeout => {
                 let timeout = env.pop(1)?.as_num(env, "Timeout must be a number")?.abs();
@@ -1204,7 +1911,7 @@ impl SysOp {
                     .into();
                 env.backend
                     .tcp_set_read_timeout(handle, timeout)
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
             SysOp::TcpSetWriteTimeout => {
                 let timeout = env.pop(1)?.as_num(env, "Timeout must be a number")?.abs();
@@ -1219,7 +1926,41 @@ impl SysOp {
                     .into();
                 env.backend
                     .tcp_set_write_timeout(handle, timeout)
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+            }
+            SysOp::UdpBind => {
+                let addr = env.pop(1)?.as_string(env, "Address must be a string")?;
+                let handle = env
+                    .backend
+                    .udp_bind(&addr)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+                env.open_handles.lock().insert(handle);
+                env.push(handle);
+            }
+            SysOp::UdpSend => {
+                let bytes = write_bytes(env.pop(1)?, env)?;
+                let addr = env.pop(2)?.as_string(env, "Address must be a string")?;
+                let handle = env
+                    .pop(3)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                env.backend
+                    .udp_send_to(handle, &bytes, &addr)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+            }
+            SysOp::UdpReceive => {
+                let max_len = env.pop(1)?.as_nat(env, "Count must be an integer")?;
+                env.validate_alloc(max_len, 1)?;
+                let handle = env
+                    .pop(2)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                let (bytes, addr) = env
+                    .backend
+                    .udp_receive_from(handle, max_len)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+                env.push(addr);
+                env.push(Array::from(bytes.as_slice()));
             }
             SysOp::HttpsWrite => {
                 let http = env
@@ -1232,15 +1973,26 @@ impl SysOp {
                 let res = env
                     .backend
                     .https_get(&http, handle)
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                 env.push(res);
             }
+            SysOp::Download => {
+                let url = env.pop(1)?.as_string(env, "URL must be a string")?;
+                let path = env
+                    .backend
+                    .download(&url)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+                env.push(path);
+            }
             SysOp::Close => {
                 let handle = env
                     .pop(1)?
                     .as_nat(env, "Handle must be an natural number")?
                     .into();
-                env.backend.close(handle).map_err(|e| env.error(e))?;
+                env.backend
+                    .close(handle)
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
+                env.open_handles.lock().remove(&handle);
             }
             SysOp::RunInherit => {
                 let (command, args) = value_to_command(&env.pop(1)?, env)?;
@@ -1248,7 +2000,7 @@ impl SysOp {
                 let code = env
                     .backend
                     .run_command_inherit(&command, &args)
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                 env.push(code);
             }
             SysOp::RunCapture => {
@@ -1257,7 +2009,7 @@ impl SysOp {
                 let (code, stdout, stderr) = env
                     .backend
                     .run_command_capture(&command, &args)
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
                 env.push(stderr);
                 env.push(stdout);
                 env.push(code);
@@ -1266,13 +2018,120 @@ impl SysOp {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
                 env.backend
                     .change_directory(&path)
-                    .map_err(|e| env.error(e))?;
+                    .map_err(|e| env.error(e).with_kind(ErrorKind::Io))?;
             }
         }
         Ok(())
     }
 }
 
+/// Bytes that are percent-encoded, which is all but the "unreserved" characters of RFC 3986
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+fn hex_decode(text: &str) -> Result<Vec<u8>, String> {
+    let pairs = text.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(format!(
+            "Hex text must have an even number of digits, but it has {}",
+            text.len()
+        ));
+    }
+    pairs
+        .map(|pair| {
+            let pair = String::from_utf8_lossy(pair);
+            if !pair.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("Invalid hex digits {pair:?}"));
+            }
+            Ok(u8::from_str_radix(&pair, 16).unwrap())
+        })
+        .collect()
+}
+
+fn format_time(time: f64, items: &[Item]) -> Result<String, String> {
+    let secs = time.floor();
+    let nanos = ((time - secs) * 1e9).round().min(999_999_999.0) as u32;
+    let datetime = (time.is_finite())
+        .then(|| DateTime::<Utc>::from_timestamp(secs as i64, nanos))
+        .flatten()
+        .ok_or_else(|| format!("{time} is out of range for a datetime"))?;
+    Ok(datetime.format_with_items(items.iter()).to_string())
+}
+
+fn parse_time(s: &str) -> Result<f64, String> {
+    let s = s.trim();
+    let from_parts = |secs: i64, nanos: u32| secs as f64 + nanos as f64 / 1e9;
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Ok(from_parts(
+            datetime.timestamp(),
+            datetime.timestamp_subsec_nanos(),
+        ));
+    }
+    for pattern in [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(s, pattern) {
+            return Ok(from_parts(
+                datetime.timestamp(),
+                datetime.timestamp_subsec_nanos(),
+            ));
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        let datetime = date.and_time(NaiveTime::MIN);
+        return Ok(from_parts(datetime.timestamp(), 0));
+    }
+    Err(format!("{s:?} is not a valid ISO 8601 datetime"))
+}
+
+const HASH_ALGORITHMS: &str = "md5, sha1, sha224, sha256, sha384, or sha512";
+
+fn hash_bytes(algorithm: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    fn hash<D: Digest>(data: &[u8]) -> Vec<u8> {
+        D::digest(data).to_vec()
+    }
+    Ok(match algorithm {
+        "md5" => hash::<Md5>(data),
+        "sha1" => hash::<Sha1>(data),
+        "sha224" => hash::<Sha224>(data),
+        "sha256" => hash::<Sha256>(data),
+        "sha384" => hash::<Sha384>(data),
+        "sha512" => hash::<Sha512>(data),
+        _ => {
+            return Err(format!(
+                "Unknown hash algorithm {algorithm:?}, expected {HASH_ALGORITHMS}"
+            ))
+        }
+    })
+}
+
+fn hmac_bytes(algorithm: &str, key: &[u8], message: &[u8]) -> Result<Vec<u8>, String> {
+    fn hmac<D: Digest + BlockSizeUser>(key: &[u8], message: &[u8]) -> Vec<u8> {
+        // HMAC accepts keys of any length
+        let mut mac = SimpleHmac::<D>::new_from_slice(key).unwrap();
+        mac.update(message);
+        mac.finalize().into_bytes().to_vec()
+    }
+    Ok(match algorithm {
+        "md5" => hmac::<Md5>(key, message),
+        "sha1" => hmac::<Sha1>(key, message),
+        "sha224" => hmac::<Sha224>(key, message),
+        "sha256" => hmac::<Sha256>(key, message),
+        "sha384" => hmac::<Sha384>(key, message),
+        "sha512" => hmac::<Sha512>(key, message),
+        _ => {
+            return Err(format!(
+                "Unknown hash algorithm {algorithm:?}, expected {HASH_ALGORITHMS}"
+            ))
+        }
+    })
+}
+
 fn value_to_command(value: &Value, env: &Uiua) -> UiuaResult<(String, Vec<String>)> {
     let mut strings = Vec::new();
     match value {
@@ -1334,6 +2193,13 @@ fn value_to_command(value: &Value, env: &Uiua) -> UiuaResult<(String, Vec<String
                 value.type_name_plural()
             )))
         }
+        #[cfg(feature = "rational")]
+        Value::Rational(_) => {
+            return Err(env.error(format!(
+                "Command must be a string or box array, but it is {}",
+                value.type_name_plural()
+            )))
+        }
     }
     if strings.is_empty() {
         return Err(env.error("Command array not be empty"));
@@ -1402,6 +2268,238 @@ pub fn value_to_image(value: &Value) -> Result<DynamicImage, String> {
     })
 }
 
+/// An image as a flat buffer of channel values
+struct PixelBuffer {
+    height: usize,
+    width: usize,
+    channels: usize,
+    data: Vec<f64>,
+}
+
+impl PixelBuffer {
+    fn from_value(value: &Value, env: &Uiua) -> UiuaResult<Self> {
+        let data: Vec<f64> = match value {
+            Value::Num(nums) => nums.data.iter().copied().collect(),
+            #[cfg(feature = "bytes")]
+            Value::Byte(bytes) => bytes.data.iter().map(|&b| b as f64).collect(),
+            _ => return Err(env.error("Image must be a numeric array")),
+        };
+        let [height, width, channels] = match *value.shape() {
+            [a, b] => [a, b, 1],
+            [a, b, c] => [a, b, c],
+            _ => {
+                return Err(env.error(format!(
+                    "Image must be a rank 2 or 3 numeric array, but it is rank {}",
+                    value.rank()
+                )))
+            }
+        };
+        if !(1..=4).contains(&channels) {
+            return Err(env.error(format!(
+                "For a color image, the last dimension of the image array \
+                must be between 1 and 4 but it is {channels}"
+            )));
+        }
+        Ok(PixelBuffer {
+            height,
+            width,
+            channels,
+            data,
+        })
+    }
+    fn into_array(self, flat_gray: bool) -> Array<f64> {
+        let shape: Shape = if flat_gray && self.channels == 1 {
+            tiny_vec![self.height, self.width]
+        } else {
+            tiny_vec![self.height, self.width, self.channels]
+        };
+        Array::new(shape, self.data.into_iter().collect::<CowSlice<_>>())
+    }
+    fn has_alpha(&self) -> bool {
+        matches!(self.channels, 2 | 4)
+    }
+    fn is_color(&self) -> bool {
+        self.channels >= 3
+    }
+    /// Get the color and alpha of the pixel at the given index
+    fn pixel(&self, i: usize) -> ([f64; 3], f64) {
+        let px = &self.data[i * self.channels..][..self.channels];
+        match *px {
+            [v] => ([v; 3], 1.0),
+            [v, a] => ([v; 3], a),
+            [r, g, b] => ([r, g, b], 1.0),
+            [r, g, b, a] => ([r, g, b], a),
+            _ => unreachable!("channel count checked on creation"),
+        }
+    }
+    fn grayscale(self) -> Array<f64> {
+        if !self.is_color() {
+            return self.into_array(true);
+        }
+        let alpha = self.has_alpha();
+        let mut data = Vec::with_capacity(self.height * self.width * (1 + alpha as usize));
+        for i in 0..self.height * self.width {
+            let ([r, g, b], a) = self.pixel(i);
+            data.push(0.2126 * r + 0.7152 * g + 0.0722 * b);
+            if alpha {
+                data.push(a);
+            }
+        }
+        PixelBuffer {
+            channels: 1 + alpha as usize,
+            data,
+            ..self
+        }
+        .into_array(true)
+    }
+    fn resize_nearest(&self, height: usize, width: usize) -> Self {
+        let mut data = Vec::with_capacity(height * width * self.channels);
+        if self.height > 0 && self.width > 0 {
+            for y in 0..height {
+                let sy = ((y * self.height) / height).min(self.height - 1);
+                for x in 0..width {
+                    let sx = ((x * self.width) / width).min(self.width - 1);
+                    let i = (sy * self.width + sx) * self.channels;
+                    data.extend_from_slice(&self.data[i..][..self.channels]);
+                }
+            }
+        } else {
+            data.resize(height * width * self.channels, 0.0);
+        }
+        PixelBuffer {
+            height,
+            width,
+            channels: self.channels,
+            data,
+        }
+    }
+    fn resize_bilinear(&self, height: usize, width: usize) -> Self {
+        if self.height == 0 || self.width == 0 {
+            return self.resize_nearest(height, width);
+        }
+        // Map an output coordinate to the two nearest source coordinates
+        // and the weight of the second one
+        fn sample(i: usize, from: usize, to: usize) -> (usize, usize, f64) {
+            let s = ((i as f64 + 0.5) * from as f64 / to as f64 - 0.5).max(0.0);
+            let lo = (s.floor() as usize).min(from - 1);
+            let hi = (lo + 1).min(from - 1);
+            (lo, hi, s - lo as f64)
+        }
+        let c = self.channels;
+        let mut data = Vec::with_capacity(height * width * c);
+        for y in 0..height {
+            let (y0, y1, ty) = sample(y, self.height, height);
+            for x in 0..width {
+                let (x0, x1, tx) = sample(x, self.width, width);
+                let at = |y: usize, x: usize, ch: usize| self.data[(y * self.width + x) * c + ch];
+                for ch in 0..c {
+                    let top = at(y0, x0, ch) * (1.0 - tx) + at(y0, x1, ch) * tx;
+                    let bottom = at(y1, x0, ch) * (1.0 - tx) + at(y1, x1, ch) * tx;
+                    data.push(top * (1.0 - ty) + bottom * ty);
+                }
+            }
+        }
+        PixelBuffer {
+            height,
+            width,
+            channels: c,
+            data,
+        }
+    }
+    fn over(&self, bottom: &Self) -> Array<f64> {
+        let color = self.is_color() || bottom.is_color();
+        let alpha = bottom.has_alpha();
+        let channels = if color { 3 } else { 1 } + alpha as usize;
+        let mut data = Vec::with_capacity(self.height * self.width * channels);
+        for i in 0..self.height * self.width {
+            let (top_rgb, top_a) = self.pixel(i);
+            let (bot_rgb, bot_a) = bottom.pixel(i);
+            let out_a = top_a + bot_a * (1.0 - top_a);
+            let blend = |t: f64, b: f64| {
+                if out_a == 0.0 {
+                    0.0
+                } else {
+                    (t * top_a + b * bot_a * (1.0 - top_a)) / out_a
+                }
+            };
+            let rgb = [0, 1, 2].map(|j| blend(top_rgb[j], bot_rgb[j]));
+            if color {
+                data.extend_from_slice(&rgb);
+            } else {
+                data.push(rgb[0]);
+            }
+            if alpha {
+                data.push(out_a);
+            }
+        }
+        PixelBuffer {
+            height: self.height,
+            width: self.width,
+            channels,
+            data,
+        }
+        .into_array(false)
+    }
+}
+
+/// Apply a color conversion to every color in an array whose last axis is 3 or 4
+fn map_colors(value: &Value, f: fn([f64; 3]) -> [f64; 3], env: &Uiua) -> UiuaResult<Array<f64>> {
+    let mut data: Vec<f64> = match value {
+        Value::Num(nums) => nums.data.iter().copied().collect(),
+        #[cfg(feature = "bytes")]
+        Value::Byte(bytes) => bytes.data.iter().map(|&b| b as f64).collect(),
+        _ => return Err(env.error("Colors must be a numeric array")),
+    };
+    let channels = value.shape().last().copied().unwrap_or(0);
+    if ![3, 4].contains(&channels) {
+        return Err(env.error(format!(
+            "The last axis of a color array must be 3 or 4, but its shape is {}",
+            value.format_shape()
+        )));
+    }
+    for px in data.chunks_exact_mut(channels) {
+        let [a, b, c] = f([px[0], px[1], px[2]]);
+        px[..3].copy_from_slice(&[a, b, c]);
+    }
+    Ok(Array::new(
+        Shape::from(value.shape()),
+        data.into_iter().collect::<CowSlice<_>>(),
+    ))
+}
+
+fn rgb_to_hsv([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    } / 6.0;
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    [hue, saturation, max]
+}
+
+fn hsv_to_rgb([h, s, v]: [f64; 3]) -> [f64; 3] {
+    let h = h.rem_euclid(1.0) * 6.0;
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let m = v - c;
+    let [r, g, b] = match h as usize {
+        0 => [c, x, 0.0],
+        1 => [x, c, 0.0],
+        2 => [0.0, c, x],
+        3 => [0.0, x, c],
+        4 => [x, 0.0, c],
+        _ => [c, 0.0, x],
+    };
+    [r + m, g + m, b + m]
+}
+
 #[doc(hidden)]
 pub fn value_to_sample(audio: &Value) -> Result<Vec<[f32; 2]>, String> {
     let unrolled: Vec<f32> = match audio {
@@ -1479,6 +2577,76 @@ pub fn value_to_audio_channels(audio: &Value) -> Result<Vec<Vec<f64>>, String> {
     Ok(channels)
 }
 
+/// Apply a function to each channel of some audio
+fn map_audio_channels(audio: &Value, env: &Uiua, f: impl Fn(&mut [f64])) -> UiuaResult<Array<f64>> {
+    let mut samples: Vec<f64> = match audio {
+        Value::Num(nums) => nums.data.iter().copied().collect(),
+        #[cfg(feature = "bytes")]
+        Value::Byte(bytes) => bytes.data.iter().map(|&b| b as f64).collect(),
+        _ => return Err(env.error("Audio must be a numeric array")),
+    };
+    match audio.rank() {
+        1 => f(&mut samples),
+        2 if audio.row_len() > 0 => samples.chunks_exact_mut(audio.row_len()).for_each(f),
+        2 => {}
+        n => {
+            return Err(env.error(format!(
+                "Audio must be a rank 1 or 2 numeric array, but it is rank {n}"
+            )))
+        }
+    }
+    Ok(Array::new(
+        Shape::from(audio.shape()),
+        samples.into_iter().collect::<CowSlice<_>>(),
+    ))
+}
+
+/// A biquad filter, with coefficients from the Audio EQ Cookbook
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    fn new(kind: &str, freq: f64, q: f64, sample_rate: f64) -> Result<Self, String> {
+        if freq <= 0.0 || freq >= sample_rate / 2.0 {
+            return Err(format!(
+                "Filter frequency must be between 0 and {} Hz, but it is {freq}",
+                sample_rate / 2.0
+            ));
+        }
+        if q <= 0.0 {
+            return Err(format!("Filter Q factor must be positive, but it is {q}"));
+        }
+        let w0 = TAU * freq / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let b = match kind {
+            "lowpass" => [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            "highpass" => [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            "bandpass" => [alpha, 0.0, -alpha],
+            "notch" => [1.0, -2.0 * cos, 1.0],
+            kind => return Err(format!("Invalid filter type: {kind}")),
+        };
+        let a0 = 1.0 + alpha;
+        Ok(Biquad {
+            b: b.map(|b| b / a0),
+            a: [-2.0 * cos / a0, (1.0 - alpha) / a0],
+        })
+    }
+    fn apply(&self, samples: &mut [f64]) {
+        let [b0, b1, b2] = self.b;
+        let [a1, a2] = self.a;
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        for sample in samples {
+            let x = *sample;
+            let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+            (x2, x1, y2, y1) = (x1, x, y1, y);
+            *sample = y;
+        }
+    }
+}
+
 #[doc(hidden)]
 pub fn value_to_wav_bytes(audio: &Value, sample_rate: u32) -> Result<Vec<u8>, String> {
     #[cfg(not(feature = "audio"))]
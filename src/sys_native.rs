@@ -1,15 +1,16 @@
 use std::{
     any::Any,
+    cell::Cell,
     env,
     fs::{self, File},
-    io::{stderr, stdin, stdout, Read, Write},
+    io::{self, stderr, stdin, stdout, Read, Write},
     net::*,
     path::Path,
     process::Command,
     slice,
     sync::atomic::{self, AtomicU64},
     thread::sleep,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{Handle, SysBackend, UiuaError};
@@ -28,6 +29,7 @@ struct GlobalNativeSys {
     files: DashMap<Handle, Buffered<File>>,
     tcp_listeners: DashMap<Handle, TcpListener>,
     tcp_sockets: DashMap<Handle, Buffered<TcpStream>>,
+    udp_sockets: DashMap<Handle, UdpSocket>,
     hostnames: DashMap<Handle, String>,
     #[cfg(feature = "audio")]
     audio_stream_time: parking_lot::Mutex<Option<f64>>,
@@ -40,6 +42,7 @@ enum SysStream<'a> {
     File(dashmap::mapref::one::RefMut<'a, Handle, Buffered<File>>),
     TcpListener(dashmap::mapref::one::RefMut<'a, Handle, TcpListener>),
     TcpSocket(dashmap::mapref::one::RefMut<'a, Handle, Buffered<TcpStream>>),
+    UdpSocket,
 }
 
 impl Default for GlobalNativeSys {
@@ -49,6 +52,7 @@ impl Default for GlobalNativeSys {
             files: DashMap::new(),
             tcp_listeners: DashMap::new(),
             tcp_sockets: DashMap::new(),
+            udp_sockets: DashMap::new(),
             hostnames: DashMap::new(),
             #[cfg(feature = "audio")]
             audio_stream_time: parking_lot::Mutex::new(None),
@@ -66,6 +70,7 @@ impl GlobalNativeSys {
             if !self.files.contains_key(&handle)
                 && !self.tcp_listeners.contains_key(&handle)
                 && !self.tcp_sockets.contains_key(&handle)
+                && !self.udp_sockets.contains_key(&handle)
             {
                 return handle;
             }
@@ -79,6 +84,8 @@ impl GlobalNativeSys {
             SysStream::TcpListener(listener)
         } else if let Some(socket) = self.tcp_sockets.get_mut(&handle) {
             SysStream::TcpSocket(socket)
+        } else if self.udp_sockets.contains_key(&handle) {
+            SysStream::UdpSocket
         } else {
             return Err("Invalid file handle".to_string());
         })
@@ -87,6 +94,54 @@ impl GlobalNativeSys {
 
 static NATIVE_SYS: Lazy<GlobalNativeSys> = Lazy::new(Default::default);
 
+thread_local! {
+    /// The time after which blocking operations on this thread give up
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Get the time left before the deadline, if there is one
+fn time_left() -> Result<Option<Duration>, String> {
+    let Some(deadline) = DEADLINE.with(Cell::get) else {
+        return Ok(None);
+    };
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(left) if !left.is_zero() => Ok(Some(left)),
+        _ => Err("Timed out".into()),
+    }
+}
+
+/// Connect a TCP socket, giving up at the deadline
+fn connect(addr: impl ToSocketAddrs) -> Result<TcpStream, String> {
+    if time_left()?.is_none() {
+        return TcpStream::connect(addr).map_err(|e| e.to_string());
+    }
+    let mut error = "Address did not resolve".to_string();
+    for addr in addr.to_socket_addrs().map_err(|e| e.to_string())? {
+        match TcpStream::connect_timeout(&addr, time_left()?.unwrap()) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => error = e.to_string(),
+        }
+    }
+    Err(error)
+}
+
+/// Do blocking IO on a TCP socket, with its timeouts shortened to end at the deadline
+fn with_deadline<T>(stream: &TcpStream, f: impl FnOnce() -> io::Result<T>) -> Result<T, String> {
+    let Some(left) = time_left()? else {
+        return f().map_err(|e| e.to_string());
+    };
+    let read_timeout = stream.read_timeout().map_err(|e| e.to_string())?;
+    let write_timeout = stream.write_timeout().map_err(|e| e.to_string())?;
+    let shorten = |timeout: Option<Duration>| Some(timeout.map_or(left, |t| t.min(left)));
+    (stream.set_read_timeout(shorten(read_timeout)))
+        .and_then(|_| stream.set_write_timeout(shorten(write_timeout)))
+        .map_err(|e| e.to_string())?;
+    let res = f();
+    _ = stream.set_read_timeout(read_timeout);
+    _ = stream.set_write_timeout(write_timeout);
+    res.map_err(|e| e.to_string())
+}
+
 #[cfg(feature = "audio")]
 #[doc(hidden)]
 pub fn set_audio_stream_time(time: f64) {
@@ -119,6 +174,9 @@ impl SysBackend for NativeSys {
         stderr.write_all(s.as_bytes()).map_err(|e| e.to_string())?;
         stderr.flush().map_err(|e| e.to_string())
     }
+    fn set_deadline(&self, deadline: Option<Instant>) {
+        DEADLINE.with(|cell| cell.set(deadline));
+    }
     fn scan_line_stdin(&self) -> Result<Option<String>, String> {
         let mut buffer = Vec::new();
         let mut b = 0u8;
@@ -231,13 +289,16 @@ impl SysBackend for NativeSys {
             }
             SysStream::TcpListener(_) => return Err("Cannot read from a tcp listener".to_string()),
             SysStream::TcpSocket(mut socket) => {
+                let stream = socket.get_ref().try_clone().map_err(|e| e.to_string())?;
                 let mut buf = Vec::new();
-                Write::by_ref(&mut *socket)
-                    .take(len as u64)
-                    .read_to_end(&mut buf)
-                    .map_err(|e| e.to_string())?;
+                with_deadline(&stream, || {
+                    Write::by_ref(&mut *socket)
+                        .take(len as u64)
+                        .read_to_end(&mut buf)
+                })?;
                 buf
             }
+            SysStream::UdpSocket => return Err("Cannot read from a udp socket".to_string()),
         })
     }
     fn write(&self, handle: Handle, conts: &[u8]) -> Result<(), String> {
@@ -255,12 +316,25 @@ impl SysBackend for NativeSys {
         match NATIVE_SYS.get_stream(handle)? {
             SysStream::File(mut file) => file.write_all(conts).map_err(|e| e.to_string()),
             SysStream::TcpListener(_) => Err("Cannot write to a tcp listener".to_string()),
-            SysStream::TcpSocket(mut socket) => socket.write_all(conts).map_err(|e| e.to_string()),
+            SysStream::TcpSocket(mut socket) => {
+                let stream = socket.get_ref().try_clone().map_err(|e| e.to_string())?;
+                with_deadline(&stream, || socket.write_all(conts))
+            }
+            SysStream::UdpSocket => Err("Cannot write to a udp socket".to_string()),
         }
     }
     fn sleep(&self, seconds: f64) -> Result<(), String> {
-        sleep(Duration::from_secs_f64(seconds));
-        Ok(())
+        let duration = Duration::from_secs_f64(seconds);
+        match time_left()? {
+            Some(left) if left < duration => {
+                sleep(left);
+                Err("Timed out".into())
+            }
+            _ => {
+                sleep(duration);
+                Ok(())
+            }
+        }
     }
     #[cfg(feature = "terminal_image")]
     fn show_image(&self, image: image::DynamicImage) -> Result<(), String> {
@@ -375,12 +449,32 @@ impl SysBackend for NativeSys {
         Ok(handle)
     }
     fn tcp_accept(&self, handle: Handle) -> Result<Handle, String> {
+        // Clone the listener so the map isn't locked while waiting for a connection
         let listener = NATIVE_SYS
             .tcp_listeners
-            .get_mut(&handle)
-            .ok_or_else(|| "Invalid tcp listener handle".to_string())?;
-        let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
-        drop(listener);
+            .get(&handle)
+            .ok_or_else(|| "Invalid tcp listener handle".to_string())?
+            .try_clone()
+            .map_err(|e| e.to_string())?;
+        let stream = if time_left()?.is_some() {
+            // Poll so that waiting for a connection can end at the deadline
+            listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+            let res = loop {
+                match listener.accept() {
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => match time_left() {
+                        Ok(_) => sleep(Duration::from_millis(10)),
+                        Err(e) => break Err(e),
+                    },
+                    res => break res.map_err(|e| e.to_string()),
+                }
+            };
+            _ = listener.set_nonblocking(false);
+            let (stream, _) = res?;
+            stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+            stream
+        } else {
+            listener.accept().map_err(|e| e.to_string())?.0
+        };
         let handle = NATIVE_SYS.new_handle();
         NATIVE_SYS
             .tcp_sockets
@@ -389,7 +483,7 @@ impl SysBackend for NativeSys {
     }
     fn tcp_connect(&self, addr: &str) -> Result<Handle, String> {
         let handle = NATIVE_SYS.new_handle();
-        let stream = TcpStream::connect(addr).map_err(|e| e.to_string())?;
+        let stream = connect(addr)?;
         NATIVE_SYS
             .tcp_sockets
             .insert(handle, Buffered::new_writer(stream));
@@ -454,10 +548,53 @@ impl SysBackend for NativeSys {
             .map_err(|e| e.to_string())?;
         Ok(())
     }
+    fn udp_bind(&self, addr: &str) -> Result<Handle, String> {
+        let handle = NATIVE_SYS.new_handle();
+        let socket = UdpSocket::bind(addr).map_err(|e| e.to_string())?;
+        NATIVE_SYS.udp_sockets.insert(handle, socket);
+        Ok(handle)
+    }
+    fn udp_send_to(&self, handle: Handle, contents: &[u8], addr: &str) -> Result<(), String> {
+        let socket = NATIVE_SYS
+            .udp_sockets
+            .get(&handle)
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?;
+        socket.send_to(contents, addr).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+    fn udp_receive_from(
+        &self,
+        handle: Handle,
+        max_len: usize,
+    ) -> Result<(Vec<u8>, String), String> {
+        // Clone the socket so the map isn't locked while waiting for a packet
+        let socket = NATIVE_SYS
+            .udp_sockets
+            .get(&handle)
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?
+            .try_clone()
+            .map_err(|e| e.to_string())?;
+        let mut buf = vec![0; max_len.min(MAX_UDP_PAYLOAD)];
+        let (len, addr) = if let Some(left) = time_left()? {
+            // Shorten the read timeout so that waiting for a packet can end at the deadline
+            let timeout = socket.read_timeout().map_err(|e| e.to_string())?;
+            (socket.set_read_timeout(Some(timeout.map_or(left, |t| t.min(left)))))
+                .map_err(|e| e.to_string())?;
+            let res = socket.recv_from(&mut buf);
+            _ = socket.set_read_timeout(timeout);
+            res
+        } else {
+            socket.recv_from(&mut buf)
+        }
+        .map_err(|e| e.to_string())?;
+        buf.truncate(len);
+        Ok((buf, addr.to_string()))
+    }
     fn close(&self, handle: Handle) -> Result<(), String> {
         if NATIVE_SYS.files.remove(&handle).is_some()
             || NATIVE_SYS.tcp_listeners.remove(&handle).is_some()
             || NATIVE_SYS.tcp_sockets.remove(&handle).is_some()
+            || NATIVE_SYS.udp_sockets.remove(&handle).is_some()
         {
             NATIVE_SYS.hostnames.remove(&handle);
             Ok(())
@@ -497,6 +634,50 @@ impl SysBackend for NativeSys {
         env::set_current_dir(path).map_err(|e| e.to_string())
     }
     #[cfg(feature = "https")]
+    fn download(&self, url: &str) -> Result<String, String> {
+        let cache_dir = download_cache_dir()?;
+        let path = cache_dir.join(cache_file_name(url));
+        if path.exists() {
+            return Ok(path.to_string_lossy().into_owned());
+        }
+        let mut url = url.to_string();
+        // Follow a limited number of redirects
+        for _ in 0..5 {
+            let HttpResponse {
+                status,
+                headers,
+                body,
+            } = http_get_url(&url)?;
+            match status {
+                200..=299 => {
+                    fs::create_dir_all(&cache_dir).map_err(|e| e.to_string())?;
+                    // Write to a temporary file first so that a failed
+                    // download never leaves a partial file in the cache
+                    let temp_path = path.with_extension("part");
+                    fs::write(&temp_path, body).map_err(|e| e.to_string())?;
+                    fs::rename(&temp_path, &path).map_err(|e| e.to_string())?;
+                    return Ok(path.to_string_lossy().into_owned());
+                }
+                300..=399 => {
+                    let location = headers
+                        .into_iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case("location"))
+                        .map(|(_, value)| value)
+                        .ok_or_else(|| format!("Redirect from {url} has no location"))?;
+                    let location = resolve_location(&url, &location);
+                    if url.starts_with("https://") && !location.starts_with("https://") {
+                        return Err(format!(
+                            "Refusing to follow a redirect from {url} to insecure {location}"
+                        ));
+                    }
+                    url = location;
+                }
+                status => return Err(format!("Downloading {url} failed with status {status}")),
+            }
+        }
+        Err(format!("Too many redirects downloading {url}"))
+    }
+    #[cfg(feature = "https")]
     fn https_get(&self, request: &str, handle: Handle) -> Result<String, String> {
         let host = NATIVE_SYS
             .hostnames
@@ -504,23 +685,6 @@ impl SysBackend for NativeSys {
             .ok_or_else(|| "Invalid tcp socket handle".to_string())?;
         let request = check_http(request.to_string(), &host)?;
 
-        // https://github.com/rustls/rustls/blob/c9cfe3499681361372351a57a00ccd793837ae9c/examples/src/bin/simpleclient.rs
-        static CLIENT_CONFIG: Lazy<std::sync::Arc<rustls::ClientConfig>> = Lazy::new(|| {
-            let mut store = rustls::RootCertStore::empty();
-            store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
-                rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
-                    ta.subject,
-                    ta.spki,
-                    ta.name_constraints,
-                )
-            }));
-            rustls::ClientConfig::builder()
-                .with_safe_defaults()
-                .with_root_certificates(store)
-                .with_no_client_auth()
-                .into()
-        });
-
         let mut socket = NATIVE_SYS
             .tcp_sockets
             .get_mut(&handle)
@@ -528,15 +692,17 @@ impl SysBackend for NativeSys {
 
         let server_name = rustls::ServerName::try_from(host.as_str()).map_err(|e| e.to_string())?;
         let tcp_stream = socket.get_mut();
+        let stream = tcp_stream.try_clone().map_err(|e| e.to_string())?;
 
-        let mut conn = rustls::ClientConnection::new(CLIENT_CONFIG.clone(), server_name)
+        let mut conn = rustls::ClientConnection::new(tls_client_config(), server_name)
             .map_err(|e| e.to_string())?;
         let mut tls = rustls::Stream::new(&mut conn, tcp_stream);
 
-        tls.write_all(request.as_bytes())
-            .map_err(|e| e.to_string())?;
         let mut buffer = Vec::new();
-        tls.read_to_end(&mut buffer).map_err(|e| e.to_string())?;
+        with_deadline(&stream, || {
+            tls.write_all(request.as_bytes())?;
+            tls.read_to_end(&mut buffer)
+        })?;
         let s = String::from_utf8(buffer).map_err(|e| {
             "Error converting HTTP Response to utf-8: ".to_string() + &e.to_string()
         })?;
@@ -545,6 +711,205 @@ impl SysBackend for NativeSys {
     }
 }
 
+#[cfg(feature = "https")]
+fn tls_client_config() -> std::sync::Arc<rustls::ClientConfig> {
+    // https://github.com/rustls/rustls/blob/c9cfe3499681361372351a57a00ccd793837ae9c/examples/src/bin/simpleclient.rs
+    static CLIENT_CONFIG: Lazy<std::sync::Arc<rustls::ClientConfig>> = Lazy::new(|| {
+        let mut store = rustls::RootCertStore::empty();
+        store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+            rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+                ta.subject,
+                ta.spki,
+                ta.name_constraints,
+            )
+        }));
+        rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(store)
+            .with_no_client_auth()
+            .into()
+    });
+    CLIENT_CONFIG.clone()
+}
+
+/// Get the directory that downloaded files are cached in
+///
+/// This is a `uiua` directory in the platform's user cache directory.
+/// Tests use the temporary directory instead so that they do not touch the user's cache.
+#[cfg(feature = "https")]
+fn download_cache_dir() -> Result<std::path::PathBuf, String> {
+    use std::path::PathBuf;
+    let base = if cfg!(test) {
+        Some(env::temp_dir())
+    } else if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| Path::new(&home).join("Library").join("Caches"))
+    } else {
+        (env::var_os("XDG_CACHE_HOME").filter(|dir| Path::new(dir).is_absolute()))
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+    };
+    base.map(|base| base.join("uiua"))
+        .ok_or_else(|| "Unable to find a cache directory for downloads".into())
+}
+
+/// Get the name of the file a URL is cached in
+///
+/// This uses FNV-1a rather than the standard library's hasher,
+/// because the name must be stable between builds.
+#[cfg(feature = "https")]
+fn cache_file_name(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    let name: String = (url.trim_end_matches('/').rsplit('/').next())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect();
+    format!("{hash:016x}-{name}")
+}
+
+#[cfg(feature = "https")]
+struct HttpResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// The largest payload that a UDP packet can carry
+const MAX_UDP_PAYLOAD: usize = 65507;
+
+/// The largest HTTP response that a download may read
+#[cfg(feature = "https")]
+const MAX_RESPONSE_SIZE: u64 = 1 << 30;
+
+/// Make a GET request to a URL
+#[cfg(feature = "https")]
+fn http_get_url(url: &str) -> Result<HttpResponse, String> {
+    let (tls, rest) = if let Some(rest) = url.strip_prefix("https://") {
+        (true, rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        (false, rest)
+    } else {
+        return Err(format!(
+            "URL must start with http:// or https://, but it is {url}"
+        ));
+    };
+    let (authority, path) = rest.find('/').map_or((rest, "/"), |i| rest.split_at(i));
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse().map_err(|_| format!("Invalid port in {url}"))?,
+        ),
+        None => (authority, if tls { 443 } else { 80 }),
+    };
+    let request = format!(
+        "GET {path} HTTP/1.1\r\nHost: {authority}\r\nUser-Agent: uiua\r\nConnection: close\r\n\r\n"
+    );
+    let mut stream = connect((host, port))?;
+    let timeouts = stream.try_clone().map_err(|e| e.to_string())?;
+    let mut response = Vec::new();
+    if tls {
+        let server_name = rustls::ServerName::try_from(host).map_err(|e| e.to_string())?;
+        let mut conn = rustls::ClientConnection::new(tls_client_config(), server_name)
+            .map_err(|e| e.to_string())?;
+        let mut tls = rustls::Stream::new(&mut conn, &mut stream);
+        with_deadline(&timeouts, || {
+            tls.write_all(request.as_bytes())?;
+            (&mut tls)
+                .take(MAX_RESPONSE_SIZE + 1)
+                .read_to_end(&mut response)
+        })?;
+    } else {
+        with_deadline(&timeouts, || {
+            stream.write_all(request.as_bytes())?;
+            (&mut stream)
+                .take(MAX_RESPONSE_SIZE + 1)
+                .read_to_end(&mut response)
+        })?;
+    }
+    if response.len() as u64 > MAX_RESPONSE_SIZE {
+        return Err(format!(
+            "Response from {url} is larger than the limit of {MAX_RESPONSE_SIZE} bytes"
+        ));
+    }
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut res = httparse::Response::new(&mut headers);
+    let body_start = match res.parse(&response) {
+        Ok(httparse::Status::Complete(len)) => len,
+        Ok(httparse::Status::Partial) => return Err("Incomplete HTTP response".into()),
+        Err(e) => return Err(format!("Invalid HTTP response: {e}")),
+    };
+    let status = res.code.unwrap_or_default();
+    let headers: Vec<(String, String)> = (res.headers.iter())
+        .map(|h| (h.name.into(), String::from_utf8_lossy(h.value).into_owned()))
+        .collect();
+    let chunked = headers.iter().any(|(name, value)| {
+        name.eq_ignore_ascii_case("transfer-encoding") && value.contains("chunked")
+    });
+    let body = if chunked {
+        decode_chunked(&response[body_start..])?
+    } else {
+        response[body_start..].to_vec()
+    };
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Decode an HTTP body sent with chunked transfer encoding
+#[cfg(feature = "https")]
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::new();
+    loop {
+        let (start, size) = match httparse::parse_chunk_size(body) {
+            Ok(httparse::Status::Complete(chunk)) => chunk,
+            Ok(httparse::Status::Partial) => return Err("Incomplete HTTP response".into()),
+            Err(_) => return Err("Invalid chunk size in HTTP response".into()),
+        };
+        if size == 0 {
+            return Ok(decoded);
+        }
+        let chunk = (body.get(start..))
+            .and_then(|rest| rest.get(..size as usize))
+            .ok_or("Incomplete HTTP response")?;
+        decoded.extend_from_slice(chunk);
+        // Each chunk is followed by a CRLF
+        body = body.get(start + size as usize + 2..).unwrap_or_default();
+    }
+}
+
+/// Resolve a redirect's location, which may be relative to the URL that was requested
+#[cfg(feature = "https")]
+fn resolve_location(url: &str, location: &str) -> String {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        return location.into();
+    }
+    let (scheme, rest) = url.split_once("://").unwrap_or(("http", url));
+    if let Some(location) = location.strip_prefix("//") {
+        return format!("{scheme}://{location}");
+    }
+    let (authority, path) = rest.find('/').map_or((rest, "/"), |i| rest.split_at(i));
+    if location.starts_with('/') {
+        return format!("{scheme}://{authority}{location}");
+    }
+    // Other locations replace the last segment of the path
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let dir = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+    format!("{scheme}://{authority}{dir}{location}")
+}
+
 /// Takes an HTTP request, validates it, and fixes it (if possible) by adding
 /// the HTTP version and trailing newlines if they aren't present.
 ///
@@ -1,9 +1,10 @@
 use std::{
     borrow::Cow,
     cmp::Ordering,
+    collections::HashMap,
     fmt,
     hash::{Hash, Hasher},
-    mem::take,
+    mem::{size_of, take},
 };
 
 use ecow::EcoVec;
@@ -14,7 +15,7 @@ use crate::{
     boxed::Boxed,
     cowslice::CowSlice,
     grid_fmt::GridFmt,
-    Complex, Uiua, UiuaResult,
+    Complex, ErrorKind, Rational, Uiua, UiuaResult,
 };
 
 /// A generic array value
@@ -30,6 +31,9 @@ pub enum Value {
     /// Complex number array
     #[cfg(feature = "complex")]
     Complex(Array<Complex>),
+    /// Rational number array
+    #[cfg(feature = "rational")]
+    Rational(Array<Rational>),
     /// Common character array
     Char(Array<char>),
     /// Common box array
@@ -50,6 +54,8 @@ impl fmt::Debug for Value {
             Self::Byte(array) => array.fmt(f),
             #[cfg(feature = "complex")]
             Self::Complex(array) => array.fmt(f),
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => array.fmt(f),
             Self::Char(array) => array.fmt(f),
             Self::Box(array) => array.fmt(f),
         }
@@ -73,6 +79,8 @@ impl Value {
             Self::Box(_) => 2,
             #[cfg(feature = "complex")]
             Self::Complex(_) => 3,
+            #[cfg(feature = "rational")]
+            Self::Rational(_) => 4,
         }
     }
     /// Get a reference to a possible number array
@@ -116,6 +124,8 @@ impl Value {
             Self::Byte(array) => Box::new(array.rows().map(Value::from)),
             #[cfg(feature = "complex")]
             Self::Complex(array) => Box::new(array.rows().map(Value::from)),
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => Box::new(array.rows().map(Value::from)),
             Self::Char(array) => Box::new(array.rows().map(Value::from)),
             Self::Box(array) => Box::new(array.rows().map(Value::from)),
         }
@@ -128,6 +138,8 @@ impl Value {
             Self::Byte(array) => Box::new(array.into_rows().map(Value::from)),
             #[cfg(feature = "complex")]
             Self::Complex(array) => Box::new(array.into_rows().map(Value::from)),
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => Box::new(array.into_rows().map(Value::from)),
             Self::Char(array) => Box::new(array.into_rows().map(Value::from)),
             Self::Box(array) => Box::new(array.into_rows().map(Value::from)),
         }
@@ -140,6 +152,8 @@ impl Value {
             Self::Byte(array) => Box::new(array.data.into_iter().map(Value::from)),
             #[cfg(feature = "complex")]
             Self::Complex(array) => Box::new(array.data.into_iter().map(Value::from)),
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => Box::new(array.data.into_iter().map(Value::from)),
             Self::Char(array) => Box::new(array.data.into_iter().map(Value::from)),
             Self::Box(array) => Box::new(array.data.into_iter().map(Value::from)),
         }
@@ -152,6 +166,8 @@ impl Value {
             Self::Byte(_) => "number",
             #[cfg(feature = "complex")]
             Self::Complex(_) => "complex",
+            #[cfg(feature = "rational")]
+            Self::Rational(_) => "rational",
             Self::Char(_) => "character",
             Self::Box(_) => "box",
         }
@@ -164,10 +180,90 @@ impl Value {
             Self::Byte(_) => "number",
             #[cfg(feature = "complex")]
             Self::Complex(_) => "complexes",
+            #[cfg(feature = "rational")]
+            Self::Rational(_) => "rationals",
             Self::Char(_) => "characters",
             Self::Box(_) => "boxes",
         }
     }
+    /// Get a summary of the value's element type, shape, and boxed contents
+    ///
+    /// ```
+    /// use uiua::{ElementType, Uiua};
+    ///
+    /// let mut env = Uiua::default();
+    /// env.load_str(r#"{"hi" 1_2 "ok"}"#).unwrap();
+    /// let schema = env.pop(()).unwrap().schema();
+    /// assert_eq!(schema.element_type, ElementType::Box);
+    /// assert_eq!(schema.shape, [3]);
+    /// assert_eq!(schema.boxed.len(), 2);
+    /// assert_eq!(schema.boxed[0].element_type, ElementType::Char);
+    /// assert_eq!(schema.depth(), 1);
+    ///
+    /// // Boxed values of the same type and rank share a schema
+    /// env.load_str("{1_2 3_4_5}").unwrap();
+    /// let schema = env.pop(()).unwrap().schema();
+    /// assert_eq!(schema.boxed.len(), 1);
+    /// assert_eq!(schema.boxed[0].shape, [3]);
+    /// ```
+    pub fn schema(&self) -> Schema {
+        let mut boxed = Vec::new();
+        if let Value::Box(array) = self {
+            // Where each element type and rank's schema is in `boxed`
+            let mut indices = HashMap::new();
+            for Boxed(value) in &array.data {
+                let schema = value.schema();
+                let key = (schema.element_type, schema.shape.len());
+                if let Some(&i) = indices.get(&key) {
+                    Schema::merge(&mut boxed[i], schema);
+                } else {
+                    indices.insert(key, boxed.len());
+                    boxed.push(schema);
+                }
+            }
+        }
+        Schema {
+            element_type: self.element_type(),
+            shape: self.shape().to_vec(),
+            boxed,
+        }
+    }
+    /// Get the type of the value's elements
+    pub fn element_type(&self) -> ElementType {
+        match self {
+            Self::Num(_) => ElementType::Num,
+            #[cfg(feature = "bytes")]
+            Self::Byte(_) => ElementType::Byte,
+            #[cfg(feature = "complex")]
+            Self::Complex(_) => ElementType::Complex,
+            #[cfg(feature = "rational")]
+            Self::Rational(_) => ElementType::Rational,
+            Self::Char(_) => ElementType::Char,
+            Self::Box(_) => ElementType::Box,
+        }
+    }
+    /// Iterate over the value's elements in row-major order without cloning them
+    ///
+    /// ```
+    /// use uiua::{CellRef, Value};
+    ///
+    /// let value = Value::from("hi");
+    /// let cells: Vec<CellRef> = value.iter_cells().collect();
+    /// assert_eq!(cells, [CellRef::Char('h'), CellRef::Char('i')]);
+    /// ```
+    pub fn iter_cells(&self) -> Box<dyn ExactSizeIterator<Item = CellRef<'_>> + '_> {
+        match self {
+            Self::Num(array) => Box::new(array.data.iter().copied().map(CellRef::Num)),
+            #[cfg(feature = "bytes")]
+            Self::Byte(array) => Box::new(array.data.iter().copied().map(CellRef::Byte)),
+            #[cfg(feature = "complex")]
+            Self::Complex(array) => Box::new(array.data.iter().copied().map(CellRef::Complex)),
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => Box::new(array.data.iter().copied().map(CellRef::Rational)),
+            Self::Char(array) => Box::new(array.data.iter().copied().map(CellRef::Char)),
+            Self::Box(array) => Box::new(array.data.iter().map(|Boxed(value)| CellRef::Box(value))),
+        }
+    }
     /// Get the shape of the value
     pub fn shape(&self) -> &[usize] {
         self.generic_ref_shallow(
@@ -176,6 +272,7 @@ impl Value {
             Array::shape,
             Array::shape,
             Array::shape,
+            Array::shape,
         )
     }
     /// Get the number of rows
@@ -186,6 +283,7 @@ impl Value {
             Array::row_count,
             Array::row_count,
             Array::row_count,
+            Array::row_count,
         )
     }
     /// Get the number of element in each row
@@ -196,6 +294,7 @@ impl Value {
             Array::row_len,
             Array::row_len,
             Array::row_len,
+            Array::row_len,
         )
     }
     /// Get the number of elements
@@ -206,6 +305,45 @@ impl Value {
             Array::element_count,
             Array::element_count,
             Array::element_count,
+            Array::element_count,
+        )
+    }
+    /// Get the number of bytes taken up by each element
+    pub(crate) fn element_size(&self) -> usize {
+        self.generic_ref_shallow(
+            |_| size_of::<f64>(),
+            |_| size_of::<u8>(),
+            |_| size_of::<Complex>(),
+            |_| size_of::<Rational>(),
+            |_| size_of::<char>(),
+            |_| size_of::<Boxed>(),
+        )
+    }
+    /// Get the number of bytes taken up by the elements, including those of boxed values
+    pub(crate) fn data_size(&self) -> usize {
+        self.generic_ref_shallow(
+            Array::data_size,
+            Array::data_size,
+            Array::data_size,
+            Array::data_size,
+            Array::data_size,
+            |arr| {
+                arr.data_size()
+                    + (arr.data.iter())
+                        .map(|b| b.as_value().data_size())
+                        .sum::<usize>()
+            },
+        )
+    }
+    /// Get a pointer to the elements' buffer, for checking whether two values share it
+    pub(crate) fn data_ptr(&self) -> *const u8 {
+        self.generic_ref_shallow(
+            |arr| arr.data.as_ptr().cast(),
+            |arr| arr.data.as_ptr().cast(),
+            |arr| arr.data.as_ptr().cast(),
+            |arr| arr.data.as_ptr().cast(),
+            |arr| arr.data.as_ptr().cast(),
+            |arr| arr.data.as_ptr().cast(),
         )
     }
     pub(crate) fn first_dim_zero(&self) -> Self {
@@ -215,6 +353,8 @@ impl Value {
             Self::Byte(array) => array.first_dim_zero().into(),
             #[cfg(feature = "complex")]
             Self::Complex(array) => array.first_dim_zero().into(),
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => array.first_dim_zero().into(),
             Self::Char(array) => array.first_dim_zero().into(),
             Self::Box(array) => array.first_dim_zero().into(),
         }
@@ -227,6 +367,7 @@ impl Value {
             Array::format_shape,
             Array::format_shape,
             Array::format_shape,
+            Array::format_shape,
         )
     }
     /// Get the rank
@@ -235,12 +376,15 @@ impl Value {
     }
     /// Get a mutable reference to the shape
     pub fn shape_mut(&mut self) -> &mut Shape {
+        *self.map_keys_mut() = None;
         match self {
             Self::Num(array) => &mut array.shape,
             #[cfg(feature = "bytes")]
             Self::Byte(array) => &mut array.shape,
             #[cfg(feature = "complex")]
             Self::Complex(array) => &mut array.shape,
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => &mut array.shape,
             Self::Char(array) => &mut array.shape,
             Self::Box(array) => &mut array.shape,
         }
@@ -252,6 +396,7 @@ impl Value {
             Array::validate_shape,
             Array::validate_shape,
             Array::validate_shape,
+            Array::validate_shape,
         )
     }
     /// Get the row at the given index
@@ -262,6 +407,7 @@ impl Value {
             |arr| arr.row(i).into(),
             |arr| arr.row(i).into(),
             |arr| arr.row(i).into(),
+            |arr| arr.row(i).into(),
         )
     }
     pub(crate) fn generic_into_deep<T>(
@@ -269,6 +415,7 @@ impl Value {
         n: impl FnOnce(Array<f64>) -> T,
         _b: impl FnOnce(Array<u8>) -> T,
         _co: impl FnOnce(Array<Complex>) -> T,
+        _ra: impl FnOnce(Array<Rational>) -> T,
         ch: impl FnOnce(Array<char>) -> T,
         f: impl FnOnce(Array<Boxed>) -> T,
     ) -> T {
@@ -278,9 +425,11 @@ impl Value {
             Self::Byte(array) => _b(array),
             #[cfg(feature = "complex")]
             Self::Complex(array) => _co(array),
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => _ra(array),
             Self::Char(array) => ch(array),
             Self::Box(array) => match array.into_unboxed() {
-                Ok(value) => value.generic_into_deep(n, _b, _co, ch, f),
+                Ok(value) => value.generic_into_deep(n, _b, _co, _ra, ch, f),
                 Err(array) => f(array),
             },
         }
@@ -290,6 +439,7 @@ impl Value {
         n: impl FnOnce(&'a Array<f64>) -> T,
         _b: impl FnOnce(&'a Array<u8>) -> T,
         _co: impl FnOnce(&'a Array<Complex>) -> T,
+        _ra: impl FnOnce(&'a Array<Rational>) -> T,
         ch: impl FnOnce(&'a Array<char>) -> T,
         f: impl FnOnce(&'a Array<Boxed>) -> T,
     ) -> T {
@@ -299,6 +449,8 @@ impl Value {
             Self::Byte(array) => _b(array),
             #[cfg(feature = "complex")]
             Self::Complex(array) => _co(array),
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => _ra(array),
             Self::Char(array) => ch(array),
             Self::Box(array) => f(array),
         }
@@ -308,6 +460,7 @@ impl Value {
         n: impl FnOnce(&'a Array<f64>) -> T,
         _b: impl FnOnce(&'a Array<u8>) -> T,
         _co: impl FnOnce(&'a Array<Complex>) -> T,
+        _ra: impl FnOnce(&'a Array<Rational>) -> T,
         ch: impl FnOnce(&'a Array<char>) -> T,
         f: impl FnOnce(&'a Array<Boxed>) -> T,
     ) -> T {
@@ -317,21 +470,25 @@ impl Value {
             Self::Byte(array) => _b(array),
             #[cfg(feature = "complex")]
             Self::Complex(array) => _co(array),
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => _ra(array),
             Self::Char(array) => ch(array),
             Self::Box(array) => {
                 if let Some(bx) = array.as_scalar() {
-                    bx.as_value().generic_ref_deep(n, _b, _co, ch, f)
+                    bx.as_value().generic_ref_deep(n, _b, _co, _ra, ch, f)
                 } else {
                     f(array)
                 }
             }
         }
     }
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn generic_ref_env_deep<'a, T: 'a>(
         &'a self,
         n: impl FnOnce(&'a Array<f64>, &Uiua) -> UiuaResult<T>,
         b: impl FnOnce(&'a Array<u8>, &Uiua) -> UiuaResult<T>,
         co: impl FnOnce(&'a Array<Complex>, &Uiua) -> UiuaResult<T>,
+        ra: impl FnOnce(&'a Array<Rational>, &Uiua) -> UiuaResult<T>,
         ch: impl FnOnce(&'a Array<char>, &Uiua) -> UiuaResult<T>,
         f: impl FnOnce(&'a Array<Boxed>, &Uiua) -> UiuaResult<T>,
         env: &Uiua,
@@ -340,6 +497,7 @@ impl Value {
             |a| n(a, env),
             |a| b(a, env),
             |a| co(a, env),
+            |a| ra(a, env),
             |a| ch(a, env),
             |a| f(a, env),
         )
@@ -349,6 +507,7 @@ impl Value {
         n: impl FnOnce(&mut Array<f64>) -> T,
         _b: impl FnOnce(&mut Array<u8>) -> T,
         _co: impl FnOnce(&mut Array<Complex>) -> T,
+        _ra: impl FnOnce(&mut Array<Rational>) -> T,
         ch: impl FnOnce(&mut Array<char>) -> T,
         f: impl FnOnce(&mut Array<Boxed>) -> T,
     ) -> T {
@@ -358,10 +517,12 @@ impl Value {
             Self::Byte(array) => _b(array),
             #[cfg(feature = "complex")]
             Self::Complex(array) => _co(array),
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => _ra(array),
             Self::Char(array) => ch(array),
             Self::Box(array) => {
                 if let Some(bx) = array.as_scalar_mut() {
-                    bx.as_value_mut().generic_mut_deep(n, _b, _co, ch, f)
+                    bx.as_value_mut().generic_mut_deep(n, _b, _co, _ra, ch, f)
                 } else {
                     f(array)
                 }
@@ -375,6 +536,7 @@ impl Value {
         n: impl FnOnce(Array<f64>, Array<f64>) -> Result<T, E>,
         _b: impl FnOnce(Array<u8>, Array<u8>) -> Result<T, E>,
         _co: impl FnOnce(Array<Complex>, Array<Complex>) -> Result<T, E>,
+        _ra: impl FnOnce(Array<Rational>, Array<Rational>) -> Result<T, E>,
         ch: impl FnOnce(Array<char>, Array<char>) -> Result<T, E>,
         f: impl FnOnce(Array<Boxed>, Array<Boxed>) -> Result<T, E>,
         err: impl FnOnce(Self, Self) -> E,
@@ -397,6 +559,20 @@ impl Value {
             (Self::Complex(a), Self::Byte(b)) => _co(a, b.convert()),
             #[cfg(all(feature = "complex", feature = "bytes"))]
             (Self::Byte(a), Self::Complex(b)) => _co(a.convert(), b),
+            #[cfg(feature = "rational")]
+            (Self::Rational(a), Self::Rational(b)) => _ra(a, b),
+            #[cfg(feature = "rational")]
+            (Self::Rational(a), Self::Num(b)) => _ra(a, b.convert()),
+            #[cfg(feature = "rational")]
+            (Self::Num(a), Self::Rational(b)) => _ra(a.convert(), b),
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Self::Rational(a), Self::Byte(b)) => _ra(a, b.convert()),
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Self::Byte(a), Self::Rational(b)) => _ra(a.convert(), b),
+            #[cfg(all(feature = "rational", feature = "complex"))]
+            (Self::Rational(a), Self::Complex(b)) => _co(a.convert(), b),
+            #[cfg(all(feature = "rational", feature = "complex"))]
+            (Self::Complex(a), Self::Rational(b)) => _co(a, b.convert()),
             (Self::Char(a), Self::Char(b)) => ch(a, b),
             (Self::Box(a), Self::Box(b)) => f(a, b),
             (Self::Box(a), b) => f(a, b.coerce_to_boxes()),
@@ -412,6 +588,8 @@ impl Value {
             Self::Byte(arr) => arr.data.reserve_min(min),
             #[cfg(feature = "complex")]
             Self::Complex(arr) => arr.data.reserve_min(min),
+            #[cfg(feature = "rational")]
+            Self::Rational(arr) => arr.data.reserve_min(min),
             Self::Char(arr) => arr.data.reserve_min(min),
             Self::Box(arr) => arr.data.reserve_min(min),
         }
@@ -424,6 +602,8 @@ impl Value {
             Self::Byte(array) => array.grid_string(),
             #[cfg(feature = "complex")]
             Self::Complex(array) => array.grid_string(),
+            #[cfg(feature = "rational")]
+            Self::Rational(array) => array.grid_string(),
             Self::Char(array) => array.grid_string(),
             Self::Box(array) => array.grid_string(),
         }
@@ -444,9 +624,9 @@ impl Value {
         Ok(match self {
             Value::Num(nums) => {
                 if nums.rank() > 0 {
-                    return Err(
-                        env.error(format!("{requirement}, but its rank is {}", nums.rank()))
-                    );
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", nums.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 let num = nums.data[0];
                 if num == 0.0 {
@@ -460,14 +640,16 @@ impl Value {
             #[cfg(feature = "bytes")]
             Value::Byte(bytes) => {
                 if bytes.rank() > 0 {
-                    return Err(
-                        env.error(format!("{requirement}, but its rank is {}", bytes.rank()))
-                    );
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", bytes.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 bytes.data[0] != 0
             }
             value => {
-                return Err(env.error(format!("{requirement}, but it is {}", value.type_name())))
+                return Err(env
+                    .error(format!("{requirement}, but it is {}", value.type_name()))
+                    .with_kind(ErrorKind::Type))
             }
         })
     }
@@ -481,9 +663,9 @@ impl Value {
         Ok(match self {
             Value::Num(nums) => {
                 if nums.rank() > 0 {
-                    return Err(
-                        env.error(format!("{requirement}, but its rank is {}", nums.rank()))
-                    );
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", nums.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 let num = nums.data[0];
                 if num < 0.0 {
@@ -497,14 +679,16 @@ impl Value {
             #[cfg(feature = "bytes")]
             Value::Byte(bytes) => {
                 if bytes.rank() > 0 {
-                    return Err(
-                        env.error(format!("{requirement}, but its rank is {}", bytes.rank()))
-                    );
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", bytes.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 bytes.data[0] as usize
             }
             value => {
-                return Err(env.error(format!("{requirement}, but it is {}", value.type_name())))
+                return Err(env
+                    .error(format!("{requirement}, but it is {}", value.type_name()))
+                    .with_kind(ErrorKind::Type))
             }
         })
     }
@@ -518,9 +702,9 @@ impl Value {
         Ok(match self {
             Value::Num(nums) => {
                 if nums.rank() > 0 {
-                    return Err(
-                        env.error(format!("{requirement}, but its rank is {}", nums.rank()))
-                    );
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", nums.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 let num = nums.data[0];
                 if num.fract() != 0.0 {
@@ -531,14 +715,16 @@ impl Value {
             #[cfg(feature = "bytes")]
             Value::Byte(bytes) => {
                 if bytes.rank() > 0 {
-                    return Err(
-                        env.error(format!("{requirement}, but its rank is {}", bytes.rank()))
-                    );
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", bytes.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 bytes.data[0] as isize
             }
             value => {
-                return Err(env.error(format!("{requirement}, but it is {}", value.type_name())))
+                return Err(env
+                    .error(format!("{requirement}, but it is {}", value.type_name()))
+                    .with_kind(ErrorKind::Type))
             }
         })
     }
@@ -552,23 +738,25 @@ impl Value {
         Ok(match self {
             Value::Num(nums) => {
                 if nums.rank() > 0 {
-                    return Err(
-                        env.error(format!("{requirement}, but its rank is {}", nums.rank()))
-                    );
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", nums.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 nums.data[0]
             }
             #[cfg(feature = "bytes")]
             Value::Byte(bytes) => {
                 if bytes.rank() > 0 {
-                    return Err(
-                        env.error(format!("{requirement}, but its rank is {}", bytes.rank()))
-                    );
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", bytes.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 bytes.data[0] as f64
             }
             value => {
-                return Err(env.error(format!("{requirement}, but it is {}", value.type_name())))
+                return Err(env
+                    .error(format!("{requirement}, but it is {}", value.type_name()))
+                    .with_kind(ErrorKind::Type))
             }
         })
     }
@@ -636,9 +824,9 @@ impl Value {
         Ok(match self {
             Value::Num(nums) => {
                 if nums.rank() > 1 {
-                    return Err(
-                        env.error(format!("{requirement}, but its rank is {}", nums.rank()))
-                    );
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", nums.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 let mut result = Vec::with_capacity(nums.row_count());
                 for &num in nums.data() {
@@ -652,9 +840,9 @@ impl Value {
             #[cfg(feature = "bytes")]
             Value::Byte(bytes) => {
                 if bytes.rank() > 1 {
-                    return Err(
-                        env.error(format!("{requirement}, but its rank is {}", bytes.rank()))
-                    );
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", bytes.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 let mut result = Vec::with_capacity(bytes.row_count());
                 for &byte in bytes.data() {
@@ -667,10 +855,12 @@ impl Value {
                 result
             }
             value => {
-                return Err(env.error(format!(
-                    "{requirement}, but it is {}",
-                    value.type_name_plural()
-                )))
+                return Err(env
+                    .error(format!(
+                        "{requirement}, but it is {}",
+                        value.type_name_plural()
+                    ))
+                    .with_kind(ErrorKind::Type))
             }
         })
     }
@@ -698,10 +888,12 @@ impl Value {
         Ok(match self {
             Value::Num(nums) => {
                 if !test_shape(self.shape()) {
-                    return Err(env.error(format!(
-                        "{requirement}, but its shape is {}",
-                        nums.format_shape()
-                    )));
+                    return Err(env
+                        .error(format!(
+                            "{requirement}, but its shape is {}",
+                            nums.format_shape()
+                        ))
+                        .with_kind(ErrorKind::Shape));
                 }
                 let mut result = EcoVec::with_capacity(nums.element_count());
                 for &num in nums.data() {
@@ -715,10 +907,12 @@ impl Value {
             #[cfg(feature = "bytes")]
             Value::Byte(bytes) => {
                 if !test_shape(self.shape()) {
-                    return Err(env.error(format!(
-                        "{requirement}, but its shape is {}",
-                        bytes.format_shape()
-                    )));
+                    return Err(env
+                        .error(format!(
+                            "{requirement}, but its shape is {}",
+                            bytes.format_shape()
+                        ))
+                        .with_kind(ErrorKind::Shape));
                 }
                 let mut result = EcoVec::with_capacity(bytes.element_count());
                 for &byte in bytes.data() {
@@ -731,10 +925,12 @@ impl Value {
                 Array::new(self.shape(), result)
             }
             value => {
-                return Err(env.error(format!(
-                    "{requirement}, but its type is {}",
-                    value.type_name()
-                )))
+                return Err(env
+                    .error(format!(
+                        "{requirement}, but its type is {}",
+                        value.type_name()
+                    ))
+                    .with_kind(ErrorKind::Type))
             }
         })
     }
@@ -748,9 +944,9 @@ impl Value {
         match self {
             Value::Char(chars) => {
                 if chars.rank() > 1 {
-                    return Err(
-                        env.error(format!("{requirement}, but its rank is {}", chars.rank()))
-                    );
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", chars.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 return Ok(chars.data().iter().collect());
             }
@@ -762,10 +958,12 @@ impl Value {
             }
             _ => {}
         }
-        Err(env.error(format!(
-            "{requirement}, but its type is {}",
-            self.type_name()
-        )))
+        Err(env
+            .error(format!(
+                "{requirement}, but its type is {}",
+                self.type_name()
+            ))
+            .with_kind(ErrorKind::Type))
     }
     /// Attempt to convert the array to a list of bytes
     ///
@@ -778,27 +976,45 @@ impl Value {
             #[cfg(feature = "bytes")]
             Value::Byte(a) => {
                 if a.rank() != 1 {
-                    return Err(env.error(format!("{requirement}, but its rank is {}", a.rank())));
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", a.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 a.data.into()
             }
             Value::Num(a) => {
                 if a.rank() != 1 {
-                    return Err(env.error(format!("{requirement}, but its rank is {}", a.rank())));
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", a.rank()))
+                        .with_kind(ErrorKind::Shape));
+                }
+                let mut bytes = Vec::with_capacity(a.data.len());
+                for f in a.data {
+                    if f.fract() != 0.0 || !(0.0..256.0).contains(&f) {
+                        return Err(env.error(format!(
+                            "{requirement}, but it contains {f}, \
+                            which is not an integer from 0 to 255"
+                        )));
+                    }
+                    bytes.push(f as u8);
                 }
-                a.data.into_iter().map(|f| f as u8).collect()
+                bytes
             }
             Value::Char(a) => {
                 if a.rank() != 1 {
-                    return Err(env.error(format!("{requirement}, but its rank is {}", a.rank())));
+                    return Err(env
+                        .error(format!("{requirement}, but its rank is {}", a.rank()))
+                        .with_kind(ErrorKind::Shape));
                 }
                 a.data.into_iter().collect::<String>().into_bytes()
             }
             value => {
-                return Err(env.error(format!(
-                    "{requirement}, but its type is {}",
-                    value.type_name()
-                )))
+                return Err(env
+                    .error(format!(
+                        "{requirement}, but its type is {}",
+                        value.type_name()
+                    ))
+                    .with_kind(ErrorKind::Type))
             }
         })
     }
@@ -860,6 +1076,8 @@ impl Value {
             Value::Byte(arr) => arr.convert_with(|v| Boxed(Value::from(v))),
             #[cfg(feature = "complex")]
             Value::Complex(arr) => arr.convert_with(|v| Boxed(Value::from(v))),
+            #[cfg(feature = "rational")]
+            Value::Rational(arr) => arr.convert_with(|v| Boxed(Value::from(v))),
             Value::Char(arr) => arr.convert_with(|v| Boxed(Value::from(v))),
             Value::Box(arr) => arr,
         }
@@ -872,6 +1090,8 @@ impl Value {
             Value::Byte(arr) => Cow::Owned(arr.convert_ref_with(|v| Boxed(Value::from(v)))),
             #[cfg(feature = "complex")]
             Value::Complex(arr) => Cow::Owned(arr.convert_ref_with(|v| Boxed(Value::from(v)))),
+            #[cfg(feature = "rational")]
+            Value::Rational(arr) => Cow::Owned(arr.convert_ref_with(|v| Boxed(Value::from(v)))),
             Value::Char(arr) => Cow::Owned(arr.convert_ref_with(|v| Boxed(Value::from(v)))),
             Value::Box(arr) => Cow::Borrowed(arr),
         }
@@ -880,39 +1100,42 @@ impl Value {
 
 macro_rules! value_from {
     ($ty:ty, $variant:ident) => {
-        impl From<$ty> for Value {
-            fn from(item: $ty) -> Self {
-                Self::$variant(Array::from(item))
-            }
-        }
         impl From<Array<$ty>> for Value {
             fn from(array: Array<$ty>) -> Self {
                 Self::$variant(array)
             }
         }
+        value_from!($ty);
+    };
+    ($ty:ty) => {
+        impl From<$ty> for Value {
+            fn from(item: $ty) -> Self {
+                Array::from(item).into()
+            }
+        }
         impl From<EcoVec<$ty>> for Value {
             fn from(vec: EcoVec<$ty>) -> Self {
-                Self::$variant(Array::from(vec))
+                Array::from(vec).into()
             }
         }
         impl From<CowSlice<$ty>> for Value {
             fn from(vec: CowSlice<$ty>) -> Self {
-                Self::$variant(Array::from(vec))
+                Array::from(vec).into()
             }
         }
         impl From<(Shape, EcoVec<$ty>)> for Value {
             fn from((shape, data): (Shape, EcoVec<$ty>)) -> Self {
-                Self::$variant(Array::new(shape, data))
+                Array::new(shape, data).into()
             }
         }
         impl From<(Shape, CowSlice<$ty>)> for Value {
             fn from((shape, data): (Shape, CowSlice<$ty>)) -> Self {
-                Self::$variant(Array::new(shape, data))
+                Array::new(shape, data).into()
             }
         }
         impl FromIterator<$ty> for Value {
             fn from_iter<I: IntoIterator<Item = $ty>>(iter: I) -> Self {
-                Self::$variant(Array::from_iter(iter))
+                Array::from_iter(iter).into()
             }
         }
     };
@@ -925,6 +1148,20 @@ value_from!(char, Char);
 value_from!(Boxed, Box);
 #[cfg(feature = "complex")]
 value_from!(Complex, Complex);
+#[cfg(feature = "rational")]
+value_from!(Rational);
+
+#[cfg(feature = "rational")]
+impl From<Array<Rational>> for Value {
+    fn from(array: Array<Rational>) -> Self {
+        // Rationals that did not fit were approximated, so the array falls back to numbers
+        if array.data.iter().any(|r| r.is_approx()) {
+            array.convert_with(f64::from).into()
+        } else {
+            Self::Rational(array)
+        }
+    }
+}
 
 #[cfg(not(feature = "bytes"))]
 impl From<u8> for Value {
@@ -959,6 +1196,20 @@ impl From<Array<Complex>> for Value {
     }
 }
 
+#[cfg(not(feature = "rational"))]
+impl From<Rational> for Value {
+    fn from(r: Rational) -> Self {
+        Self::from(f64::from(r))
+    }
+}
+
+#[cfg(not(feature = "rational"))]
+impl From<Array<Rational>> for Value {
+    fn from(array: Array<Rational>) -> Self {
+        array.convert::<f64>().into()
+    }
+}
+
 impl FromIterator<usize> for Value {
     fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
         iter.into_iter().map(|i| i as f64).collect()
@@ -1017,15 +1268,13 @@ macro_rules! value_un_impl {
                         (array.shape, new).into()
                     },)*)*
                     Value::Box(mut array) => {
-                        let mut new_data = EcoVec::with_capacity(array.flat_len());
-                        for b in array.data {
-                            new_data.push(Boxed(b.0.$name(env)?));
+                        for b in &mut array.data {
+                            b.0 = take(&mut b.0).$name(env)?;
                         }
-                        array.data = new_data.into();
                         array.into()
                     }
                     #[allow(unreachable_patterns)]
-                    val => return Err($name::error(val.type_name(), env))
+                    val => return Err($name::error(val.type_name(), env).with_kind(ErrorKind::Type))
                 })
             }
         }
@@ -1036,86 +1285,120 @@ value_un_impl!(
     neg,
     [Num, num],
     ("bytes", Byte, byte),
-    ["complex", Complex, com]
+    ["complex", Complex, com],
+    ["rational", Rational, rat]
 );
 value_un_impl!(
     not,
     [Num, num],
     ("bytes", Byte, byte),
-    ["complex", Complex, com]
+    ["complex", Complex, com],
+    ["rational", Rational, rat]
 );
 value_un_impl!(
     abs,
     [Num, num],
     ("bytes", Byte, byte),
-    ("complex", Complex, com)
+    ("complex", Complex, com),
+    ["rational", Rational, rat]
 );
 value_un_impl!(
     sign,
     [Num, num],
     ["bytes", Byte, byte],
-    ["complex", Complex, com]
+    ["complex", Complex, com],
+    ["rational", Rational, rat]
 );
 value_un_impl!(
     sqrt,
     [Num, num],
     ("bytes", Byte, byte),
-    ["complex", Complex, com]
+    ["complex", Complex, com],
+    ("rational", Rational, rat)
 );
 value_un_impl!(
     sin,
     [Num, num],
     ("bytes", Byte, byte),
-    ["complex", Complex, com]
+    ["complex", Complex, com],
+    ("rational", Rational, rat)
 );
 value_un_impl!(
     cos,
     [Num, num],
     ("bytes", Byte, byte),
-    ["complex", Complex, com]
+    ["complex", Complex, com],
+    ("rational", Rational, rat)
 );
 value_un_impl!(
     asin,
     [Num, num],
     ("bytes", Byte, byte),
-    ["complex", Complex, com]
+    ["complex", Complex, com],
+    ("rational", Rational, rat)
 );
 value_un_impl!(
     acos,
     [Num, num],
     ("bytes", Byte, byte),
-    ["complex", Complex, com]
+    ["complex", Complex, com],
+    ("rational", Rational, rat)
 );
 value_un_impl!(
     floor,
     [Num, num],
     ["bytes", Byte, byte],
-    ["complex", Complex, com]
+    ["complex", Complex, com],
+    ["rational", Rational, rat]
 );
 value_un_impl!(
     ceil,
     [Num, num],
     ["bytes", Byte, byte],
-    ["complex", Complex, com]
+    ["complex", Complex, com],
+    ["rational", Rational, rat]
 );
 value_un_impl!(
     round,
     [Num, num],
     ["bytes", Byte, byte],
-    ["complex", Complex, com]
+    ["complex", Complex, com],
+    ["rational", Rational, rat]
+);
+value_un_impl!(
+    prime,
+    [Num, num],
+    ["bytes", Byte, byte],
+    ("rational", Rational, rat)
 );
 value_un_impl!(
     complex_re,
     [Num, generic],
     ["bytes", Byte, generic],
     ("complex", Complex, com),
-    [Char, generic]
+    [Char, generic],
+    ["rational", Rational, generic]
 );
 value_un_impl!(
     complex_im,
     [Num, num],
     ["bytes", Byte, byte],
-    ("complex", Complex, com)
+    ("complex", Complex, com),
+    ["rational", Rational, rat]
+);
+value_un_impl!(
+    complex_arg,
+    [Num, num],
+    ["bytes", Byte, byte],
+    ("complex", Complex, com),
+    ("rational", Rational, rat)
+);
+value_un_impl!(
+    complex_conj,
+    [Num, generic],
+    ["bytes", Byte, generic],
+    ["complex", Complex, com],
+    ["rational", Rational, generic]
 );
 
 macro_rules! val_retry {
@@ -1195,7 +1478,10 @@ macro_rules! value_bin_impl {
                             }
                         }
                     },
-                    (a, b) => return Err($name::error(a.type_name(), b.type_name(), env)),
+                    (a, b) => {
+                        return Err($name::error(a.type_name(), b.type_name(), env)
+                            .with_kind(ErrorKind::Type))
+                    }
                 })
             }
         }
@@ -1215,6 +1501,13 @@ macro_rules! value_bin_math_impl {
             ("complex", Num, Complex, x_com),
             ("byte", "complex", Complex, Byte, com_x, com_x),
             ("byte", "complex", Byte, Complex, x_com, com_x),
+            ("rational", Rational, Rational, rat_x),
+            ("rational", Rational, Num, rat_x),
+            ("rational", Num, Rational, x_rat),
+            ("bytes", "rational", Rational, Byte, rat_x),
+            ("bytes", "rational", Byte, Rational, x_rat),
+            ("complex", "rational", Complex, Rational, com_x),
+            ("complex", "rational", Rational, Complex, x_com),
             $($($tt)*)?
         );
     };
@@ -1239,6 +1532,30 @@ value_bin_math_impl!(modulus);
 value_bin_math_impl!(pow);
 value_bin_math_impl!(log);
 value_bin_math_impl!(atan2);
+value_bin_impl!(
+    gcd,
+    [Num, num_num],
+    ("bytes", Byte, Byte, byte_byte, num_num),
+    ("bytes", Byte, Num, byte_num, num_num),
+    ("bytes", Num, Byte, num_byte, num_num),
+);
+value_bin_impl!(
+    lcm,
+    [Num, num_num],
+    ("bytes", Byte, Byte, byte_byte, num_num),
+    ("bytes", Byte, Num, byte_num, num_num),
+    ("bytes", Num, Byte, num_byte, num_num),
+);
+value_bin_impl!(
+    round_to,
+    [Num, num_num],
+    ("bytes", Byte, Byte, byte_byte, num_num),
+    ("bytes", Byte, Num, byte_num, num_num),
+    ("bytes", Num, Byte, num_byte, num_num),
+    ("rational", Rational, Rational, rat_x),
+    ("rational", Rational, Num, rat_x),
+    ("rational", Num, Rational, x_rat),
+);
 value_bin_math_impl!(min, [Char, char_char]);
 value_bin_math_impl!(max, [Char, char_char]);
 
@@ -1272,10 +1589,19 @@ macro_rules! cmp_impls {
                 ("complex", Num, Complex, x_com),
                 ("byte", "complex", Complex, Byte, com_x),
                 ("byte", "complex", Byte, Complex, x_com),
+                ["rational", Rational, same_type],
+                ("rational", Rational, Num, rat_x),
+                ("rational", Num, Rational, x_rat),
+                ("bytes", "rational", Rational, Byte, rat_x),
+                ("bytes", "rational", Byte, Rational, x_rat),
+                ("complex", "rational", Complex, Rational, com_x),
+                ("complex", "rational", Rational, Complex, x_com),
                 // Type comparable
                 (Num, Char, always_less),
                 ("bytes", Byte, Char, always_less),
                 (Char, Num, always_greater),
+                ("rational", Rational, Char, always_less),
+                ("rational", Char, Rational, always_greater),
                 ("bytes", Char, Byte, always_greater),
             );
         )*
@@ -1284,18 +1610,97 @@ macro_rules! cmp_impls {
 
 cmp_impls!(is_eq, is_ne, is_lt, is_le, is_gt, is_ge);
 
+impl Value {
+    /// Check if the value is `equal` to another, allowing numbers to differ by [`Uiua::tolerance`]
+    #[allow(clippy::wrong_self_convention)]
+    pub(crate) fn is_eq_tolerant(self, other: Self, env: &Uiua) -> UiuaResult<Self> {
+        self.cmp_tolerant(other, true, env)
+    }
+    /// Check if the value is `not equal` to another, allowing numbers to differ by [`Uiua::tolerance`]
+    #[allow(clippy::wrong_self_convention)]
+    pub(crate) fn is_ne_tolerant(self, other: Self, env: &Uiua) -> UiuaResult<Self> {
+        self.cmp_tolerant(other, false, env)
+    }
+    fn cmp_tolerant(self, other: Self, eq: bool, env: &Uiua) -> UiuaResult<Self> {
+        let tolerance = env.tolerance();
+        if tolerance > 0.0 && self.is_real() && other.is_real() {
+            let f = move |a: f64, b: f64| (within_tolerance(a, b, tolerance) == eq) as u8;
+            let (a, b) = (self.into_nums(), other.into_nums());
+            return bin_pervade(a, b, env, InfalliblePervasiveFn::new(f)).map(Into::into);
+        }
+        if eq {
+            self.is_eq(other, env)
+        } else {
+            self.is_ne(other, env)
+        }
+    }
+    /// Check if the value `match`es another, allowing numbers to differ by [`Uiua::tolerance`]
+    pub fn matches(&self, other: &Self, env: &Uiua) -> bool {
+        let tolerance = env.tolerance();
+        if tolerance == 0.0 || self.shape() != other.shape() {
+            return self == other;
+        }
+        match (self, other) {
+            (Value::Box(a), Value::Box(b)) => {
+                (a.data.iter().zip(&b.data)).all(|(a, b)| a.as_value().matches(b.as_value(), env))
+            }
+            (a, b) if a.is_real() && b.is_real() => {
+                let (a, b) = (a.clone().into_nums(), b.clone().into_nums());
+                (a.data.iter().zip(&b.data)).all(|(&a, &b)| within_tolerance(a, b, tolerance))
+            }
+            _ => self == other,
+        }
+    }
+    fn is_real(&self) -> bool {
+        match self {
+            Value::Num(_) => true,
+            #[cfg(feature = "bytes")]
+            Value::Byte(_) => true,
+            #[cfg(feature = "rational")]
+            Value::Rational(_) => true,
+            _ => false,
+        }
+    }
+    fn into_nums(self) -> Array<f64> {
+        match self {
+            Value::Num(array) => array,
+            #[cfg(feature = "bytes")]
+            Value::Byte(array) => array.convert(),
+            #[cfg(feature = "rational")]
+            Value::Rational(array) => array.convert(),
+            _ => unreachable!("into_nums called on a non-real value"),
+        }
+    }
+}
+
+fn within_tolerance(a: f64, b: f64, tolerance: f64) -> bool {
+    a.array_cmp(&b) == Ordering::Equal || (a - b).abs() <= tolerance
+}
+
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Value::Num(a), Value::Num(b)) => a == b,
             #[cfg(feature = "bytes")]
             (Value::Byte(a), Value::Byte(b)) => a == b,
+            #[cfg(feature = "complex")]
+            (Value::Complex(a), Value::Complex(b)) => a == b,
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Rational(b)) => a == b,
             (Value::Char(a), Value::Char(b)) => a == b,
             (Value::Box(a), Value::Box(b)) => a == b,
             #[cfg(feature = "bytes")]
             (Value::Num(a), Value::Byte(b)) => a == b,
             #[cfg(feature = "bytes")]
             (Value::Byte(a), Value::Num(b)) => a == b,
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Num(b)) => a == b,
+            #[cfg(feature = "rational")]
+            (Value::Num(a), Value::Rational(b)) => a == b,
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Rational(a), Value::Byte(b)) => a == b,
+            #[cfg(all(feature = "rational", feature = "bytes"))]
+            (Value::Byte(a), Value::Rational(b)) => a == b,
             _ => false,
         }
     }
@@ -1321,6 +1726,8 @@ impl Ord for Value {
             (Value::Byte(a), Value::Byte(b)) => a.cmp(b),
             #[cfg(feature = "complex")]
             (Value::Complex(a), Value::Complex(b)) => a.cmp(b),
+            #[cfg(feature = "rational")]
+            (Value::Rational(a), Value::Rational(b)) => a.cmp(b),
             (Value::Char(a), Value::Char(b)) => a.cmp(b),
             (Value::Box(a), Value::Box(b)) => a.cmp(b),
             #[cfg(feature = "bytes")]
@@ -1337,6 +1744,10 @@ impl Ord for Value {
             (Value::Complex(_), _) => Ordering::Less,
             #[cfg(feature = "complex")]
             (_, Value::Complex(_)) => Ordering::Greater,
+            #[cfg(feature = "rational")]
+            (Value::Rational(_), _) => Ordering::Less,
+            #[cfg(feature = "rational")]
+            (_, Value::Rational(_)) => Ordering::Greater,
             (Value::Char(_), _) => Ordering::Less,
             (_, Value::Char(_)) => Ordering::Greater,
         }
@@ -1352,6 +1763,8 @@ impl Hash for Value {
             Value::Byte(arr) => arr.hash(state),
             #[cfg(feature = "complex")]
             Value::Complex(arr) => arr.hash(state),
+            #[cfg(feature = "rational")]
+            Value::Rational(arr) => arr.hash(state),
             Value::Char(arr) => arr.hash(state),
             Value::Box(arr) => arr.hash(state),
         }
@@ -1366,6 +1779,8 @@ impl fmt::Display for Value {
             Value::Byte(b) => b.grid_string().fmt(f),
             #[cfg(feature = "complex")]
             Value::Complex(c) => c.grid_string().fmt(f),
+            #[cfg(feature = "rational")]
+            Value::Rational(r) => r.grid_string().fmt(f),
             Value::Box(v) => v.grid_string().fmt(f),
             Value::Char(c) if c.rank() < 2 => c.fmt(f),
             Value::Char(c) => c.grid_string().fmt(f),
@@ -1373,6 +1788,88 @@ impl fmt::Display for Value {
     }
 }
 
+/// The type of a [`Value`]'s elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementType {
+    /// Numbers
+    Num,
+    /// Bytes
+    #[cfg(feature = "bytes")]
+    Byte,
+    /// Complex numbers
+    #[cfg(feature = "complex")]
+    Complex,
+    /// Rational numbers
+    #[cfg(feature = "rational")]
+    Rational,
+    /// Characters
+    Char,
+    /// Boxes
+    Box,
+}
+
+/// A summary of the structure of a [`Value`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Schema {
+    /// The type of the elements
+    pub element_type: ElementType,
+    /// The shape
+    pub shape: Vec<usize>,
+    /// The schemas of the boxed values, in the order they first appear
+    ///
+    /// Boxed values with the same element type and rank share one schema,
+    /// whose shape has the longest length of each axis.
+    ///
+    /// This is empty if the elements are not boxes
+    pub boxed: Vec<Schema>,
+}
+
+impl Schema {
+    /// Merge the schema of another value with the same element type and rank
+    fn merge(&mut self, other: Schema) {
+        for (a, b) in self.shape.iter_mut().zip(other.shape) {
+            *a = (*a).max(b);
+        }
+        for schema in other.boxed {
+            let same = (self.boxed.iter_mut()).find(|s| {
+                s.element_type == schema.element_type && s.shape.len() == schema.shape.len()
+            });
+            if let Some(same) = same {
+                same.merge(schema);
+            } else {
+                self.boxed.push(schema);
+            }
+        }
+    }
+    /// Get the maximum number of boxes that have to be opened to reach a non-box value
+    pub fn depth(&self) -> usize {
+        (self.boxed.iter())
+            .map(|schema| schema.depth() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// A reference to a single element of a [`Value`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellRef<'a> {
+    /// A number
+    Num(f64),
+    /// A byte
+    #[cfg(feature = "bytes")]
+    Byte(u8),
+    /// A complex number
+    #[cfg(feature = "complex")]
+    Complex(Complex),
+    /// A rational number
+    #[cfg(feature = "rational")]
+    Rational(Rational),
+    /// A character
+    Char(char),
+    /// The value in a box
+    Box(&'a Value),
+}
+
 #[derive(Clone, Default)]
 pub(crate) struct ValueBuilder {
     value: Option<Value>,